@@ -0,0 +1,51 @@
+//! `hica agent`: scans that report to a central server, for a dashboard of
+//! how the cache of many machines grows. Each report is the summary
+//! `--format json` prints, wrapped with what identifies the machine.
+
+use crate::report::json_string;
+
+/// Files holding a stable id of the machine, as systemd and D-Bus keep it.
+const MACHINE_ID_FILES: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+/// What tells this machine apart from the others reporting: `[agent]
+/// machine_id` if set, else the system's machine id, else the host name.
+pub fn machine_id(configured: Option<&str>) -> String {
+    if let Some(id) = configured {
+        return id.to_string();
+    }
+    MACHINE_ID_FILES
+        .iter()
+        .filter_map(|file| std::fs::read_to_string(file).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .unwrap_or_else(crate::platform::hostname)
+}
+
+/// The report sent to the server:
+///
+/// `{"machine":{"id":..,"host":..,"os":..,"arch":..},"hica":..,"summary":{..}}`
+pub fn payload(machine_id: &str, host: &str, summary: &str) -> String {
+    format!(
+        "{{\"machine\":{{\"id\":{},\"host\":{},\"os\":\"{}\",\"arch\":\"{}\"}},\"hica\":\"{}\",\"summary\":{}}}",
+        json_string(machine_id),
+        json_string(host),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        env!("CARGO_PKG_VERSION"),
+        summary
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Json;
+
+    #[test]
+    fn wraps_the_summary() {
+        assert_eq!(machine_id(Some("web-1")), "web-1");
+        let payload = Json::parse(&payload("web-1", "web1.example.com", r#"{"total":{"files":2,"bytes":30}}"#)).unwrap();
+        assert_eq!(payload.get("machine").and_then(|m| m.get("id")).and_then(Json::as_str), Some("web-1"));
+        assert_eq!(payload.get("summary").and_then(|s| s.get("total")).and_then(|t| t.get("bytes")).and_then(Json::as_u64), Some(30));
+    }
+}
@@ -0,0 +1,147 @@
+//! Anonymized reports, for sharing scan results in bug reports and forums.
+//! With `--anonymize`, home directories in exported reports become `~` (or
+//! `~user-<hash>` for other users' homes), and user names elsewhere in paths
+//! are hashed. The hashes are salted per run, so they can't be looked up by
+//! hashing likely names, but the same user keeps the same hash within a report.
+
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::paths::{home_dir, user_homes};
+
+static ANONYMIZER: OnceLock<Anonymizer> = OnceLock::new();
+
+/// Anonymizes every report written for the rest of the run.
+pub fn enable() {
+    let _ = ANONYMIZER.set(Anonymizer::new(home_dir(), user_homes(), current_user()));
+}
+
+/// The current user's name, as the environment has it.
+fn current_user() -> Option<String> {
+    ["USER", "USERNAME", "LOGNAME"].iter().find_map(|var| std::env::var(var).ok().filter(|name| !name.is_empty()))
+}
+
+/// `path` as an exported report should show it. Names are kept as they are,
+/// rather than escaped as on the terminal, for the report's own format to
+/// quote.
+pub fn path(path: &Path) -> Cow<'_, str> {
+    match ANONYMIZER.get() {
+        Some(anonymizer) => Cow::Owned(anonymizer.redact(&path.to_string_lossy())),
+        None => path.to_string_lossy(),
+    }
+}
+
+/// A message that may mention paths, such as an error, as an exported report
+/// should show it.
+pub fn text(text: &str) -> Cow<'_, str> {
+    match ANONYMIZER.get() {
+        Some(anonymizer) => Cow::Owned(anonymizer.redact(text)),
+        None => Cow::Borrowed(text),
+    }
+}
+
+struct Anonymizer {
+    /// Home directories and what they become, longest first so a home is
+    /// never mistaken for one whose name it starts with.
+    homes: Vec<(String, String)>,
+    /// User names and their hashes.
+    names: Vec<(String, String)>,
+}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+impl Anonymizer {
+    fn new(own_home: Option<PathBuf>, homes: Vec<(String, PathBuf)>, own_name: Option<String>) -> Self {
+        let salt = RandomState::new();
+        let hashed = |name: &str| format!("user-{:08x}", salt.hash_one(name) as u32);
+
+        let own_name = own_name.or_else(|| Some(own_home.as_ref()?.file_name()?.to_string_lossy().into_owned()));
+        let mut names: Vec<(String, String)> = homes
+            .iter()
+            .map(|(name, _)| name.clone())
+            .chain(own_name)
+            .map(|name| {
+                let hash = hashed(&name);
+                (name, hash)
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let own_home = own_home.map(|home| home.to_string_lossy().trim_end_matches(is_separator).to_string());
+        let mut redacted_homes: Vec<(String, String)> = homes
+            .into_iter()
+            .map(|(name, home)| (home.to_string_lossy().trim_end_matches(is_separator).to_string(), format!("~{}", hashed(&name))))
+            .filter(|(home, _)| Some(home) != own_home.as_ref())
+            .collect();
+        redacted_homes.extend(own_home.filter(|home| !home.is_empty()).map(|home| (home, "~".to_string())));
+        redacted_homes.sort_by_key(|(home, _)| std::cmp::Reverse(home.len()));
+
+        Anonymizer { homes: redacted_homes, names }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (home, replacement) in &self.homes {
+            text = replace_bounded(&text, home, replacement, false);
+        }
+        for (name, hash) in &self.names {
+            text = replace_bounded(&text, name, hash, true);
+        }
+        text
+    }
+}
+
+/// Replaces each occurrence of `from` that ends at a path separator or where
+/// the text or a word ends, and, for a `component`, also starts right after a
+/// separator.
+fn replace_bounded(text: &str, from: &str, to: &str, component: bool) -> String {
+    if from.is_empty() {
+        return text.to_string();
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(from) {
+        let (before, after) = (&rest[..index], &rest[index + from.len()..]);
+        let starts = !component || before.ends_with(is_separator);
+        let ends = after.chars().next().is_none_or(|c| is_separator(c) || !(c.is_alphanumeric() || c == '.' || c == '-' || c == '_'));
+        result.push_str(before);
+        result.push_str(if starts && ends { to } else { from });
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_homes_and_user_names() {
+        let anonymizer = Anonymizer::new(
+            Some(PathBuf::from("/home/alice")),
+            vec![("alice".to_string(), PathBuf::from("/home/alice")), ("bob".to_string(), PathBuf::from("/home/bob"))],
+            Some("alice".to_string()),
+        );
+        assert_eq!(anonymizer.redact("/home/alice/.cache/pip/x"), "~/.cache/pip/x");
+        assert_eq!(anonymizer.redact("/home/alice"), "~");
+        assert_eq!(anonymizer.redact("/home/alicex/notes"), "/home/alicex/notes");
+
+        let bob = anonymizer.redact("/home/bob/.cache/x");
+        assert!(bob.starts_with("~user-") && bob.ends_with("/.cache/x") && !bob.contains("bob"), "{}", bob);
+        let mounted = anonymizer.redact("/media/bob/USB/cache");
+        assert_eq!(mounted, format!("/media/{}/USB/cache", &bob[1..bob.find('/').unwrap()]));
+
+        assert_eq!(
+            anonymizer.redact("failed to delete /home/alice/a.log: Permission denied"),
+            "failed to delete ~/a.log: Permission denied"
+        );
+        assert_eq!(anonymizer.redact("/srv/bobcat/data"), "/srv/bobcat/data");
+    }
+}
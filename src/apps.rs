@@ -0,0 +1,226 @@
+//! Application caches hica can attribute to the app that owns them, so the
+//! summary can say which apps the space goes to: Electron apps, IDEs and
+//! language servers.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// Directories apps keep their data in, as runs of lowercased components:
+/// `~/.config`, `~/Library/Application Support` and `%APPDATA%`.
+const APP_DATA_ROOTS: &[&[&str]] = &[&[".config"], &["application support"], &["appdata", "roaming"]];
+
+/// The Chromium cache directories every Electron app has.
+const ELECTRON_CACHES: &[&str] = &["cache", "code cache", "gpucache", "dawncache", "dawngraphitecache", "dawnwebgpucache"];
+const ELECTRON_WORKER_CACHES: &[&str] = &["cachestorage", "scriptcache"];
+
+/// Display names of well-known Electron apps, by their lowercased data
+/// directory. Other apps only count when their data directory has Chromium's
+/// `Local State`, and go by their directory's name.
+const ELECTRON_APPS: &[(&str, &str)] = &[
+    ("slack", "Slack"),
+    ("discord", "Discord"),
+    ("code", "VS Code"),
+    ("code - insiders", "VS Code Insiders"),
+    ("vscodium", "VSCodium"),
+    ("microsoft teams", "Teams"),
+    ("teams", "Teams"),
+    ("signal", "Signal"),
+    ("obsidian", "Obsidian"),
+    ("spotify", "Spotify"),
+];
+
+/// The Electron app whose Chromium caches `path` is in: the `Cache`,
+/// `Code Cache`, `GPUCache` and `Service Worker/CacheStorage` directories of
+/// an app's data directory, or of one of its `Partitions`.
+pub fn electron_app(path: &Path) -> Option<String> {
+    let components: Vec<Cow<str>> = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_string_lossy(),
+            _ => Cow::Borrowed(""),
+        })
+        .collect();
+    let lowercase: Vec<String> = components.iter().map(|c| c.to_lowercase()).collect();
+    // Only directories count, not the file itself
+    let dirs = &lowercase[..lowercase.len().saturating_sub(1)];
+
+    APP_DATA_ROOTS.iter().find_map(|root| {
+        let below = dirs.windows(root.len()).position(|run| run == *root)? + root.len();
+        // Teams keeps its data in `%APPDATA%\Microsoft\Teams`
+        let app = if dirs.get(below).is_some_and(|dir| dir == "microsoft") { below + 1 } else { below };
+        let mut rest = dirs.get(app + 1..)?;
+        if let [partitions, _, inner @ ..] = rest
+            && partitions == "partitions"
+        {
+            rest = inner;
+        }
+
+        let cache = match rest {
+            [service_worker, worker_cache, ..] if service_worker == "service worker" => {
+                ELECTRON_WORKER_CACHES.contains(&worker_cache.as_str())
+            }
+            [cache, ..] => ELECTRON_CACHES.contains(&cache.as_str()),
+            [] => false,
+        };
+        if !cache {
+            return None;
+        }
+        match ELECTRON_APPS.iter().find(|(dir, _)| *dir == lowercase[app]) {
+            Some((_, name)) => Some(name.to_string()),
+            None => {
+                let app_dir: PathBuf = path.components().take(app + 1).collect();
+                is_chromium_data(app_dir).then(|| components[app].to_string())
+            }
+        }
+    })
+}
+
+/// Whether directories hold Chromium's `Local State`, each checked only once.
+static CHROMIUM_DATA: LazyLock<Mutex<HashMap<PathBuf, bool>>> = LazyLock::new(Mutex::default);
+
+fn is_chromium_data(dir: PathBuf) -> bool {
+    let mut known = CHROMIUM_DATA.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *known.entry(dir).or_insert_with_key(|dir| dir.join("Local State").is_file())
+}
+
+/// JetBrains IDEs by the start of their directory names, e.g. `PyCharm2024.1`;
+/// longer prefixes first, so `PyCharmCE` isn't taken for `PyCharm`.
+const JETBRAINS_PRODUCTS: &[(&str, &str)] = &[
+    ("intellijidea", "IntelliJ IDEA"),
+    ("ideaic", "IntelliJ IDEA CE"),
+    ("pycharmce", "PyCharm CE"),
+    ("pycharm", "PyCharm"),
+    ("webstorm", "WebStorm"),
+    ("clion", "CLion"),
+    ("goland", "GoLand"),
+    ("rider", "Rider"),
+    ("rubymine", "RubyMine"),
+    ("phpstorm", "PhpStorm"),
+    ("datagrip", "DataGrip"),
+    ("rustrover", "RustRover"),
+    ("androidstudio", "Android Studio"),
+];
+
+/// The parts of a JetBrains IDE's system directory that it rebuilds on its
+/// own; local history and logs live next to them and are left alone.
+const JETBRAINS_CACHES: &[&str] = &["caches", "index", "compile-server", "jcef_cache"];
+
+/// The parts of VS Code's data directory it fills again by itself.
+const VSCODE_CACHES: &[&str] = &["cacheddata", "cachedextensionvsixs", "cachedextensions", "cachedprofilesdata", "cachedconfigurations"];
+const VSCODE_DIRS: &[(&str, &str)] = &[("code", "VS Code"), ("code - insiders", "VS Code Insiders"), ("vscodium", "VSCodium")];
+
+/// Language servers' index caches, by the directory they keep under the
+/// user or project cache directory (`.cache`, `Caches`).
+const LANGUAGE_SERVERS: &[(&str, &str)] = &[
+    ("clangd", "clangd"),
+    ("gopls", "gopls"),
+    ("typescript", "TypeScript server"),
+    ("jdtls", "Eclipse JDT LS"),
+    ("pyright", "Pyright"),
+];
+
+/// The IDE, or `<IDE> <version>`, a JetBrains directory name stands for.
+fn jetbrains_product(dir: &str) -> Option<String> {
+    let lowercase = dir.trim_start_matches('.').to_lowercase();
+    let (prefix, name) = JETBRAINS_PRODUCTS.iter().find(|(prefix, _)| lowercase.starts_with(prefix))?;
+    let version = &dir.trim_start_matches('.')[prefix.len()..];
+    if version.is_empty() {
+        Some(name.to_string())
+    } else {
+        version.starts_with(|c: char| c.is_ascii_digit()).then(|| format!("{} {}", name, version))
+    }
+}
+
+/// The IDE or language server whose caches `path` is in: JetBrains caches
+/// and indexes (`~/.cache/JetBrains/<IDE>/caches`, or `system/caches` in the
+/// older per-IDE directories), VS Code's cached data, extensions and
+/// workspace storage, and language server indexes.
+pub fn ide_cache(path: &Path) -> Option<String> {
+    let components: Vec<Cow<str>> = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_string_lossy(),
+            _ => Cow::Borrowed(""),
+        })
+        .collect();
+    let lowercase: Vec<String> = components.iter().map(|c| c.to_lowercase()).collect();
+    let dirs = &lowercase[..lowercase.len().saturating_sub(1)];
+
+    for (i, window) in dirs.windows(3).enumerate() {
+        let [parent, dir, child] = window else { continue };
+        // `JetBrains/<IDE>/caches`, and `.<IDE>/system/caches` before 2020
+        let jetbrains = if parent == "jetbrains" {
+            JETBRAINS_CACHES.contains(&child.as_str()).then(|| jetbrains_product(&components[i + 1])).flatten()
+        } else if dir == "system" && parent.starts_with('.') {
+            JETBRAINS_CACHES.contains(&child.as_str()).then(|| jetbrains_product(&components[i])).flatten()
+        } else {
+            None
+        };
+        if jetbrains.is_some() {
+            return jetbrains;
+        }
+    }
+
+    for (i, dir) in dirs.iter().enumerate() {
+        let below = |offset: usize| dirs.get(i + offset).map(String::as_str);
+        if let Some((_, name)) = VSCODE_DIRS.iter().find(|(vscode, _)| vscode == dir)
+            && APP_DATA_ROOTS.iter().any(|root| i >= root.len() && dirs[i - root.len()..i] == **root)
+            && (below(1).is_some_and(|cache| VSCODE_CACHES.contains(&cache)) || (below(1) == Some("user") && below(2) == Some("workspacestorage")))
+        {
+            return Some(name.to_string());
+        }
+    }
+
+    dirs.windows(2).find_map(|pair| {
+        let cache_dir = pair[0] == ".cache" || pair[0] == "caches";
+        let server = LANGUAGE_SERVERS.iter().find(|(dir, _)| *dir == pair[1]).filter(|_| cache_dir);
+        // rust-analyzer keeps its own build output in `target/rust-analyzer`
+        let rust_analyzer = (pair[0] == "target" && pair[1] == "rust-analyzer").then_some("rust-analyzer");
+        server.map(|(_, name)| *name).or(rust_analyzer).map(str::to_string)
+    })
+}
+
+/// The app an application cache belongs to, where hica can tell.
+pub fn app_of(path: &Path) -> Option<String> {
+    electron_app(path).or_else(|| ide_cache(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_electron_caches_to_their_app() {
+        let app = |path: &str| electron_app(Path::new(path));
+        assert_eq!(app("/home/alice/.config/Slack/Cache/Cache_Data/f_000001").as_deref(), Some("Slack"));
+        assert_eq!(app("/home/alice/.config/Code/Code Cache/js/index").as_deref(), Some("VS Code"));
+        assert_eq!(app("/Users/alice/Library/Application Support/discord/GPUCache/data_0").as_deref(), Some("Discord"));
+        assert_eq!(
+            app("C:/Users/alice/AppData/Roaming/Microsoft/Teams/Service Worker/CacheStorage/a/b").as_deref(),
+            Some("Teams")
+        );
+        assert_eq!(app("/home/alice/.config/Slack/Partitions/team1/Cache/Cache_Data/f_01").as_deref(), Some("Slack"));
+        assert_eq!(app("/home/alice/.config/Notes/GPUCache/data_1"), None);
+
+        assert_eq!(app("/home/alice/.config/Slack/Local Storage/leveldb/000003.log"), None);
+        assert_eq!(app("/home/alice/.config/Slack/Service Worker/Database/000001.log"), None);
+        assert_eq!(app("/home/alice/.config/Cache/settings.json"), None);
+    }
+
+    #[test]
+    fn attributes_ide_caches_to_their_ide() {
+        let ide = |path: &str| ide_cache(Path::new(path));
+        assert_eq!(ide("/home/alice/.cache/JetBrains/PyCharm2024.1/caches/content.dat").as_deref(), Some("PyCharm 2024.1"));
+        assert_eq!(ide("/home/alice/.cache/JetBrains/IntelliJIdea2024.2/index/stubs/a").as_deref(), Some("IntelliJ IDEA 2024.2"));
+        assert_eq!(ide("/home/alice/.CLion2019.3/system/caches/names.dat").as_deref(), Some("CLion 2019.3"));
+        assert_eq!(ide("/home/alice/.cache/JetBrains/PyCharm2024.1/LocalHistory/changes.storageData"), None);
+        assert_eq!(ide("/home/alice/.config/Code/CachedExtensionVSIXs/ms-python.python-2024.1").as_deref(), Some("VS Code"));
+        assert_eq!(ide("/home/alice/.config/Code/User/workspaceStorage/1a2b/state.vscdb").as_deref(), Some("VS Code"));
+        assert_eq!(ide("/home/alice/.config/Code/User/settings.json"), None);
+        assert_eq!(ide("/home/alice/project/.cache/clangd/index/main.cpp.idx").as_deref(), Some("clangd"));
+        assert_eq!(ide("/home/alice/project/target/rust-analyzer/flycheck0/stdout").as_deref(), Some("rust-analyzer"));
+        assert_eq!(ide("/home/alice/code/system/caches/notes.txt"), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tokio::fs;
+use tokio::process::Command;
+
+/// Where compressed logs go when archiving instead of deleting.
+#[derive(Debug, Clone)]
+pub enum ArchiveTarget {
+    /// Next to the original, as `name.log.gz`
+    InPlace,
+    /// Into this directory, mirroring the layout below the scan root
+    Directory(PathBuf),
+}
+
+impl ArchiveTarget {
+    pub fn destination(&self, path: &Path, root: &Path) -> PathBuf {
+        let base = match self {
+            ArchiveTarget::InPlace => path.to_path_buf(),
+            ArchiveTarget::Directory(dir) => {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                let relative: PathBuf = relative
+                    .components()
+                    .filter(|c| matches!(c, std::path::Component::Normal(_)))
+                    .collect();
+                dir.join(relative)
+            }
+        };
+
+        let mut name = base.into_os_string();
+        name.push(".gz");
+        PathBuf::from(name)
+    }
+}
+
+/// Whether the file is already compressed, so archiving it again is pointless.
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["gz", "xz", "bz2", "zst", "zip"].contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Carries the original's permissions, timestamps, ownership and extended
+/// attributes over to its archive, so programs that check them accept the
+/// file once it's decompressed again. Ownership can only be kept with the
+/// privileges to change it, and is otherwise left as it is.
+fn preserve_metadata(original: &Path, archive: &Path) -> io::Result<()> {
+    let metadata = std::fs::metadata(crate::platform::long_path(original))?;
+    let file = std::fs::OpenOptions::new().write(true).open(archive)?;
+
+    let mut times = std::fs::FileTimes::new().set_modified(metadata.modified()?);
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    file.set_times(times)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let _ = std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()));
+    }
+    // After the owner, since changing it can clear the setuid and setgid bits
+    file.set_permissions(metadata.permissions())?;
+
+    crate::platform::copy_xattrs(original, archive)
+}
+
+/// Compresses `path` into `destination` with the system `gzip` and removes the
+/// original, returning the compressed size. The archive keeps the original's
+/// metadata. An existing destination is never overwritten.
+pub async fn gzip_file(path: &Path, destination: &Path) -> io::Result<u64> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let output = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(destination)?;
+
+    let status = Command::new("gzip")
+        .arg("-c")
+        .arg(path)
+        .stdout(Stdio::from(output))
+        .stderr(Stdio::null())
+        .status()
+        .await;
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            let _ = fs::remove_file(destination).await;
+            return Err(io::Error::other(format!("gzip failed ({})", status)));
+        }
+        Err(e) => {
+            let _ = fs::remove_file(destination).await;
+            return Err(e);
+        }
+    }
+
+    if let Err(e) = preserve_metadata(path, destination) {
+        let _ = fs::remove_file(destination).await;
+        return Err(e);
+    }
+
+    let compressed = fs::metadata(destination).await?.len();
+    fs::remove_file(crate::platform::long_path(path)).await?;
+    Ok(compressed)
+}
@@ -0,0 +1,228 @@
+//! `hica bench`: walks a tree with a few traversal strategies and times each,
+//! to see which way of walking suits a machine's disks and filesystems.
+//!
+//! The walks only list directories and read metadata, like the scan does,
+//! without classifying anything. They stay on the filesystem they start on.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+/// A way of walking a tree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// One directory after the other with async IO, as the scan walks
+    SequentialAsync,
+    /// Up to `jobs` directories listed at once by async tasks
+    ConcurrentTasks,
+    /// `jobs` threads listing directories with blocking IO
+    BlockingThreads,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::SequentialAsync, Strategy::ConcurrentTasks, Strategy::BlockingThreads];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::SequentialAsync => "sequential async",
+            Strategy::ConcurrentTasks => "concurrent tasks",
+            Strategy::BlockingThreads => "blocking threads",
+        }
+    }
+
+    /// Whether the strategy does anything with more than one job.
+    pub fn is_parallel(&self) -> bool {
+        *self != Strategy::SequentialAsync
+    }
+}
+
+/// What a walk came across.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Count {
+    pub directories: u64,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl Count {
+    fn add(&mut self, other: Count) {
+        self.directories += other.directories;
+        self.files += other.files;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Walks `root` once with `strategy` and `jobs`, returning what it found and
+/// how long it took.
+pub async fn run(root: &Path, strategy: Strategy, jobs: usize) -> (Count, Duration) {
+    let device = device_of(root);
+    let started = Instant::now();
+    let count = match strategy {
+        Strategy::SequentialAsync => sequential(root, device).await,
+        Strategy::ConcurrentTasks => concurrent(root, device, jobs).await,
+        Strategy::BlockingThreads => {
+            let root = root.to_path_buf();
+            tokio::task::spawn_blocking(move || threaded(&root, device, jobs))
+                .await
+                .expect("a benchmark thread panicked")
+        }
+    };
+    (count, started.elapsed())
+}
+
+#[cfg(unix)]
+fn device(metadata: &std::fs::Metadata) -> Option<u64> {
+    Some(std::os::unix::fs::MetadataExt::dev(metadata))
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+fn device_of(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().as_ref().and_then(device)
+}
+
+/// Sorts an entry into the subdirectories to walk or the files counted,
+/// leaving out symlinks and other filesystems.
+fn visit(path: PathBuf, metadata: &std::fs::Metadata, device_of_root: Option<u64>, subdirs: &mut Vec<PathBuf>, count: &mut Count) {
+    if metadata.is_dir() {
+        if device_of_root.is_none() || device(metadata) == device_of_root {
+            subdirs.push(path);
+        }
+    } else if metadata.is_file() {
+        count.files += 1;
+        count.bytes += metadata.len();
+    }
+}
+
+async fn list_async(dir: PathBuf, device: Option<u64>) -> (Vec<PathBuf>, Count) {
+    let mut subdirs = Vec::new();
+    let mut count = Count::default();
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return (subdirs, count);
+    };
+    count.directories += 1;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if let Ok(metadata) = tokio::fs::symlink_metadata(&path).await {
+            visit(path, &metadata, device, &mut subdirs, &mut count);
+        }
+    }
+    (subdirs, count)
+}
+
+fn list_blocking(dir: PathBuf, device: Option<u64>) -> (Vec<PathBuf>, Count) {
+    let mut subdirs = Vec::new();
+    let mut count = Count::default();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return (subdirs, count);
+    };
+    count.directories += 1;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            visit(path, &metadata, device, &mut subdirs, &mut count);
+        }
+    }
+    (subdirs, count)
+}
+
+async fn sequential(root: &Path, device: Option<u64>) -> Count {
+    let mut pending = vec![root.to_path_buf()];
+    let mut count = Count::default();
+    while let Some(dir) = pending.pop() {
+        let (subdirs, found) = list_async(dir, device).await;
+        pending.extend(subdirs);
+        count.add(found);
+    }
+    count
+}
+
+async fn concurrent(root: &Path, device: Option<u64>, jobs: usize) -> Count {
+    let mut pending = vec![root.to_path_buf()];
+    let mut running = FuturesUnordered::new();
+    let mut count = Count::default();
+    loop {
+        while running.len() < jobs
+            && let Some(dir) = pending.pop()
+        {
+            running.push(tokio::spawn(list_async(dir, device)));
+        }
+        match running.next().await {
+            Some(listed) => {
+                let (subdirs, found) = listed.expect("a benchmark task panicked");
+                pending.extend(subdirs);
+                count.add(found);
+            }
+            None => return count,
+        }
+    }
+}
+
+/// Directories waiting to be listed, and how many threads are listing one.
+struct Queue {
+    pending: Vec<PathBuf>,
+    busy: usize,
+    count: Count,
+}
+
+fn threaded(root: &Path, device: Option<u64>, jobs: usize) -> Count {
+    let queue = Mutex::new(Queue { pending: vec![root.to_path_buf()], busy: 0, count: Count::default() });
+    let changed = Condvar::new();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let dir = {
+                    let mut queue = queue.lock().unwrap();
+                    loop {
+                        if let Some(dir) = queue.pending.pop() {
+                            queue.busy += 1;
+                            break dir;
+                        }
+                        // Nothing left and nobody listing anything that could add more
+                        if queue.busy == 0 {
+                            changed.notify_all();
+                            return;
+                        }
+                        queue = changed.wait(queue).unwrap();
+                    }
+                };
+                let (subdirs, found) = list_blocking(dir, device);
+                let mut queue = queue.lock().unwrap();
+                queue.pending.extend(subdirs);
+                queue.busy -= 1;
+                queue.count.add(found);
+                changed.notify_all();
+            });
+        }
+    });
+    queue.into_inner().unwrap().count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn strategies_find_the_same_files() {
+        let root = std::env::temp_dir().join(format!("hica-bench-{}", std::process::id()));
+        for dir in ["a/b/c", "a/d", "e"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("file"), [0u8; 10]).unwrap();
+        }
+
+        let mut counts = Vec::new();
+        for strategy in Strategy::ALL {
+            counts.push(run(&root, strategy, 3).await.0);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(counts[0], Count { directories: 6, files: 3, bytes: 30 });
+        assert!(counts.iter().all(|count| *count == counts[0]));
+    }
+}
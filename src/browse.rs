@@ -0,0 +1,465 @@
+//! `hica detect --browse`: an ncdu-like browser of the cache found, to walk
+//! down the directories holding it, largest first, and queue what to delete.
+//! Nothing is deleted until the queue is confirmed, all at once, at the end.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use console::{Key, Term};
+
+use crate::classify::CacheCategory;
+use crate::i18n;
+use crate::paths;
+use crate::scan::CacheFile;
+use crate::size::format_size;
+use crate::tr;
+
+/// A directory of the tree being browsed, with the cache below it.
+#[derive(Default)]
+struct Dir {
+    size: u64,
+    children: BTreeMap<String, Dir>,
+    /// Indices of the cache files directly inside
+    files: Vec<usize>,
+}
+
+impl Dir {
+    fn insert(&mut self, dirs: &[String], index: usize, size: u64) {
+        self.size += size;
+        match dirs.split_first() {
+            Some((dir, rest)) => self.children.entry(dir.clone()).or_default().insert(rest, index, size),
+            None => self.files.push(index),
+        }
+    }
+
+    /// Indices of every cache file below.
+    fn all_files(&self) -> Vec<usize> {
+        let mut files = self.files.clone();
+        for child in self.children.values() {
+            files.extend(child.all_files());
+        }
+        files
+    }
+}
+
+/// A line of the listing: a subdirectory or a cache file.
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Dir(String),
+    File(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sort {
+    Size,
+    Name,
+}
+
+/// What the screen lists: the tree of the cache, or the deletion queue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    Tree,
+    Queue,
+}
+
+/// What browsing ends with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Continue,
+    Quit,
+    Delete,
+}
+
+/// How much of an entry is marked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Marked {
+    None,
+    Some,
+    All,
+}
+
+/// Width of the size bars.
+const BAR: usize = 10;
+
+pub struct Browser<'a> {
+    cache_files: &'a [CacheFile],
+    root: PathBuf,
+    tree: Dir,
+    /// Names of the directories entered, from the root down
+    path: Vec<String>,
+    /// The selected line at each level, the current one last
+    cursors: Vec<usize>,
+    sort: Sort,
+    marked: Vec<bool>,
+    view: View,
+    /// The selected line of the queue
+    queue_cursor: usize,
+    /// Whether the bottom line asks to delete the queue
+    confirming: bool,
+    /// Said on the bottom line until the next key
+    message: Option<String>,
+}
+
+impl<'a> Browser<'a> {
+    pub fn new(cache_files: &'a [CacheFile], root: &Path) -> Self {
+        let mut tree = Dir::default();
+        for (index, file) in cache_files.iter().enumerate() {
+            let parent = file.path.parent().unwrap_or(&file.path);
+            let relative = parent.strip_prefix(root).unwrap_or(parent);
+            let dirs: Vec<String> = relative
+                .components()
+                .map(|component| paths::escape(component.as_os_str()).into_owned())
+                .collect();
+            tree.insert(&dirs, index, file.size);
+        }
+        Browser {
+            cache_files,
+            root: root.to_path_buf(),
+            tree,
+            path: Vec::new(),
+            cursors: vec![0],
+            sort: Sort::Size,
+            marked: vec![false; cache_files.len()],
+            view: View::Tree,
+            queue_cursor: 0,
+            confirming: false,
+            message: None,
+        }
+    }
+
+    fn current(&self) -> &Dir {
+        self.path.iter().fold(&self.tree, |dir, name| &dir.children[name])
+    }
+
+    fn file_name(&self, index: usize) -> String {
+        self.cache_files[index].path.file_name().map(|name| paths::escape(name).into_owned()).unwrap_or_default()
+    }
+
+    /// Where a file lies below the root, as the queue lists it.
+    fn relative_name(&self, index: usize) -> String {
+        let path = &self.cache_files[index].path;
+        paths::display(path.strip_prefix(&self.root).unwrap_or(path)).to_string()
+    }
+
+    fn size_of(&self, entry: &Entry) -> u64 {
+        match entry {
+            Entry::Dir(name) => self.current().children[name].size,
+            Entry::File(index) => self.cache_files[*index].size,
+        }
+    }
+
+    fn name_of(&self, entry: &Entry) -> String {
+        match entry {
+            Entry::Dir(name) => format!("{}/", name),
+            Entry::File(index) if self.view == View::Queue => self.relative_name(*index),
+            Entry::File(index) => self.file_name(*index),
+        }
+    }
+
+    /// The lines of the current directory, or of the queue, in the chosen order.
+    fn entries(&self) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = match self.view {
+            View::Tree => {
+                let dir = self.current();
+                dir.children.keys().cloned().map(Entry::Dir).chain(dir.files.iter().copied().map(Entry::File)).collect()
+            }
+            View::Queue => (0..self.cache_files.len()).filter(|&index| self.marked[index]).map(Entry::File).collect(),
+        };
+        match self.sort {
+            Sort::Size => entries.sort_by_cached_key(|entry| (std::cmp::Reverse(self.size_of(entry)), self.name_of(entry))),
+            Sort::Name => entries.sort_by_cached_key(|entry| self.name_of(entry)),
+        }
+        entries
+    }
+
+    fn files_of(&self, entry: &Entry) -> Vec<usize> {
+        match entry {
+            Entry::Dir(name) => self.current().children[name].all_files(),
+            Entry::File(index) => vec![*index],
+        }
+    }
+
+    fn marked_state(&self, entry: &Entry) -> Marked {
+        let files = self.files_of(entry);
+        match files.iter().filter(|&&index| self.marked[index]).count() {
+            0 => Marked::None,
+            count if count == files.len() => Marked::All,
+            _ => Marked::Some,
+        }
+    }
+
+    /// Marks everything in the entry, or unmarks it if it's all marked already.
+    fn toggle(&mut self, entry: &Entry) {
+        let mark = self.marked_state(entry) != Marked::All;
+        for index in self.files_of(entry) {
+            self.marked[index] = mark;
+        }
+    }
+
+    /// Flips the mark of every file below the current directory.
+    fn invert(&mut self) {
+        for index in self.current().all_files() {
+            self.marked[index] = !self.marked[index];
+        }
+    }
+
+    /// The category most of an entry's cache is in.
+    fn category_of(&self, entry: &Entry) -> Option<CacheCategory> {
+        let mut sizes: HashMap<CacheCategory, u64> = HashMap::new();
+        for index in self.files_of(entry) {
+            *sizes.entry(self.cache_files[index].category).or_default() += self.cache_files[index].size;
+        }
+        sizes.into_iter().max_by_key(|&(category, size)| (size, std::cmp::Reverse(category as usize))).map(|(category, _)| category)
+    }
+
+    /// Marks every file of `category` anywhere in the cache, or unmarks them
+    /// if they're all marked already.
+    fn toggle_category(&mut self, category: CacheCategory) {
+        let files: Vec<usize> = (0..self.cache_files.len()).filter(|&index| self.cache_files[index].category == category).collect();
+        let mark = files.iter().any(|&index| !self.marked[index]);
+        for &index in &files {
+            self.marked[index] = mark;
+        }
+        let size = files.iter().map(|&index| self.cache_files[index].size).sum();
+        let key = if mark { "browse.category-marked" } else { "browse.category-unmarked" };
+        self.message = Some(tr!(key, files.len(), category.as_str(), format_size(size)));
+    }
+
+    /// The marked files and the space deleting them frees, counting hard
+    /// links to the same data once.
+    pub fn marked_totals(&self) -> (usize, u64) {
+        let mut linked = HashSet::new();
+        self.marked_files().fold((0, 0), |(count, size), file| {
+            let freed = if file.link_id.is_none_or(|id| linked.insert(id)) { file.size } else { 0 };
+            (count + 1, size + freed)
+        })
+    }
+
+    fn marked_files(&self) -> impl Iterator<Item = &CacheFile> {
+        self.cache_files.iter().zip(&self.marked).filter(|(_, marked)| **marked).map(|(file, _)| file)
+    }
+
+    /// Takes a key, returning whether browsing goes on or how it ends.
+    fn handle(&mut self, key: Key) -> Step {
+        self.message = None;
+        if self.confirming {
+            self.confirming = false;
+            return match key {
+                Key::Char(answer) if i18n::lang().is_yes(&answer.to_string()) => Step::Delete,
+                _ => Step::Continue,
+            };
+        }
+
+        let entries = self.entries();
+        let cursor = match self.view {
+            View::Tree => self.cursors.last_mut().expect("there's always a cursor"),
+            View::Queue => &mut self.queue_cursor,
+        };
+        match key {
+            Key::ArrowUp | Key::Char('k') => *cursor = cursor.saturating_sub(1),
+            Key::ArrowDown | Key::Char('j') => *cursor = (*cursor + 1).min(entries.len().saturating_sub(1)),
+            Key::PageUp => *cursor = cursor.saturating_sub(10),
+            Key::PageDown => *cursor = (*cursor + 10).min(entries.len().saturating_sub(1)),
+            Key::Home => *cursor = 0,
+            Key::End => *cursor = entries.len().saturating_sub(1),
+            Key::ArrowRight | Key::Enter | Key::Char('l') => {
+                if let Some(Entry::Dir(name)) = entries.get(*cursor) {
+                    self.path.push(name.clone());
+                    self.cursors.push(0);
+                }
+            }
+            Key::ArrowLeft | Key::Backspace | Key::Char('h') if self.view == View::Tree && self.path.pop().is_some() => {
+                self.cursors.pop();
+            }
+            Key::Char('s') => {
+                self.sort = if self.sort == Sort::Size { Sort::Name } else { Sort::Size };
+                *cursor = 0;
+            }
+            Key::Char(' ') => {
+                if let Some(entry) = entries.get(*cursor) {
+                    // Unmarking in the queue takes the line away, leaving the cursor on the next one
+                    let last = if self.view == View::Queue { entries.len().saturating_sub(2) } else { entries.len() - 1 };
+                    *cursor = if self.view == View::Queue { (*cursor).min(last) } else { (*cursor + 1).min(last) };
+                    self.toggle(entry);
+                }
+            }
+            Key::Char('i') if self.view == View::Tree => self.invert(),
+            Key::Char('c') => {
+                if let Some(category) = entries.get(*cursor).and_then(|entry| self.category_of(entry)) {
+                    self.toggle_category(category);
+                    self.queue_cursor = self.queue_cursor.min(self.marked_totals().0.saturating_sub(1));
+                }
+            }
+            Key::Tab | Key::Char('v') => {
+                self.view = if self.view == View::Tree { View::Queue } else { View::Tree };
+                self.queue_cursor = 0;
+            }
+            Key::Char('d') | Key::Char('x') if self.marked.contains(&true) => self.confirming = true,
+            Key::Char('q') | Key::Escape | Key::CtrlC => return Step::Quit,
+            _ => {}
+        }
+        Step::Continue
+    }
+
+    /// The screen, `height` lines of at most `width` columns.
+    fn render(&self, width: usize, height: usize) -> Vec<String> {
+        let entries = self.entries();
+        let cursor = match self.view {
+            View::Tree => *self.cursors.last().expect("there's always a cursor"),
+            View::Queue => self.queue_cursor,
+        };
+        let rows = height.saturating_sub(2).max(1);
+        let first = (cursor + 1).saturating_sub(rows);
+        let largest = entries.iter().map(|entry| self.size_of(entry)).max().unwrap_or(0).max(1);
+
+        let (count, size) = self.marked_totals();
+        let sort = if self.sort == Sort::Size { "size" } else { "name" };
+        let title = match self.view {
+            View::Tree => {
+                let dir = self.path.iter().fold(self.root.clone(), |dir, name| dir.join(name));
+                tr!("browse.title", paths::display(&dir), format_size(self.current().size), sort)
+            }
+            View::Queue => tr!("browse.queue-title", count, format_size(size), sort),
+        };
+        let mut lines = vec![console::truncate_str(&title, width, "…").bold().to_string()];
+
+        for (i, entry) in entries.iter().enumerate().skip(first).take(rows) {
+            let size = self.size_of(entry);
+            let filled = (size as u128 * BAR as u128 / largest as u128) as usize;
+            let mark = match self.marked_state(entry) {
+                Marked::None => ' ',
+                Marked::Some => '+',
+                Marked::All => '*',
+            };
+            let line = format!("{} {:>10} [{}{}] {}", mark, format_size(size), "#".repeat(filled), " ".repeat(BAR - filled), self.name_of(entry));
+            let line = console::truncate_str(&line, width, "…").into_owned();
+            lines.push(if i == cursor { line.reversed().to_string() } else { line });
+        }
+        lines.resize(height.saturating_sub(1), String::new());
+
+        let footer = match &self.message {
+            _ if self.confirming => tr!("browse.confirm", count, format_size(size)),
+            Some(message) => message.clone(),
+            None => tr!("browse.footer", count, format_size(size)),
+        };
+        let footer = console::truncate_str(&footer, width, "…");
+        lines.push(match &self.message {
+            _ if self.confirming => footer.red().bold().to_string(),
+            Some(_) => footer.yellow().to_string(),
+            None => footer.dimmed().to_string(),
+        });
+        lines
+    }
+}
+
+/// Browses `cache_files` below `root` in the terminal until the user is done,
+/// returning the queue they confirmed deleting, or nothing if they quit.
+pub fn run(cache_files: &[CacheFile], root: &Path) -> io::Result<Vec<CacheFile>> {
+    let term = Term::stdout();
+    let mut browser = Browser::new(cache_files, root);
+    // The alternate screen leaves the scan's output in place below it
+    print!("\x1b[?1049h");
+    term.hide_cursor()?;
+    let result = (|| loop {
+        let (height, width) = term.size();
+        let mut screen = String::new();
+        for line in browser.render(width as usize, height as usize) {
+            screen.push_str(&line);
+            screen.push_str("\x1b[K\r\n");
+        }
+        screen.truncate(screen.len() - 2);
+        print!("\x1b[H{}", screen);
+        io::stdout().flush()?;
+        match browser.handle(term.read_key()?) {
+            Step::Continue => {}
+            step => return Ok(step),
+        }
+    })();
+    term.show_cursor()?;
+    print!("\x1b[?1049l");
+    io::stdout().flush()?;
+    result.map(|step| match step {
+        Step::Delete => browser.marked_files().cloned().collect(),
+        _ => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{CacheCategory, Risk};
+
+    fn file(path: &str, size: u64) -> CacheFile {
+        CacheFile {
+            path: PathBuf::from(path),
+            size,
+            category: CacheCategory::Other,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        }
+    }
+
+    #[test]
+    fn drills_down_and_marks() {
+        let files = [file("/h/.cache/a/1.tmp", 10), file("/h/.cache/a/2.tmp", 5), file("/h/.cache/b.tmp", 20), file("/h/x.log", 1)];
+        let mut browser = Browser::new(&files, Path::new("/h"));
+
+        assert_eq!(browser.entries(), [Entry::Dir(".cache".into()), Entry::File(3)]);
+        browser.handle(Key::Enter);
+        assert_eq!(browser.entries(), [Entry::File(2), Entry::Dir("a".into())]);
+        browser.handle(Key::Char('s'));
+        assert_eq!(browser.entries(), [Entry::Dir("a".into()), Entry::File(2)]);
+        browser.handle(Key::Char('s'));
+
+        // Marking the directory marks both files in it
+        browser.handle(Key::ArrowDown);
+        browser.handle(Key::Char(' '));
+        assert_eq!(browser.marked_totals(), (2, 35 - 20));
+        browser.handle(Key::ArrowLeft);
+        assert_eq!(browser.marked_state(&Entry::Dir(".cache".into())), Marked::Some);
+        browser.handle(Key::Char(' '));
+        assert_eq!(browser.marked_totals(), (3, 35));
+
+        let screen = browser.render(60, 6);
+        assert_eq!(screen.len(), 6);
+        assert!(screen[1].contains("* ") && screen[1].contains(".cache/"));
+        assert_eq!(browser.handle(Key::Char('q')), Step::Quit);
+    }
+
+    #[test]
+    fn builds_and_confirms_a_queue() {
+        let mut files = [file("/h/a/1.log", 10), file("/h/a/2.tmp", 5), file("/h/b/3.log", 20), file("/h/b/4.tmp", 1)];
+        files[0].category = CacheCategory::Log;
+        files[2].category = CacheCategory::Log;
+        let mut browser = Browser::new(&files, Path::new("/h"));
+
+        // Selecting by category reaches into every directory
+        assert_eq!(browser.entries(), [Entry::Dir("b".into()), Entry::Dir("a".into())]);
+        browser.handle(Key::Char('c'));
+        assert_eq!(browser.marked, [true, false, true, false]);
+        assert!(browser.render(80, 5)[4].contains("2 Log"));
+
+        browser.handle(Key::Enter);
+        browser.handle(Key::Char('i'));
+        assert_eq!(browser.marked, [true, false, false, true]);
+        assert_eq!(browser.marked_totals(), (2, 11));
+
+        // The queue lists what's marked, and unmarking there drops it
+        browser.handle(Key::Char('v'));
+        assert_eq!(browser.entries(), [Entry::File(0), Entry::File(3)]);
+        assert_eq!(browser.name_of(&Entry::File(3)), "b/4.tmp");
+        browser.handle(Key::Char(' '));
+        assert_eq!(browser.entries(), [Entry::File(3)]);
+
+        // Anything but yes backs out of deleting
+        browser.handle(Key::Char('d'));
+        assert!(browser.render(80, 5)[4].contains("Delete the 1 queued files"));
+        assert_eq!(browser.handle(Key::Char('n')), Step::Continue);
+        browser.handle(Key::Char('d'));
+        assert_eq!(browser.handle(Key::Char('y')), Step::Delete);
+    }
+}
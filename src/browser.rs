@@ -0,0 +1,317 @@
+//! Browser profiles and their caches. Browsers keep caches next to data that
+//! must never be touched (history, bookmarks, cookies, local storage), so only
+//! the cache directories of real profile layouts count as browser cache.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+
+/// Browsers whose profile caches hica knows how to attribute.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Firefox,
+    Edge,
+    Safari,
+}
+
+impl Browser {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Browser::Chrome => "Chrome",
+            Browser::Chromium => "Chromium",
+            Browser::Firefox => "Firefox",
+            Browser::Edge => "Edge",
+            Browser::Safari => "Safari",
+        }
+    }
+
+    /// Process names the browser runs under on the supported platforms.
+    fn process_names(&self) -> &'static [&'static str] {
+        match self {
+            Browser::Chrome => &["chrome", "google-chrome", "google chrome"],
+            Browser::Chromium => &["chromium", "chromium-browser", "chromium-browse"],
+            Browser::Firefox => &["firefox", "firefox-bin", "firefox-esr"],
+            Browser::Edge => &["msedge", "microsoft-edge", "microsoft edge"],
+            Browser::Safari => &["safari"],
+        }
+    }
+
+    /// Works out which browser's profile a path belongs to, if any.
+    pub fn from_path(path: &Path) -> Option<Browser> {
+        locate(path).map(|location| location.browser)
+    }
+
+    pub fn is_running(&self, processes: &HashSet<String>) -> bool {
+        self.process_names().iter().any(|name| processes.contains(*name))
+    }
+}
+
+/// Whether a browser directory holds profile data, with the caches below it,
+/// or nothing but caches.
+#[derive(Clone, Copy, PartialEq)]
+enum RootKind {
+    Data,
+    Cache,
+}
+
+/// Where a browser keeps its profiles on one platform, as a run of lowercased
+/// path components.
+struct Root {
+    browser: Browser,
+    components: &'static [&'static str],
+    kind: RootKind,
+    /// Where Firefox's `profiles.ini` is, relative to the directory above
+    /// `components`, for naming profiles.
+    profiles_ini: &'static [&'static str],
+}
+
+const fn root(browser: Browser, components: &'static [&'static str], kind: RootKind) -> Root {
+    Root { browser, components, kind, profiles_ini: &[] }
+}
+
+const fn firefox(components: &'static [&'static str], kind: RootKind, profiles_ini: &'static [&'static str]) -> Root {
+    Root { browser: Browser::Firefox, components, kind, profiles_ini }
+}
+
+/// Longer runs first, so `.cache/mozilla/firefox` isn't taken for the data
+/// directory `mozilla/firefox`.
+const ROOTS: &[Root] = &[
+    // Linux
+    root(Browser::Chrome, &[".config", "google-chrome"], RootKind::Data),
+    root(Browser::Chrome, &[".cache", "google-chrome"], RootKind::Cache),
+    root(Browser::Chromium, &[".config", "chromium"], RootKind::Data),
+    root(Browser::Chromium, &[".cache", "chromium"], RootKind::Cache),
+    root(Browser::Edge, &[".config", "microsoft-edge"], RootKind::Data),
+    root(Browser::Edge, &[".cache", "microsoft-edge"], RootKind::Cache),
+    firefox(&[".cache", "mozilla", "firefox"], RootKind::Cache, &[".mozilla", "firefox", "profiles.ini"]),
+    firefox(&[".mozilla", "firefox"], RootKind::Data, &[".mozilla", "firefox", "profiles.ini"]),
+    // macOS
+    root(Browser::Chrome, &["application support", "google", "chrome"], RootKind::Data),
+    root(Browser::Chrome, &["caches", "google", "chrome"], RootKind::Cache),
+    root(Browser::Chromium, &["application support", "chromium"], RootKind::Data),
+    root(Browser::Chromium, &["caches", "chromium"], RootKind::Cache),
+    root(Browser::Edge, &["application support", "microsoft edge"], RootKind::Data),
+    root(Browser::Edge, &["caches", "microsoft edge"], RootKind::Cache),
+    firefox(&["caches", "firefox"], RootKind::Cache, &["application support", "firefox", "profiles.ini"]),
+    firefox(&["application support", "firefox"], RootKind::Data, &["application support", "firefox", "profiles.ini"]),
+    root(Browser::Safari, &["caches", "com.apple.safari"], RootKind::Cache),
+    // Windows, where `Local` holds the caches and `Roaming` Firefox's profiles
+    root(Browser::Chrome, &["google", "chrome", "user data"], RootKind::Data),
+    root(Browser::Chromium, &["chromium", "user data"], RootKind::Data),
+    root(Browser::Edge, &["microsoft", "edge", "user data"], RootKind::Data),
+    firefox(&["local", "mozilla", "firefox"], RootKind::Cache, &["roaming", "mozilla", "firefox", "profiles.ini"]),
+    firefox(&["roaming", "mozilla", "firefox"], RootKind::Data, &["roaming", "mozilla", "firefox", "profiles.ini"]),
+];
+
+/// Cache directories of a Chromium profile; `Service Worker` only counts with
+/// its `CacheStorage` and `ScriptCache`.
+const CHROMIUM_CACHES: &[&str] = &["cache", "code cache", "gpucache", "dawncache", "dawngraphitecache", "dawnwebgpucache"];
+const CHROMIUM_WORKER_CACHES: &[&str] = &["cachestorage", "scriptcache"];
+/// Caches shared by all profiles, right in the user data directory.
+const CHROMIUM_SHARED_CACHES: &[&str] = &["shadercache", "grshadercache", "graphitedawncache"];
+/// Cache directories of a Firefox profile that lives with its data.
+const FIREFOX_CACHES: &[&str] = &["cache2", "startupcache", "jumplistcache", "offlinecache", "thumbnails"];
+
+/// Where a path is in a browser's profiles.
+#[derive(Debug, PartialEq)]
+pub struct BrowserLocation {
+    pub browser: Browser,
+    /// The profile's name, or `None` for caches all profiles share
+    pub profile: Option<String>,
+    /// Whether the path is in one of the profile's caches, rather than its
+    /// data
+    pub cache: bool,
+}
+
+impl BrowserLocation {
+    /// E.g. "Chrome profile `Default`".
+    pub fn describe(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("{} profile `{}`", self.browser.as_str(), profile),
+            None => self.browser.as_str().to_string(),
+        }
+    }
+}
+
+/// Finds the browser profile `path` is in, and whether it's in the profile's
+/// caches. Everything else under a browser's data directory is profile data.
+pub fn locate(path: &Path) -> Option<BrowserLocation> {
+    let components: Vec<Component> = path.components().collect();
+    let lowercase: Vec<String> = components
+        .iter()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_string_lossy().to_lowercase(),
+            _ => String::new(),
+        })
+        .collect();
+
+    ROOTS.iter().find_map(|root| {
+        let start = lowercase.windows(root.components.len()).position(|run| run == root.components)?;
+        let below = start + root.components.len();
+        if below >= lowercase.len() {
+            return None;
+        }
+        // Only what's in a directory below the root belongs to a profile
+        let dirs = &lowercase[below..lowercase.len() - 1];
+        let name = |index: usize| components[below + index].as_os_str().to_string_lossy().into_owned();
+
+        match root.browser {
+            // Crash reports are left to the crash dump rules
+            _ if dirs.first().is_some_and(|dir| dir == "crashpad" || dir == "crash reports") => None,
+            Browser::Safari => Some(BrowserLocation { browser: root.browser, profile: None, cache: true }),
+            Browser::Firefox => {
+                let offset = usize::from(dirs.first().is_some_and(|dir| dir == "profiles"));
+                let profile_dir = dirs.get(offset)?;
+                let cache = match root.kind {
+                    RootKind::Cache => true,
+                    RootKind::Data => dirs.get(offset + 1).is_some_and(|dir| FIREFOX_CACHES.contains(&dir.as_str())),
+                };
+                let above: PathBuf = components[..start].iter().collect();
+                let profile = firefox_profile_name(&root.profiles_ini.iter().fold(above, |path, c| path.join(c)), profile_dir)
+                    .unwrap_or_else(|| name(offset));
+                Some(BrowserLocation { browser: root.browser, profile: Some(profile), cache })
+            }
+            _ => {
+                let first = dirs.first()?;
+                if root.kind == RootKind::Data && CHROMIUM_SHARED_CACHES.contains(&first.as_str()) {
+                    return Some(BrowserLocation { browser: root.browser, profile: None, cache: true });
+                }
+                let cache = match root.kind {
+                    RootKind::Cache => true,
+                    RootKind::Data => match dirs.get(1).map(String::as_str) {
+                        Some("service worker") => dirs.get(2).is_some_and(|dir| CHROMIUM_WORKER_CACHES.contains(&dir.as_str())),
+                        Some(dir) => CHROMIUM_CACHES.contains(&dir),
+                        None => false,
+                    },
+                };
+                Some(BrowserLocation { browser: root.browser, profile: Some(name(0)), cache })
+            }
+        }
+    })
+}
+
+/// Firefox profile names by the lowercased name of their directory, per
+/// `profiles.ini`, so each is only read once.
+static FIREFOX_PROFILES: LazyLock<Mutex<HashMap<PathBuf, HashMap<String, String>>>> = LazyLock::new(Mutex::default);
+
+fn firefox_profile_name(profiles_ini: &Path, profile_dir: &str) -> Option<String> {
+    let mut profiles = FIREFOX_PROFILES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let names = profiles.entry(profiles_ini.to_path_buf()).or_insert_with(|| {
+        std::fs::read_to_string(profiles_ini).map(|ini| parse_profiles_ini(&ini)).unwrap_or_default()
+    });
+    names.get(profile_dir).cloned()
+}
+
+/// Maps the lowercased directory names of the profiles in a `profiles.ini`
+/// to the profiles' names.
+fn parse_profiles_ini(ini: &str) -> HashMap<String, String> {
+    let mut profiles = HashMap::new();
+    let mut in_profile = false;
+    let (mut name, mut dir) = (None, None);
+    let mut finish = |name: &mut Option<String>, dir: &mut Option<String>| {
+        if let (Some(name), Some(dir)) = (name.take(), dir.take()) {
+            profiles.insert(dir, name);
+        }
+    };
+
+    for line in ini.lines().map(str::trim) {
+        if line.starts_with('[') {
+            finish(&mut name, &mut dir);
+            in_profile = line.starts_with("[Profile");
+        } else if in_profile && let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "Name" => name = Some(value.trim().to_string()),
+                "Path" => dir = value.trim().rsplit(['/', '\\']).next().map(str::to_lowercase),
+                _ => {}
+            }
+        }
+    }
+    finish(&mut name, &mut dir);
+    profiles
+}
+
+/// Lowercased names of the running processes, without any `.exe` suffix.
+/// Empty if the process list can't be read.
+pub fn running_processes() -> HashSet<String> {
+    let names: Vec<String> = if cfg!(target_os = "linux") {
+        std::fs::read_dir("/proc")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("comm")).ok())
+            .collect()
+    } else if cfg!(windows) {
+        // CSV rows like "chrome.exe","1234",...
+        command_lines("tasklist", &["/fo", "csv", "/nh"])
+            .into_iter()
+            .filter_map(|line| line.split(',').next().map(|name| name.trim_matches('"').to_string()))
+            .collect()
+    } else {
+        command_lines("ps", &["-A", "-o", "comm="])
+    };
+
+    names
+        .into_iter()
+        .map(|name| {
+            let name = name.trim();
+            // `ps` on macOS reports full executable paths
+            let name = name.rsplit('/').next().unwrap_or(name).to_lowercase();
+            name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+        })
+        .collect()
+}
+
+fn command_lines(program: &str, args: &[&str]) -> Vec<String> {
+    match Command::new(program).args(args).output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_profile_caches_are_cache() {
+        let location = |path: &str| locate(Path::new(path));
+
+        let cache = location("/home/alice/.config/google-chrome/Profile 1/Code Cache/js/index").unwrap();
+        assert_eq!(cache, BrowserLocation { browser: Browser::Chrome, profile: Some("Profile 1".to_string()), cache: true });
+        assert!(location("/home/alice/.cache/chromium/Default/Cache/Cache_Data/f_000001").unwrap().cache);
+        assert!(location("C:/Users/alice/AppData/Local/Microsoft/Edge/User Data/Default/Service Worker/CacheStorage/a/b").unwrap().cache);
+
+        for data in [
+            "/home/alice/.config/google-chrome/Default/History",
+            "/home/alice/.config/google-chrome/Default/Bookmarks",
+            "/home/alice/.config/google-chrome/Default/Local Storage/leveldb/000003.log",
+            "/home/alice/.config/google-chrome/Default/Service Worker/Database/000001.log",
+            "/home/alice/.mozilla/firefox/abcd.default-release/places.sqlite",
+            "/home/alice/.mozilla/firefox/abcd.default-release/cookies.sqlite",
+        ] {
+            assert!(!location(data).unwrap().cache, "{}", data);
+        }
+
+        let firefox = location("/Users/alice/Library/Caches/Firefox/Profiles/abcd.default-release/cache2/entries/0A1B").unwrap();
+        assert_eq!(firefox.browser, Browser::Firefox);
+        assert!(firefox.cache);
+        assert_eq!(firefox.profile.as_deref(), Some("abcd.default-release"));
+
+        assert_eq!(location("/home/alice/chrome-extension/notes.txt"), None);
+        assert_eq!(location("/home/alice/.config/google-chrome/Crashpad/completed/a1b2.dmp"), None);
+    }
+
+    #[test]
+    fn names_firefox_profiles() {
+        let ini = "[Install4F96D1932A9F858E]\nDefault=Profiles/abcd.default-release\n\n\
+                   [Profile1]\nName=default\nIsRelative=1\nPath=Profiles/wxyz.default\n\n\
+                   [Profile0]\nName=Work\nIsRelative=1\nPath=Profiles/abcd.default-release\nDefault=1\n";
+        let profiles = parse_profiles_ini(ini);
+        assert_eq!(profiles.get("abcd.default-release").map(String::as_str), Some("Work"));
+        assert_eq!(profiles.get("wxyz.default").map(String::as_str), Some("default"));
+        assert_eq!(profiles.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+//! How far a scan got, saved now and then so an interrupted scan of a huge
+//! tree can pick up where it left off instead of starting over.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::lock::stable_hash;
+use crate::paths::user_cache_dir;
+use crate::report::write_atomically;
+
+/// The directories a scan has finished, and the cache-looking files it found
+/// in them, all relative to the scan root.
+#[derive(Debug, Default, PartialEq)]
+pub struct Checkpoint {
+    /// The canonical scan root, so a checkpoint is only resumed for the same tree
+    pub root: PathBuf,
+    /// Finished directories, none inside another
+    done: HashSet<PathBuf>,
+    files: Vec<PathBuf>,
+}
+
+impl Checkpoint {
+    pub fn new(root: PathBuf) -> Checkpoint {
+        Checkpoint { root, ..Checkpoint::default() }
+    }
+
+    /// Where the checkpoint of a scan of `root` is kept:
+    /// `hica/scan-checkpoint-<hash of the canonical root>` in the user cache
+    /// directory, so scans of different trees running at once keep their own.
+    pub fn default_path(root: &Path) -> Option<PathBuf> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let hash = stable_hash(root.to_string_lossy().as_bytes());
+        user_cache_dir().map(|dir| dir.join("hica").join(format!("scan-checkpoint-{:016x}", hash)))
+    }
+
+    pub fn load(file: &Path) -> Option<Checkpoint> {
+        Checkpoint::decode(&std::fs::read(file).ok()?)
+    }
+
+    pub fn save(&self, file: &Path) -> std::io::Result<()> {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_atomically(file, self.encode())
+    }
+
+    pub fn is_done(&self, dir: &Path) -> bool {
+        self.done.contains(dir)
+    }
+
+    /// Records that `dir` has been walked completely, with the files found
+    /// directly in it. Its subdirectories were recorded as they finished.
+    ///
+    /// Paths that aren't valid Unicode can't be saved, so a directory with
+    /// such a name, or holding such a file, is left to be walked again.
+    /// Returns whether `dir` was recorded.
+    pub fn complete(&mut self, dir: PathBuf, files: Vec<PathBuf>) -> bool {
+        if dir.to_str().is_none() || files.iter().any(|file| file.to_str().is_none()) {
+            return false;
+        }
+        self.done.retain(|done| !done.starts_with(&dir));
+        self.done.insert(dir);
+        self.files.extend(files);
+        true
+    }
+
+    pub fn done_count(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// NUL-separated records: the root, then `d` and `f` followed by a path
+    /// for each finished directory and found file.
+    fn encode(&self) -> String {
+        let mut records = vec![self.root.to_string_lossy().into_owned()];
+        records.extend(self.done.iter().map(|dir| format!("d{}", dir.display())));
+        records.extend(self.files.iter().map(|file| format!("f{}", file.display())));
+        records.join("\0")
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Checkpoint> {
+        let text = std::str::from_utf8(bytes).ok()?;
+        let mut records = text.split('\0');
+        let mut checkpoint = Checkpoint::new(PathBuf::from(records.next().filter(|root| !root.is_empty())?));
+
+        for record in records {
+            match record.split_at_checked(1)? {
+                ("d", dir) => checkpoint.done.insert(PathBuf::from(dir)),
+                ("f", file) => {
+                    checkpoint.files.push(PathBuf::from(file));
+                    true
+                }
+                _ => return None,
+            };
+        }
+        Some(checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completing_a_directory_covers_its_subdirectories() {
+        let mut checkpoint = Checkpoint::new(PathBuf::from("/data"));
+        checkpoint.complete(PathBuf::from("a/b"), vec![PathBuf::from("a/b/x.log")]);
+        checkpoint.complete(PathBuf::from("a/c"), vec![]);
+        checkpoint.complete(PathBuf::from("a"), vec![PathBuf::from("a/y.tmp")]);
+
+        assert_eq!(checkpoint.done_count(), 1);
+        assert!(checkpoint.is_done(Path::new("a")));
+        assert!(!checkpoint.is_done(Path::new("a/b")));
+        assert_eq!(checkpoint.files().len(), 2);
+    }
+
+    #[test]
+    fn round_trips_through_encoding() {
+        let mut checkpoint = Checkpoint::new(PathBuf::from("/data"));
+        checkpoint.complete(PathBuf::from("logs"), vec![PathBuf::from("logs/old file.log")]);
+
+        assert_eq!(Checkpoint::decode(checkpoint.encode().as_bytes()), Some(checkpoint));
+        assert_eq!(Checkpoint::decode(b""), None);
+        assert_eq!(Checkpoint::decode(b"/data\0xbogus"), None);
+    }
+
+    #[test]
+    fn keeps_a_checkpoint_per_root() {
+        let (data, logs) = (Checkpoint::default_path(Path::new("/data")), Checkpoint::default_path(Path::new("/var/log")));
+        if data.is_some() {
+            assert_ne!(data, logs);
+            assert_eq!(data, Checkpoint::default_path(Path::new("/data")));
+        }
+    }
+}
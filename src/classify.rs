@@ -0,0 +1,631 @@
+use std::ffi::OsStr;
+use std::path::{Component, Path};
+
+use clap::ValueEnum;
+
+use crate::apps;
+use crate::browser;
+use crate::compiler::compiler_cache;
+use crate::jvm::jvm_cache;
+use crate::package::package_cache;
+use crate::paths::escape;
+use crate::plugins;
+use crate::python::python_cache;
+
+/// The kind of a cache file. On the command line and in JSON, a category goes
+/// by its lowercased name.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum CacheCategory {
+    Browser,
+    System,
+    Application,
+    Log,
+    Temporary,
+    Backup,
+    Container,
+    Package,
+    Thumbnail,
+    Build,
+    CrashDump,
+    Trash,
+    Other,
+}
+
+impl CacheCategory {
+    pub const ALL: [CacheCategory; 13] = [
+        CacheCategory::Browser,
+        CacheCategory::System,
+        CacheCategory::Application,
+        CacheCategory::Log,
+        CacheCategory::Temporary,
+        CacheCategory::Backup,
+        CacheCategory::Container,
+        CacheCategory::Package,
+        CacheCategory::Thumbnail,
+        CacheCategory::Build,
+        CacheCategory::CrashDump,
+        CacheCategory::Trash,
+        CacheCategory::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheCategory::Browser => "Browser",
+            CacheCategory::System => "System",
+            CacheCategory::Application => "Application",
+            CacheCategory::Log => "Log",
+            CacheCategory::Temporary => "Temporary",
+            CacheCategory::Backup => "Backup",
+            CacheCategory::Container => "Container",
+            CacheCategory::Package => "Package",
+            CacheCategory::Thumbnail => "Thumbnail",
+            CacheCategory::Build => "Build",
+            CacheCategory::CrashDump => "CrashDump",
+            CacheCategory::Trash => "Trash",
+            CacheCategory::Other => "Other",
+        }
+    }
+}
+
+/// How sure hica is that a file is cache, by how it matched. On the command
+/// line and in JSON, a level goes by its lowercased name.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, PartialOrd, Ord, ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum Risk {
+    /// In a location a known tool, browser or app keeps its cache in
+    Safe,
+    /// By a cache extension or the name of its directory
+    Likely,
+    /// By a word in its file name only
+    Risky,
+}
+
+impl Risk {
+    pub const ALL: [Risk; 3] = [Risk::Safe, Risk::Likely, Risk::Risky];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Risk::Safe => "safe",
+            Risk::Likely => "likely",
+            Risk::Risky => "risky",
+        }
+    }
+}
+
+/// Splits a lowercased name into its alphanumeric words, so that patterns only
+/// match on word boundaries ("debug.log" contains "log", "catalog.rs" doesn't).
+fn words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty())
+}
+
+fn has_word(name: &str, patterns: &[&str]) -> bool {
+    words(name).any(|word| patterns.contains(&word))
+}
+
+/// A file or directory name, lowercased for matching. Every pattern is ASCII,
+/// so names that aren't valid Unicode still match on their other bytes: the
+/// invalid ones become `�`, which splits words like punctuation does.
+pub fn lowercase_name(name: &OsStr) -> String {
+    name.to_string_lossy().to_lowercase()
+}
+
+/// Returns the lowercased extension of a file name, ignoring leading dots so
+/// that ".cache" is a name rather than an extension.
+fn extension(file_name: &str) -> Option<&str> {
+    let trimmed = file_name.trim_start_matches('.');
+    trimmed.rfind('.').map(|i| &trimmed[i + 1..])
+}
+
+/// Lowercased names of the directories above the file, nearest last.
+fn dir_components(path: &Path) -> Vec<String> {
+    let parent = match path.parent() {
+        Some(parent) => parent,
+        None => return Vec::new(),
+    };
+
+    parent
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(lowercase_name(name)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The first of `patterns` that is a word of one of the directories, as a reason.
+fn dir_word(dirs: &[String], patterns: &[&str]) -> Option<String> {
+    dirs.iter()
+        .find(|dir| has_word(dir, patterns))
+        .map(|dir| format!("directory `{}`", dir))
+}
+
+fn name_word(file_name: &str, patterns: &[&str]) -> Option<String> {
+    words(file_name)
+        .find(|word| patterns.contains(word))
+        .map(|word| format!("word `{}` in the file name", word))
+}
+
+fn ext_in(ext: Option<&str>, patterns: &[&str]) -> Option<String> {
+    ext.filter(|ext| patterns.contains(ext))
+        .map(|ext| format!("extension `.{}`", ext))
+}
+
+/// The trash or recycle bin the file is in: the freedesktop trash
+/// (`~/.local/share/Trash`, `.Trash-<uid>` on other volumes), macOS's `.Trash`
+/// and `.Trashes`, and Windows' `$Recycle.Bin`.
+fn trash_dir(dirs: &[String]) -> Option<String> {
+    let named = dirs.iter().find(|dir| {
+        matches!(dir.as_str(), ".trash" | ".trashes" | "$recycle.bin")
+            || dir.strip_prefix(".trash-").is_some_and(|uid| !uid.is_empty() && uid.bytes().all(|b| b.is_ascii_digit()))
+    });
+    named.map(|dir| format!("directory `{}`", dir)).or_else(|| {
+        dirs.windows(2)
+            .any(|pair| pair[0] == "share" && pair[1] == "trash")
+            .then(|| "directory `share/trash`".to_string())
+    })
+}
+
+/// The storage roots of container engines: `/var/lib/docker` and a rootless
+/// `~/.local/share/docker`, `containers/storage` under `/var/lib` or
+/// `~/.local/share` for Podman and friends, Docker Desktop's data on macOS and
+/// `ProgramData\Docker` on Windows. Any other directory named `docker` is
+/// just a directory.
+fn container_storage(dirs: &[String]) -> Option<String> {
+    const ROOTS: [&[&str]; 6] = [
+        &["var", "lib", "docker"],
+        &[".local", "share", "docker"],
+        &["lib", "containers", "storage"],
+        &["share", "containers", "storage"],
+        &["containers", "com.docker.docker", "data"],
+        &["programdata", "docker"],
+    ];
+    ROOTS.iter()
+        .find(|root| dirs.windows(root.len()).any(|window| window == **root))
+        .map(|root| format!("directory `{}`", root.join("/")))
+}
+
+/// Thumbnails generated by file managers: `~/.cache/thumbnails` (and the
+/// older `~/.thumbnails`), and the `Thumbs.db`/`thumbcache_*.db` of Windows.
+fn thumbnail(dirs: &[String], file_name: &str) -> Option<String> {
+    dirs.iter()
+        .find(|dir| *dir == ".thumbnails")
+        .map(|dir| format!("directory `{}`", dir))
+        .or_else(|| {
+            dirs.windows(2)
+                .any(|pair| pair[0] == ".cache" && pair[1] == "thumbnails")
+                .then(|| "directory `.cache/thumbnails`".to_string())
+        })
+        .or_else(|| {
+            (file_name == "thumbs.db" || (file_name.starts_with("thumbcache_") && file_name.ends_with(".db")))
+                .then(|| format!("thumbnail database `{}`", file_name))
+        })
+}
+
+/// Whether the file starts the way a dump does: an ELF core file, or a
+/// Windows minidump or full memory dump.
+fn has_dump_header(path: &Path) -> bool {
+    let mut head = [0u8; 18];
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    if std::io::Read::read_exact(&mut file, &mut head).is_err() {
+        return false;
+    }
+    if head.starts_with(b"MDMP") || head.starts_with(b"PAGEDU64") || head.starts_with(b"PAGEDUMP") {
+        return true;
+    }
+    // `e_type` is ET_CORE, in the byte order `EI_DATA` gives
+    let e_type = match head[5] {
+        1 => u16::from_le_bytes([head[16], head[17]]),
+        2 => u16::from_be_bytes([head[16], head[17]]),
+        _ => return false,
+    };
+    head.starts_with(b"\x7fELF") && e_type == 4
+}
+
+/// Anything in the directories crash handlers write to: systemd-coredump,
+/// apport's `/var/crash`, macOS's `DiagnosticReports` and Windows'
+/// `CrashDumps`. Elsewhere, core files (`core`, `core.<pid>`, and
+/// systemd-coredump's `core.<program>...`) and minidumps only count when
+/// their contents say they're dumps, as the names are common enough.
+fn crash_dump(path: &Path, dirs: &[String], file_name: &str, ext: Option<&str>) -> Option<String> {
+    let crash_dir = dirs.iter()
+        .find(|dir| matches!(dir.as_str(), "diagnosticreports" | "crashdumps"))
+        .map(|dir| format!("directory `{}`", dir))
+        .or_else(|| {
+            let crash_dir = |pair: &[String]| {
+                (pair[0] == "systemd" && pair[1] == "coredump") || (pair[0] == "var" && pair[1] == "crash")
+            };
+            dirs.windows(2)
+                .find(|pair| crash_dir(pair))
+                .map(|pair| format!("directory `{}/{}`", pair[0], pair[1]))
+        });
+    if crash_dir.is_some() {
+        return crash_dir;
+    }
+
+    let number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let core = file_name == "core"
+        || file_name.strip_prefix("core.").is_some_and(|rest| {
+            let fields: Vec<&str> = rest.split('.').collect();
+            // `core.<pid>`, or `core.<program>.<uid>.<boot id>.<pid>.<time>`
+            (fields.len() == 1 && number(fields[0])) || (fields.len() >= 5 && number(fields[1]) && number(fields[3]))
+        });
+    let named = ext_in(ext, &["dmp", "mdmp", "hdmp"]).or_else(|| core.then(|| "core file".to_string()))?;
+    has_dump_header(path).then_some(named)
+}
+
+/// The directories builds put their output in.
+fn build_dir(dirs: &[String]) -> Option<&str> {
+    dirs.iter()
+        .map(String::as_str)
+        .find(|dir| matches!(*dir, "build" | "_build" | "builddir" | "target" | "obj" | "out" | ".libs" | "bazel-out") || dir.starts_with("cmake-build-"))
+}
+
+/// Object files and loose Python bytecode in a build directory. Anywhere else
+/// they may be all there is of a library or program.
+fn build_object(dirs: &[String], ext: Option<&str>) -> Option<String> {
+    let ext = ext.filter(|ext| matches!(*ext, "o" | "pyc" | "pyo"))?;
+    build_dir(dirs).map(|dir| format!("`.{}` file in directory `{}`", ext, dir))
+}
+
+/// What a build regenerates: object files, and the caches of build tools.
+fn build_output(path: &Path, dirs: &[String], ext: Option<&str>) -> Option<String> {
+    build_object(dirs, ext).or_else(|| build_cache(path))
+}
+
+/// The caches of build tools: compiler caches, and Python bytecode and the
+/// caches of Python's test and lint tools.
+fn build_cache(path: &Path) -> Option<String> {
+    compiler_cache(path).map(|tool| format!("{} cache", tool)).or_else(|| {
+        python_cache(path)
+            .filter(|cache| cache.category == CacheCategory::Build)
+            .map(|cache| format!("{} cache", cache.tool))
+    })
+}
+
+/// Downloads kept by package managers: system, Python and JVM ones.
+fn package_download(path: &Path) -> Option<String> {
+    let tool = python_cache(path)
+        .filter(|cache| cache.category == CacheCategory::Package)
+        .map(|cache| cache.tool)
+        .or_else(|| jvm_cache(path).filter(|cache| cache.category == CacheCategory::Package).map(|cache| cache.tool));
+    package_cache(path)
+        .map(|cache| cache.manager)
+        .or(tool)
+        .map(|tool| format!("{} download cache", tool))
+}
+
+/// Every category check in the order they're tried, each with the reason it
+/// matched if it did. The first match decides the category.
+pub fn category_checks(path: &Path) -> Vec<(CacheCategory, Option<String>)> {
+    let file_name = match path.file_name() {
+        Some(name) => lowercase_name(name),
+        None => return Vec::new(),
+    };
+    let ext = extension(&file_name);
+    let dirs = dir_components(path);
+
+    // Whatever it was before, a file in the trash is trash now
+    let trash = trash_dir(&dirs);
+
+    // Container engine storage, which only the engine itself can clean safely
+    let container = container_storage(&dirs);
+
+    // Package manager downloads
+    let package = package_download(path);
+
+    let crash = crash_dump(path, &dirs, &file_name, ext);
+
+    let thumbnails = thumbnail(&dirs, &file_name);
+
+    let browser = browser::locate(path)
+        .filter(|location| location.cache)
+        .map(|location| format!("{} cache", location.describe()));
+
+    let electron = apps::electron_app(path).map(|app| format!("{}'s Electron cache", app));
+
+    let ide = apps::ide_cache(path).map(|ide| format!("{} cache", ide));
+
+    let build = build_output(path, &dirs, ext);
+
+    let log_patterns = ["log", "logs"];
+    let log = ext_in(ext, &["log"])
+        .or_else(|| name_word(&file_name, &log_patterns))
+        .or_else(|| dir_word(&dirs, &log_patterns));
+
+    let temp_patterns = ["tmp", "temp"];
+    let temporary = ext_in(ext, &["tmp", "temp", "swp", "swo", "crdownload", "part"])
+        .or_else(|| name_word(&file_name, &temp_patterns))
+        .or_else(|| dir_word(&dirs, &temp_patterns));
+
+    let backup_patterns = ["backup", "backups"];
+    let backup = ext_in(ext, &["bak", "backup", "old"])
+        .or_else(|| file_name.ends_with('~').then(|| "trailing `~`".to_string()))
+        .or_else(|| name_word(&file_name, &backup_patterns))
+        .or_else(|| dir_word(&dirs, &backup_patterns));
+
+    let system = dir_word(&dirs, &["system", "cache", "caches"])
+        .or_else(|| ext_in(ext, &["cache"]))
+        .or_else(|| name_word(&file_name, &["cache"]));
+
+    let application = dir_word(&dirs, &["app", "apps", "application", "applications"]);
+
+    vec![
+        (CacheCategory::Trash, trash),
+        (CacheCategory::Container, container),
+        (CacheCategory::Package, package),
+        (CacheCategory::CrashDump, crash),
+        (CacheCategory::Thumbnail, thumbnails),
+        (CacheCategory::Browser, browser),
+        (CacheCategory::Application, electron),
+        (CacheCategory::Application, ide),
+        (CacheCategory::Build, build),
+        (CacheCategory::Log, log),
+        (CacheCategory::Temporary, temporary),
+        (CacheCategory::Backup, backup),
+        (CacheCategory::System, system),
+        (CacheCategory::Application, application),
+        (CacheCategory::Other, Some("fallback".to_string())),
+    ]
+}
+
+/// The category of a cache file and the reason it was chosen.
+pub fn explain_category(path: &Path) -> Option<(CacheCategory, String)> {
+    if let Some(plugins::Verdict::Cache { category, reason, .. }) = plugins::classify(path) {
+        return Some((category, reason));
+    }
+    category_checks(path)
+        .into_iter()
+        .find_map(|(category, reason)| reason.map(|reason| (category, reason)))
+}
+
+pub fn classify_cache_file(path: &Path) -> Option<CacheCategory> {
+    explain_category(path).map(|(category, _)| category)
+}
+
+/// Why `path` counts as a cache file, and how sure that makes hica, if it
+/// does.
+pub fn cache_file_match(path: &Path) -> Option<(String, Risk)> {
+    let cache_extensions = [
+        "cache", "tmp", "temp", "swp", "swo", "bak",
+        "log", "old", "backup", "crdownload", "part",
+    ];
+
+    let cache_directories = [
+        "cache", "caches", ".cache", "temp", ".temp", "tmp", ".tmp",
+        "logs", ".logs", "backup", ".backup", "old", ".old",
+    ];
+
+    // Plugins know the applications they were written for better than any pattern
+    match plugins::classify(path) {
+        Some(plugins::Verdict::Cache { risk, reason, .. }) => return Some((reason, risk)),
+        Some(plugins::Verdict::Keep { .. }) => return None,
+        None => {}
+    }
+
+    let file_name = lowercase_name(path.file_name()?);
+    let ext = extension(&file_name);
+    let dirs = dir_components(path);
+
+    // Browser profiles are all cache or all data, whatever the file names
+    if let Some(location) = browser::locate(path) {
+        return location.cache.then(|| (format!("{} cache", location.describe()), Risk::Safe));
+    }
+
+    // Trash, thumbnails, crash dumps, and the caches of build tools,
+    // Electron apps and IDEs, whatever their names
+    if let Some(reason) = trash_dir(&dirs)
+        .or_else(|| thumbnail(&dirs, &file_name))
+        .or_else(|| crash_dump(path, &dirs, &file_name, ext))
+        .or_else(|| build_cache(path))
+        .or_else(|| apps::electron_app(path).map(|app| format!("{}'s Electron cache", app)))
+        .or_else(|| apps::ide_cache(path).map(|ide| format!("{} cache", ide)))
+    {
+        return Some((reason, Risk::Safe));
+    }
+
+    // Package manager download caches hold arbitrary archive names, next to
+    // the lock file the package manager itself relies on
+    if let Some(reason) = package_download(path) {
+        return (file_name != "lock").then_some((reason, Risk::Safe));
+    }
+
+    if file_name == ".ds_store" {
+        return Some(("Finder metadata".to_string(), Risk::Safe));
+    }
+
+    // Check by extension
+    if let Some(reason) = ext_in(ext, &cache_extensions).or_else(|| build_object(&dirs, ext)) {
+        return Some((reason, Risk::Likely));
+    }
+
+    // Check by directory name
+    let parent_name = path.parent().and_then(|parent| parent.file_name());
+    if let Some(name) = parent_name
+        && cache_directories.contains(&lowercase_name(name).as_str())
+    {
+        return Some((format!("parent directory `{}`", escape(name)), Risk::Likely));
+    }
+
+    if file_name.starts_with("~$") {
+        return Some(("Office lock file".to_string(), Risk::Likely));
+    }
+
+    // Check for common cache file patterns, as whole words of the file name
+    name_word(&file_name, &["cache", "temp", "tmp", "log", "backup", "crdownload"]).map(|reason| (reason, Risk::Risky))
+}
+
+/// Why `path` counts as a cache file, if it does.
+pub fn cache_file_reason(path: &Path) -> Option<String> {
+    cache_file_match(path).map(|(reason, _)| reason)
+}
+
+pub fn is_cache_file(path: &Path) -> bool {
+    cache_file_reason(path).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substrings_inside_words_do_not_match() {
+        let path = Path::new("/home/alice/blogposts/catalog.rs");
+        assert!(!is_cache_file(path));
+        assert_eq!(classify_cache_file(path), Some(CacheCategory::Other));
+
+        assert!(!is_cache_file(Path::new("/home/alice/templates/contemporary.html")));
+        assert!(!is_cache_file(Path::new("/home/alice/parts/department.txt")));
+        assert!(!is_cache_file(Path::new("/home/alice/src/oldest_friend.md")));
+    }
+
+    #[test]
+    fn classification_ignores_partial_component_matches() {
+        // "knowledge" only contains "edge" and "applesauce" only contains "app"
+        let path = Path::new("/srv/applesauce/knowledge/notes.cache");
+        assert_eq!(classify_cache_file(path), Some(CacheCategory::System));
+
+        let path = Path::new("/home/alice/dialogs/catalog/index.tmp");
+        assert_eq!(classify_cache_file(path), Some(CacheCategory::Temporary));
+    }
+
+    #[test]
+    fn detects_by_extension() {
+        assert!(is_cache_file(Path::new("/var/tmp/build.TMP")));
+        assert!(is_cache_file(Path::new("/home/alice/debug.log")));
+        assert!(is_cache_file(Path::new("/home/alice/movie.mkv.crdownload")));
+    }
+
+    #[test]
+    fn detects_by_parent_directory() {
+        let path = Path::new("/home/alice/.config/App/Cache/data_0");
+        assert!(is_cache_file(path));
+        assert_eq!(classify_cache_file(path), Some(CacheCategory::System));
+    }
+
+    #[test]
+    fn detects_by_whole_words_in_file_name() {
+        assert!(is_cache_file(Path::new("/home/alice/server-log.txt")));
+        assert!(is_cache_file(Path::new("/home/alice/thumb_cache.db")));
+        assert!(is_cache_file(Path::new("/home/alice/~$report.docx")));
+        assert!(is_cache_file(Path::new("/Users/alice/.DS_Store")));
+    }
+
+    #[test]
+    fn classifies_by_category() {
+        let browser = Path::new("/home/alice/.cache/google-chrome/Default/Cache/f_000001");
+        assert_eq!(classify_cache_file(browser), Some(CacheCategory::Browser));
+
+        let log = Path::new("/var/log/syslog.1");
+        assert_eq!(classify_cache_file(log), Some(CacheCategory::Log));
+
+        let backup = Path::new("/home/alice/notes.txt.bak");
+        assert_eq!(classify_cache_file(backup), Some(CacheCategory::Backup));
+
+        let package = Path::new("/var/cache/apt/archives/curl_8.5.0-2_amd64.deb");
+        assert!(is_cache_file(package));
+        assert_eq!(classify_cache_file(package), Some(CacheCategory::Package));
+        assert!(!is_cache_file(Path::new("/var/cache/apt/archives/lock")));
+
+        let container = Path::new("/home/alice/.local/share/containers/storage/overlay/l/cache.tmp");
+        assert_eq!(classify_cache_file(container), Some(CacheCategory::Container));
+        assert_eq!(classify_cache_file(Path::new("/var/lib/docker/overlay2/abc/diff/tmp/x.tmp")), Some(CacheCategory::Container));
+        assert_ne!(classify_cache_file(Path::new("/home/alice/projects/app/docker/build.log")), Some(CacheCategory::Container));
+    }
+
+    #[test]
+    fn classifies_trash_thumbnails_crash_dumps_and_build_output() {
+        let trash = Path::new("/home/alice/.local/share/Trash/files/report.log");
+        assert!(is_cache_file(trash));
+        assert_eq!(classify_cache_file(trash), Some(CacheCategory::Trash));
+        assert_eq!(classify_cache_file(Path::new("/media/usb/.Trash-1000/files/photo.jpg")), Some(CacheCategory::Trash));
+        assert_eq!(classify_cache_file(Path::new("/Users/alice/.Trash/notes.txt")), Some(CacheCategory::Trash));
+        assert_eq!(classify_cache_file(Path::new("C:/$Recycle.Bin/S-1-5-21/$RABC123.docx")), Some(CacheCategory::Trash));
+        assert!(!is_cache_file(Path::new("/home/alice/trash/notes.txt")));
+
+        let thumbnail = Path::new("/home/alice/.cache/thumbnails/normal/0a1b2c.png");
+        assert!(is_cache_file(thumbnail));
+        assert_eq!(classify_cache_file(thumbnail), Some(CacheCategory::Thumbnail));
+        assert_eq!(classify_cache_file(Path::new("/home/alice/Pictures/Thumbs.db")), Some(CacheCategory::Thumbnail));
+
+        let dir = std::env::temp_dir().join(format!("hica-dumps-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut elf_core = b"\x7fELF\x02\x01\x01".to_vec();
+        elf_core.extend([0; 9]);
+        elf_core.extend([4, 0]);
+        let dumps: [(&str, &[u8]); 5] = [
+            ("core", &elf_core),
+            ("core.4242", &elf_core),
+            ("app.dmp", b"MDMP\x93\xa7\0\0\0\0\0\0\0\0\0\0\0\0"),
+            ("core.1", b"not a core file, just named like one"),
+            ("notes.dmp", b"a database dump someone wants to keep"),
+        ];
+        for (name, contents) in dumps {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        let categories: Vec<Option<CacheCategory>> = dumps.iter().map(|(name, _)| classify_cache_file(&dir.join(name))).collect();
+        let cache: Vec<bool> = dumps.iter().map(|(name, _)| is_cache_file(&dir.join(name))).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(&categories[..3], [Some(CacheCategory::CrashDump); 3]);
+        assert_eq!(cache, [true, true, true, false, false]);
+        assert_ne!(categories[3], Some(CacheCategory::CrashDump));
+        assert_ne!(categories[4], Some(CacheCategory::CrashDump));
+        assert!(!is_cache_file(Path::new("/home/alice/src/core.rs")));
+        assert!(!is_cache_file(Path::new("/home/alice/site/core.min.js")));
+        for dump in [
+            "/var/lib/systemd/coredump/core.bash.1000.3f2a.4242.1760486400000000.zst",
+            "/Users/alice/Library/Logs/DiagnosticReports/Safari-2025-10-15-093012.ips",
+            "/var/crash/_usr_bin_python3.12.1000.crash",
+            "C:/Users/alice/AppData/Local/CrashDumps/app.exe.4242.dmp",
+        ] {
+            assert_eq!(classify_cache_file(Path::new(dump)), Some(CacheCategory::CrashDump), "{}", dump);
+        }
+
+        let bytecode = Path::new("/home/alice/project/__pycache__/main.cpython-312.pyc");
+        assert!(is_cache_file(bytecode));
+        assert_eq!(classify_cache_file(bytecode), Some(CacheCategory::Build));
+        assert_eq!(classify_cache_file(Path::new("/home/alice/project/build/main.o")), Some(CacheCategory::Build));
+        assert_eq!(classify_cache_file(Path::new("/home/alice/project/cmake-build-debug/CMakeFiles/app.dir/main.cpp.o")), Some(CacheCategory::Build));
+        assert!(!is_cache_file(Path::new("/home/alice/vendor/libfoo/foo.o")));
+        assert!(!is_cache_file(Path::new("/opt/app/lib/module.pyc")));
+    }
+
+    #[test]
+    fn explains_matches() {
+        let path = Path::new("/home/alice/.config/App/Cache/data_0");
+        assert_eq!(cache_file_reason(path).as_deref(), Some("parent directory `Cache`"));
+        assert_eq!(
+            explain_category(path),
+            Some((CacheCategory::System, "directory `cache`".to_string()))
+        );
+
+        let path = Path::new("/home/alice/build.log");
+        assert_eq!(cache_file_reason(path).as_deref(), Some("extension `.log`"));
+        assert_eq!(explain_category(path).unwrap().0, CacheCategory::Log);
+    }
+
+    #[test]
+    fn rates_matches_by_how_specific_they_are() {
+        let risk = |path: &str| cache_file_match(Path::new(path)).map(|(_, risk)| risk);
+        assert_eq!(risk("/home/alice/.cache/pip/http-v2/a/b/c"), Some(Risk::Safe));
+        assert_eq!(risk("/home/alice/.cache/thumbnails/large/abc.png"), Some(Risk::Safe));
+        assert_eq!(risk("/home/alice/project/debug.log"), Some(Risk::Likely));
+        assert_eq!(risk("/home/alice/.config/App/Cache/data_0"), Some(Risk::Likely));
+        assert_eq!(risk("/home/alice/notes/cache-plan.txt"), Some(Risk::Risky));
+        assert_eq!(risk("/home/alice/notes/plan.txt"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classifies_names_that_are_not_unicode() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = |bytes: &[u8]| Path::new(OsStr::from_bytes(bytes)).to_path_buf();
+        assert!(is_cache_file(&path(b"/home/alice/build-\xff.log")));
+        assert!(is_cache_file(&path(b"/home/alice/\xfe\xff/tmp/data")));
+        assert_eq!(classify_cache_file(&path(b"/home/alice/logs/\xff\xfe.txt")), Some(CacheCategory::Log));
+        assert!(!is_cache_file(&path(b"/home/alice/notes/\xffplan.txt")));
+    }
+}
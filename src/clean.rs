@@ -0,0 +1,532 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use colored::Colorize;
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use tokio::fs;
+
+use crate::tr;
+use crate::archive::{gzip_file, is_compressed, ArchiveTarget};
+use crate::classify::CacheCategory;
+use crate::errors::{Operation, PathError};
+use crate::interrupt;
+use crate::pause;
+use crate::platform::{locked_reason, long_path, shared_bytes};
+use crate::progress::{self, ProgressEvent, ProgressEvents};
+use crate::protect::ProtectedPaths;
+use crate::size::{format_size, format_size_with_color};
+use crate::scan::{link_count, CacheFile};
+
+/// Caps on how much a single run may delete, so a bad rule can't wipe a disk.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DeleteLimits {
+    pub max_size: Option<u64>,
+    pub max_count: Option<u64>,
+    /// No more files are started once this passes.
+    pub deadline: Option<Instant>,
+}
+
+impl DeleteLimits {
+    fn allows(&self, count: u64, size: u64) -> bool {
+        self.max_count.is_none_or(|max| count <= max) && self.max_size.is_none_or(|max| size <= max)
+    }
+}
+
+/// How deletions failing with transient errors, like a file another program
+/// briefly holds open, are retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after that.
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { retries: 3, delay: Duration::from_millis(100) }
+    }
+}
+
+/// Shell commands run around a cleanup, such as stopping the service that
+/// holds the logs about to go and starting it again afterwards.
+#[derive(Debug, Default, Clone)]
+pub struct CleanHooks {
+    /// Run before anything is deleted; if it fails, nothing is.
+    pub pre: Option<String>,
+    /// Run once the deletions are done, whether or not they all succeeded.
+    pub post: Option<String>,
+}
+
+impl CleanHooks {
+    /// Runs the pre-clean hook, if there is one, before `files` files or
+    /// locations totaling `bytes` go. `root` is the scan root, where there
+    /// is one. Fails with the message to show if the hook does.
+    pub async fn before(&self, root: Option<&Path>, files: usize, bytes: u64) -> Result<(), String> {
+        let Some(hook) = &self.pre else {
+            return Ok(());
+        };
+        let mut vars = vec![("HICA_FILES", files.to_string()), ("HICA_BYTES", bytes.to_string())];
+        vars.extend(root.map(|root| ("HICA_ROOT", root.display().to_string())));
+        run_hook(hook, &vars).await.map_err(|e| tr!("hook.pre-failed", hook, e))
+    }
+
+    /// Runs the post-clean hook, if there is one, warning if it fails.
+    pub async fn after(&self, root: Option<&Path>, deleted: u64, freed: u64, failed: usize) {
+        let Some(hook) = &self.post else {
+            return;
+        };
+        let mut vars = vec![("HICA_DELETED", deleted.to_string()), ("HICA_FREED", freed.to_string()), ("HICA_FAILED", failed.to_string())];
+        vars.extend(root.map(|root| ("HICA_ROOT", root.display().to_string())));
+        if let Err(e) = run_hook(hook, &vars).await {
+            eprintln!("{} {}", "[Warning!]".red(), tr!("hook.post-failed", hook, e));
+        }
+    }
+}
+
+/// Runs `command` in the shell with `vars` set, its output going to stderr so
+/// it doesn't mix with reports printed to stdout.
+async fn run_hook(command: &str, vars: &[(&str, String)]) -> Result<(), String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = tokio::process::Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = tokio::process::Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command).envs(vars.iter().map(|(name, value)| (name, value))).stdin(std::process::Stdio::null()).stdout(io::stderr());
+    match shell.status().await {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(status.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Whether an error is likely to go away on its own, so retrying makes sense.
+fn is_transient(error: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)) {
+        return true;
+    }
+    matches!(
+        error.kind(),
+        io::ErrorKind::ResourceBusy
+            | io::ErrorKind::ExecutableFileBusy
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+    )
+}
+
+/// Removes the file, retrying transient failures with exponential backoff.
+async fn remove_with_retries(path: &Path, retry: RetryPolicy) -> io::Result<()> {
+    let path = long_path(path);
+    let mut delay = retry.delay;
+    for _ in 0..retry.retries {
+        match fs::remove_file(&path).await {
+            Err(e) if is_transient(&e) => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    fs::remove_file(&path).await
+}
+
+pub struct CleanOptions {
+    /// The scan root, which archived logs are laid out relative to.
+    pub root: PathBuf,
+    pub limits: DeleteLimits,
+    /// Compress Log-category files instead of deleting them.
+    pub archive: Option<ArchiveTarget>,
+    /// Remove directories left empty by the cleanup, up to (not including) the root.
+    pub prune_empty_dirs: bool,
+    pub retry: RetryPolicy,
+    pub hooks: CleanHooks,
+    /// Print nothing, for callers that report the summary themselves.
+    pub quiet: bool,
+}
+
+/// What a cleanup did, for reporting it beyond the terminal.
+#[derive(Debug, Default)]
+pub struct CleanSummary {
+    pub deleted_count: u64,
+    pub deleted_size: u64,
+    /// The files deleted, for recording exactly what went.
+    pub deleted: Vec<CacheFile>,
+    /// One per file that couldn't be deleted or archived.
+    pub errors: Vec<PathError>,
+    /// Whether the deadline passed before every file was processed.
+    pub out_of_time: bool,
+}
+
+/// How many files are deleted or archived at once.
+const CONCURRENCY: usize = 32;
+
+enum Action {
+    Delete,
+    Archive(PathBuf),
+}
+
+enum Outcome {
+    /// Deleted, freeing this many bytes (none for one of several hard links),
+    /// with this many shared with clones or snapshots and still on disk and
+    /// the file's link count just before
+    Deleted { freed: u64, cloned: u64, links: u64 },
+    /// Archived, saving this many bytes
+    Archived(u64),
+    Failed { error: PathError, transient: bool },
+}
+
+async fn process(file: &CacheFile, action: &Action, retry: RetryPolicy) -> Outcome {
+    match action {
+        Action::Archive(destination) => match gzip_file(&file.path, destination).await {
+            Ok(compressed) => Outcome::Archived(file.size.saturating_sub(compressed)),
+            Err(e) => Outcome::Failed {
+                error: PathError::new(Operation::Archive, &file.path, &e),
+                transient: is_transient(&e),
+            },
+        },
+        Action::Delete => {
+            // Removing one of several hard links doesn't free the data
+            let links = fs::metadata(long_path(&file.path)).await.map_or(1, |m| link_count(&m));
+            let shared = links > 1;
+            // Neither does the part of it that clones or snapshots share
+            let cloned = if shared { 0 } else { shared_bytes(&long_path(&file.path)).unwrap_or(0).min(file.size) };
+            match remove_with_retries(&file.path, retry).await {
+                Ok(_) => Outcome::Deleted { freed: if shared { 0 } else { file.size - cloned }, cloned, links },
+                Err(e) => {
+                    let mut error = PathError::new(Operation::Delete, &file.path, &e);
+                    if let Some(reason) = locked_reason(&file.path) {
+                        error.message = format!("{} ({})", error.message, reason);
+                    }
+                    Outcome::Failed { error, transient: is_transient(&e) }
+                }
+            }
+        }
+    }
+}
+
+/// The hard links to one file's data that a run deletes, whose space is only
+/// freed once the last link to it is gone.
+#[derive(Default)]
+struct LinkGroup {
+    /// Links in the run not processed yet
+    pending: usize,
+    removed: u64,
+    /// The most links the file was seen with, before any of these went
+    links: u64,
+}
+
+fn create_progress_bar(len: u64) -> ProgressBar {
+    let pb = ProgressBar::new(len);
+    pb.set_style(ProgressStyle::with_template("{spinner:.red} [{elapsed_precise}] [{bar:40.red/blue}] {pos}/{len} files ({per_sec:0}, ETA {eta}), {msg} freed")
+        .unwrap()
+        .progress_chars("=> "));
+    pb
+}
+
+/// Deletes (or archives) the cache files, between the pre- and post-clean
+/// hooks. Fails without touching anything if the pre-clean hook does.
+pub async fn delete_cache_files(cache_files: Vec<CacheFile>, protected: &ProtectedPaths, options: &CleanOptions) -> Result<CleanSummary, String> {
+    let mut skipped_count = 0;
+    let mut planned_count = 0;
+    let mut planned_size = 0;
+    let mut jobs = Vec::new();
+    
+    // Decide everything up front, so the limits hold however the deletions interleave
+    let mut files = cache_files.into_iter();
+    while let Some(file) = files.next() {
+        if protected.is_protected(&file.path) {
+            skipped_count += 1;
+            continue;
+        }
+        
+        let action = match &options.archive {
+            Some(_) if file.category == CacheCategory::Log && is_compressed(&file.path) => continue,
+            Some(target) if file.category == CacheCategory::Log => Action::Archive(target.destination(&file.path, &options.root)),
+            _ => Action::Delete,
+        };
+        
+        if !options.limits.allows(planned_count + 1, planned_size + file.size) {
+            let remaining: Vec<CacheFile> = std::iter::once(file)
+                .chain(files)
+                .filter(|f| !protected.is_protected(&f.path))
+                .collect();
+            if !options.quiet {
+                println!("\n{} {}",
+                    "[Stopped!]".yellow(),
+                    tr!("delete.limit", remaining.len().to_string().cyan(), format_size_with_color(remaining.iter().map(|f| f.size).sum()))
+                );
+            }
+            break;
+        }
+        planned_count += 1;
+        planned_size += file.size;
+        jobs.push((file, action));
+    }
+    
+    if !jobs.is_empty() {
+        options.hooks.before(Some(&options.root), jobs.len(), jobs.iter().map(|(file, _)| file.size).sum()).await?;
+    }
+    
+    if !options.quiet {
+        println!("\n{} {}", "🗑️".red(), tr!("delete.start"));
+    }
+    
+    let mut permanent = Vec::new();
+    let mut transient = Vec::new();
+    let mut deleted_count = 0;
+    let mut deleted_size = 0;
+    let mut deleted = Vec::new();
+    let mut shared_count = 0;
+    let mut cloned_count = 0;
+    let mut cloned_size = 0;
+    let mut archived_count = 0;
+    let mut archived_saved = 0;
+    let mut emptied_dirs = BTreeSet::new();
+    
+    let mut link_groups: HashMap<(u64, u64), LinkGroup> = HashMap::new();
+    for (file, action) in &jobs {
+        if let (Some(id), Action::Delete) = (file.link_id, action) {
+            link_groups.entry(id).or_default().pending += 1;
+        }
+    }
+    
+    let total = jobs.len() as u64;
+    let pb = if progress::shows_bar() && !options.quiet { create_progress_bar(total) } else { ProgressBar::hidden() };
+    let mut events = ProgressEvents::default();
+    pb.set_message(format_size(0));
+    // On Ctrl-C, no more files are started, but those in flight are finished
+    let _graceful = interrupt::graceful();
+    let mut results = stream::iter(jobs)
+        .take_while(|_| {
+            let out_of_time = options.limits.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            std::future::ready(!interrupt::interrupted() && !out_of_time)
+        })
+        .map(|(file, action)| async move {
+            pause::wait_while_paused().await;
+            let linked = file.link_id.filter(|_| matches!(action, Action::Delete));
+            let outcome = process(&file, &action, options.retry).await;
+            (file, linked, outcome)
+        })
+        .buffer_unordered(CONCURRENCY);
+    
+    while let Some((file, linked, outcome)) = results.next().await {
+        let mut group = linked.and_then(|id| link_groups.get_mut(&id));
+        match outcome {
+            // The links' data is credited once, when the last of them is done
+            Outcome::Deleted { links, .. } if group.is_some() => {
+                deleted_count += 1;
+                if let Some(group) = group.as_mut() {
+                    group.removed += 1;
+                    group.links = group.links.max(links);
+                }
+                if let Some(parent) = file.path.parent() {
+                    emptied_dirs.insert(parent.to_path_buf());
+                }
+                deleted.push(file.clone());
+            }
+            Outcome::Deleted { freed, cloned, .. } => {
+                deleted_count += 1;
+                deleted_size += freed;
+                if cloned > 0 {
+                    cloned_count += 1;
+                    cloned_size += cloned;
+                } else if freed == 0 && file.size > 0 {
+                    shared_count += 1;
+                }
+                if let Some(parent) = file.path.parent() {
+                    emptied_dirs.insert(parent.to_path_buf());
+                }
+                deleted.push(file.clone());
+            }
+            Outcome::Archived(saved) => {
+                archived_count += 1;
+                archived_saved += saved;
+            }
+            Outcome::Failed { error, transient: true } => transient.push(error),
+            Outcome::Failed { error, transient: false } => permanent.push(error),
+        }
+        if let Some(group) = group {
+            group.pending -= 1;
+            if group.pending == 0 {
+                if group.removed >= group.links {
+                    deleted_size += file.size;
+                } else {
+                    shared_count += group.removed;
+                }
+            }
+        }
+        pb.set_message(format_size(deleted_size + archived_saved));
+        pb.inc(1);
+        events.update(ProgressEvent {
+            phase: "deleting",
+            files: pb.position(),
+            total: Some(total),
+            bytes: deleted_size + archived_saved,
+            current: Some(file.path),
+            ..ProgressEvent::default()
+        });
+    }
+    let processed = pb.position();
+    pb.finish_and_clear();
+    events.finish();
+    let out_of_time = processed < total && !interrupt::interrupted();
+    
+    if total > 0 {
+        options.hooks.after(Some(&options.root), deleted_count, deleted_size, permanent.len() + transient.len()).await;
+    }
+    
+    if options.quiet {
+        if options.prune_empty_dirs {
+            prune_empty_dirs(emptied_dirs, &options.root, protected).await;
+        }
+        let errors = permanent.into_iter().chain(transient).collect();
+        return Ok(CleanSummary { deleted_count, deleted_size, deleted, errors, out_of_time });
+    }
+    
+    if processed < total {
+        let label = if out_of_time { "[Out of time]" } else { "[Interrupted]" };
+        println!("\n{} {}", label.yellow().bold(), tr!("delete.interrupted", (total - processed).to_string().cyan()));
+    }
+    
+    println!("\n{} {}", 
+        "[OK!]".green(), 
+        tr!("delete.done", deleted_count.to_string().cyan(), format_size_with_color(deleted_size))
+    );
+    if shared_count > 0 {
+        println!("{} {}", "[Note]".blue(), tr!("delete.linked", shared_count.to_string().cyan()));
+    }
+    if cloned_count > 0 {
+        println!("{} {}", "[Note]".blue(), tr!("delete.cloned", cloned_count.to_string().cyan(), format_size_with_color(cloned_size)));
+    }
+    if options.archive.is_some() {
+        println!("{} {}",
+            "[OK!]".green(),
+            tr!("delete.archived", archived_count.to_string().cyan(), format_size_with_color(archived_saved))
+        );
+    }
+    // The errors themselves are listed with the rest of the run's at the end
+    if !permanent.is_empty() {
+        println!("{} {}", "[Failed!]".red(), tr!("delete.failed", permanent.len().to_string().cyan()));
+    }
+    if !transient.is_empty() {
+        println!("{} {}",
+            "[Failed!]".red(),
+            tr!("delete.busy", transient.len().to_string().cyan(), options.retry.retries)
+        );
+    }
+    if options.prune_empty_dirs {
+        let pruned = prune_empty_dirs(emptied_dirs, &options.root, protected).await;
+        println!("{} {}", "[OK!]".green(), tr!("delete.pruned", pruned.to_string().cyan()));
+    }
+    if skipped_count > 0 {
+        println!("{} {}", "[Protected]".blue(), tr!("protected.skipped", skipped_count.to_string().cyan()));
+    }
+    
+    let errors = permanent.into_iter().chain(transient).collect();
+    Ok(CleanSummary { deleted_count, deleted_size, deleted, errors, out_of_time })
+}
+
+/// Removes the given directories if they are now empty, then their parents,
+/// never going above or removing `root` itself.
+async fn prune_empty_dirs(dirs: BTreeSet<PathBuf>, root: &Path, protected: &ProtectedPaths) -> usize {
+    let mut pruned = 0;
+    
+    // Deepest first, so removing a child can leave its parent empty in turn
+    let mut dirs: Vec<PathBuf> = dirs.into_iter().collect();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    
+    for dir in &dirs {
+        let mut current = dir.as_path();
+        while current != root && current.starts_with(root) && !protected.is_protected(current) {
+            // Fails if the directory still has entries, which is where we stop
+            if fs::remove_dir(long_path(current)).await.is_err() {
+                break;
+            }
+            pruned += 1;
+            
+            current = match current.parent() {
+                Some(parent) => parent,
+                None => break,
+            };
+        }
+    }
+    
+    pruned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::Risk;
+
+    #[tokio::test]
+    async fn deletes_nothing_when_the_pre_hook_fails() {
+        let dir = std::env::temp_dir().join(format!("hica-clean-hook-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.tmp");
+        std::fs::write(&path, "cache").unwrap();
+
+        let file = CacheFile {
+            path: path.clone(),
+            size: 5,
+            category: CacheCategory::Temporary,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+        let options = CleanOptions {
+            root: dir.clone(),
+            limits: DeleteLimits::default(),
+            archive: None,
+            prune_empty_dirs: false,
+            retry: RetryPolicy::default(),
+            hooks: CleanHooks { pre: Some("exit 1".to_string()), post: None },
+            quiet: true,
+        };
+        let result = delete_cache_files(vec![file], &ProtectedPaths::new(&[]), &options).await;
+        let kept = path.exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err());
+        assert!(kept);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn frees_hard_linked_data_once_its_last_link_is_gone() {
+        let dir = std::env::temp_dir().join(format!("hica-clean-links-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache_file = |name: &str| {
+            let path = dir.join(name);
+            let link_id = crate::scan::link_id(&std::fs::metadata(&path).unwrap());
+            CacheFile { path, size: 5, category: CacheCategory::Temporary, risk: Risk::Safe, link_id, modified: None, accessed: None }
+        };
+        let options = CleanOptions {
+            root: dir.clone(),
+            limits: DeleteLimits::default(),
+            archive: None,
+            prune_empty_dirs: false,
+            retry: RetryPolicy::default(),
+            hooks: CleanHooks::default(),
+            quiet: true,
+        };
+
+        std::fs::write(dir.join("a.tmp"), "cache").unwrap();
+        std::fs::hard_link(dir.join("a.tmp"), dir.join("b.tmp")).unwrap();
+        std::fs::hard_link(dir.join("a.tmp"), dir.join("c.tmp")).unwrap();
+        let one = delete_cache_files(vec![cache_file("a.tmp")], &ProtectedPaths::new(&[]), &options).await.unwrap();
+        let rest = delete_cache_files(vec![cache_file("b.tmp"), cache_file("c.tmp")], &ProtectedPaths::new(&[]), &options).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((one.deleted_count, one.deleted_size), (1, 0));
+        assert_eq!((rest.deleted_count, rest.deleted_size), (2, 5));
+    }
+}
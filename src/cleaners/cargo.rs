@@ -0,0 +1,67 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{existing, CleanTarget, Cleaner};
+use crate::paths::home_dir;
+
+pub struct CargoCleaner;
+
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".cargo")))
+}
+
+impl Cleaner for CargoCleaner {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Cargo registry downloads, git checkouts and build output"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let mut targets = Vec::new();
+
+        if let Some(home) = cargo_home() {
+            targets.push(CleanTarget::directory(
+                "registry cache",
+                home.join("registry").join("cache"),
+                "downloaded .crate files, fetched again when needed",
+                true,
+            ));
+            targets.push(CleanTarget::directory(
+                "registry sources",
+                home.join("registry").join("src"),
+                "unpacked crate sources, re-extracted from the cache",
+                true,
+            ));
+            targets.push(CleanTarget::directory(
+                "git checkouts",
+                home.join("git").join("checkouts"),
+                "working copies of git dependencies",
+                true,
+            ));
+            targets.push(CleanTarget::directory(
+                "git database",
+                home.join("git").join("db"),
+                "bare clones of git dependencies, cloned again when needed",
+                false,
+            ));
+        }
+
+        // The current project's build output, only when we're in a Cargo project
+        if PathBuf::from("Cargo.toml").is_file() {
+            targets.push(CleanTarget::directory(
+                "build output (./target)",
+                PathBuf::from("target"),
+                "everything is rebuilt on the next build",
+                false,
+            ));
+        }
+
+        existing(targets)
+    }
+}
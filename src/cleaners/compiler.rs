@@ -0,0 +1,72 @@
+use super::{dir_size, existing, CleanTarget, Cleaner, Location};
+use crate::compiler::{cache_dir, is_installed, size_limit};
+
+pub struct CcacheCleaner;
+pub struct SccacheCleaner;
+
+impl Cleaner for CcacheCleaner {
+    fn name(&self) -> &'static str {
+        "ccache"
+    }
+
+    fn description(&self) -> &'static str {
+        "ccache's compiler cache, trimmed to its size limit by ccache itself"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let Some(dir) = cache_dir("ccache").filter(|dir| dir.is_dir()) else {
+            return Vec::new();
+        };
+
+        // Without ccache there's nothing to trim with, only the whole cache to remove
+        if !is_installed("ccache") {
+            return existing(vec![CleanTarget::directory(
+                "cache",
+                dir,
+                "every cached compilation, rebuilt from scratch on the next builds",
+                false,
+            )]);
+        }
+
+        let size = dir_size(&dir);
+        let over_limit = size_limit("ccache").map_or(0, |limit| size.saturating_sub(limit));
+        vec![
+            CleanTarget {
+                label: "over the size limit",
+                location: Location::Command { program: "ccache", args: &["--cleanup"], size: over_limit },
+                note: "the least recently used results beyond `max_size`",
+                default: true,
+            },
+            CleanTarget {
+                label: "cache",
+                location: Location::Command { program: "ccache", args: &["--clear"], size },
+                note: "every cached compilation, rebuilt from scratch on the next builds",
+                default: false,
+            },
+        ]
+    }
+}
+
+impl Cleaner for SccacheCleaner {
+    fn name(&self) -> &'static str {
+        "sccache"
+    }
+
+    fn description(&self) -> &'static str {
+        "sccache's local compiler cache"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        // sccache trims its cache to `SCCACHE_CACHE_SIZE` as it goes, and has
+        // no command to do so, so what's left is removing all of it
+        let Some(dir) = cache_dir("sccache") else {
+            return Vec::new();
+        };
+        existing(vec![CleanTarget::directory(
+            "cache",
+            dir,
+            "every cached compilation; stop the server first with `sccache --stop-server`",
+            false,
+        )])
+    }
+}
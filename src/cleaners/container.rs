@@ -0,0 +1,133 @@
+use super::{command_lines, CleanTarget, Cleaner, Location};
+
+/// Docker or Podman, whose storage is only safely cleaned through their CLIs.
+pub struct ContainerCleaner {
+    program: &'static str,
+}
+
+impl ContainerCleaner {
+    pub const DOCKER: ContainerCleaner = ContainerCleaner { program: "docker" };
+    pub const PODMAN: ContainerCleaner = ContainerCleaner { program: "podman" };
+
+    /// Sums a column of sizes printed by the CLI.
+    fn sum_sizes(&self, args: &[&str]) -> u64 {
+        command_lines(self.program, args)
+            .iter()
+            .filter_map(|line| parse_si_size(line))
+            .sum()
+    }
+
+    /// The reclaimable size of one row of `system df`, e.g. "Build Cache".
+    fn reclaimable(&self, kind: &str) -> Option<u64> {
+        command_lines(self.program, &["system", "df", "--format", "{{.Type}}\t{{.Reclaimable}}"])
+            .iter()
+            .find_map(|line| {
+                let (row, reclaimable) = line.split_once('\t')?;
+                if row.trim().eq_ignore_ascii_case(kind) {
+                    parse_si_size(reclaimable)
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn command(&self, args: &'static [&'static str], size: u64) -> Location {
+        Location::Command { program: self.program, args, size }
+    }
+}
+
+/// Parses the 1000-based sizes Docker and Podman print, such as `1.2GB`,
+/// `512kB` or `0B (virtual 1.1GB)`; only the leading size is used.
+pub fn parse_si_size(text: &str) -> Option<u64> {
+    let text = text.split_whitespace().next()?;
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        _ => return None,
+    };
+
+    Some((number * multiplier) as u64)
+}
+
+impl Cleaner for ContainerCleaner {
+    fn name(&self) -> &'static str {
+        self.program
+    }
+
+    fn description(&self) -> &'static str {
+        match self.program {
+            "docker" => "Docker dangling images, stopped containers and build cache",
+            _ => "Podman dangling images and stopped containers",
+        }
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        // `system df` fails when the CLI is missing or the daemon is down
+        let unused_images = match self.reclaimable("Images") {
+            Some(size) => size,
+            None => return Vec::new(),
+        };
+
+        let mut targets = vec![
+            CleanTarget {
+                label: "dangling images",
+                location: self.command(
+                    &["image", "prune", "--force"],
+                    self.sum_sizes(&["images", "--filter", "dangling=true", "--format", "{{.Size}}"]),
+                ),
+                note: "untagged image layers no longer used by any tag",
+                default: true,
+            },
+            CleanTarget {
+                label: "stopped containers",
+                location: self.command(
+                    &["container", "prune", "--force"],
+                    self.sum_sizes(&["ps", "--all", "--filter", "status=exited", "--filter", "status=created", "--format", "{{.Size}}"]),
+                ),
+                note: "containers that have exited, along with their writable layers",
+                default: true,
+            },
+        ];
+
+        if self.program == "docker"
+            && let Some(size) = self.reclaimable("Build Cache")
+        {
+            targets.push(CleanTarget {
+                label: "build cache",
+                location: self.command(&["builder", "prune", "--force"], size),
+                note: "BuildKit layers, rebuilt on the next build",
+                default: true,
+            });
+        }
+
+        targets.push(CleanTarget {
+            label: "unused images",
+            location: self.command(&["image", "prune", "--all", "--force"], unused_images),
+            note: "every image not used by a container, pulled again when needed",
+            default: false,
+        });
+
+        targets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cli_sizes() {
+        assert_eq!(parse_si_size("1.5GB"), Some(1_500_000_000));
+        assert_eq!(parse_si_size("512kB"), Some(512_000));
+        assert_eq!(parse_si_size("0B (virtual 1.1GB)"), Some(0));
+        assert_eq!(parse_si_size("2.3GB (45%)"), Some(2_300_000_000));
+        assert_eq!(parse_si_size("N/A"), None);
+    }
+}
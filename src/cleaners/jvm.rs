@@ -0,0 +1,132 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{existing, CleanTarget, Cleaner};
+use crate::paths::{home_dir, user_cache_dir};
+
+pub struct GradleCleaner;
+pub struct MavenCleaner;
+pub struct SbtCleaner;
+
+fn gradle_home() -> Option<PathBuf> {
+    env::var_os("GRADLE_USER_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".gradle")))
+}
+
+fn coursier_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("COURSIER_CACHE").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = user_cache_dir()?;
+    if cfg!(windows) {
+        Some(base.join("Coursier").join("Cache").join("v1"))
+    } else if cfg!(target_os = "macos") {
+        Some(base.join("Coursier").join("v1"))
+    } else {
+        Some(base.join("coursier").join("v1"))
+    }
+}
+
+impl Cleaner for GradleCleaner {
+    fn name(&self) -> &'static str {
+        "gradle"
+    }
+
+    fn description(&self) -> &'static str {
+        "Gradle dependency and build caches, daemon logs and wrapper downloads"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let home = match gradle_home() {
+            Some(home) => home,
+            None => return Vec::new(),
+        };
+
+        existing(vec![
+            CleanTarget::directory(
+                "caches",
+                home.join("caches"),
+                "downloaded dependencies and build cache; stop running daemons first with `gradle --stop`",
+                true,
+            ),
+            CleanTarget::directory(
+                "daemon logs",
+                home.join("daemon"),
+                "logs and registry of past daemons; stop running daemons first",
+                true,
+            ),
+            CleanTarget::directory(
+                "wrapper distributions",
+                home.join("wrapper").join("dists"),
+                "Gradle versions downloaded by gradlew, fetched again on the next build",
+                false,
+            ),
+        ])
+    }
+}
+
+impl Cleaner for MavenCleaner {
+    fn name(&self) -> &'static str {
+        "maven"
+    }
+
+    fn description(&self) -> &'static str {
+        "Maven's local repository"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let repository = match home_dir() {
+            Some(home) => home.join(".m2").join("repository"),
+            None => return Vec::new(),
+        };
+
+        existing(vec![CleanTarget::directory(
+            "local repository",
+            repository,
+            "downloaded artifacts, slow to fetch again, and anything installed with `mvn install`, which is lost",
+            false,
+        )])
+    }
+}
+
+impl Cleaner for SbtCleaner {
+    fn name(&self) -> &'static str {
+        "sbt"
+    }
+
+    fn description(&self) -> &'static str {
+        "sbt's Coursier and Ivy caches and launcher downloads"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let mut targets = Vec::new();
+
+        if let Some(coursier) = coursier_cache_dir() {
+            targets.push(CleanTarget::directory(
+                "Coursier cache",
+                coursier,
+                "downloaded dependencies, fetched again on the next build",
+                true,
+            ));
+        }
+        if let Some(home) = home_dir() {
+            targets.push(CleanTarget::directory(
+                "Ivy cache",
+                home.join(".ivy2").join("cache"),
+                "dependencies resolved by older sbt versions; `~/.ivy2/local` is kept",
+                true,
+            ));
+            targets.push(CleanTarget::directory(
+                "launcher downloads",
+                home.join(".sbt").join("boot"),
+                "sbt and Scala versions the launcher fetched, fetched again when needed",
+                false,
+            ));
+        }
+
+        existing(targets)
+    }
+}
@@ -0,0 +1,186 @@
+//! Tool-specific cleaners, which know where a tool keeps its caches and which
+//! parts of them are safe to remove.
+
+mod cargo;
+mod compiler;
+mod container;
+mod jvm;
+mod npm;
+mod package;
+mod pip;
+mod python;
+mod trash;
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use walkdir::WalkDir;
+
+pub use trash::{home_trash, trash_size};
+
+use crate::package::PACKAGE_CACHES;
+use crate::scan::link_id;
+
+/// How a cache location is cleaned.
+pub enum Location {
+    /// A directory that is removed outright.
+    Directory(PathBuf),
+    /// Space only the tool itself can free, by running `program args...`.
+    Command {
+        program: &'static str,
+        args: &'static [&'static str],
+        size: u64,
+    },
+}
+
+/// A cache location a cleaner can remove.
+pub struct CleanTarget {
+    pub label: &'static str,
+    pub location: Location,
+    /// What removing it costs, e.g. "re-downloaded on the next build".
+    pub note: &'static str,
+    /// Removed by a plain `hica clean <tool>`; the rest need `--all`.
+    pub default: bool,
+}
+
+impl CleanTarget {
+    pub fn directory(label: &'static str, path: PathBuf, note: &'static str, default: bool) -> Self {
+        CleanTarget { label, location: Location::Directory(path), note, default }
+    }
+
+    pub fn size(&self) -> u64 {
+        match &self.location {
+            Location::Directory(path) => dir_size(path),
+            Location::Command { size, .. } => *size,
+        }
+    }
+
+    /// The path, or the command that does the cleaning.
+    pub fn describe(&self) -> String {
+        match &self.location {
+            Location::Directory(path) => path.display().to_string(),
+            Location::Command { program, args, .. } => format!("{} {}", program, args.join(" ")),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match &self.location {
+            Location::Directory(path) => Some(path),
+            Location::Command { .. } => None,
+        }
+    }
+
+    pub async fn clean(&self) -> io::Result<()> {
+        match &self.location {
+            Location::Directory(path) => tokio::fs::remove_dir_all(crate::platform::long_path(path)).await,
+            Location::Command { program, args, .. } => {
+                let status = tokio::process::Command::new(program).args(*args).status().await?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(io::Error::other(format!("{} failed ({})", program, status)))
+                }
+            }
+        }
+    }
+}
+
+pub trait Cleaner {
+    /// The name used on the command line, e.g. `cargo`.
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// The cache locations that exist on this machine.
+    fn targets(&self) -> Vec<CleanTarget>;
+}
+
+/// Every cleaner hica knows about.
+pub fn registry() -> Vec<Box<dyn Cleaner>> {
+    let mut cleaners: Vec<Box<dyn Cleaner>> = vec![
+        Box::new(cargo::CargoCleaner),
+        Box::new(compiler::CcacheCleaner),
+        Box::new(compiler::SccacheCleaner),
+        Box::new(container::ContainerCleaner::DOCKER),
+        Box::new(container::ContainerCleaner::PODMAN),
+        Box::new(jvm::GradleCleaner),
+        Box::new(jvm::MavenCleaner),
+        Box::new(jvm::SbtCleaner),
+        Box::new(npm::NpmCleaner),
+        Box::new(pip::PipCleaner),
+        Box::new(python::PythonCleaner),
+        Box::new(trash::TrashCleaner),
+    ];
+    for cache in PACKAGE_CACHES {
+        cleaners.push(Box::new(package::PackageCleaner { cache }));
+    }
+    cleaners
+}
+
+pub fn find(name: &str) -> Option<Box<dyn Cleaner>> {
+    registry()
+        .into_iter()
+        .find(|cleaner| cleaner.name().eq_ignore_ascii_case(name))
+}
+
+/// Total size of the files below `path`, without following symlinks. Files
+/// hard linked several times inside it, as some caches do, count once.
+pub fn dir_size(path: &Path) -> u64 {
+    let mut linked = HashSet::new();
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .filter(|metadata| link_id(metadata).is_none_or(|id| linked.insert(id)))
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Keeps only the targets that exist.
+fn existing(targets: Vec<CleanTarget>) -> Vec<CleanTarget> {
+    targets
+        .into_iter()
+        .filter(|target| target.path().is_none_or(Path::exists))
+        .collect()
+}
+
+/// How long a tool gets to answer a query, as a container engine whose
+/// daemon hangs would otherwise hold up every scan.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs a command and returns its stdout lines, or nothing if it can't be run,
+/// fails or takes longer than `COMMAND_TIMEOUT`.
+fn command_lines(program: &str, args: &[&str]) -> Vec<String> {
+    let Ok(mut child) = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn() else {
+        return Vec::new();
+    };
+    // Read on the side, so a chatty command can't fill the pipe and stall
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = io::Read::read_to_end(&mut stdout, &mut text);
+        text
+    });
+
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break None;
+            }
+        }
+    };
+    let text = reader.join().unwrap_or_default();
+    match status {
+        Some(status) if status.success() => String::from_utf8_lossy(&text).lines().map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,55 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{existing, CleanTarget, Cleaner};
+use crate::paths::home_dir;
+
+pub struct NpmCleaner;
+
+fn npm_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("npm_config_cache").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        return env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join("npm-cache"));
+    }
+    home_dir().map(|home| home.join(".npm"))
+}
+
+impl Cleaner for NpmCleaner {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn description(&self) -> &'static str {
+        "npm package cache and debug logs"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let cache = match npm_cache_dir() {
+            Some(cache) => cache,
+            None => return Vec::new(),
+        };
+
+        existing(vec![
+            CleanTarget::directory(
+                "package cache (_cacache)",
+                cache.join("_cacache"),
+                "packages are downloaded again on the next install",
+                true,
+            ),
+            CleanTarget::directory(
+                "debug logs",
+                cache.join("_logs"),
+                "logs of past npm runs",
+                true,
+            ),
+            CleanTarget::directory(
+                "npx cache",
+                cache.join("_npx"),
+                "packages installed by npx, fetched again when run",
+                false,
+            ),
+        ])
+    }
+}
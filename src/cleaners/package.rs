@@ -0,0 +1,32 @@
+use super::{dir_size, CleanTarget, Cleaner, Location};
+use crate::package::PackageCache;
+
+/// Cleans a system package manager's download cache with its own command.
+pub struct PackageCleaner {
+    pub cache: &'static PackageCache,
+}
+
+impl Cleaner for PackageCleaner {
+    fn name(&self) -> &'static str {
+        self.cache.manager
+    }
+
+    fn description(&self) -> &'static str {
+        "downloaded packages kept by the system package manager"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let location = match self.cache.location() {
+            Some(location) if location.exists() => location,
+            _ => return Vec::new(),
+        };
+
+        let (program, args) = self.cache.clean_command.split_first().expect("clean command is never empty");
+        vec![CleanTarget {
+            label: "package downloads",
+            location: Location::Command { program, args, size: dir_size(&location) },
+            note: "installed packages are unaffected; downloads are fetched again when needed",
+            default: true,
+        }]
+    }
+}
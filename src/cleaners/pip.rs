@@ -0,0 +1,58 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{existing, CleanTarget, Cleaner};
+use crate::paths::user_cache_dir;
+
+pub struct PipCleaner;
+
+pub(super) fn pip_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("PIP_CACHE_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = user_cache_dir()?;
+    if cfg!(windows) {
+        Some(base.join("pip").join("Cache"))
+    } else {
+        Some(base.join("pip"))
+    }
+}
+
+impl Cleaner for PipCleaner {
+    fn name(&self) -> &'static str {
+        "pip"
+    }
+
+    fn description(&self) -> &'static str {
+        "pip's HTTP and wheel caches"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let cache = match pip_cache_dir() {
+            Some(cache) => cache,
+            None => return Vec::new(),
+        };
+
+        existing(vec![
+            CleanTarget::directory(
+                "HTTP cache",
+                cache.join("http-v2"),
+                "downloaded packages, fetched again on the next install",
+                true,
+            ),
+            CleanTarget::directory(
+                "legacy HTTP cache",
+                cache.join("http"),
+                "downloads cached by older pip versions",
+                true,
+            ),
+            CleanTarget::directory(
+                "wheel cache",
+                cache.join("wheels"),
+                "locally built wheels, rebuilt from source when needed",
+                true,
+            ),
+        ])
+    }
+}
@@ -0,0 +1,151 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use super::pip::pip_cache_dir;
+use super::{dir_size, existing, CleanTarget, Cleaner, Location};
+use crate::paths::{home_dir, user_cache_dir};
+
+/// The Python ecosystem as a whole: pip, Poetry and conda downloads, and the
+/// bytecode and tool caches of the project in the current directory.
+pub struct PythonCleaner;
+
+fn poetry_cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("POETRY_CACHE_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = user_cache_dir()?;
+    if cfg!(windows) {
+        Some(base.join("pypoetry").join("Cache"))
+    } else {
+        Some(base.join("pypoetry"))
+    }
+}
+
+/// The `pkgs` directories of the conda installs in the home directory.
+fn conda_pkgs_dirs() -> Vec<PathBuf> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    ["miniconda3", "anaconda3", "miniforge3", "mambaforge", ".conda"]
+        .iter()
+        .map(|install| home.join(install).join("pkgs"))
+        .collect()
+}
+
+fn has_conda() -> bool {
+    std::process::Command::new("conda")
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether the current directory is a Python project.
+fn in_python_project() -> bool {
+    ["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"]
+        .iter()
+        .any(|file| Path::new(file).is_file())
+}
+
+/// The tool cache directories in the project below `root`, without looking
+/// into version control, virtualenvs or the caches themselves.
+fn project_caches(root: &Path) -> Vec<(&'static str, PathBuf)> {
+    let mut caches = Vec::new();
+    let mut walk = WalkDir::new(root).into_iter();
+    while let Some(Ok(entry)) = walk.next() {
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let label = match entry.file_name().to_str() {
+            Some("__pycache__") => "bytecode (__pycache__)",
+            Some(".pytest_cache") => "pytest cache",
+            Some(".mypy_cache") => "mypy cache",
+            Some(".ruff_cache") => "ruff cache",
+            Some(".git" | "node_modules") => {
+                walk.skip_current_dir();
+                continue;
+            }
+            _ if entry.path().join("pyvenv.cfg").is_file() => {
+                walk.skip_current_dir();
+                continue;
+            }
+            _ => continue,
+        };
+        caches.push((label, entry.path().to_path_buf()));
+        walk.skip_current_dir();
+    }
+    caches
+}
+
+impl Cleaner for PythonCleaner {
+    fn name(&self) -> &'static str {
+        "python"
+    }
+
+    fn description(&self) -> &'static str {
+        "pip, Poetry and conda downloads, and the project's bytecode and tool caches"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        let mut targets = Vec::new();
+
+        if let Some(pip) = pip_cache_dir() {
+            targets.push(CleanTarget::directory(
+                "pip cache",
+                pip,
+                "downloaded packages and built wheels, fetched or built again when needed",
+                true,
+            ));
+        }
+        if let Some(poetry) = poetry_cache_dir() {
+            // Poetry's virtualenvs live next to these and are left alone
+            targets.push(CleanTarget::directory(
+                "Poetry cache",
+                poetry.join("cache"),
+                "package metadata from the repositories, fetched again when needed",
+                true,
+            ));
+            targets.push(CleanTarget::directory(
+                "Poetry artifacts",
+                poetry.join("artifacts"),
+                "downloaded packages, fetched again on the next install",
+                true,
+            ));
+        }
+        // conda knows which extracted packages environments still link to
+        let pkgs: Vec<PathBuf> = conda_pkgs_dirs().into_iter().filter(|pkgs| pkgs.is_dir()).collect();
+        if !pkgs.is_empty() && has_conda() {
+            targets.push(CleanTarget {
+                label: "conda packages",
+                location: Location::Command {
+                    program: "conda",
+                    args: &["clean", "--packages", "--tarballs", "--yes"],
+                    size: pkgs.iter().map(|pkgs| dir_size(pkgs)).sum(),
+                },
+                note: "downloaded archives and packages no environment uses, fetched again when needed",
+                default: true,
+            });
+        } else {
+            for pkgs in pkgs {
+                targets.push(CleanTarget::directory(
+                    "conda packages",
+                    pkgs,
+                    "downloaded and extracted packages; environments linking to them may need reinstalling",
+                    false,
+                ));
+            }
+        }
+
+        if in_python_project() {
+            for (label, path) in project_caches(Path::new(".")) {
+                targets.push(CleanTarget::directory(label, path, "created again when the code next runs", true));
+            }
+        }
+
+        existing(targets)
+    }
+}
@@ -0,0 +1,134 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use super::{dir_size, existing, CleanTarget, Cleaner, Location};
+use crate::paths::home_dir;
+
+/// The trash or recycle bin, emptied the way the desktop does it so its
+/// bookkeeping stays consistent.
+pub struct TrashCleaner;
+
+/// The trash in the home directory: `~/.Trash` on macOS, and elsewhere the
+/// freedesktop one, `$XDG_DATA_HOME/Trash`.
+pub fn home_trash() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        return Some(home_dir()?.join(".Trash"));
+    }
+    let data = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(home_dir()?.join(".local").join("share")))?;
+    Some(data.join("Trash"))
+}
+
+/// The freedesktop trashes other volumes keep for this user: `.Trash/<uid>`
+/// or `.Trash-<uid>` at the top of each mount. As the spec asks, a shared
+/// `.Trash` only counts if it's a real directory with the sticky bit set, and
+/// neither kind may be a symlink or belong to someone else, since anyone who
+/// can write to the volume could plant one.
+#[cfg(unix)]
+fn volume_trashes() -> Vec<PathBuf> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let Some(uid) = crate::platform::current_user_id() else {
+        return Vec::new();
+    };
+    let own_dir = |path: &Path| std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir() && metadata.uid() == uid);
+    let shared_dir = |path: &Path| std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0);
+    crate::mounts::mounts()
+        .into_iter()
+        .flat_map(|mount| {
+            let shared = mount.point.join(".Trash");
+            let in_shared = shared_dir(&shared).then(|| shared.join(uid.to_string()));
+            in_shared.into_iter().chain([mount.point.join(format!(".Trash-{}", uid))])
+        })
+        .filter(|trash| own_dir(trash))
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn volume_trashes() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+/// How much the trash holds, measured without asking the desktop.
+pub fn trash_size() -> u64 {
+    if cfg!(windows) {
+        return crate::platform::recycle_bin_size().unwrap_or(0);
+    }
+    if cfg!(target_os = "macos") {
+        return home_dir().map_or(0, |home| dir_size(&home.join(".Trash")));
+    }
+    home_trash().iter().chain(&volume_trashes()).map(|trash| dir_size(trash)).sum()
+}
+
+/// Whether gio can reach the `trash:` location, which takes the gvfs daemon;
+/// without it, `gio trash --empty` quietly does nothing.
+fn gio_trash_works() -> bool {
+    std::process::Command::new("gio")
+        .args(["info", "trash:///"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+impl Cleaner for TrashCleaner {
+    fn name(&self) -> &'static str {
+        "trash"
+    }
+
+    fn description(&self) -> &'static str {
+        "The trash (Recycle Bin on Windows), emptied through the desktop"
+    }
+
+    fn targets(&self) -> Vec<CleanTarget> {
+        const NOTE: &str = "deleted files waiting in the trash, gone for good";
+
+        if cfg!(windows) {
+            let Some(size) = crate::platform::recycle_bin_size() else {
+                return Vec::new();
+            };
+            let location = Location::Command {
+                program: "powershell",
+                args: &["-NoProfile", "-Command", "Clear-RecycleBin -Force"],
+                size,
+            };
+            return vec![CleanTarget { label: "Recycle Bin", location, note: NOTE, default: true }];
+        }
+
+        if cfg!(target_os = "macos") {
+            let Some(trash) = home_dir().map(|home| home.join(".Trash")).filter(|trash| trash.is_dir()) else {
+                return Vec::new();
+            };
+            let location = Location::Command {
+                program: "osascript",
+                args: &["-e", "tell application \"Finder\" to empty trash"],
+                size: dir_size(&trash),
+            };
+            return vec![CleanTarget { label: "Trash", location, note: NOTE, default: true }];
+        }
+
+        let home = home_trash().filter(|trash| trash.is_dir());
+        let volumes = volume_trashes();
+        if home.is_none() && volumes.is_empty() {
+            return Vec::new();
+        }
+
+        // gio empties every trash of the user and tells file managers about it
+        if gio_trash_works() {
+            let size = home.iter().chain(&volumes).map(|trash| dir_size(trash)).sum();
+            let location = Location::Command { program: "gio", args: &["trash", "--empty"], size };
+            return vec![CleanTarget { label: "Trash", location, note: NOTE, default: true }];
+        }
+
+        // Without it, emptying the trash means removing the deleted files
+        // together with the entries describing them, as the spec has it
+        let mut targets = Vec::new();
+        for trash in home.iter().chain(&volumes) {
+            targets.push(CleanTarget::directory("trashed files", trash.join("files"), NOTE, true));
+            targets.push(CleanTarget::directory("trash info", trash.join("info"), "where the trashed files came from", true));
+        }
+        existing(targets)
+    }
+}
@@ -0,0 +1,239 @@
+//! `hica compare`: where two scans differ, per category and per directory,
+//! such as two workstations that should look alike but don't.
+//!
+//! Either side can be a `--format json` summary, a `--format jsonl` report
+//! or a `hica export`. Summaries only have totals per category; the other
+//! two list every file, so they can be compared directory by directory too.
+//! Directories are compared relative to the path scanned, which lets scans
+//! of different home directories line up.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::classify::CacheCategory;
+use crate::export::Export;
+use crate::json::Json;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl Totals {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// One side of a comparison.
+#[derive(Debug)]
+pub struct Side {
+    /// What to call it: the name of its file
+    pub label: String,
+    /// The machine scanned, where the report says
+    pub host: Option<String>,
+    /// The path scanned, where the report says
+    pub root: Option<PathBuf>,
+    pub total: Totals,
+    /// In the order of `CacheCategory::ALL`
+    pub categories: Vec<Totals>,
+    /// The cache below each directory, relative to the root, if the report lists files
+    pub dirs: Option<HashMap<PathBuf, u64>>,
+}
+
+impl Side {
+    fn from_files(label: String, root: PathBuf, files: impl IntoIterator<Item = (PathBuf, CacheCategory, u64)>) -> Side {
+        let mut total = Totals::default();
+        let mut categories = vec![Totals::default(); CacheCategory::ALL.len()];
+        let mut dirs: HashMap<PathBuf, u64> = HashMap::new();
+        for (path, category, size) in files {
+            total.add(size);
+            categories[category_index(category)].add(size);
+            let relative = path.strip_prefix(&root).unwrap_or(&path);
+            for dir in relative.parent().into_iter().flat_map(Path::ancestors) {
+                if dir != Path::new("") {
+                    *dirs.entry(dir.to_path_buf()).or_default() += size;
+                }
+            }
+        }
+        Side { label, host: None, root: Some(root), total, categories, dirs: Some(dirs) }
+    }
+
+    fn from_summary(label: String, summary: &Json) -> Result<Side, String> {
+        let totals = |json: Option<&Json>| -> Result<Totals, String> {
+            let json = json.ok_or("a category is missing")?;
+            let number = |key| json.get(key).and_then(Json::as_u64).ok_or(format!("`{}` is missing", key));
+            Ok(Totals { files: number("files")?, bytes: number("bytes")? })
+        };
+        let categories = summary.get("categories").ok_or("`categories` is missing")?;
+        Ok(Side {
+            label,
+            host: None,
+            root: summary.get("path").and_then(Json::as_str).map(PathBuf::from),
+            total: totals(summary.get("total"))?,
+            categories: CacheCategory::ALL.iter().map(|c| totals(categories.get(&c.as_str().to_lowercase()))).collect::<Result<_, _>>()?,
+            dirs: None,
+        })
+    }
+}
+
+fn category_index(category: CacheCategory) -> usize {
+    CacheCategory::ALL.iter().position(|&c| c == category).unwrap_or(CacheCategory::ALL.len() - 1)
+}
+
+/// Reads a report or export, telling which it is from its contents.
+pub fn load(file: &Path) -> Result<Side, String> {
+    let bytes = std::fs::read(file).map_err(|e| e.to_string())?;
+    let label = file.file_stem().map_or_else(|| file.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+    if bytes.starts_with(b"HICASCAN") {
+        let export = Export::decode(&bytes)?;
+        let files = export.files.into_iter().map(|file| (file.path, file.category, file.size));
+        return Ok(Side { host: Some(export.host), ..Side::from_files(label, export.root, files) });
+    }
+
+    let text = String::from_utf8(bytes).map_err(|_| "neither a JSON report nor a hica export".to_string())?;
+    // Signed reports end with a line holding the signature
+    let lines: Vec<Json> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Json::parse)
+        .filter(|json| !json.as_ref().is_ok_and(|json| json.get("signature").is_some()))
+        .collect::<Result<_, _>>()?;
+    match lines.as_slice() {
+        [summary] if summary.get("categories").is_some() => Side::from_summary(label, summary),
+        files => {
+            let mut listed = Vec::new();
+            for file in files {
+                let path = file.get("path").and_then(Json::as_str).ok_or("a line has no `path`")?;
+                let size = file.get("size").and_then(Json::as_u64).ok_or("a line has no `size`")?;
+                let category = file.get("category").and_then(Json::as_str).and_then(|c| CacheCategory::from_str(c, true).ok()).unwrap_or(CacheCategory::Other);
+                listed.push((PathBuf::from(path), category, size));
+            }
+            let root = common_dir(listed.iter().map(|(path, _, _)| path.as_path()));
+            Ok(Side::from_files(label, root, listed))
+        }
+    }
+}
+
+/// The deepest directory all of `paths` are in.
+fn common_dir<'a>(paths: impl IntoIterator<Item = &'a Path>) -> PathBuf {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let dir = path.parent().unwrap_or(path);
+        common = Some(match common {
+            None => dir.to_path_buf(),
+            Some(common) => common.ancestors().find(|ancestor| dir.starts_with(ancestor)).unwrap_or(Path::new("")).to_path_buf(),
+        });
+    }
+    common.unwrap_or_default()
+}
+
+/// Categories whose cache differs between the sides, largest change first.
+pub fn category_deltas(a: &Side, b: &Side) -> Vec<(CacheCategory, Totals, Totals)> {
+    let mut deltas: Vec<(CacheCategory, Totals, Totals)> = CacheCategory::ALL
+        .iter()
+        .zip(a.categories.iter().zip(&b.categories))
+        .filter(|(_, (a, b))| a != b)
+        .map(|(&category, (&a, &b))| (category, a, b))
+        .collect();
+    deltas.sort_by_key(|(_, a, b)| std::cmp::Reverse(a.bytes.abs_diff(b.bytes)));
+    deltas
+}
+
+/// The directories that explain most of the difference, largest change
+/// first. A directory whose change is nearly all in one of its
+/// subdirectories gives way to it, and nothing below a directory listed
+/// is listed too.
+pub fn path_deltas(a: &Side, b: &Side) -> Option<Vec<(PathBuf, u64, u64)>> {
+    let (a_dirs, b_dirs) = (a.dirs.as_ref()?, b.dirs.as_ref()?);
+    let mut dirs: BTreeMap<&Path, (u64, u64)> = BTreeMap::new();
+    for (dir, &size) in a_dirs {
+        dirs.entry(dir).or_default().0 = size;
+    }
+    for (dir, &size) in b_dirs {
+        dirs.entry(dir).or_default().1 = size;
+    }
+    let delta = |(a, b): (u64, u64)| b as i128 - a as i128;
+
+    // The largest change of a subdirectory, per directory
+    let mut largest_child: HashMap<&Path, i128> = HashMap::new();
+    for (dir, &sizes) in &dirs {
+        if let Some(parent) = dir.parent() {
+            let largest = largest_child.entry(parent).or_default();
+            if delta(sizes).abs() > largest.abs() {
+                *largest = delta(sizes);
+            }
+        }
+    }
+
+    let mut candidates: Vec<(&Path, (u64, u64))> = dirs
+        .iter()
+        .map(|(&dir, &sizes)| (dir, sizes))
+        .filter(|&(dir, sizes)| {
+            let change = delta(sizes);
+            let child = largest_child.get(dir).copied().unwrap_or(0);
+            change != 0 && !(child.signum() == change.signum() && child.abs() * 10 >= change.abs() * 9)
+        })
+        .collect();
+    candidates.sort_by_key(|&(dir, sizes)| (std::cmp::Reverse(delta(sizes).abs()), dir));
+
+    let mut picked: Vec<(PathBuf, u64, u64)> = Vec::new();
+    for (dir, (a, b)) in candidates {
+        if !picked.iter().any(|(listed, _, _)| dir.starts_with(listed)) {
+            picked.push((dir.to_path_buf(), a, b));
+        }
+    }
+    Some(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn side(root: &str, files: &[(&str, CacheCategory, u64)]) -> Side {
+        Side::from_files(root.to_string(), PathBuf::from(root), files.iter().map(|&(path, category, size)| (PathBuf::from(path), category, size)))
+    }
+
+    #[test]
+    fn finds_where_two_machines_differ() {
+        let a = side("/home/alice", &[
+            ("/home/alice/.cache/pip/a", CacheCategory::Package, 100),
+            ("/home/alice/code/app/target/x.o", CacheCategory::Build, 50),
+        ]);
+        let b = side("/home/bob", &[
+            ("/home/bob/.cache/pip/a", CacheCategory::Package, 100),
+            ("/home/bob/code/app/target/x.o", CacheCategory::Build, 50),
+            ("/home/bob/code/app/target/debug/y.o", CacheCategory::Build, 1000),
+            ("/home/bob/code/app/target/debug/z.o", CacheCategory::Build, 5),
+            ("/home/bob/.cache/thumbnails/t.png", CacheCategory::Thumbnail, 20),
+        ]);
+
+        let categories = category_deltas(&a, &b);
+        assert_eq!(categories.iter().map(|(c, _, _)| *c).collect::<Vec<_>>(), [CacheCategory::Build, CacheCategory::Thumbnail]);
+        assert_eq!((categories[0].1.bytes, categories[0].2.bytes), (50, 1055));
+
+        // The build growth is all in target/debug, and the thumbnails are new
+        let paths = path_deltas(&a, &b).unwrap();
+        assert_eq!(paths, [
+            (PathBuf::from("code/app/target/debug"), 0, 1005),
+            (PathBuf::from(".cache/thumbnails"), 0, 20),
+        ]);
+    }
+
+    #[test]
+    fn reads_summaries_and_file_lists() {
+        let summary = Json::parse(r#"{"path":"/srv","total":{"files":3,"bytes":30},"categories":{"build":{"files":3,"bytes":30}}}"#).unwrap();
+        assert!(Side::from_summary("a".into(), &summary).is_err());
+
+        let categories: Vec<String> = CacheCategory::ALL.iter().map(|c| format!("\"{}\":{{\"files\":1,\"bytes\":10}}", c.as_str().to_lowercase())).collect();
+        let summary = Json::parse(&format!(r#"{{"path":"/srv","total":{{"files":13,"bytes":130}},"categories":{{{}}}}}"#, categories.join(","))).unwrap();
+        let side = Side::from_summary("a".into(), &summary).unwrap();
+        assert_eq!((side.total.bytes, side.categories[0].bytes, side.dirs), (130, 10, None));
+
+        assert_eq!(common_dir([Path::new("/srv/a/b/1"), Path::new("/srv/a/c/2"), Path::new("/srv/a/3")]), Path::new("/srv/a"));
+    }
+}
@@ -0,0 +1,162 @@
+//! Compiler caches (ccache and sccache), which grow up to a configured limit
+//! and trim themselves from then on, so their size only means something next
+//! to that limit.
+
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+use crate::classify::lowercase_name;
+use crate::paths::{home_dir, user_cache_dir};
+use crate::size::parse_size;
+
+pub const TOOLS: [&str; 2] = ["ccache", "sccache"];
+
+/// The default limits: 5 GB for ccache and 10 GiB for sccache.
+const CCACHE_DEFAULT_LIMIT: u64 = 5_000_000_000;
+const SCCACHE_DEFAULT_LIMIT: u64 = 10 << 30;
+
+/// Where `tool` keeps its cache: `CCACHE_DIR`/`SCCACHE_DIR` if set, otherwise
+/// the platform default (`~/.ccache` where an old ccache left one).
+pub fn cache_dir(tool: &str) -> Option<PathBuf> {
+    let variable = if tool == "ccache" { "CCACHE_DIR" } else { "SCCACHE_DIR" };
+    if let Some(dir) = env::var_os(variable).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = user_cache_dir()?;
+    if tool == "ccache" {
+        let legacy = home_dir().map(|home| home.join(".ccache")).filter(|dir| dir.is_dir());
+        return legacy.or_else(|| Some(base.join("ccache")));
+    }
+    if cfg!(windows) {
+        Some(base.join("Mozilla").join("sccache").join("cache"))
+    } else if cfg!(target_os = "macos") {
+        Some(base.join("Mozilla.sccache"))
+    } else {
+        Some(base.join("sccache"))
+    }
+}
+
+/// Each tool's cache directory, looked up once rather than for every file.
+static CACHE_DIRS: LazyLock<Vec<(&str, PathBuf)>> =
+    LazyLock::new(|| TOOLS.iter().filter_map(|&tool| Some((tool, cache_dir(tool)?))).collect());
+
+/// The compiler cache `path` is in, by the tools' default locations and
+/// `CCACHE_DIR`/`SCCACHE_DIR`, leaving out ccache's configuration.
+pub fn compiler_cache(path: &Path) -> Option<&'static str> {
+    // The configuration lives in the cache directory, but isn't cache
+    if path.file_name().is_some_and(|name| name == "ccache.conf") {
+        return None;
+    }
+    if let Some((tool, _)) = CACHE_DIRS.iter().find(|(_, dir)| path.starts_with(dir)) {
+        return Some(tool);
+    }
+
+    let dirs: Vec<String> = path
+        .parent()?
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(lowercase_name(name)),
+            _ => None,
+        })
+        .collect();
+    let has = |run: &[&str]| dirs.windows(run.len()).any(|window| window == run);
+    if has(&[".ccache"]) || has(&[".cache", "ccache"]) || has(&["caches", "ccache"]) {
+        Some("ccache")
+    } else if has(&[".cache", "sccache"]) || has(&["caches", "mozilla.sccache"]) || has(&["mozilla", "sccache", "cache"]) {
+        Some("sccache")
+    } else {
+        None
+    }
+}
+
+/// Parses ccache's sizes, where `k`, `M`, `G` and `T` are 1000-based, `Ki`
+/// and so on 1024-based, a `B` may follow, and a bare number is in GB.
+fn parse_ccache_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let unit = unit.trim();
+    let unit = unit.strip_suffix('B').unwrap_or(unit);
+    let multiplier = match unit {
+        "" | "G" => 1e9,
+        "k" | "K" => 1e3,
+        "M" => 1e6,
+        "T" => 1e12,
+        "Ki" => 1024.0,
+        "Mi" => (1u64 << 20) as f64,
+        "Gi" => (1u64 << 30) as f64,
+        "Ti" => (1u64 << 40) as f64,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+/// The size `tool` trims its cache to, asking the tool itself where it's
+/// installed and falling back to its configuration and defaults. `None`
+/// means there's no limit.
+pub fn size_limit(tool: &str) -> Option<u64> {
+    if tool == "sccache" {
+        return match env::var("SCCACHE_CACHE_SIZE") {
+            Ok(size) => parse_size(&size).ok(),
+            Err(_) => Some(SCCACHE_DEFAULT_LIMIT),
+        };
+    }
+
+    let configured = Command::new("ccache")
+        .args(["--get-config", "max_size"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .or_else(|| env::var("CCACHE_MAXSIZE").ok())
+        .or_else(|| {
+            let config = std::fs::read_to_string(cache_dir("ccache")?.join("ccache.conf")).ok()?;
+            config.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "max_size").then(|| value.trim().to_string())
+            })
+        });
+    match configured {
+        // A limit of 0 turns trimming off
+        Some(size) => parse_ccache_size(&size).filter(|&limit| limit > 0),
+        None => Some(CCACHE_DEFAULT_LIMIT),
+    }
+}
+
+/// Whether `tool` is installed, so it can clean its own cache.
+pub fn is_installed(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ccache_sizes() {
+        assert_eq!(parse_ccache_size("5.0G"), Some(5_000_000_000));
+        assert_eq!(parse_ccache_size("5.0 GB"), Some(5_000_000_000));
+        assert_eq!(parse_ccache_size("500M"), Some(500_000_000));
+        assert_eq!(parse_ccache_size("2Gi"), Some(2 << 30));
+        assert_eq!(parse_ccache_size("20"), Some(20_000_000_000));
+        assert_eq!(parse_ccache_size("lots"), None);
+    }
+
+    #[test]
+    fn finds_compiler_caches() {
+        assert_eq!(compiler_cache(Path::new("/home/alice/.cache/ccache/a/b/0123abcdR")), Some("ccache"));
+        assert_eq!(compiler_cache(Path::new("/home/alice/.ccache/3/4/5678.manifest")), Some("ccache"));
+        assert_eq!(compiler_cache(Path::new("/Users/alice/Library/Caches/Mozilla.sccache/a/b/abcdef")), Some("sccache"));
+        assert_eq!(compiler_cache(Path::new("/home/alice/.cache/ccache/ccache.conf")), None);
+        assert_eq!(compiler_cache(Path::new("/home/alice/src/ccache/main.c")), None);
+    }
+}
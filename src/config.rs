@@ -0,0 +1,795 @@
+//! Loading of the optional `config.toml`.
+//!
+//! Only the small subset of TOML that hica needs is understood: `[section]`
+//! headers, `key = value` pairs, strings, integers, booleans and (possibly
+//! multi-line) arrays.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::classify::CacheCategory;
+use crate::clean::{CleanHooks, RetryPolicy};
+use crate::duration::parse_duration;
+use crate::notify::{EmailSettings, WebhookFormat};
+use crate::paths::{expand_home, home_dir};
+use crate::scan::Staleness;
+use crate::size::parse_size;
+use crate::theme::{parse_color, Theme};
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Paths that must never be deleted, on top of the built-in ones.
+    pub protected: Vec<String>,
+    /// Most bytes a single run may delete.
+    pub max_delete_size: Option<u64>,
+    /// Most files a single run may delete.
+    pub max_delete_count: Option<u64>,
+    /// Deleting more than this asks for typed confirmation.
+    pub confirm_size: Option<u64>,
+    /// How deletions failing with transient errors are retried.
+    pub retry: RetryPolicy,
+    /// Commands run before and after each cleanup.
+    pub clean_hooks: CleanHooks,
+    /// Where runs with `--yes` report their results.
+    pub webhook_url: Option<String>,
+    pub webhook_format: WebhookFormat,
+    /// Where the summary of runs with `--yes` is emailed.
+    pub email: EmailSettings,
+    /// Show a desktop notification when a run finishes (also `--notify`).
+    pub desktop_notify: bool,
+    /// Most filesystem operations per second in `--nice` runs.
+    pub nice_ops_per_second: Option<u32>,
+    /// When cache files count as stale.
+    pub staleness: Staleness,
+    /// Categories smaller than this are collapsed into one line of the summary.
+    pub thresholds: Vec<(CacheCategory, u64)>,
+    /// The secret `--sign` keys reports with.
+    pub signing_key_file: Option<PathBuf>,
+    /// The colors output is printed in.
+    pub theme: Theme,
+    /// Where `hica agent` posts its reports.
+    pub agent_endpoint: Option<String>,
+    /// What identifies this machine in agent reports, instead of the system's machine id.
+    pub agent_machine_id: Option<String>,
+    /// Holds the bearer token agent reports are sent with.
+    pub agent_token_file: Option<PathBuf>,
+    /// A script every cache file a scan finds is passed through.
+    pub policy_script: Option<PathBuf>,
+}
+
+/// A single `key = value` pair together with where it was found.
+struct Entry {
+    section: String,
+    key: String,
+    value: Value,
+    line: usize,
+}
+
+impl Entry {
+    fn name(&self) -> String {
+        if self.section.is_empty() {
+            self.key.clone()
+        } else {
+            format!("{}.{}", self.section, self.key)
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ConfigError {
+        ConfigError { line: self.line, message: message.into() }
+    }
+
+    fn count(&self) -> Result<u64, ConfigError> {
+        match self.value {
+            Value::Integer(n) if n >= 0 => Ok(n as u64),
+            _ => Err(self.error(format!("`{}` must be a non-negative integer", self.name()))),
+        }
+    }
+
+    /// A size given either as a number of bytes or a string like "10GB".
+    fn size(&self) -> Result<u64, ConfigError> {
+        match &self.value {
+            Value::String(s) => parse_size(s).map_err(|e| self.error(format!("`{}`: {}", self.name(), e))),
+            _ => self.count(),
+        }
+    }
+
+    /// A duration given as a string like "250ms" or "2s".
+    fn duration(&self) -> Result<std::time::Duration, ConfigError> {
+        match &self.value {
+            Value::String(s) => parse_duration(s).map_err(|e| self.error(format!("`{}`: {}", self.name(), e))),
+            _ => Err(self.error(format!("`{}` must be a duration string like \"500ms\"", self.name()))),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, ConfigError> {
+        match self.value {
+            Value::Boolean(b) => Ok(b),
+            _ => Err(self.error(format!("`{}` must be true or false", self.name()))),
+        }
+    }
+
+    fn string(&self) -> Result<String, ConfigError> {
+        match &self.value {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err(self.error(format!("`{}` must be a string", self.name()))),
+        }
+    }
+
+    fn color(&self) -> Result<colored::Color, ConfigError> {
+        let name = self.string()?;
+        parse_color(&name).ok_or_else(|| self.error(format!("unknown color `{}` for `{}`, expected a name like \"bright blue\" or \"#rrggbb\"", name, self.name())))
+    }
+
+    fn strings(&self) -> Result<Vec<String>, ConfigError> {
+        let items = match &self.value {
+            Value::Array(items) => items,
+            _ => return Err(self.error(format!("`{}` must be an array of strings", self.name()))),
+        };
+
+        items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s.clone()),
+                _ => Err(self.error(format!("`{}` must be an array of strings", self.name()))),
+            })
+            .collect()
+    }
+}
+
+/// The commented config written by `hica config init`. Every setting is shown
+/// with an example but left commented out, so the defaults still apply.
+pub const TEMPLATE: &str = r##"# hica configuration
+#
+# Uncomment and edit the settings you want to change. Run `hica config check`
+# after editing to validate this file.
+
+[protect]
+# Paths that are never deleted, on top of the built-in ones (~/.ssh, ~/.gnupg,
+# /etc, .git, ...). Entries containing a slash (or starting with ~) protect that
+# path and everything below it; bare names match any file or directory with
+# that name.
+paths = [
+    # "~/projects",
+    # "node_modules",
+]
+
+[clean]
+# Caps on how much a single run may delete. Sizes are 1024-based and accept
+# B, KB, MB, GB and TB suffixes. The --max-delete-size and --max-delete-count
+# options override these.
+# max_delete_size = "50GB"
+# max_delete_count = 100000
+
+# Deleting more than this, like deleting files that only matched by a word in
+# their name, asks you to type the number of files rather than just "y".
+# confirm_size = "20GB"
+
+# Deletions failing because a file is busy (such as a sharing violation on
+# Windows) are retried this many times, waiting retry_delay before the first
+# retry and twice as long before each one after that.
+# retries = 3
+# retry_delay = "100ms"
+
+# Shell commands run before and after anything is deleted, such as stopping
+# the service that writes the logs and starting it again. If pre_hook fails,
+# hica deletes nothing. They get HICA_ROOT, HICA_FILES and HICA_BYTES, and
+# post_hook also HICA_DELETED, HICA_FREED and HICA_FAILED. `hica clean`,
+# `hica system --clean` and `hica apps --clean` run them too, without
+# HICA_ROOT and counting locations in HICA_FILES.
+# pre_hook = "systemctl stop myapp"
+# post_hook = "systemctl start myapp; df -h /var | logger -t hica"
+
+[notify]
+# Show a desktop notification when a run finishes, like --notify.
+# desktop = true
+
+# Post the results of runs with --yes (such as scheduled ones) to a webhook,
+# as hica's own JSON ("json"), a Slack message with a breakdown by category
+# ("slack", which compatible webhooks show as plain text) or a Discord embed
+# ("discord").
+# webhook_url = "https://hooks.slack.com/services/..."
+# webhook_format = "slack"
+
+[email]
+# Email the summary of runs with --yes, with the full report attached as
+# JSON. smtps:// connects with TLS; smtp:// upgrades with STARTTLS, and
+# insists on it when a user is set. The password is read from password_file.
+# smtp_url = "smtps://smtp.example.com:465"
+# from = "hica@example.com"
+# to = ["admins@example.com"]
+# user = "hica@example.com"
+# password_file = "~/.config/hica/smtp.password"
+
+[stale]
+# Cache files unused for this long count as stale. Summaries show how much of
+# each category is stale, and `hica detect --stale` only flags those files.
+# after = "30d"
+# Count reads as use too, going by access times (like --atime). Many systems
+# mount with relatime, which updates them at most once a day.
+# use_atime = true
+
+[thresholds]
+# Categories smaller than their threshold are summed up in one "below
+# threshold" line of the summary, so it shows what matters on this machine.
+# Keys are category names as --category takes them.
+# log = "100MB"
+# thumbnail = "500MB"
+
+[sign]
+# The secret --sign keys reports with, e.g. 32 random bytes from
+# `head -c 32 /dev/urandom > ~/.config/hica/signing.key`. Keep it readable
+# only by whoever runs and verifies the cleanups.
+# key_file = "~/.config/hica/signing.key"
+
+[agent]
+# Where `hica agent` posts the summary of each scan, for a dashboard of many
+# machines. Reports carry the machine id from /etc/machine-id unless
+# machine_id is set. With token_file, they're sent with the token it holds
+# as "Authorization: Bearer <token>".
+# endpoint = "https://hica.example.com/api/reports"
+# machine_id = "build-runner-7"
+# token_file = "~/.config/hica/agent.token"
+
+[policy]
+# An executable every cache file a scan finds is passed through, which can
+# leave files out or change their category or risk. It runs with whatever
+# interpreter its #! line names; see "Policy Scripts" in the README.
+# script = "~/.config/hica/policy.lua"
+
+[nice]
+# Runs with --nice already use the lowest CPU and disk priority. This also
+# caps how many files and directories they look at per second.
+# ops_per_second = 500
+
+[theme]
+# The colors of sizes, categories, risk levels and file names. Start from a
+# palette: "default", "light" for light backgrounds, or "colorblind", which
+# doesn't rely on telling red from green. Then change single colors, by name
+# ("red", "bright blue", ...) or as "#rrggbb" on terminals with 24-bit color.
+# palette = "light"
+# sizes = ["purple", "blue", "green", "yellow", "red"]  # B, KB, MB, GB, TB
+# category = "magenta"
+# risk_safe = "green"
+# risk_likely = "yellow"
+# risk_risky = "red"
+# file_name = "yellow"
+"##;
+
+/// Where the config file lives when `--config` isn't given.
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        None => home_dir()?.join(".config"),
+    };
+
+    Some(base.join("hica").join("config.toml"))
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the default location when no path
+    /// is given. A missing default config is not an error.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(source) => Config::parse(&source).map_err(|e| ConfigError {
+                line: e.line,
+                message: format!("{}: {}", path.display(), e.message),
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Ok(Config::default()),
+            Err(e) => Err(ConfigError {
+                line: 0,
+                message: format!("failed to read {}: {}", path.display(), e),
+            }),
+        }
+    }
+
+    /// Reads the config file for `hica config check`, returning where it was
+    /// found along with everything wrong with it.
+    pub fn check(path: Option<&Path>) -> Result<(PathBuf, Config, Vec<ConfigError>), ConfigError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => default_path().ok_or_else(|| ConfigError {
+                line: 0,
+                message: "could not determine the config directory".to_string(),
+            })?,
+        };
+
+        let source = fs::read_to_string(&path).map_err(|e| ConfigError {
+            line: 0,
+            message: format!("failed to read {}: {}", path.display(), e),
+        })?;
+        let (config, errors) = Config::parse_all(&source);
+        Ok((path, config, errors))
+    }
+
+    pub fn parse(source: &str) -> Result<Config, ConfigError> {
+        let (config, mut errors) = Config::parse_all(source);
+        match errors.is_empty() {
+            true => Ok(config),
+            false => Err(errors.remove(0)),
+        }
+    }
+
+    /// Parses as much of the config as possible, collecting every error
+    /// instead of stopping at the first, for `hica config check`.
+    pub fn parse_all(source: &str) -> (Config, Vec<ConfigError>) {
+        let mut config = Config::default();
+        let (mut entries, mut errors) = parse_entries(source);
+        // A palette is the base the other theme colors change, wherever it's set
+        entries.sort_by_key(|entry| !(entry.section == "theme" && entry.key == "palette"));
+
+        for entry in entries {
+            if let Err(e) = config.apply(&entry) {
+                errors.push(e);
+            }
+        }
+
+        errors.sort_by_key(|e| e.line);
+        (config, errors)
+    }
+
+    fn apply(&mut self, entry: &Entry) -> Result<(), ConfigError> {
+        match (entry.section.as_str(), entry.key.as_str()) {
+            ("protect", "paths") => self.protected = entry.strings()?,
+            ("clean", "max_delete_size") => self.max_delete_size = Some(entry.size()?),
+            ("clean", "max_delete_count") => self.max_delete_count = Some(entry.count()?),
+            ("clean", "confirm_size") => self.confirm_size = Some(entry.size()?),
+            ("clean", "retries") => {
+                self.retry.retries = u32::try_from(entry.count()?).map_err(|_| entry.error(format!("`{}` must be at most {}", entry.name(), u32::MAX)))?;
+            }
+            ("clean", "retry_delay") => self.retry.delay = entry.duration()?,
+            ("clean", "pre_hook") => self.clean_hooks.pre = Some(entry.string()?),
+            ("clean", "post_hook") => self.clean_hooks.post = Some(entry.string()?),
+            ("notify", "webhook_url") => {
+                let url = entry.string()?;
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    return Err(entry.error("`notify.webhook_url` must be an http:// or https:// URL"));
+                }
+                self.webhook_url = Some(url);
+            }
+            ("notify", "desktop") => self.desktop_notify = entry.boolean()?,
+            ("notify", "webhook_format") => {
+                self.webhook_format = WebhookFormat::parse(&entry.string()?)
+                    .ok_or_else(|| entry.error("`notify.webhook_format` must be \"json\", \"slack\" or \"discord\""))?
+            }
+            ("email", "smtp_url") => {
+                let url = entry.string()?;
+                if !url.starts_with("smtps://") && !url.starts_with("smtp://") {
+                    return Err(entry.error("`email.smtp_url` must be an smtp:// or smtps:// URL"));
+                }
+                self.email.smtp_url = Some(url);
+            }
+            ("email", "from") => self.email.from = Some(entry.string()?),
+            ("email", "to") => self.email.to = entry.strings()?,
+            ("email", "user") => self.email.user = Some(entry.string()?),
+            ("email", "password_file") => self.email.password_file = Some(expand_home(&entry.string()?)),
+            ("stale", "after") => self.staleness.after = entry.duration()?,
+            ("stale", "use_atime") => self.staleness.use_atime = entry.boolean()?,
+            ("thresholds", category) => {
+                let category = CacheCategory::from_str(category, true)
+                    .map_err(|_| entry.error(format!("unknown category `{}` in [thresholds]", category)))?;
+                self.thresholds.push((category, entry.size()?));
+            }
+            ("sign", "key_file") => self.signing_key_file = Some(expand_home(&entry.string()?)),
+            ("agent", "endpoint") => {
+                let url = entry.string()?;
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    return Err(entry.error("`agent.endpoint` must be an http:// or https:// URL"));
+                }
+                self.agent_endpoint = Some(url);
+            }
+            ("agent", "machine_id") => self.agent_machine_id = Some(entry.string()?),
+            ("agent", "token_file") => self.agent_token_file = Some(expand_home(&entry.string()?)),
+            ("policy", "script") => self.policy_script = Some(expand_home(&entry.string()?)),
+            ("nice", "ops_per_second") => match entry.count()? {
+                0 => return Err(entry.error("`nice.ops_per_second` must be at least 1")),
+                n => self.nice_ops_per_second = Some(n.min(u32::MAX as u64) as u32),
+            },
+            ("theme", "palette") => {
+                self.theme = Theme::palette(&entry.string()?).ok_or_else(|| {
+                    entry.error(format!("`theme.palette` must be one of {}", Theme::PALETTES.map(|name| format!("\"{}\"", name)).join(", ")))
+                })?
+            }
+            ("theme", "sizes") => {
+                let colors = entry.strings()?;
+                if colors.len() != self.theme.sizes.len() {
+                    return Err(entry.error("`theme.sizes` must list 5 colors, for B, KB, MB, GB and TB"));
+                }
+                for (slot, name) in self.theme.sizes.iter_mut().zip(colors) {
+                    *slot = parse_color(&name).ok_or_else(|| entry.error(format!("unknown color `{}` in `theme.sizes`", name)))?;
+                }
+            }
+            ("theme", "category") => self.theme.category = entry.color()?,
+            ("theme", "risk_safe") => self.theme.risks[0] = entry.color()?,
+            ("theme", "risk_likely") => self.theme.risks[1] = entry.color()?,
+            ("theme", "risk_risky") => self.theme.risks[2] = entry.color()?,
+            ("theme", "file_name") => self.theme.name = entry.color()?,
+            _ => return Err(entry.error(format!("unknown setting `{}`", entry.name()))),
+        }
+        Ok(())
+    }
+}
+
+/// Splits the source into entries, skipping (and reporting) lines that
+/// don't parse so the rest of the file is still checked.
+fn parse_entries(source: &str) -> (Vec<Entry>, Vec<ConfigError>) {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut errors = Vec::new();
+    let mut section = String::new();
+    let mut lines = source.lines().enumerate();
+
+    while let Some((index, raw)) = lines.next() {
+        match parse_line(index + 1, raw, &mut lines, &mut section) {
+            Ok(Some(entry)) if entries.iter().any(|e| e.section == entry.section && e.key == entry.key) => {
+                errors.push(entry.error(format!("duplicate setting `{}`", entry.key)));
+            }
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+
+    (entries, errors)
+}
+
+/// Parses one line, and the lines after it for a multi-line array. Section
+/// headers update `section` and yield no entry.
+fn parse_line<'a>(
+    line_no: usize,
+    raw: &str,
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    section: &mut String,
+) -> Result<Option<Entry>, ConfigError> {
+    let error = |message: String| ConfigError { line: line_no, message };
+    let line = strip_comment(raw).trim();
+
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(header) = line.strip_prefix('[') {
+        let name = header
+            .strip_suffix(']')
+            .ok_or_else(|| error("unterminated section header".to_string()))?
+            .trim();
+        // Even when invalid, later keys belong to this section and not the previous one
+        *section = name.to_string();
+        if name.is_empty() || !name.split('.').all(is_bare_key) {
+            return Err(error(format!("invalid section name `{}`", name)));
+        }
+        return Ok(None);
+    }
+
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| error(format!("expected `key = value`, found `{}`", line)))?;
+    let key = key.trim();
+    if !is_bare_key(key) {
+        return Err(error(format!("invalid key `{}`", key)));
+    }
+
+    // Arrays may span several lines; keep reading until the brackets balance
+    let mut value = value.trim().to_string();
+    while value.starts_with('[') && !brackets_balanced(&value) {
+        match lines.next() {
+            Some((_, next)) => {
+                value.push(' ');
+                value.push_str(strip_comment(next).trim());
+            }
+            None => return Err(error(format!("unterminated array for `{}`", key))),
+        }
+    }
+
+    let (parsed, rest) = parse_value(&value).map_err(error)?;
+    if !rest.trim().is_empty() {
+        return Err(error(format!("unexpected `{}` after value", rest.trim())));
+    }
+
+    Ok(Some(Entry {
+        section: section.clone(),
+        key: key.to_string(),
+        value: parsed,
+        line: line_no,
+    }))
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Removes a trailing `# comment`, ignoring `#` inside strings.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '#' => return &line[..i],
+                _ => {}
+            },
+        }
+    }
+
+    line
+}
+
+fn brackets_balanced(value: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            },
+        }
+    }
+
+    depth <= 0
+}
+
+/// Parses one value from the start of `input`, returning it and the rest.
+fn parse_value(input: &str) -> Result<(Value, &str), String> {
+    let input = input.trim_start();
+
+    if let Some(rest) = input.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('\\') => value.push('\\'),
+                    Some('"') => value.push('"'),
+                    Some(other) => return Err(format!("unknown escape `\\{}`", other)),
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+
+    if let Some(rest) = input.strip_prefix('\'') {
+        return match rest.find('\'') {
+            Some(end) => Ok((Value::String(rest[..end].to_string()), &rest[end + 1..])),
+            None => Err("unterminated string".to_string()),
+        };
+    }
+
+    if let Some(mut rest) = input.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+
+            let (item, after) = parse_value(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err("expected `,` or `]` in array".to_string());
+            }
+        }
+    }
+
+    let end = input
+        .find(|c: char| c == ',' || c == ']' || c.is_whitespace())
+        .unwrap_or(input.len());
+    let (token, rest) = input.split_at(end);
+
+    match token {
+        "true" => Ok((Value::Boolean(true), rest)),
+        "false" => Ok((Value::Boolean(false), rest)),
+        "" => Err("missing value".to_string()),
+        _ => token
+            .replace('_', "")
+            .parse::<i64>()
+            .map(|n| (Value::Integer(n), rest))
+            .map_err(|_| format!("invalid value `{}` (strings must be quoted)", token)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_protected_paths() {
+        let source = r#"
+            # Never touch these
+            [protect]
+            paths = [
+                "~/projects",   # work
+                '/srv/data',
+            ]
+        "#;
+
+        let config = Config::parse(source).unwrap();
+        assert_eq!(config.protected, vec!["~/projects", "/srv/data"]);
+    }
+
+    #[test]
+    fn parses_delete_limits() {
+        let config = Config::parse("[clean]\nmax_delete_size = \"2GB\"\nmax_delete_count = 500\n").unwrap();
+        assert_eq!(config.max_delete_size, Some(2 << 30));
+        assert_eq!(config.max_delete_count, Some(500));
+
+        let config = Config::parse("[clean]\nconfirm_size = \"20GB\"\n").unwrap();
+        assert_eq!(config.confirm_size, Some(20 << 30));
+
+        let config = Config::parse("[clean]\nretries = 5\nretry_delay = \"250ms\"\n").unwrap();
+        assert_eq!(config.retry.retries, 5);
+        assert!(Config::parse("[clean]\nretries = 4294967296\n").is_err());
+        assert_eq!(config.retry.delay, std::time::Duration::from_millis(250));
+
+        let config = Config::parse("[clean]\npre_hook = \"systemctl stop app\"\npost_hook = \"systemctl start app\"\n").unwrap();
+        assert_eq!(config.clean_hooks.pre.as_deref(), Some("systemctl stop app"));
+        assert_eq!(config.clean_hooks.post.as_deref(), Some("systemctl start app"));
+
+        assert!(Config::parse("[clean]\nmax_delete_count = -1\n").is_err());
+
+        let config = Config::parse("[nice]\nops_per_second = 200\n").unwrap();
+        assert_eq!(config.nice_ops_per_second, Some(200));
+        assert!(Config::parse("[nice]\nops_per_second = 0\n").is_err());
+    }
+
+    #[test]
+    fn parses_thresholds() {
+        let config = Config::parse("[thresholds]\nlog = \"100MB\"\ncrashdump = 0\n").unwrap();
+        assert_eq!(config.thresholds, vec![(CacheCategory::Log, 100 << 20), (CacheCategory::CrashDump, 0)]);
+        assert!(Config::parse("[thresholds]\nlogs = \"1GB\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_staleness() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.staleness.after, std::time::Duration::from_secs(30 * 24 * 60 * 60));
+
+        let config = Config::parse("[stale]\nafter = \"2w\"\nuse_atime = true\n").unwrap();
+        assert_eq!(config.staleness.after, std::time::Duration::from_secs(14 * 24 * 60 * 60));
+        assert!(config.staleness.use_atime);
+        assert!(Config::parse("[stale]\nafter = 30\n").is_err());
+    }
+
+    #[test]
+    fn parses_webhook() {
+        let config = Config::parse("[notify]\nwebhook_url = \"https://hooks.example.com/x\"\nwebhook_format = \"slack\"\ndesktop = true\n").unwrap();
+        assert_eq!(config.webhook_url.as_deref(), Some("https://hooks.example.com/x"));
+        assert_eq!(config.webhook_format, WebhookFormat::Slack);
+        assert!(config.desktop_notify);
+
+        assert!(Config::parse("[notify]\nwebhook_format = \"xml\"\n").is_err());
+
+        let config = Config::parse("[email]\nsmtp_url = \"smtps://mail.example.com\"\nfrom = \"hica@example.com\"\nto = [\"ops@example.com\"]\n").unwrap();
+        assert_eq!(config.email.smtp_url.as_deref(), Some("smtps://mail.example.com"));
+        assert_eq!(config.email.to, ["ops@example.com"]);
+        assert!(Config::parse("[email]\nsmtp_url = \"mail.example.com\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_agent() {
+        let config = Config::parse("[agent]\nendpoint = \"https://fleet.example.com/reports\"\nmachine_id = \"web-1\"\n").unwrap();
+        assert_eq!(config.agent_endpoint.as_deref(), Some("https://fleet.example.com/reports"));
+        assert_eq!(config.agent_machine_id.as_deref(), Some("web-1"));
+        assert!(Config::parse("[agent]\nendpoint = \"fleet.example.com\"\n").is_err());
+
+        let config = Config::parse("[policy]\nscript = \"/etc/hica/policy.lua\"\n").unwrap();
+        assert_eq!(config.policy_script.as_deref(), Some(std::path::Path::new("/etc/hica/policy.lua")));
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let err = Config::parse("[protect]\n\npaths = \"/srv\"\n").unwrap_err();
+        assert_eq!(err.line, 3);
+
+        let err = Config::parse("[protect]\nfoo = 1\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("protect.foo"));
+
+        let err = Config::parse("[protect\n").unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn template_is_valid() {
+        let config = Config::parse(TEMPLATE).unwrap();
+        assert!(config.protected.is_empty());
+        assert_eq!(config.max_delete_size, None);
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let source = "[clean]\nmax_delete_count = \"many\"\nmax_delete_size = 1GB\n[bogus]\nkey = 1\n[notify]\nwebhook_url = \"ftp://x\"\n";
+        let (_, errors) = Config::parse_all(source);
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 5, 7]);
+    }
+
+    #[test]
+    fn parses_theme() {
+        let config = Config::parse("[theme]\ncategory = \"#00ff00\"\npalette = \"light\"\nrisk_risky = \"bright red\"\n").unwrap();
+        let light = Theme::palette("light").unwrap();
+        assert_eq!(config.theme.category, colored::Color::TrueColor { r: 0, g: 255, b: 0 });
+        assert_eq!(config.theme.risks, [light.risks[0], light.risks[1], colored::Color::BrightRed]);
+        assert!(Config::parse("[theme]\npalette = \"neon\"\n").is_err());
+        assert!(Config::parse("[theme]\nsizes = [\"red\"]\n").is_err());
+    }
+
+    #[test]
+    fn parses_scalar_values() {
+        assert_eq!(parse_value("\"a # b\"").unwrap().0, Value::String("a # b".into()));
+        assert_eq!(parse_value("1_000").unwrap().0, Value::Integer(1000));
+        assert_eq!(parse_value("false").unwrap().0, Value::Boolean(false));
+        assert!(parse_value("yes").is_err());
+    }
+}
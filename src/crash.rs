@@ -0,0 +1,91 @@
+//! Crash dumps and reports, which pile up unnoticed and are often the largest
+//! files on a disk: telling which program left them behind helps decide
+//! whether they're still needed for a bug report.
+
+use std::path::Path;
+
+/// The program a crash dump or report belongs to, going by the naming scheme
+/// of the tool that wrote it:
+///
+/// - systemd-coredump: `core.<program>.<uid>.<boot id>.<pid>.<time>[.zst]`
+/// - apport: `_usr_bin_<program>.<uid>.crash`
+/// - macOS: `<Program>-<yyyy>-<mm>-<dd>-<hhmmss>.ips` (or `.crash`)
+/// - Windows Error Reporting: `<program>.exe.<pid>.dmp`
+///
+/// Plain `core` and `core.<pid>` files don't say.
+pub fn crashed_program(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+
+    if let Some(rest) = name.strip_prefix("core.") {
+        let program = rest.split('.').next()?;
+        // `core.<pid>` only has a number where the program name would be
+        return (!program.is_empty() && !program.bytes().all(|b| b.is_ascii_digit())).then(|| unescape_systemd(program));
+    }
+
+    if let Some(stem) = name.strip_suffix(".crash")
+        && stem.starts_with('_')
+    {
+        let executable = stem.rsplit_once('.').map_or(stem, |(executable, _uid)| executable);
+        return Some(executable.replace('_', "/"));
+    }
+
+    if let Some(stem) = name.strip_suffix(".ips").or_else(|| name.strip_suffix(".crash")) {
+        return dated_prefix(stem).map(str::to_string);
+    }
+
+    let stem = name.strip_suffix(".dmp")?;
+    let (program, pid) = stem.rsplit_once('.')?;
+    (pid.bytes().all(|b| b.is_ascii_digit()) && program.to_lowercase().ends_with(".exe")).then(|| program.to_string())
+}
+
+/// The part of a macOS report name before its `-yyyy-mm-dd-` timestamp.
+fn dated_prefix(stem: &str) -> Option<&str> {
+    let bytes = stem.as_bytes();
+    (1..bytes.len()).find_map(|i| {
+        let date = bytes.get(i..i + 12)?;
+        let digits = |range: std::ops::Range<usize>| date[range].iter().all(u8::is_ascii_digit);
+        let is_date = date[0] == b'-' && digits(1..5) && date[5] == b'-' && digits(6..8) && date[8] == b'-' && digits(9..11) && date[11] == b'-';
+        is_date.then(|| &stem[..i])
+    })
+}
+
+/// systemd escapes unusual characters in the program name as `\xNN`.
+fn unescape_systemd(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(start) = rest.find("\\x") {
+        unescaped.push_str(&rest[..start]);
+        let escaped = rest.get(start + 2..start + 4).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if byte.is_ascii() => {
+                unescaped.push(byte as char);
+                rest = &rest[start + 4..];
+            }
+            _ => {
+                unescaped.push_str("\\x");
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_crashed_program() {
+        let program = |path: &str| crashed_program(Path::new(path));
+        assert_eq!(
+            program("/var/lib/systemd/coredump/core.gnome\\x2dshell.1000.3f2a.4242.1760486400000000.zst").as_deref(),
+            Some("gnome-shell")
+        );
+        assert_eq!(program("/var/crash/_usr_bin_python3.12.1000.crash").as_deref(), Some("/usr/bin/python3.12"));
+        assert_eq!(program("/Users/alice/Library/Logs/DiagnosticReports/Safari-2025-10-15-093012.ips").as_deref(), Some("Safari"));
+        assert_eq!(program("C:/Users/alice/AppData/Local/CrashDumps/app.exe.4242.dmp").as_deref(), Some("app.exe"));
+        assert_eq!(program("/home/alice/core.4242"), None);
+        assert_eq!(program("/home/alice/core"), None);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::scan::CacheFile;
+
+/// How much of each file is hashed before deciding whether to read all of it.
+const HEAD_SIZE: u64 = 64 * 1024;
+
+/// Files with identical contents.
+#[derive(Debug)]
+pub struct DuplicateSet {
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// Bytes that would be saved by keeping a single copy.
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+async fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<u64> {
+    let mut file = File::open(path).await?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut remaining = limit.unwrap_or(u64::MAX);
+
+    while remaining > 0 {
+        let want = buffer.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let read = file.read(&mut buffer[..want]).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+        remaining -= read as u64;
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Whether the two files hold the same bytes, read side by side to the end.
+async fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (File::open(a).await?, File::open(b).await?);
+    let (mut buffer_a, mut buffer_b) = (vec![0u8; 64 * 1024], vec![0u8; 64 * 1024]);
+
+    loop {
+        let read = a.read(&mut buffer_a).await?;
+        if read == 0 {
+            // Nothing left of `a`, so they match if nothing's left of `b` either
+            return Ok(b.read(&mut buffer_b[..1]).await? == 0);
+        }
+        if b.read_exact(&mut buffer_b[..read]).await.is_err() || buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Splits each group of files with matching hashes into the files that are
+/// really identical, since a 64-bit hash alone can collide.
+async fn confirm(groups: Vec<Vec<&CacheFile>>) -> Vec<Vec<&CacheFile>> {
+    let mut result = Vec::new();
+
+    for group in groups {
+        let mut copies: Vec<Vec<&CacheFile>> = Vec::new();
+        for file in group {
+            let mut placed = false;
+            for set in &mut copies {
+                if same_contents(&set[0].path, &file.path).await.unwrap_or(false) {
+                    set.push(file);
+                    placed = true;
+                    break;
+                }
+            }
+            if !placed {
+                copies.push(vec![file]);
+            }
+        }
+        result.extend(copies.into_iter().filter(|files| files.len() > 1));
+    }
+
+    result
+}
+
+/// Splits each group into subgroups of files with the same hash, dropping
+/// files that turn out to be unique.
+async fn regroup(groups: Vec<Vec<&CacheFile>>, limit: Option<u64>) -> Vec<Vec<&CacheFile>> {
+    let mut result = Vec::new();
+
+    for group in groups {
+        let mut by_hash: HashMap<u64, Vec<&CacheFile>> = HashMap::new();
+        for file in group {
+            if let Ok(hash) = hash_file(&file.path, limit).await {
+                by_hash.entry(hash).or_default().push(file);
+            }
+        }
+        result.extend(by_hash.into_values().filter(|files| files.len() > 1));
+    }
+
+    result
+}
+
+/// Finds sets of cache files with identical contents, largest waste first.
+///
+/// Files are grouped by size, then by a hash of their first 64 KiB, and only
+/// the remaining candidates are hashed in full and then compared byte for
+/// byte.
+pub async fn find_duplicates(cache_files: &[CacheFile]) -> Vec<DuplicateSet> {
+    let mut by_size: HashMap<u64, Vec<&CacheFile>> = HashMap::new();
+    let mut seen_links = std::collections::HashSet::new();
+
+    for file in cache_files {
+        // Empty files share nothing, and hard links to one file aren't copies
+        if file.size == 0 || file.link_id.is_some_and(|id| !seen_links.insert(id)) {
+            continue;
+        }
+        by_size.entry(file.size).or_default().push(file);
+    }
+
+    let candidates: Vec<Vec<&CacheFile>> = by_size.into_values().filter(|files| files.len() > 1).collect();
+    let mut groups = regroup(candidates, Some(HEAD_SIZE)).await;
+
+    // Files no bigger than the head have already been hashed in full
+    let (small, large): (Vec<_>, Vec<_>) = groups.drain(..).partition(|files| files[0].size <= HEAD_SIZE);
+    groups = small;
+    groups.extend(regroup(large, None).await);
+
+    let mut sets: Vec<DuplicateSet> = confirm(groups)
+        .await
+        .into_iter()
+        .map(|files| {
+            let mut paths: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+            paths.sort();
+            DuplicateSet { size: files[0].size, paths }
+        })
+        .collect();
+    sets.sort_by_key(|set| std::cmp::Reverse(set.wasted()));
+    sets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{CacheCategory, Risk};
+
+    fn cache_file(path: PathBuf) -> CacheFile {
+        let size = std::fs::metadata(&path).unwrap().len();
+        CacheFile { path, size, category: CacheCategory::Temporary, risk: Risk::Likely, link_id: None, modified: None, accessed: None }
+    }
+
+    #[tokio::test]
+    async fn reports_only_identical_files() {
+        let dir = std::env::temp_dir().join(format!("hica-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let large: Vec<u8> = (0..HEAD_SIZE as usize * 2).map(|i| (i % 251) as u8).collect();
+        let mut large_changed = large.clone();
+        *large_changed.last_mut().unwrap() ^= 1;
+        let contents: [(&str, &[u8]); 6] = [
+            ("a", b"same contents"),
+            ("b", b"same contents"),
+            ("c", b"same length!!"),
+            ("d", b"shorter"),
+            ("large", &large),
+            ("large-changed", &large_changed),
+        ];
+        for (name, data) in contents {
+            std::fs::write(dir.join(name), data).unwrap();
+        }
+        let files: Vec<CacheFile> = contents.iter().map(|(name, _)| cache_file(dir.join(name))).collect();
+
+        let sets = find_duplicates(&files).await;
+        let same = same_contents(&dir.join("a"), &dir.join("b")).await.unwrap();
+        let different = same_contents(&dir.join("a"), &dir.join("c")).await.unwrap();
+        let prefix = same_contents(&dir.join("a"), &dir.join("d")).await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].paths, [dir.join("a"), dir.join("b")]);
+        assert!(same);
+        assert!(!different);
+        assert!(!prefix);
+    }
+}
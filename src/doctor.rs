@@ -0,0 +1,247 @@
+//! `hica doctor`: a look at everything a run depends on, from the config to
+//! the tools hica calls, as the first stop when something doesn't work.
+
+use std::path::{Path, PathBuf};
+
+use crate::cleaners::home_trash;
+use crate::config::{self, Config};
+use crate::mounts::{self, Mount};
+use crate::platform::{self, find_program};
+use crate::plugins;
+use crate::schedule::Backend;
+use crate::size::format_size;
+use crate::tr;
+use crate::walker;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Ok,
+    Note,
+    Warning,
+    Failed,
+}
+
+/// One thing checked, and what came of it.
+#[derive(Debug)]
+pub struct Finding {
+    pub status: Status,
+    pub message: String,
+}
+
+fn finding(status: Status, message: impl Into<String>) -> Finding {
+    Finding { status, message: message.into() }
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|metadata| std::os::unix::fs::MetadataExt::dev(&metadata))
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Whether `a` and `b` are on the same filesystem, so moving files between
+/// them is a rename rather than a copy. None where it can't be told.
+fn same_filesystem(a: &Path, b: &Path) -> Option<bool> {
+    Some(device_of(a)? == device_of(b)?)
+}
+
+/// Whether the config file parses, and whether what it points to is there.
+pub fn check_config(path: Option<&Path>) -> Vec<Finding> {
+    let Some(path) = path.map(Path::to_path_buf).or_else(config::default_path) else {
+        return vec![finding(Status::Failed, tr!("doctor.no-config-dir"))];
+    };
+    if !path.exists() {
+        return vec![finding(Status::Note, tr!("doctor.no-config", path.display()))];
+    }
+    let (config, errors) = match Config::check(Some(&path)) {
+        Ok((_, config, errors)) => (config, errors),
+        Err(e) => return vec![finding(Status::Failed, e.message)],
+    };
+
+    let mut findings: Vec<Finding> = errors.iter().map(|e| finding(Status::Failed, format!("{}:{}: {}", path.display(), e.line, e.message))).collect();
+    if errors.is_empty() {
+        findings.push(finding(Status::Ok, tr!("doctor.config-valid", path.display())));
+    }
+    for entry in &config.protected {
+        let expanded = crate::paths::expand_home(entry);
+        if entry.contains(['/', '\\']) && !expanded.exists() {
+            findings.push(finding(Status::Note, tr!("doctor.protected-missing", expanded.display())));
+        }
+    }
+    let secrets = [
+        ("sign.key_file", config.signing_key_file.as_ref()),
+        ("agent.token_file", config.agent_token_file.as_ref()),
+        ("email.password_file", config.email.password_file.as_ref()),
+    ];
+    for (key, file) in secrets.into_iter().filter_map(|(key, file)| Some((key, file?))) {
+        if let Err(e) = std::fs::read(file) {
+            findings.push(finding(Status::Failed, tr!("doctor.secret-unreadable", key, file.display(), e)));
+        }
+    }
+    if let Some(script) = &config.policy_script {
+        match plugins::is_executable(script) {
+            true => findings.push(finding(Status::Ok, tr!("doctor.policy-ok", script.display()))),
+            false => findings.push(finding(Status::Failed, tr!("doctor.policy-missing", script.display()))),
+        }
+    }
+    if let Some(dir) = path.parent().map(|dir| dir.join("plugins")) {
+        let (loaded, failures) = plugins::load(&dir);
+        for plugin in loaded {
+            findings.push(finding(Status::Ok, tr!("doctor.plugin", plugin.name, plugin.roots.len())));
+        }
+        for failure in failures {
+            findings.push(finding(Status::Warning, tr!("doctor.plugin-failed", failure)));
+        }
+    }
+
+    let needs_curl = [
+        (config.webhook_url.is_some(), "notify.webhook_url"),
+        (config.email.smtp_url.is_some(), "email.smtp_url"),
+        (config.agent_endpoint.is_some(), "agent.endpoint"),
+    ];
+    let uses: Vec<&str> = needs_curl.iter().filter(|(set, _)| *set).map(|(_, key)| *key).collect();
+    if !uses.is_empty() && find_program("curl").is_none() {
+        findings.push(finding(Status::Failed, tr!("doctor.no-curl", uses.join("`, `"))));
+    }
+    findings
+}
+
+/// Whether `root` can be scanned, and where files moved out of it would go.
+pub fn check_root(root: &Path, archive: Option<&Path>) -> Vec<Finding> {
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) => return vec![finding(Status::Failed, tr!("doctor.unreadable", root.display(), e))],
+    };
+    let mut findings = Vec::new();
+    let subdirs: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir())).map(|entry| entry.path()).collect();
+    let unreadable = subdirs.iter().filter(|dir| std::fs::read_dir(dir).is_err()).count();
+    match unreadable {
+        0 => findings.push(finding(Status::Ok, tr!("doctor.readable", root.display(), subdirs.len()))),
+        n => findings.push(finding(Status::Warning, tr!("doctor.some-unreadable", n, subdirs.len(), root.display()))),
+    }
+
+    let mounts = mounts::mounts();
+    let absolute = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    if let Some(mount) = mounts::mount_of(&mounts, &absolute) {
+        findings.push(finding(Status::Note, tr!("doctor.mount", mount.point.display(), mount.fs_type, platform::free_space(root).map_or_else(|| tr!("doctor.unknown-space"), format_size))));
+        if mount.read_only {
+            findings.push(finding(Status::Warning, tr!("doctor.read-only")));
+        }
+        if mount.is_network() {
+            findings.push(finding(Status::Note, tr!("doctor.network")));
+        }
+    }
+    findings.extend(check_trash(root, mounts::mount_of(&mounts, &absolute)));
+    if let Some(archive) = archive {
+        findings.push(match same_filesystem(root, archive) {
+            Some(true) => finding(Status::Note, tr!("doctor.archive-same", archive.display())),
+            Some(false) => finding(Status::Note, tr!("doctor.archive-other", archive.display())),
+            None => finding(Status::Warning, tr!("doctor.archive-unknown", archive.display())),
+        });
+    }
+    findings
+}
+
+/// Where a file manager would move files from `root` when trashing them.
+/// Where files trashed on the volume mounted at `point` go for the user `uid`,
+/// in the order the desktop tries them: `.Trashes/<uid>` on macOS, and the
+/// freedesktop `.Trash/<uid>` or `.Trash-<uid>` elsewhere.
+fn volume_trashes(point: &Path, uid: u32) -> Vec<PathBuf> {
+    if cfg!(target_os = "macos") {
+        return vec![point.join(".Trashes").join(uid.to_string())];
+    }
+    vec![point.join(".Trash").join(uid.to_string()), point.join(format!(".Trash-{}", uid))]
+}
+
+fn check_trash(root: &Path, mount: Option<&Mount>) -> Option<Finding> {
+    if cfg!(windows) {
+        return Some(finding(Status::Ok, tr!("doctor.recycle-bin")));
+    }
+    let trash = home_trash()?;
+    // The trash may not have been made yet, but would be where it's missing
+    let home_side = trash.ancestors().find(|dir| dir.exists())?;
+    if same_filesystem(root, home_side)? {
+        return Some(finding(Status::Ok, tr!("doctor.trash-same", trash.display())));
+    }
+    let volume_trash = mount
+        .zip(platform::current_user_id())
+        .and_then(|(mount, uid)| volume_trashes(&mount.point, uid).into_iter().find(|trash| trash.is_dir()));
+    Some(match volume_trash {
+        Some(trash) => finding(Status::Ok, tr!("doctor.trash-volume", trash.display())),
+        None => finding(Status::Note, tr!("doctor.trash-other", trash.display())),
+    })
+}
+
+/// What this platform and machine offer hica.
+pub fn check_platform() -> Vec<Finding> {
+    let mut findings = vec![
+        finding(Status::Note, tr!("doctor.version", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH)),
+        finding(Status::Note, tr!("doctor.threads", walker::default_jobs())),
+        finding(Status::Note, tr!("doctor.scheduler", format!("{:?}", Backend::detect()).to_lowercase())),
+    ];
+    findings.push(match platform::is_privileged() {
+        true => finding(Status::Note, tr!("doctor.privileged")),
+        false => finding(Status::Note, tr!("doctor.unprivileged")),
+    });
+    if cfg!(windows) {
+        findings.push(finding(Status::Note, tr!("doctor.mft")));
+    }
+    if cfg!(any(target_os = "linux", target_os = "macos")) {
+        findings.push(finding(Status::Note, tr!("doctor.shared")));
+    }
+
+    let mut tools = vec![
+        ("curl", tr!("doctor.for-curl")),
+        ("ssh", tr!("doctor.for-ssh")),
+        ("gzip", "--archive".to_string()),
+    ];
+    if cfg!(target_os = "linux") {
+        tools.extend([("gio", tr!("doctor.for-gio")), ("notify-send", tr!("doctor.for-notify-send"))]);
+    } else if cfg!(target_os = "macos") {
+        tools.push(("osascript", tr!("doctor.for-osascript")));
+    } else if cfg!(windows) {
+        tools.push(("powershell", tr!("doctor.for-powershell")));
+    }
+    for (program, used_for) in tools {
+        findings.push(match find_program(program) {
+            Some(path) => finding(Status::Ok, tr!("doctor.tool-found", program, used_for, path.display())),
+            None => finding(Status::Warning, tr!("doctor.tool-missing", program, used_for)),
+        });
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_roots_and_filesystems() {
+        let dir = std::env::temp_dir().join(format!("hica-doctor-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let findings = check_root(&dir, Some(&dir));
+        let missing = check_root(&dir.join("missing"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(findings[0].status, Status::Ok);
+        assert!(findings[0].message.ends_with("the 1 directories in it are readable"));
+        if cfg!(unix) {
+            assert!(findings.iter().any(|f| f.message.contains("on the same filesystem, so archiving")));
+        }
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].status, Status::Failed);
+    }
+
+    #[test]
+    fn finds_volume_trashes() {
+        let trashes = volume_trashes(Path::new("/Volumes/USB"), 501);
+        if cfg!(target_os = "macos") {
+            assert_eq!(trashes, [PathBuf::from("/Volumes/USB/.Trashes/501")]);
+        } else {
+            assert_eq!(trashes, [PathBuf::from("/Volumes/USB/.Trash/501"), PathBuf::from("/Volumes/USB/.Trash-501")]);
+        }
+    }
+}
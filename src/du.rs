@@ -0,0 +1,133 @@
+//! Disk usage of a whole tree, cache or not, so the caches can be seen next to
+//! everything else that takes up the space.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::classify::is_cache_file;
+use crate::errors::{self, Operation, PathError};
+use crate::interrupt;
+use crate::scan::link_id;
+
+/// Sizes of a part of the tree, with the part of them that's cache.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Usage {
+    pub files: u64,
+    pub bytes: u64,
+    pub cache_files: u64,
+    pub cache_bytes: u64,
+}
+
+impl Usage {
+    fn add(&mut self, bytes: u64, cache: bool) {
+        self.files += 1;
+        self.bytes += bytes;
+        if cache {
+            self.cache_files += 1;
+            self.cache_bytes += bytes;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DiskUsage {
+    pub total: Usage,
+    /// Each entry directly in the root, largest first
+    pub entries: Vec<(PathBuf, Usage)>,
+    /// Set when Ctrl-C stopped the walk, so the sizes are too small
+    pub interrupted: bool,
+    pub errors: Vec<PathError>,
+}
+
+/// Adds up every file below `root` like `du -x`: without following symlinks
+/// or crossing into other filesystems, and counting hard-linked files once.
+/// `on_file` is called with the number of files counted so far.
+pub fn disk_usage(root: &Path, mut on_file: impl FnMut(u64)) -> DiskUsage {
+    let mut usage = DiskUsage::default();
+    let mut linked = HashSet::new();
+    let mut entries: Vec<(PathBuf, Usage)> = Vec::new();
+
+    for entry in WalkDir::new(root).same_file_system(true) {
+        if interrupt::interrupted() {
+            usage.interrupted = true;
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                if let (Some(path), Some(io)) = (e.path(), e.io_error())
+                    && errors::is_reportable(io)
+                {
+                    usage.errors.push(PathError::new(Operation::List, path, io));
+                }
+                continue;
+            }
+        };
+        // Entries directly in the root get a row, even when they hold nothing
+        if entry.depth() == 1 && entry.file_type().is_dir() {
+            entries.push((entry.path().to_path_buf(), Usage::default()));
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                if let Some(io) = e.io_error()
+                    && errors::is_reportable(io)
+                {
+                    usage.errors.push(PathError::new(Operation::Stat, entry.path(), io));
+                }
+                continue;
+            }
+        };
+        if link_id(&metadata).is_some_and(|id| !linked.insert(id)) {
+            continue;
+        }
+
+        let cache = is_cache_file(entry.path());
+        usage.total.add(metadata.len(), cache);
+        if let Ok(relative) = entry.path().strip_prefix(root)
+            && let Some(top) = relative.components().next()
+        {
+            let top = root.join(top);
+            match entries.iter_mut().rev().find(|(path, _)| *path == top) {
+                Some((_, usage)) => usage.add(metadata.len(), cache),
+                None => {
+                    let mut file = Usage::default();
+                    file.add(metadata.len(), cache);
+                    entries.push((top, file));
+                }
+            }
+        }
+        on_file(usage.total.files);
+    }
+
+    entries.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.bytes));
+    usage.entries = entries;
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_usage_by_entry_and_cache() {
+        let root = std::env::temp_dir().join(format!("hica-du-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("project/cache")).unwrap();
+        std::fs::create_dir_all(root.join("empty")).unwrap();
+        std::fs::write(root.join("project/cache/blob"), [0u8; 300]).unwrap();
+        std::fs::write(root.join("project/main.rs"), [0u8; 100]).unwrap();
+        std::fs::write(root.join("notes.txt"), [0u8; 10]).unwrap();
+
+        let usage = disk_usage(&root, |_| {});
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(usage.total, Usage { files: 3, bytes: 410, cache_files: 1, cache_bytes: 300 });
+        let names: Vec<_> = usage.entries.iter().map(|(path, usage)| (path.file_name().unwrap().to_str().unwrap(), usage.bytes)).collect();
+        assert_eq!(names, [("project", 400), ("notes.txt", 10), ("empty", 0)]);
+    }
+}
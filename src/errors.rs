@@ -0,0 +1,114 @@
+//! Problems with single paths during a run: files that couldn't be looked at,
+//! directories that couldn't be listed, deletions that failed. They're
+//! collected as they happen and reported together once the run is done, so
+//! they don't scroll away between the rest of the output.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use colored::Colorize;
+
+use crate::anonymize;
+use crate::paths;
+use crate::report::json_string;
+use crate::tr;
+
+/// What hica was doing when it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    /// Reading a file's metadata
+    Stat,
+    /// Listing a directory
+    List,
+    Delete,
+    Archive,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 4] = [Operation::Stat, Operation::List, Operation::Delete, Operation::Archive];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Stat => "stat",
+            Operation::List => "list",
+            Operation::Delete => "delete",
+            Operation::Archive => "archive",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathError {
+    pub operation: Operation,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl PathError {
+    pub fn new(operation: Operation, path: impl Into<PathBuf>, error: &io::Error) -> Self {
+        PathError { operation, path: path.into(), message: error.to_string() }
+    }
+
+    /// The error as a JSON object, with the path anonymized if reports are.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"operation\":\"{}\",\"path\":{},\"error\":{}}}",
+            self.operation.as_str(),
+            json_string(&anonymize::path(&self.path)),
+            json_string(&anonymize::text(&self.message))
+        )
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to {} {}: {}", self.operation.as_str(), paths::display(&self.path), self.message)
+    }
+}
+
+/// Whether an error is worth reporting. A file that vanished between being
+/// listed and looked at, like a dangling symlink or a cache entry its program
+/// just evicted, isn't a problem.
+pub fn is_reportable(error: &io::Error) -> bool {
+    error.kind() != io::ErrorKind::NotFound
+}
+
+/// How many errors the summary lists before counting the rest.
+const MAX_LISTED: usize = 20;
+
+/// Prints the errors of a run as one table: how many of each kind, then the
+/// first of them with their paths.
+pub fn print_summary(errors: &[PathError]) {
+    if errors.is_empty() {
+        return;
+    }
+
+    println!("\n{} {}", "[Errors]".red().bold(), tr!("errors.title", errors.len().to_string().cyan()));
+    for operation in Operation::ALL {
+        let count = errors.iter().filter(|e| e.operation == operation).count();
+        if count > 0 {
+            println!("  {:<8} {}", operation.as_str(), count.to_string().cyan());
+        }
+    }
+    println!();
+    for error in errors.iter().take(MAX_LISTED) {
+        println!("  {:<8} {}\n           {}", error.operation.as_str(), paths::display(&error.path), error.message.red());
+    }
+    if errors.len() > MAX_LISTED {
+        println!("  ... and {} more", (errors.len() - MAX_LISTED).to_string().cyan());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_errors() {
+        let error = PathError::new(Operation::Delete, "/tmp/a \"b\".log", &io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(error.to_string(), "failed to delete /tmp/a \"b\".log: permission denied");
+        assert_eq!(error.to_json(), r#"{"operation":"delete","path":"/tmp/a \"b\".log","error":"permission denied"}"#);
+        assert!(!is_reportable(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+}
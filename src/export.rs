@@ -0,0 +1,217 @@
+//! Scans saved to a file with `hica export` and read back with `hica import`,
+//! so the cache of a server can be looked into, filtered and planned for on
+//! another machine.
+//!
+//! The file is compact binary: a header, then a record per cache file. Paths
+//! mostly share their start with the one before, so each only stores what
+//! differs, and numbers are LEB128 varints.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::classify::{CacheCategory, Risk};
+use crate::scan::CacheFile;
+
+const MAGIC: &[u8] = b"HICASCAN";
+const VERSION: u8 = 1;
+
+const LINKED: u8 = 1;
+const MODIFIED: u8 = 2;
+const ACCESSED: u8 = 4;
+
+/// A scan as exported: where and when it was taken, and the cache it found.
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub host: String,
+    /// The path scanned, as the machine it was scanned on spells it
+    pub root: PathBuf,
+    pub taken: SystemTime,
+    pub files: Vec<CacheFile>,
+}
+
+impl Export {
+    /// Writes the export to `file`, or to stdout for `-`.
+    pub fn save(&self, file: &Path) -> std::io::Result<()> {
+        if file == Path::new("-") {
+            return std::io::Write::write_all(&mut std::io::stdout().lock(), &self.encode());
+        }
+        crate::report::write_atomically(file, self.encode())
+    }
+
+    /// Reads an export from `file`, or from stdin for `-`.
+    pub fn load(file: &Path) -> Result<Export, String> {
+        let bytes = if file == Path::new("-") {
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut std::io::stdin().lock(), &mut bytes).map(|_| bytes)
+        } else {
+            std::fs::read(file)
+        };
+        Export::decode(&bytes.map_err(|e| e.to_string())?)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_bytes(&mut out, self.host.as_bytes());
+        write_bytes(&mut out, path_bytes(&self.root));
+        write_varint(&mut out, seconds(self.taken));
+        write_varint(&mut out, self.files.len() as u64);
+
+        let mut previous: &[u8] = &[];
+        for file in &self.files {
+            let path = path_bytes(&file.path);
+            let shared = previous.iter().zip(path).take_while(|(a, b)| a == b).count();
+            write_varint(&mut out, shared as u64);
+            write_bytes(&mut out, &path[shared..]);
+            previous = path;
+
+            write_varint(&mut out, file.size);
+            out.push(CacheCategory::ALL.iter().position(|&c| c == file.category).unwrap_or(CacheCategory::ALL.len() - 1) as u8);
+            out.push(Risk::ALL.iter().position(|&r| r == file.risk).unwrap_or(Risk::ALL.len() - 1) as u8);
+            let flags = [(file.link_id.is_some(), LINKED), (file.modified.is_some(), MODIFIED), (file.accessed.is_some(), ACCESSED)];
+            out.push(flags.iter().filter(|(set, _)| *set).fold(0, |all, (_, flag)| all | flag));
+            if let Some((device, inode)) = file.link_id {
+                write_varint(&mut out, device);
+                write_varint(&mut out, inode);
+            }
+            for time in [file.modified, file.accessed].into_iter().flatten() {
+                write_varint(&mut out, seconds(time));
+            }
+        }
+        out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Export, String> {
+        let rest = bytes.strip_prefix(MAGIC).ok_or("not a scan exported by hica")?;
+        let mut reader = Reader { bytes: rest };
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(format!("exported by a newer hica (format version {})", version));
+        }
+        let host = String::from_utf8_lossy(reader.bytes()?).into_owned();
+        let root = path_from(reader.bytes()?.to_vec());
+        let taken = time(reader.varint()?);
+        let count = reader.varint()?;
+
+        let mut files = Vec::new();
+        let mut previous: Vec<u8> = Vec::new();
+        for _ in 0..count {
+            let shared = reader.varint()? as usize;
+            let mut path = previous.get(..shared).ok_or("corrupt path")?.to_vec();
+            path.extend_from_slice(reader.bytes()?);
+            previous.clone_from(&path);
+
+            let size = reader.varint()?;
+            let category = *CacheCategory::ALL.get(reader.byte()? as usize).ok_or("unknown category")?;
+            let risk = *Risk::ALL.get(reader.byte()? as usize).ok_or("unknown risk")?;
+            let flags = reader.byte()?;
+            let link_id = if flags & LINKED != 0 { Some((reader.varint()?, reader.varint()?)) } else { None };
+            let modified = if flags & MODIFIED != 0 { Some(time(reader.varint()?)) } else { None };
+            let accessed = if flags & ACCESSED != 0 { Some(time(reader.varint()?)) } else { None };
+            files.push(CacheFile { path: path_from(path), size, category, risk, link_id, modified, accessed });
+        }
+        Ok(Export { host, root, taken, files })
+    }
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn time(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+fn path_bytes(path: &Path) -> &[u8] {
+    path.as_os_str().as_encoded_bytes()
+}
+
+/// A path read back. Off Unix, a name that isn't valid Unicode comes back
+/// with replacement characters.
+fn path_from(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(unix)]
+    {
+        PathBuf::from(<std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, String> {
+        let (&byte, rest) = self.bytes.split_first().ok_or("the file ends early")?;
+        self.bytes = rest;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("corrupt number".to_string())
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let length = self.varint()? as usize;
+        if length > self.bytes.len() {
+            return Err("the file ends early".to_string());
+        }
+        let (bytes, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let file = |path: &str, size: u64| CacheFile {
+            path: PathBuf::from(path),
+            size,
+            category: CacheCategory::Build,
+            risk: Risk::Likely,
+            link_id: None,
+            modified: Some(time(1_700_000_000)),
+            accessed: None,
+        };
+        let mut files = vec![file("/srv/app/target/a.o", 300), file("/srv/app/target/b.o", 1 << 40), file("/srv/cache.tmp", 0)];
+        files[2].category = CacheCategory::Temporary;
+        files[2].link_id = Some((2049, 123_456));
+        let export = Export { host: "box".to_string(), root: PathBuf::from("/srv"), taken: time(1_700_000_500), files };
+
+        let bytes = export.encode();
+        let back = Export::decode(&bytes).unwrap();
+        assert_eq!((back.host.as_str(), back.root.as_path(), back.taken), ("box", Path::new("/srv"), export.taken));
+        assert_eq!(format!("{:?}", back.files), format!("{:?}", export.files));
+
+        assert!(Export::decode(b"PK\x03\x04").is_err());
+        assert!(Export::decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! A small implementation of `.gitignore` matching, for scans that should
+//! leave alone whatever a repository ignores.
+
+use std::path::{Path, PathBuf};
+
+/// Version control metadata, which scans never descend into.
+pub const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+struct Rule {
+    /// The pattern split at `/`, with `**` segments kept as they are
+    segments: Vec<String>,
+    negated: bool,
+    dir_only: bool,
+    /// Whether the pattern is relative to the file's directory, rather than
+    /// matching a name at any depth
+    anchored: bool,
+}
+
+/// The rules of one `.gitignore` file.
+pub struct Gitignore {
+    /// The directory holding the file, which anchored patterns are relative to
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    pub fn parse(base: PathBuf, source: &str) -> Gitignore {
+        let rules = source.lines().filter_map(parse_rule).collect();
+        Gitignore { base, rules }
+    }
+
+    /// Reads `dir/.gitignore`, if there is one with any rules in it.
+    pub fn load(dir: &Path) -> Option<Gitignore> {
+        let source = std::fs::read_to_string(dir.join(".gitignore")).ok()?;
+        let gitignore = Gitignore::parse(dir.to_path_buf(), &source);
+        (!gitignore.rules.is_empty()).then_some(gitignore)
+    }
+
+    /// Whether the file decides about `path`: `Some(true)` if it's ignored,
+    /// `Some(false)` if a negated rule re-includes it.
+    pub fn matches(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?;
+        let components: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if components.is_empty() {
+            return None;
+        }
+
+        // The last matching rule wins
+        self.rules.iter().rev().find_map(|rule| {
+            if rule.dir_only && !is_dir {
+                return None;
+            }
+            let matched = if rule.anchored {
+                match_segments(&rule.segments, &components)
+            } else {
+                match_glob(&rule.segments[0], components.last()?)
+            };
+            matched.then_some(!rule.negated)
+        })
+    }
+}
+
+/// The `.gitignore` files in effect in a directory, outermost first.
+#[derive(Clone, Default)]
+pub struct IgnoreStack {
+    files: Vec<std::sync::Arc<Gitignore>>,
+}
+
+impl IgnoreStack {
+    /// The `.gitignore` files of the repository `dir` is in, from its root
+    /// down to `dir` itself, so a scan of a subdirectory sees them too.
+    pub fn for_dir(dir: &Path) -> IgnoreStack {
+        let mut stack = IgnoreStack::default();
+        let repository = dir.ancestors().find(|ancestor| ancestor.join(".git").exists());
+
+        if let Some(repository) = repository {
+            let mut ancestors: Vec<&Path> = dir.ancestors().take_while(|a| a.starts_with(repository)).collect();
+            ancestors.reverse();
+            for ancestor in ancestors {
+                stack = stack.with_dir(ancestor);
+            }
+        }
+
+        stack
+    }
+
+    /// The stack for a subdirectory, adding its own `.gitignore` if any.
+    pub fn with_dir(&self, dir: &Path) -> IgnoreStack {
+        match Gitignore::load(dir) {
+            Some(gitignore) => {
+                let mut files = self.files.clone();
+                files.push(std::sync::Arc::new(gitignore));
+                IgnoreStack { files }
+            }
+            None => self.clone(),
+        }
+    }
+
+    /// Whether `path` is ignored, with deeper files overriding outer ones.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|gitignore| gitignore.matches(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    // A slash anywhere but at the end ties the pattern to this directory
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+
+    Some(Rule {
+        segments: pattern.split('/').map(str::to_string).collect(),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+/// Matches path components against pattern segments, where `**` stands for
+/// any number of components (including none).
+fn match_segments(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => {
+            !path.is_empty() && match_glob(first, &path[0]) && match_segments(rest, &path[1..])
+        }
+    }
+}
+
+/// Matches one name against a glob with `*`, `?` and `[...]` classes.
+pub(crate) fn match_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    glob_chars(&pattern, &name)
+}
+
+fn glob_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_chars(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_chars(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let Some(end) = pattern.iter().skip(2).position(|&c| c == ']').map(|i| i + 2) else {
+                // An unterminated class is a literal `[`
+                return name.first() == Some(&'[') && glob_chars(&pattern[1..], &name[1..]);
+            };
+            let Some(&c) = name.first() else { return false };
+
+            let class = &pattern[1..end];
+            let (negated, class) = match class.first() {
+                Some('!') | Some('^') => (true, &class[1..]),
+                _ => (false, class),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    matched |= class[i] == c;
+                    i += 1;
+                }
+            }
+            matched != negated && glob_chars(&pattern[end + 1..], &name[1..])
+        }
+        Some('\\') if pattern.len() > 1 => name.first() == Some(&pattern[1]) && glob_chars(&pattern[2..], &name[1..]),
+        Some(&literal) => name.first() == Some(&literal) && glob_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gitignore(source: &str) -> Gitignore {
+        Gitignore::parse(PathBuf::from("/repo"), source)
+    }
+
+    #[test]
+    fn matches_names_at_any_depth() {
+        let rules = gitignore("*.log\ntarget/\n# comment\n");
+        assert_eq!(rules.matches(Path::new("/repo/a/b/debug.log"), false), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/crate/target"), true), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/target"), false), None);
+        assert_eq!(rules.matches(Path::new("/repo/src/main.rs"), false), None);
+        assert_eq!(rules.matches(Path::new("/elsewhere/debug.log"), false), None);
+    }
+
+    #[test]
+    fn anchors_patterns_with_slashes() {
+        let rules = gitignore("/build\ndocs/*.tmp\nlogs/**/old\n");
+        assert_eq!(rules.matches(Path::new("/repo/build"), true), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/src/build"), true), None);
+        assert_eq!(rules.matches(Path::new("/repo/docs/a.tmp"), false), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/docs/x/a.tmp"), false), None);
+        assert_eq!(rules.matches(Path::new("/repo/logs/old"), true), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/logs/a/b/old"), true), Some(true));
+    }
+
+    #[test]
+    fn negation_reincludes() {
+        let rules = gitignore("*.log\n!keep.log\n");
+        assert_eq!(rules.matches(Path::new("/repo/x.log"), false), Some(true));
+        assert_eq!(rules.matches(Path::new("/repo/keep.log"), false), Some(false));
+    }
+
+    #[test]
+    fn globs_support_classes() {
+        assert!(match_glob("file[0-9].txt", "file7.txt"));
+        assert!(!match_glob("file[!0-9].txt", "file7.txt"));
+        assert!(match_glob("?.c", "a.c"));
+        assert!(!match_glob("*.c", "a.cc"));
+    }
+}
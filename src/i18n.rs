@@ -0,0 +1,327 @@
+//! Translations of the user-facing messages.
+//!
+//! Messages are looked up by key and may contain positional placeholders
+//! (`{0}`, `{1}`, ...). Keys missing from a language fall back to English.
+
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Lang {
+    En,
+    De,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+impl Lang {
+    /// The language asked for by the locale variables, in the order POSIX
+    /// gives them precedence, e.g. `de_DE.UTF-8`.
+    pub fn from_env() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Lang::from_locale(&value))
+            .unwrap_or(Lang::En)
+    }
+
+    fn from_locale(locale: &str) -> Option<Lang> {
+        let language = locale.split(['_', '.', '@', '-']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "de" => Some(Lang::De),
+            "en" | "c" | "posix" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    /// Whether `answer` means yes to a (y/N) prompt in this language.
+    pub fn is_yes(&self, answer: &str) -> bool {
+        let answer = answer.trim();
+        match self {
+            Lang::En => answer.eq_ignore_ascii_case("y"),
+            Lang::De => answer.eq_ignore_ascii_case("j") || answer.eq_ignore_ascii_case("y"),
+        }
+    }
+}
+
+/// Sets the language for the rest of the run; only the first call counts.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    *LANG.get_or_init(Lang::from_env)
+}
+
+/// Key, English, German.
+const MESSAGES: &[(&str, &str, &str)] = &[
+    ("scan.start", "Scanning for cache files in {0}", "Suche nach Cache-Dateien in {0}"),
+    ("scan.traversing", "Traversing directory structure...", "Durchsuche Verzeichnisstruktur..."),
+    ("scan.stats", "Scanned {0} directories and {1} files ({2}) in {3}: {4} files/s, {5}/s; {6} matched", "{0} Verzeichnisse und {1} Dateien ({2}) in {3} durchsucht: {4} Dateien/s, {5}/s; {6} Treffer"),
+    ("scan.found", "Found {0} cache files totaling {1}", "{0} Cache-Dateien mit insgesamt {1} gefunden"),
+    ("scan.other-owners", "Ignoring {0} files owned by other users ({1}); pass --owner or --any-owner to include them", "{0} Dateien anderer Benutzer werden ignoriert ({1}); mit --owner oder --any-owner werden sie einbezogen"),
+    ("scan.ignored-recent", "Ignoring {0} recently {1} files ({2})", "{0} kürzlich {1} Dateien werden ignoriert ({2})"),
+    ("scan.recent-used", "used", "verwendete"),
+    ("scan.recent-modified", "modified", "geänderte"),
+    ("scan.partial", "The scan stopped after {0}, so these results are partial; {1} directories were not reached:", "Die Suche wurde nach {0} beendet, die Ergebnisse sind daher unvollständig; {1} Verzeichnisse wurden nicht erreicht:"),
+    ("scan.file-limit", "The scan stopped after listing {0} files (--max-files), so these results are partial; {1} directories were not reached:", "Die Suche wurde nach {0} aufgelisteten Dateien beendet (--max-files), die Ergebnisse sind daher unvollständig; {1} Verzeichnisse wurden nicht erreicht:"),
+    ("scan.interrupted", "The scan was interrupted, so these results are partial; {0} directories were not reached:", "Die Suche wurde unterbrochen, die Ergebnisse sind daher unvollständig; {0} Verzeichnisse wurden nicht erreicht:"),
+    ("scan.resuming", "Resuming from a checkpoint, with {0} directories already done", "Fortsetzung ab einem Zwischenstand, {0} Verzeichnisse sind bereits erledigt"),
+    ("scan.skipped-network", "Skipping the network share {0} ({1}); --include-network scans it", "Überspringe die Netzwerkfreigabe {0} ({1}); --include-network durchsucht sie"),
+    ("scan.placeholders", "Left out {0} online-only cloud files ({1} not on this disk), as reading them downloads them", "{0} reine Online-Dateien aus der Cloud ausgelassen ({1}, nicht auf diesem Datenträger), da Lesen sie herunterlädt"),
+    ("scan.walker-fallback", "Can't read the master file table ({0}), listing directories instead", "Die Master File Table kann nicht gelesen werden ({0}), lese stattdessen die Verzeichnisse"),
+    ("scan.skipped-mount", "Skipping {0}: {1}", "Überspringe {0}: {1}"),
+    ("scan.skipped-root", "{0} is on a {1}, skipping it", "{0} liegt auf einem {1} und wird übersprungen"),
+    ("scan.checkpoint-failed", "Failed to save the scan checkpoint to {0}: {1}", "Der Zwischenstand der Suche konnte nicht in {0} gespeichert werden: {1}"),
+    ("scan.progress", "{0} cache files found, {1}/s looked at", "{0} Cache-Dateien gefunden, {1}/s durchgesehen"),
+    ("scan.no-checkpoint", "No checkpoint of a scan of {0} to resume, starting over", "Kein Zwischenstand einer Suche in {0} vorhanden, beginne von vorn"),
+    ("scan.finished", "hica scan finished", "hica-Suche abgeschlossen"),
+    ("summary.categories", "Category Summary: ", "Übersicht nach Kategorie: "),
+    ("summary.users", "Per-User Summary: ", "Übersicht nach Benutzer: "),
+    ("summary.volumes", "Volume Summary: ", "Übersicht nach Laufwerk: "),
+    ("summary.ages", "Age Breakdown: ", "Aufteilung nach Alter: "),
+    ("summary.tree", "Cache Tree: ", "Cache-Baum: "),
+    ("summary.tree-rest", "{0} more", "{0} weitere"),
+    ("summary.roots", "Caches by Directory in {0}: ", "Caches nach Verzeichnis in {0}: "),
+    ("summary.roots-direct", "(directly in it)", "(direkt darin)"),
+    ("summary.roots-expanded", "Caches in {0}: ", "Caches in {0}: "),
+    ("summary.roots-none", "No caches found in {0}", "Keine Caches in {0} gefunden"),
+    ("summary.cloned", "{0} files share {1} with clones or snapshots; deleting them frees that much less", "{0} Dateien teilen {1} mit Klonen oder Snapshots; Löschen gibt entsprechend weniger frei"),
+    ("summary.snapshots", "{0} snapshots in {1} still hold these files; deleting them frees no space until those snapshots expire", "{0} Snapshots in {1} enthalten diese Dateien noch; Löschen gibt erst Platz frei, wenn sie ablaufen"),
+    ("summary.files", "{0} files ({1})", "{0} Dateien ({1})"),
+    ("summary.stale", "{0} stale ({1})", "{0} veraltet ({1})"),
+    ("summary.below-threshold", "other (below threshold)", "andere (unter Schwellenwert)"),
+    ("summary.stale-hint", "Stale files have gone unused for {0}; pass --stale to only target those", "Veraltete Dateien wurden seit {0} nicht benutzt; mit --stale werden nur diese erfasst"),
+    ("summary.trash", "{0} waiting to be emptied, run `hica clean trash`", "{0} warten aufs Leeren, mit `hica clean trash`"),
+    ("trash.held-back", "{0} files ({1}) are in the trash and will be kept; empty it with `hica clean trash`", "{0} Dateien ({1}) liegen im Papierkorb und bleiben erhalten; mit `hica clean trash` leeren"),
+    ("summary.browser-profiles", "Browser Caches by Profile: ", "Browser-Caches nach Profil: "),
+    ("summary.browser-unknown", "other browser files", "andere Browser-Dateien"),
+    ("summary.apps", "Application Caches by App: ", "Anwendungs-Caches nach App: "),
+    ("summary.apps-other", "other applications", "andere Anwendungen"),
+    ("summary.python", "Python Caches by Tool: ", "Python-Caches nach Werkzeug: "),
+    ("summary.jvm", "JVM Build Caches by Tool: ", "JVM-Build-Caches nach Werkzeug: "),
+    ("summary.compiler", "Compiler Caches: ", "Compiler-Caches: "),
+    ("summary.compiler-limit", "{0} of a {1} limit", "{0} von höchstens {1}"),
+    ("summary.compiler-unlimited", "{0}, no size limit", "{0}, ohne Größenbeschränkung"),
+    ("maven.held-back", "{0} files ({1}) are in Maven's local repository, which also holds artifacts installed with `mvn install`; pass --include-maven to delete them", "{0} Dateien ({1}) liegen in Mavens lokalem Repository, das auch mit `mvn install` installierte Artefakte enthält; mit --include-maven werden sie gelöscht"),
+    ("export.saved", "Saved {0} cache files ({1}) to {2}", "{0} Cache-Dateien ({1}) in {2} gespeichert"),
+    ("import.source", "Scan of {0} on {1}, taken {2} ago", "Scan von {0} auf {1}, vor {2} erstellt"),
+    ("import.plan-hint", "These files are on the machine scanned; filter them and pass --print0 to get a list to delete there with `xargs -0 rm -f --`", "Diese Dateien liegen auf dem gescannten Rechner; filtern Sie sie und übergeben Sie --print0, um eine Liste zu erhalten, die dort mit `xargs -0 rm -f --` gelöscht werden kann"),
+    ("remote.start", "Scanning {0} on {1} over SSH", "Scanne {0} auf {1} über SSH"),
+    ("remote.report-only", "Scans of other machines only report; pipe --print0 to `ssh <host> 'xargs -0 rm -f --'` to delete there", "Scans anderer Rechner berichten nur; leiten Sie --print0 an `ssh <host> 'xargs -0 rm -f --'` weiter, um dort zu löschen"),
+    ("agent.no-endpoint", "Set `endpoint` in the [agent] section of the config to tell the agent where to report", "Legen Sie `endpoint` im Abschnitt [agent] der Konfiguration fest, damit der Agent weiß, wohin er berichten soll"),
+    ("agent.sent", "Reported {0} of cache to {1}", "{0} Cache an {1} gemeldet"),
+    ("agent.failed", "Failed to report to {0}: {1}", "Bericht an {0} fehlgeschlagen: {1}"),
+    ("compare.title", "Comparing {0} with {1}", "Vergleich von {0} mit {1}"),
+    ("compare.categories", "Changes by Category: ", "Änderungen nach Kategorie: "),
+    ("compare.paths", "Changes by Directory: ", "Änderungen nach Verzeichnis: "),
+    ("compare.files", "{0} → {1} files", "{0} → {1} Dateien"),
+    ("compare.same", "no difference", "kein Unterschied"),
+    ("compare.no-paths", "only --format json summaries on one side; compare --format jsonl reports or `hica export` files to see directories", "auf einer Seite nur Zusammenfassungen mit --format json; vergleichen Sie Berichte mit --format jsonl oder Dateien von `hica export`, um Verzeichnisse zu sehen"),
+    ("compare.total", "Cache in total: {0} → {1} ({2})", "Cache insgesamt: {0} → {1} ({2})"),
+    ("browse.title", "{0}: {1} of cache, sorted by {2}", "{0}: {1} Cache, sortiert nach {2}"),
+    ("browse.queue-title", "Deletion queue: {0} files, {1} to free, sorted by {2}", "Löschliste: {0} Dateien, {1} freizugeben, sortiert nach {2}"),
+    ("browse.footer", "Queued: {0} files, {1} to free  space mark  i invert  c category  v queue  s sort  d delete  q quit", "Vorgemerkt: {0} Dateien, {1} freizugeben  Leertaste markieren  i umkehren  c Kategorie  v Liste  s sortieren  d löschen  q beenden"),
+    ("browse.confirm", "Delete the {0} queued files, freeing {1}? (y/N)", "Die {0} vorgemerkten Dateien löschen und {1} freigeben? (j/N)"),
+    ("browse.category-marked", "Queued all {0} {1} files ({2})", "Alle {0} Dateien der Kategorie {1} vorgemerkt ({2})"),
+    ("browse.category-unmarked", "Took all {0} {1} files ({2}) off the queue", "Alle {0} Dateien der Kategorie {1} ({2}) aus der Liste genommen"),
+    ("summary.offenders", "Biggest Offenders: ", "Größte Verursacher: "),
+    ("summary.offender", "{0} ({1}% of the cache), mostly {2}", "{0} ({1} % des Caches), vor allem {2}"),
+    ("summary.junk", "Junk (Always Safe to Delete): ", "Datenmüll (immer unbedenklich zu löschen): "),
+    ("summary.crash-dumps", "Crash Dumps by Program: ", "Absturzabbilder nach Programm: "),
+    ("summary.crash-unknown", "unknown program", "unbekanntes Programm"),
+    ("summary.free", "{0} free", "{0} frei"),
+    ("list.title", "Cache files: ", "Cache-Dateien: "),
+    ("list.more", "... and {0} more", "... und {0} weitere"),
+    ("git.unlisted", "Could not list the files git tracks in {0}, so every matching file in it is kept", "Die von git verfolgten Dateien in {0} ließen sich nicht auflisten, daher bleiben alle passenden Dateien darin erhalten"),
+    ("git.tracked", "{0} matching files are tracked by git and will be kept:", "{0} passende Dateien werden von git verfolgt und bleiben erhalten:"),
+    ("protected.count", "{0} of these files are protected and will never be deleted", "{0} dieser Dateien sind geschützt und werden nie gelöscht"),
+    ("protected.skipped", "Skipped {0} protected files", "{0} geschützte Dateien übersprungen"),
+    ("prompt.list", "Do you want to see the full list of cache files? (y/N)", "Möchten Sie die vollständige Liste der Cache-Dateien sehen? (j/N)"),
+    ("prompt.delete", "Do you want to delete these cache files? (y/N)", "Möchten Sie diese Cache-Dateien löschen? (j/N)"),
+    ("prompt.delete-marked", "Do you want to delete the marked cache files? (y/N)", "Möchten Sie die markierten Cache-Dateien löschen? (j/N)"),
+    ("prompt.delete-junk", "Do you want to delete just the {0} junk files ({1})? (y/N)", "Möchten Sie nur die {0} Datenmüll-Dateien ({1}) löschen? (j/N)"),
+    ("prompt.delete-locations", "Do you want to delete the contents of these locations? (y/N)", "Möchten Sie den Inhalt dieser Orte löschen? (j/N)"),
+    ("prompt.delete-apps", "Do you want to delete these application caches? (y/N)", "Möchten Sie diese Anwendungs-Caches löschen? (j/N)"),
+    ("confirm.risky", "{0} of these files ({1}) only matched by a word in their name", "{0} dieser Dateien ({1}) passten nur über ein Wort im Namen"),
+    ("confirm.large", "This deletes {0}, more than the {1} set as `confirm_size`", "Damit werden {0} gelöscht, mehr als die als `confirm_size` festgelegten {1}"),
+    ("confirm.type", "Type {0} to delete them, anything else cancels:", "Geben Sie {0} ein, um sie zu löschen; alles andere bricht ab:"),
+    ("prompt.delete-targets", "Do you want to delete {0} locations totaling {1}? (y/N)", "Möchten Sie {0} Orte mit insgesamt {1} löschen? (j/N)"),
+    ("delete.canceled", "Deletion canceled", "Löschen abgebrochen"),
+    ("hook.pre-failed", "The pre-clean hook `{0}` failed ({1}), so nothing was deleted", "Der Hook vor dem Aufräumen `{0}` ist fehlgeschlagen ({1}), daher wurde nichts gelöscht"),
+    ("hook.post-failed", "The post-clean hook `{0}` failed ({1})", "Der Hook nach dem Aufräumen `{0}` ist fehlgeschlagen ({1})"),
+    ("delete.start", "Deleting cache files...", "Lösche Cache-Dateien..."),
+    ("delete.cloned", "{0} of them shared {1} with clones or snapshots, which is still on disk", "{0} davon teilten {1} mit Klonen oder Snapshots, die weiter belegt sind"),
+    ("delete.linked", "{0} of them were hard links to data linked elsewhere too, so no space was freed for them", "{0} davon waren harte Links auf Daten, die auch anderswo verlinkt sind, daher wurde für sie kein Platz frei"),
+    ("delete.done", "Deleted {0} files, freeing {1}", "{0} Dateien gelöscht, {1} freigegeben"),
+    ("delete.freed", "Freed {0}", "{0} freigegeben"),
+    ("delete.limit", "Deletion limit reached, {0} files totaling {1} will be left in place", "Löschgrenze erreicht, {0} Dateien mit insgesamt {1} bleiben erhalten"),
+    ("delete.interrupted", "{0} files were left in place", "{0} Dateien bleiben erhalten"),
+    ("delete.failed", "{0} files could not be deleted", "{0} Dateien konnten nicht gelöscht werden"),
+    ("delete.busy", "{0} files were still in use after {1} retries, so trying again later may work", "{0} Dateien waren nach {1} Versuchen noch in Benutzung, ein späterer Versuch kann helfen"),
+    ("errors.title", "{0} problems came up during the run:", "Während des Laufs gab es {0} Probleme:"),
+    ("du.start", "Measuring the disk usage of {0}", "Messe die Speicherbelegung von {0}"),
+    ("du.title", "Disk usage of {0}:", "Speicherbelegung von {0}:"),
+    ("du.cache", "{0} of it cache ({1}%)", "davon {0} Cache ({1} %)"),
+    ("du.partial", "Stopped early, so the sizes cover only part of the tree", "Vorzeitig beendet, die Größen decken nur einen Teil des Baums ab"),
+    ("du.total", "{0} files totaling {1}, of which {2} is cache ({3}%)", "{0} Dateien mit insgesamt {1}, davon {2} Cache ({3} %)"),
+    ("delete.pinned", "{0} files are hard links to data linked elsewhere too; deleting them won't free {1}", "{0} Dateien sind harte Links auf Daten, die auch anderswo verlinkt sind; ihr Löschen gibt {1} nicht frei"),
+    ("delete.archived", "Archived {0} log files, reclaiming {1}", "{0} Logdateien archiviert, {1} zurückgewonnen"),
+    ("delete.pruned", "Removed {0} empty directories", "{0} leere Verzeichnisse entfernt"),
+    ("notify.freed", "hica freed {0}", "hica hat {0} freigegeben"),
+    ("notify.body", "Deleted {0} of {1} found", "{0} von {1} gefundenen gelöscht"),
+    ("notify.errors", ", {0} errors", ", {0} Fehler"),
+    ("root.warning", "{0} is a filesystem root or an entire home directory", "{0} ist ein Dateisystem-Stammverzeichnis oder ein ganzes Home-Verzeichnis"),
+    ("root.refused", "Refusing to delete anything here without --i-know-what-im-doing", "Hier wird ohne --i-know-what-im-doing nichts gelöscht"),
+    ("browser.running", "{0} is running; deleting its cache while it's open can corrupt the profile", "{0} läuft; das Löschen des Caches bei geöffnetem Browser kann das Profil beschädigen"),
+    ("browser.skipping", "Skipping {0} files of running browsers", "{0} Dateien laufender Browser werden übersprungen"),
+    ("browser.close-first", "Close it first, or pass --skip-in-use to leave its files alone", "Schließen Sie ihn zuerst, oder lassen Sie seine Dateien mit --skip-in-use unberührt"),
+    ("clean.available", "Available cleaners: ", "Verfügbare Bereiniger: "),
+    ("clean.unknown-tool", "Unknown tool `{0}`, expected one of: {1}", "Unbekanntes Werkzeug `{0}`, erwartet wird eines von: {1}"),
+    ("clean.none-found", "No {0} caches found", "Keine {0}-Caches gefunden"),
+    ("clean.locations", "{0} cache locations: ", "{0}-Cache-Orte: "),
+    ("clean.needs-all", "[needs --all]", "[braucht --all]"),
+    ("clean.nothing-selected", "Nothing selected, pass --all to include the other locations", "Nichts ausgewählt, mit --all werden die übrigen Orte einbezogen"),
+    ("clean.skipped", "Skipped {0}", "{0} übersprungen"),
+    ("clean.cleaned", "Cleaned {0}", "{0} bereinigt"),
+    ("clean.failed", "Failed to clean {0}: {1}", "{0} konnte nicht bereinigt werden: {1}"),
+    ("clean.left-alone", "{0} locations were left alone", "{0} Orte bleiben unberührt"),
+    ("clean.in-use", "{0} files could not be deleted (probably in use)", "{0} Dateien konnten nicht gelöscht werden (vermutlich in Benutzung)"),
+    ("system.none", "No known cache locations found on this system", "Auf diesem System wurden keine bekannten Cache-Orte gefunden"),
+    ("system.title", "System cache locations: ", "System-Cache-Orte: "),
+    ("system.total", "{0} locations totaling {1}", "{0} Orte mit insgesamt {1}"),
+    ("apps.none", "No application caches found on this system", "Auf diesem System wurden keine Anwendungs-Caches gefunden"),
+    ("apps.title", "Application caches: ", "Anwendungs-Caches: "),
+    ("apps.not-found", "No caches found for {0}", "Keine Caches für {0} gefunden"),
+    ("pause.paused", "Send SIGUSR2 to resume (kill -USR2 {0})", "Mit SIGUSR2 geht es weiter (kill -USR2 {0})"),
+    ("pause.resumed", "Carrying on", "Es geht weiter"),
+    ("doctor.config", "Config", "Konfiguration"),
+    ("doctor.scanning", "Scanning {0}", "Durchsuchen von {0}"),
+    ("doctor.platform", "Platform", "Plattform"),
+    ("doctor.failed", "{0} checks failed", "{0} Prüfungen fehlgeschlagen"),
+    ("doctor.ok", "Nothing stands in hica's way", "Nichts steht hica im Weg"),
+    ("doctor.no-config-dir", "Could not determine the config directory, pass --config", "Das Konfigurationsverzeichnis ließ sich nicht bestimmen, bitte --config angeben"),
+    ("doctor.no-config", "No config file at {0}, so the defaults apply (`hica config init` writes one)", "Keine Konfigurationsdatei unter {0}, es gelten die Voreinstellungen (`hica config init` legt eine an)"),
+    ("doctor.config-valid", "{0} is valid", "{0} ist gültig"),
+    ("doctor.protected-missing", "Protected path {0} does not exist", "Der geschützte Pfad {0} existiert nicht"),
+    ("doctor.secret-unreadable", "`{0}` can't be read from {1}: {2}", "`{0}` kann nicht aus {1} gelesen werden: {2}"),
+    ("doctor.policy-ok", "Policy script {0} is executable", "Das Policy-Skript {0} ist ausführbar"),
+    ("doctor.policy-missing", "Policy script {0} is missing or not executable", "Das Policy-Skript {0} fehlt oder ist nicht ausführbar"),
+    ("doctor.plugin", "Plugin `{0}` covers {1} directories", "Plugin `{0}` deckt {1} Verzeichnisse ab"),
+    ("doctor.plugin-failed", "Plugin {0}", "Plugin {0}"),
+    ("doctor.no-curl", "`{0}` is set, but curl, which sends it, isn't installed", "`{0}` ist gesetzt, aber curl, das es sendet, ist nicht installiert"),
+    ("doctor.unreadable", "{0} can't be read: {1}", "{0} kann nicht gelesen werden: {1}"),
+    ("doctor.readable", "{0} and the {1} directories in it are readable", "{0} und die {1} Verzeichnisse darin sind lesbar"),
+    ("doctor.some-unreadable", "{0} of the {1} directories in {2} can't be read; their owner or root can scan them", "{0} der {1} Verzeichnisse in {2} sind nicht lesbar; ihr Besitzer oder root kann sie durchsuchen"),
+    ("doctor.mount", "On {0} ({1}), with {2} free", "Auf {0} ({1}), {2} frei"),
+    ("doctor.unknown-space", "unknown space", "unbekannt viel Platz"),
+    ("doctor.read-only", "The filesystem is mounted read-only, so nothing on it can be deleted", "Das Dateisystem ist schreibgeschützt eingehängt, darauf kann nichts gelöscht werden"),
+    ("doctor.network", "It's a network filesystem, which scans skip unless given --include-network", "Es ist ein Netzwerkdateisystem, das Scans ohne --include-network überspringen"),
+    ("doctor.archive-same", "Archives in {0} are on the same filesystem, so archiving frees what compression saves", "Archive in {0} liegen auf demselben Dateisystem, Archivieren gibt also frei, was die Kompression spart"),
+    ("doctor.archive-other", "Archives in {0} are on another filesystem, so archiving frees the logs' whole size here", "Archive in {0} liegen auf einem anderen Dateisystem, Archivieren gibt hier also die ganze Größe der Logs frei"),
+    ("doctor.archive-unknown", "Could not tell which filesystem {0} is on", "Es ließ sich nicht feststellen, auf welchem Dateisystem {0} liegt"),
+    ("doctor.recycle-bin", "Each drive has its own Recycle Bin, so trashing files there renames them", "Jedes Laufwerk hat einen eigenen Papierkorb, Dateien dorthin zu verschieben benennt sie also nur um"),
+    ("doctor.trash-same", "The trash {0} is on the same filesystem, so trashing files renames them", "Der Papierkorb {0} liegt auf demselben Dateisystem, Dateien dorthin zu verschieben benennt sie also nur um"),
+    ("doctor.trash-volume", "Files trashed here go to {0}, on the same filesystem", "Dateien von hier kommen in {0}, auf demselben Dateisystem"),
+    ("doctor.trash-other", "The trash {0} is on another filesystem, so trashing files copies them there", "Der Papierkorb {0} liegt auf einem anderen Dateisystem, Dateien dorthin zu verschieben kopiert sie also"),
+    ("doctor.version", "hica {0} on {1} {2}", "hica {0} auf {1} {2}"),
+    ("doctor.threads", "Walking with {0} threads by default", "Durchsucht standardmäßig mit {0} Threads"),
+    ("doctor.scheduler", "Scheduled runs use {0}", "Geplante Läufe nutzen {0}"),
+    ("doctor.privileged", "Running with administrator rights, so system caches and other users' files can be cleaned", "Läuft mit Administratorrechten, System-Caches und Dateien anderer Benutzer können also bereinigt werden"),
+    ("doctor.unprivileged", "Running without administrator rights: system caches need sudo, or an administrator on Windows", "Läuft ohne Administratorrechte: System-Caches brauchen sudo, unter Windows einen Administrator"),
+    ("doctor.mft", "--walker mft can list NTFS volumes directly when run as administrator", "--walker mft kann NTFS-Volumes als Administrator direkt auflisten"),
+    ("doctor.shared", "Space shared with reflinks, clones or snapshots is measured, so deleting shared files isn't overcounted", "Mit Reflinks, Klonen oder Snapshots geteilter Platz wird gemessen, das Löschen geteilter Dateien wird also nicht zu hoch gezählt"),
+    ("doctor.for-curl", "webhooks, email and `hica agent`", "Webhooks, E-Mail und `hica agent`"),
+    ("doctor.for-ssh", "remote scans", "entfernte Scans"),
+    ("doctor.for-gio", "emptying the trash through the desktop", "das Leeren des Papierkorbs über den Desktop"),
+    ("doctor.for-notify-send", "desktop notifications", "Desktop-Benachrichtigungen"),
+    ("doctor.for-osascript", "desktop notifications and emptying the trash", "Desktop-Benachrichtigungen und das Leeren des Papierkorbs"),
+    ("doctor.for-powershell", "desktop notifications and emptying the Recycle Bin", "Desktop-Benachrichtigungen und das Leeren des Papierkorbs"),
+    ("doctor.tool-found", "{0} ({1}) is at {2}", "{0} ({1}) liegt unter {2}"),
+    ("doctor.tool-missing", "{0} isn't installed, so {1} won't work", "{0} ist nicht installiert, daher fehlt: {1}"),
+    ("email.subject", "hica on {0}: freed {1}", "hica auf {0}: {1} freigegeben"),
+    ("email.subject-errors", ", {0} errors", ", {0} Fehler"),
+    ("email.body", "Command:  hica {0}\nFound:    {1} ({2})\nDeleted:  {3}\nFreed:    {4}\nFailures: {5}\n", "Befehl:   hica {0}\nGefunden: {1} ({2})\nGelöscht: {3}\nFrei:     {4}\nFehler:   {5}\n"),
+    ("email.attached", "The full report is attached as JSON.", "Der vollständige Bericht hängt als JSON an."),
+    ("email.failed", "Failed to email the summary: {0}", "Die Zusammenfassung konnte nicht gemailt werden: {0}"),
+    ("interrupt.stopping", "Finishing up, press Ctrl-C again to quit right away", "Wird abgeschlossen, erneut Strg+C drücken, um sofort zu beenden"),
+];
+
+/// Looks up a message and fills in its placeholders.
+pub fn tr(key: &str, args: &[&dyn Display]) -> String {
+    let template = MESSAGES
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, en, de)| match lang() {
+            Lang::En => *en,
+            Lang::De => *de,
+        })
+        .unwrap_or(key);
+
+    // One pass over the template, so an argument that happens to contain
+    // `{1}` isn't filled in again
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let placeholder = rest[start + 1..]
+            .split_once('}')
+            .and_then(|(index, _)| Some((index.len(), args.get(index.parse::<usize>().ok()?)?)));
+        match placeholder {
+            Some((len, arg)) => {
+                message.push_str(&arg.to_string());
+                rest = &rest[start + len + 2..];
+            }
+            None => {
+                message.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+/// `tr!("key", arg, ...)` translates a message with its arguments.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(, $arg:expr)* $(,)?) => {
+        $crate::i18n::tr($key, &[$(&$arg as &dyn std::fmt::Display),*])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(message: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = (0..10).filter(|i| message.contains(&format!("{{{}}}", i))).collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn translations_keep_placeholders() {
+        for (key, en, de) in MESSAGES {
+            assert_eq!(placeholders(en), placeholders(de), "placeholders differ for `{}`", key);
+        }
+    }
+
+    #[test]
+    fn fills_placeholders_once() {
+        assert_eq!(tr("Deleted {0} files, freeing {1}", &[&"{1}", &"1 MB"]), "Deleted {1} files, freeing 1 MB");
+        assert_eq!(tr("{0} {x} {9}", &[&"a"]), "a {x} {9}");
+    }
+
+    #[test]
+    fn reads_locales() {
+        assert_eq!(Lang::from_locale("de_DE.UTF-8"), Some(Lang::De));
+        assert_eq!(Lang::from_locale("C.UTF-8"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("fr_FR"), None);
+        assert!(Lang::De.is_yes("J"));
+        assert!(!Lang::En.is_yes("j"));
+    }
+}
@@ -0,0 +1,45 @@
+//! Ctrl-C handling. While a scan or a deletion runs, Ctrl-C asks it to wrap up,
+//! so the run can report what it got done instead of dying mid-output; a
+//! second Ctrl-C, or one at any other time, quits right away.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use colored::Colorize;
+
+use crate::tr;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// How many `Graceful` guards are alive
+static GRACEFUL: AtomicUsize = AtomicUsize::new(0);
+
+/// Takes over Ctrl-C for the rest of the run. Without this, Ctrl-C ends the
+/// process as usual and `interrupted` never becomes true.
+pub fn catch() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if GRACEFUL.load(Ordering::SeqCst) == 0 || INTERRUPTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+            eprintln!("\n{} {}", "[Interrupted]".yellow().bold(), tr!("interrupt.stopping"));
+        }
+    });
+}
+
+/// Whether Ctrl-C asked the run to wrap up.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Marks work that stops cleanly on Ctrl-C, for as long as it's alive.
+pub struct Graceful(());
+
+pub fn graceful() -> Graceful {
+    GRACEFUL.fetch_add(1, Ordering::SeqCst);
+    Graceful(())
+}
+
+impl Drop for Graceful {
+    fn drop(&mut self) {
+        GRACEFUL.fetch_sub(1, Ordering::SeqCst);
+    }
+}
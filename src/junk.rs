@@ -0,0 +1,147 @@
+//! Cache files that are junk by any measure: empty files, downloads given up
+//! on halfway, and archives cut off before their end. Nothing can use them,
+//! so they're safe to delete even when the rest of a cache isn't.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::platform::long_path;
+use crate::scan::CacheFile;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JunkKind {
+    Empty,
+    AbandonedDownload,
+    TruncatedArchive,
+}
+
+impl JunkKind {
+    pub const ALL: [JunkKind; 3] = [JunkKind::Empty, JunkKind::AbandonedDownload, JunkKind::TruncatedArchive];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JunkKind::Empty => "empty files",
+            JunkKind::AbandonedDownload => "abandoned downloads",
+            JunkKind::TruncatedArchive => "truncated archives",
+        }
+    }
+}
+
+/// Partial downloads of browsers and download managers.
+const DOWNLOAD_EXTENSIONS: &[&str] = &["part", "crdownload", "partial", "opdownload", "!ut"];
+/// Unchanged for this long, a partial download has been given up on.
+const ABANDONED_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+/// Empty files with these extensions mean something by existing.
+const MARKER_EXTENSIONS: &[&str] = &["lock", "pid"];
+
+/// The archive formats whose ends can be checked without unpacking them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Archive {
+    Zip,
+    SevenZip,
+    Xz,
+    Tar,
+}
+
+impl Archive {
+    fn of(extension: &str) -> Option<Archive> {
+        match extension {
+            "zip" | "jar" | "whl" | "nupkg" | "vsix" => Some(Archive::Zip),
+            "7z" => Some(Archive::SevenZip),
+            "xz" | "txz" => Some(Archive::Xz),
+            "tar" => Some(Archive::Tar),
+            _ => None,
+        }
+    }
+}
+
+/// How much of an archive's head and tail the checks look at: a zip's end
+/// record sits within the last 64 KiB and 22 bytes.
+const HEAD: usize = 32;
+const TAIL: u64 = 65_557;
+
+/// Whether an archive of `length` bytes starting with `head` and ending with
+/// `tail` stops short of its end. Files that don't look like the format at
+/// all are left alone, as they may just be named oddly.
+fn is_truncated(archive: Archive, length: u64, head: &[u8], tail: &[u8]) -> bool {
+    match archive {
+        Archive::Zip => head.starts_with(b"PK") && !tail.windows(4).any(|window| window == b"PK\x05\x06"),
+        // The start header says where the header at the end lies, and how long it is
+        Archive::SevenZip if head.len() >= 32 && head.starts_with(b"7z\xBC\xAF\x27\x1C") => {
+            let u64_at = |offset: usize| u64::from_le_bytes(head[offset..offset + 8].try_into().unwrap());
+            let end = 32u64.saturating_add(u64_at(12)).saturating_add(u64_at(20));
+            length < end
+        }
+        Archive::SevenZip => false,
+        Archive::Xz => head.starts_with(b"\xFD7zXZ\x00") && !tail.ends_with(b"YZ"),
+        Archive::Tar => !length.is_multiple_of(512),
+    }
+}
+
+fn read_ends(path: &Path, length: u64) -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut file = File::open(long_path(path))?;
+    let mut head = vec![0; HEAD.min(length as usize)];
+    file.read_exact(&mut head)?;
+    let tail_length = TAIL.min(length);
+    file.seek(SeekFrom::Start(length - tail_length))?;
+    let mut tail = vec![0; tail_length as usize];
+    file.read_exact(&mut tail)?;
+    Ok((head, tail))
+}
+
+/// Why `file` is junk, if it is. Reads the head and tail of archives.
+fn junk_kind(file: &CacheFile) -> Option<JunkKind> {
+    let extension = file.path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if file.size == 0 {
+        return (!MARKER_EXTENSIONS.contains(&extension.as_str())).then_some(JunkKind::Empty);
+    }
+    if DOWNLOAD_EXTENSIONS.contains(&extension.as_str()) && file.age(false) >= ABANDONED_AFTER {
+        return Some(JunkKind::AbandonedDownload);
+    }
+
+    let archive = Archive::of(&extension)?;
+    let length = std::fs::metadata(long_path(&file.path)).ok()?.len();
+    let (head, tail) = read_ends(&file.path, length).ok()?;
+    is_truncated(archive, length, &head, &tail).then_some(JunkKind::TruncatedArchive)
+}
+
+/// The junk among `cache_files`, with why each is junk.
+pub async fn find(cache_files: &[CacheFile]) -> Vec<(CacheFile, JunkKind)> {
+    let cache_files = cache_files.to_vec();
+    tokio::task::spawn_blocking(move || {
+        cache_files.into_iter().filter_map(|file| junk_kind(&file).map(|kind| (file, kind))).collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spots_truncated_archives() {
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend([0; 100]);
+        assert!(is_truncated(Archive::Zip, zip.len() as u64, &zip, &zip));
+        zip.extend(b"PK\x05\x06");
+        zip.extend([0; 18]);
+        assert!(!is_truncated(Archive::Zip, zip.len() as u64, &zip, &zip));
+        assert!(!is_truncated(Archive::Zip, 10, b"not a zip", b"not a zip"));
+
+        let mut seven_zip = b"7z\xBC\xAF\x27\x1C\x00\x04".to_vec();
+        seven_zip.extend([0; 4]);
+        seven_zip.extend(1000u64.to_le_bytes());
+        seven_zip.extend(50u64.to_le_bytes());
+        seven_zip.extend([0; 4]);
+        assert!(is_truncated(Archive::SevenZip, 500, &seven_zip, &[]));
+        assert!(!is_truncated(Archive::SevenZip, 1082, &seven_zip, &[]));
+
+        assert!(is_truncated(Archive::Xz, 100, b"\xFD7zXZ\x00", b"\x00\x00"));
+        assert!(!is_truncated(Archive::Xz, 100, b"\xFD7zXZ\x00", b"\x00YZ"));
+        assert!(is_truncated(Archive::Tar, 1000, &[], &[]));
+        assert!(!is_truncated(Archive::Tar, 1024, &[], &[]));
+    }
+}
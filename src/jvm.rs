@@ -0,0 +1,75 @@
+//! The caches of JVM build tools: Gradle, Maven, and sbt with Ivy and
+//! Coursier. They are re-downloadable, but often several gigabytes, and
+//! expensive to fetch again.
+
+use std::path::{Component, Path};
+
+use crate::classify::{lowercase_name, CacheCategory};
+
+/// A JVM build tool's cache a path is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JvmCache {
+    /// `gradle`, `maven`, `sbt` or `coursier`
+    pub tool: &'static str,
+    /// Package for downloads, Log for Gradle's daemon logs
+    pub category: CacheCategory,
+}
+
+/// Component runs that mark a tool's download cache, matched anywhere in a
+/// path: `~/.gradle/caches`, `~/.m2/repository`, `~/.ivy2/cache`,
+/// `~/.sbt/boot`, and Coursier's cache on Linux, macOS and Windows.
+const DOWNLOADS: &[(&[&str], &str)] = &[
+    (&[".gradle", "caches"], "gradle"),
+    (&[".m2", "repository"], "maven"),
+    (&[".ivy2", "cache"], "sbt"),
+    (&[".sbt", "boot"], "sbt"),
+    (&[".cache", "coursier"], "coursier"),
+    (&["caches", "coursier"], "coursier"),
+    (&["coursier", "cache"], "coursier"),
+];
+
+/// The JVM build tool whose cache `path` is in.
+pub fn jvm_cache(path: &Path) -> Option<JvmCache> {
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(lowercase_name(name)),
+            _ => None,
+        })
+        .collect();
+    let (file_name, dirs) = components.split_last()?;
+
+    if let Some((_, tool)) = DOWNLOADS.iter().find(|(run, _)| dirs.windows(run.len()).any(|window| window == *run)) {
+        return Some(JvmCache { tool, category: CacheCategory::Package });
+    }
+    // `~/.gradle/daemon/<version>/daemon-<pid>.out.log`
+    let daemon = dirs.windows(2).any(|pair| pair[0] == ".gradle" && pair[1] == "daemon");
+    (daemon && file_name.ends_with(".log")).then_some(JvmCache { tool: "gradle", category: CacheCategory::Log })
+}
+
+/// Whether `path` is in Maven's local repository, which also holds the
+/// artifacts of `mvn install` that can't be downloaded again.
+pub fn is_maven_repository(path: &Path) -> bool {
+    jvm_cache(path).is_some_and(|cache| cache.tool == "maven")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_jvm_build_caches() {
+        let tool = |path: &str| jvm_cache(Path::new(path)).map(|cache| (cache.tool, cache.category));
+        assert_eq!(
+            tool("/home/alice/.gradle/caches/modules-2/files-2.1/com.google.guava/guava.jar"),
+            Some(("gradle", CacheCategory::Package))
+        );
+        assert_eq!(tool("/home/alice/.gradle/daemon/8.7/daemon-4242.out.log"), Some(("gradle", CacheCategory::Log)));
+        assert_eq!(tool("/home/alice/.gradle/daemon/8.7/registry.bin"), None);
+        assert_eq!(tool("/home/alice/.m2/repository/org/slf4j/slf4j-api/2.0.9/slf4j-api-2.0.9.jar"), Some(("maven", CacheCategory::Package)));
+        assert_eq!(tool("/home/alice/.m2/settings.xml"), None);
+        assert_eq!(tool("/home/alice/.cache/coursier/v1/https/repo1.maven.org/a.jar"), Some(("coursier", CacheCategory::Package)));
+        assert_eq!(tool("/home/alice/.ivy2/local/com.example/app/1.0/app.jar"), None);
+        assert!(is_maven_repository(Path::new("C:/Users/alice/.m2/repository/junit/junit/4.13.2/junit-4.13.2.jar")));
+    }
+}
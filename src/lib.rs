@@ -0,0 +1,60 @@
+//! hica's scanning and cleaning, as a library. The `hica` binary is a thin
+//! command line over it; to drive scans from other tools, start with
+//! [`Scanner`].
+
+pub mod agent;
+pub mod anonymize;
+pub mod apps;
+pub mod archive;
+pub mod bench;
+pub mod browse;
+pub mod browser;
+pub mod checkpoint;
+pub mod classify;
+pub mod clean;
+pub mod cleaners;
+pub mod compare;
+pub mod compiler;
+pub mod config;
+pub mod crash;
+pub mod dedupe;
+pub mod doctor;
+pub mod du;
+pub mod duration;
+pub mod errors;
+pub mod export;
+pub mod gitignore;
+pub mod i18n;
+pub mod interrupt;
+pub mod json;
+pub mod junk;
+pub mod jvm;
+pub mod locations;
+pub mod lock;
+pub mod mft;
+pub mod mounts;
+pub mod notify;
+pub mod package;
+pub mod paths;
+pub mod pause;
+pub mod platform;
+pub mod plugins;
+pub mod policy;
+pub mod progress;
+pub mod protect;
+pub mod python;
+pub mod remote;
+pub mod report;
+pub mod scan;
+pub mod sign;
+pub mod snapshots;
+#[cfg(unix)]
+pub mod serve;
+pub mod schedule;
+pub mod size;
+pub mod template;
+pub mod theme;
+pub mod vcs;
+pub mod walker;
+
+pub use scan::{CacheFile, Scan, ScanNote, ScanOptions, ScanPhase, ScanProgress, Scanner};
@@ -0,0 +1,255 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::KnownLocation;
+use crate::classify::CacheCategory;
+use crate::paths::{home_dir, user_cache_dir};
+
+/// Where systemd-tmpfiles reads its configuration, highest priority first.
+const TMPFILES_DIRS: &[&str] = &["/etc/tmpfiles.d", "/run/tmpfiles.d", "/usr/lib/tmpfiles.d"];
+
+fn state_home() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".local").join("state")))
+}
+
+/// A directory systemd-tmpfiles ages out, like `/var/tmp` after 30 days.
+#[derive(Debug, PartialEq)]
+pub struct AgedDirectory {
+    pub path: PathBuf,
+    pub age: Duration,
+}
+
+/// Parses a tmpfiles.d age such as `10d`, `12h` or `1w2d`.
+fn parse_tmpfiles_age(age: &str) -> Option<Duration> {
+    let mut total = 0;
+    let mut number = String::new();
+
+    for c in age.trim_start_matches('~').chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return None,
+        };
+        total += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+
+    // A bare number is seconds
+    if !number.is_empty() {
+        total += number.parse::<u64>().ok()?;
+    }
+    (total > 0).then(|| Duration::from_secs(total))
+}
+
+/// Finds the directories a tmpfiles.d config cleans by age. Lines whose path
+/// uses specifiers (`%h`, `%t`, ...) are skipped rather than guessed at.
+pub fn parse_tmpfiles(config: &str) -> Vec<AgedDirectory> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next()?;
+            let path = fields.next()?;
+            let age = fields.nth(3)?;
+
+            if !matches!(kind.trim_end_matches(['!', '-', '+', '=', '~', '^']), "d" | "D" | "e" | "v" | "q" | "Q")
+                || !path.starts_with('/')
+                || path.contains('%')
+                || path.starts_with("/run")
+            {
+                return None;
+            }
+
+            Some(AgedDirectory { path: PathBuf::from(path), age: parse_tmpfiles_age(age)? })
+        })
+        .collect()
+}
+
+/// Finds the paths a tmpfiles.d config keeps from ageing out, its `x` and `X`
+/// lines, as globs. Specifiers (`%b`, `%u`, ...) match anything, as they
+/// stand for values only systemd knows.
+pub fn parse_tmpfiles_exclusions(config: &str) -> Vec<String> {
+    config
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let kind = fields.next()?;
+            let path = fields.next()?;
+            if !matches!(kind.trim_end_matches(['!', '-', '+', '=', '~', '^']), "x" | "X") || !path.starts_with('/') {
+                return None;
+            }
+
+            let mut pattern = String::new();
+            let mut chars = path.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '%' if chars.next() == Some('%') => pattern.push('%'),
+                    '%' => pattern.push('*'),
+                    c => pattern.push(c),
+                }
+            }
+            Some(pattern)
+        })
+        .collect()
+}
+
+/// Aged directories and exclusions from every tmpfiles.d config, where a
+/// file in a higher priority directory overrides one with the same name
+/// further down.
+fn tmpfiles_directories() -> (Vec<AgedDirectory>, Vec<String>) {
+    let mut seen_names = std::collections::HashSet::new();
+    let mut directories: Vec<AgedDirectory> = Vec::new();
+    let mut exclusions = Vec::new();
+
+    for dir in TMPFILES_DIRS {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            if !seen_names.insert(file.file_name().map(|name| name.to_os_string())) {
+                continue;
+            }
+            let config = std::fs::read_to_string(&file).unwrap_or_default();
+            exclusions.extend(parse_tmpfiles_exclusions(&config));
+            for directory in parse_tmpfiles(&config) {
+                if !directories.iter().any(|d| d.path == directory.path) {
+                    directories.push(directory);
+                }
+            }
+        }
+    }
+
+    (directories, exclusions)
+}
+
+fn location(label: &'static str, path: PathBuf, category: CacheCategory) -> KnownLocation {
+    KnownLocation {
+        label,
+        path,
+        category,
+        prefix: None,
+        user: None,
+        app: None,
+        min_age: None,
+        exclude: Vec::new(),
+    }
+}
+
+pub fn locations() -> Vec<KnownLocation> {
+    let mut locations = Vec::new();
+
+    if let Some(cache) = user_cache_dir() {
+        locations.push(location("User cache", cache, CacheCategory::Application));
+    }
+
+    let (directories, exclusions) = tmpfiles_directories();
+    for directory in directories {
+        locations.push(KnownLocation {
+            min_age: Some(directory.age),
+            exclude: exclusions.clone(),
+            ..location("Temporary files (aged out by systemd-tmpfiles)", directory.path, CacheCategory::Temporary)
+        });
+    }
+
+    locations
+}
+
+/// Applications' folders in a directory, attributed by folder name.
+fn app_dirs(dir: &Path) -> Vec<(String, PathBuf)> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.path()))
+        .collect()
+}
+
+pub fn app_caches() -> Vec<KnownLocation> {
+    let mut caches = Vec::new();
+
+    if let Some(cache) = user_cache_dir() {
+        for (app, path) in app_dirs(&cache) {
+            caches.push(KnownLocation {
+                app: Some(app),
+                ..location("App cache", path, CacheCategory::Application)
+            });
+        }
+    }
+
+    // State is precious, but the logs apps keep there are not
+    if let Some(state) = state_home() {
+        for (app, path) in app_dirs(&state) {
+            for logs in ["log", "logs"] {
+                caches.push(KnownLocation {
+                    app: Some(app.clone()),
+                    ..location("App logs", path.join(logs), CacheCategory::Log)
+                });
+            }
+        }
+    }
+
+    caches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_aged_tmpfiles_directories() {
+        let config = "
+            # Clear tmp directories separately, to make them easier to override
+            q /tmp 1777 root root 10d
+            q /var/tmp 1777 root root 30d
+            d /run/user 0755 root root -
+            D %h/.cache/thumbnails - - - 2w
+            x /tmp/systemd-private-%b-*
+            L /etc/mtab - - - - ../proc/self/mounts
+        ";
+
+        assert_eq!(
+            parse_tmpfiles(config),
+            vec![
+                AgedDirectory { path: PathBuf::from("/tmp"), age: Duration::from_secs(10 * 86400) },
+                AgedDirectory { path: PathBuf::from("/var/tmp"), age: Duration::from_secs(30 * 86400) },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_tmpfiles_exclusions() {
+        let config = "
+            q /tmp 1777 root root 10d
+            x /tmp/systemd-private-%b-*
+            X /var/tmp/systemd-private-%%literal
+            x-! /tmp/.X11-unix
+        ";
+
+        assert_eq!(parse_tmpfiles_exclusions(config), ["/tmp/systemd-private-*-*", "/var/tmp/systemd-private-%literal", "/tmp/.X11-unix"]);
+    }
+
+    #[test]
+    fn parses_compound_ages() {
+        assert_eq!(parse_tmpfiles_age("1w2d"), Some(Duration::from_secs(9 * 86400)));
+        assert_eq!(parse_tmpfiles_age("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_tmpfiles_age("-"), None);
+    }
+}
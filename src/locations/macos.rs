@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use super::KnownLocation;
+use crate::classify::CacheCategory;
+use crate::paths::home_dir;
+
+fn location(label: &'static str, path: PathBuf, category: CacheCategory, app: Option<&str>) -> KnownLocation {
+    KnownLocation {
+        label,
+        path,
+        category,
+        prefix: None,
+        user: None,
+        app: app.map(str::to_string),
+        min_age: None,
+        exclude: Vec::new(),
+    }
+}
+
+pub fn locations() -> Vec<KnownLocation> {
+    let mut locations = vec![location(
+        "System caches",
+        PathBuf::from("/Library/Caches"),
+        CacheCategory::System,
+        None,
+    )];
+
+    if let Some(home) = home_dir() {
+        let library = home.join("Library");
+        let xcode = library.join("Developer").join("Xcode");
+        locations.extend([
+            location("User caches", library.join("Caches"), CacheCategory::Application, None),
+            location("Xcode DerivedData", xcode.join("DerivedData"), CacheCategory::Application, Some("com.apple.dt.Xcode")),
+            location("iOS DeviceSupport", xcode.join("iOS DeviceSupport"), CacheCategory::Application, Some("com.apple.dt.Xcode")),
+        ]);
+    }
+
+    locations
+}
+
+/// Attributes a folder in a `Caches` directory to its app. Most are named
+/// after the owning bundle ID already; a few well-known tools aren't.
+fn owning_app(name: &str) -> String {
+    match name {
+        "CocoaPods" => "org.cocoapods.CocoaPods".to_string(),
+        "Homebrew" => "sh.brew.Homebrew".to_string(),
+        "pip" => "org.python.pip".to_string(),
+        "Yarn" => "com.yarnpkg.Yarn".to_string(),
+        _ => name.to_string(),
+    }
+}
+
+fn caches_in(dir: &Path, label: &'static str) -> Vec<KnownLocation> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            let app = owning_app(&entry.file_name().to_string_lossy());
+            location(label, entry.path(), CacheCategory::Application, Some(&app))
+        })
+        .collect()
+}
+
+pub fn app_caches() -> Vec<KnownLocation> {
+    let mut caches = caches_in(Path::new("/Library/Caches"), "System app cache");
+
+    if let Some(home) = home_dir() {
+        caches.extend(caches_in(&home.join("Library").join("Caches"), "App cache"));
+        caches.extend(
+            locations()
+                .into_iter()
+                .filter(|location| location.app.is_some()),
+        );
+    }
+
+    caches
+}
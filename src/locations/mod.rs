@@ -0,0 +1,231 @@
+//! Well-known cache locations of the operating system, found by where the
+//! platform keeps them rather than by name patterns.
+
+mod linux;
+mod macos;
+mod windows;
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use walkdir::WalkDir;
+
+use crate::cleaners::dir_size;
+use crate::gitignore::match_glob;
+use crate::platform;
+use crate::protect::ProtectedPaths;
+
+use crate::classify::CacheCategory;
+
+/// A directory whose contents are cache.
+pub struct KnownLocation {
+    pub label: &'static str,
+    pub path: PathBuf,
+    pub category: CacheCategory,
+    /// When set, only files whose names start with this belong to the cache.
+    pub prefix: Option<&'static str>,
+    /// The account the location belongs to, when scanning several users.
+    pub user: Option<String>,
+    /// The application that owns the cache, e.g. its macOS bundle ID.
+    pub app: Option<String>,
+    /// When set, only entries untouched for this long belong to the cache,
+    /// like systemd-tmpfiles ageing out `/tmp`.
+    pub min_age: Option<Duration>,
+    /// Paths, with globs, left alone along with everything in them, like the
+    /// `x` and `X` lines of tmpfiles.d.
+    pub exclude: Vec<String>,
+}
+
+/// What's inside an entry, as far as deciding whether it may go.
+struct Contents {
+    /// The latest a file in it was modified or read, or a directory in it
+    /// changed. Directories' access times are left out, since listing them
+    /// (as this does) updates those.
+    newest: Option<SystemTime>,
+    /// Whether it holds sockets, pipes or devices, which belong to running
+    /// programs.
+    special: bool,
+}
+
+fn contents(entry: &Path) -> Contents {
+    let mut newest = None;
+    let mut special = false;
+    for metadata in WalkDir::new(entry).follow_links(false).into_iter().flatten().filter_map(|entry| entry.metadata().ok()) {
+        let kind = metadata.file_type();
+        special |= !(kind.is_file() || kind.is_dir() || kind.is_symlink());
+        let accessed = metadata.accessed().ok().filter(|_| !kind.is_dir());
+        newest = newest.max(metadata.modified().ok()).max(accessed);
+    }
+    Contents { newest, special }
+}
+
+/// Whether `path` is at, above or below a path matching `pattern`, so that
+/// removing it would touch what the pattern keeps.
+fn overlaps(pattern: &str, path: &Path) -> bool {
+    let names = |path: &Path| -> Vec<String> {
+        path.components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+    names(Path::new(pattern)).iter().zip(names(path)).all(|(pattern, name)| match_glob(pattern, &name))
+}
+
+/// Whether a running program has `path`, or anything in it, open.
+fn in_use(path: &Path, open: &HashSet<PathBuf>) -> bool {
+    open.iter().any(|open| open.starts_with(path))
+}
+
+/// Known cache locations on this platform that exist, deduplicated by path.
+pub fn known_locations() -> Vec<KnownLocation> {
+    let locations = if cfg!(windows) {
+        windows::locations()
+    } else if cfg!(target_os = "macos") {
+        macos::locations()
+    } else if cfg!(target_os = "linux") {
+        linux::locations()
+    } else {
+        Vec::new()
+    };
+
+    existing(locations)
+}
+
+/// Cache locations attributed to the application that owns them.
+pub fn app_caches() -> Vec<KnownLocation> {
+    let caches = if cfg!(target_os = "macos") {
+        macos::app_caches()
+    } else if cfg!(target_os = "linux") {
+        linux::app_caches()
+    } else {
+        Vec::new()
+    };
+
+    existing(caches)
+}
+
+fn existing(mut locations: Vec<KnownLocation>) -> Vec<KnownLocation> {
+    locations.retain(|location| location.path.is_dir());
+    let mut seen = std::collections::HashSet::new();
+    locations.retain(|location| seen.insert(location.path.clone()));
+    locations
+}
+
+impl KnownLocation {
+    /// The entries directly inside the location that belong to the cache.
+    pub fn entries(&self) -> Vec<PathBuf> {
+        self.entries_except(&platform::open_paths())
+    }
+
+    /// The entries, leaving out those running programs have open. An entry
+    /// only ages out once nothing in it has been touched for `min_age`, and
+    /// never while it holds sockets or the like.
+    fn entries_except(&self, open: &HashSet<PathBuf>) -> Vec<PathBuf> {
+        let prefix = self.prefix.map(str::to_lowercase);
+
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&self.path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| {
+                prefix
+                    .as_deref()
+                    .is_none_or(|prefix| entry.file_name().to_string_lossy().to_lowercase().starts_with(prefix))
+            })
+            .map(|entry| entry.path())
+            .filter(|entry| !self.exclude.iter().any(|pattern| overlaps(pattern, entry)))
+            .filter(|entry| {
+                self.min_age.is_none_or(|min_age| {
+                    let contents = contents(entry);
+                    !contents.special
+                        && !in_use(entry, open)
+                        && contents.newest.and_then(|time| time.elapsed().ok()).is_some_and(|age| age >= min_age)
+                })
+            })
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    pub fn size(&self) -> u64 {
+        self.entries().iter().map(|entry| dir_size(entry)).sum()
+    }
+
+    /// Deletes the cache's entries, keeping the location itself. Entries are
+    /// emptied file by file rather than removed whole, so protected files,
+    /// sockets and files in use stay, along with the directories holding
+    /// them. Returns the bytes freed and how many files couldn't be removed,
+    /// which for live caches usually means they're in use.
+    pub async fn clean(&self, protected: &ProtectedPaths) -> (u64, usize) {
+        let open = platform::open_paths();
+        let mut freed = 0;
+        let mut failed = 0;
+
+        for entry in self.entries_except(&open) {
+            if protected.is_protected(&entry) {
+                continue;
+            }
+
+            for item in WalkDir::new(&entry).follow_links(false).contents_first(true).into_iter().flatten() {
+                let path = item.path();
+                let kind = item.file_type();
+                if kind.is_dir() {
+                    // Still holding what was kept, which is fine
+                    let _ = tokio::fs::remove_dir(path).await;
+                    continue;
+                }
+                if !(kind.is_file() || kind.is_symlink()) || open.contains(path) || protected.is_protected(path) {
+                    continue;
+                }
+                let size = item.metadata().map_or(0, |metadata| metadata.len());
+                match tokio::fs::remove_file(path).await {
+                    Ok(_) => freed += size,
+                    Err(_) => failed += 1,
+                }
+            }
+        }
+
+        (freed, failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn ages_entries_by_what_is_inside() {
+        let dir = std::env::temp_dir().join(format!("hica-locations-{}", std::process::id()));
+        for name in ["old", "fresh/deep", "kept"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        let long_ago = SystemTime::now() - Duration::from_secs(90 * 86400);
+        for file in ["old/a", "fresh/b", "fresh/deep/c", "kept/d"] {
+            std::fs::write(dir.join(file), "x").unwrap();
+            let time = if file == "fresh/deep/c" { SystemTime::now() } else { long_ago };
+            std::fs::File::options().write(true).open(dir.join(file)).unwrap()
+                .set_times(std::fs::FileTimes::new().set_modified(time).set_accessed(time)).unwrap();
+        }
+        for name in ["old", "fresh", "kept"] {
+            std::fs::File::open(dir.join(name)).unwrap().set_modified(long_ago).unwrap();
+        }
+        let location = KnownLocation {
+            label: "Temporary files",
+            path: dir.clone(),
+            category: CacheCategory::Temporary,
+            prefix: None,
+            user: None,
+            app: None,
+            min_age: Some(Duration::from_secs(30 * 86400)),
+            exclude: vec![format!("{}/k*", dir.display())],
+        };
+        let entries = location.entries();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries, [dir.join("old")]);
+    }
+}
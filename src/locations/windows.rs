@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::KnownLocation;
+use crate::classify::CacheCategory;
+use crate::paths::{env_var_ignore_case, expand_env_vars, expand_wildcards};
+use crate::platform::is_privileged;
+
+struct Template {
+    label: &'static str,
+    path: &'static str,
+    category: CacheCategory,
+    prefix: Option<&'static str>,
+}
+
+const fn template(label: &'static str, path: &'static str, category: CacheCategory) -> Template {
+    Template { label, path, category, prefix: None }
+}
+
+/// Locations inside each user's profile.
+const USER_TEMPLATES: &[Template] = &[
+    template("Temporary files", r"%TEMP%", CacheCategory::Temporary),
+    template("Temporary files", r"%LOCALAPPDATA%\Temp", CacheCategory::Temporary),
+    Template {
+        label: "Thumbnail cache",
+        path: r"%LOCALAPPDATA%\Microsoft\Windows\Explorer",
+        category: CacheCategory::System,
+        prefix: Some("thumbcache_"),
+    },
+    template("Internet cache", r"%LOCALAPPDATA%\Microsoft\Windows\INetCache", CacheCategory::Browser),
+    template("Chrome cache", r"%LOCALAPPDATA%\Google\Chrome\User Data\*\Cache", CacheCategory::Browser),
+    template("Chrome code cache", r"%LOCALAPPDATA%\Google\Chrome\User Data\*\Code Cache", CacheCategory::Browser),
+    template("Edge cache", r"%LOCALAPPDATA%\Microsoft\Edge\User Data\*\Cache", CacheCategory::Browser),
+    template("Edge code cache", r"%LOCALAPPDATA%\Microsoft\Edge\User Data\*\Code Cache", CacheCategory::Browser),
+    template("Firefox cache", r"%LOCALAPPDATA%\Mozilla\Firefox\Profiles\*\cache2", CacheCategory::Browser),
+];
+
+/// Machine-wide locations.
+const SYSTEM_TEMPLATES: &[Template] = &[
+    template("Windows temporary files", r"%SystemRoot%\Temp", CacheCategory::Temporary),
+    template(
+        "Delivery Optimization cache",
+        r"%SystemRoot%\ServiceProfiles\NetworkService\AppData\Local\Microsoft\Windows\DeliveryOptimization\Cache",
+        CacheCategory::System,
+    ),
+    template("Windows Update downloads", r"%SystemRoot%\SoftwareDistribution\Download", CacheCategory::System),
+];
+
+/// Profile folders that aren't real accounts.
+const SKIPPED_PROFILES: &[&str] = &["public", "default", "default user", "all users"];
+
+/// The variables a user's locations are expanded with, by user name. Without
+/// elevation only the current user's profile is readable.
+fn user_environments() -> Vec<(String, HashMap<String, String>)> {
+    let current = env_var_ignore_case("USERNAME").unwrap_or_default();
+    let mut users = vec![(current.clone(), HashMap::new())];
+
+    let profiles = env_var_ignore_case("USERPROFILE")
+        .and_then(|profile| Path::new(&profile).parent().map(Path::to_path_buf));
+    let profiles = match profiles {
+        Some(profiles) if is_privileged() => profiles,
+        _ => return users,
+    };
+
+    for entry in std::fs::read_dir(profiles).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.eq_ignore_ascii_case(&current)
+            || SKIPPED_PROFILES.contains(&name.to_lowercase().as_str())
+            || !entry.file_type().is_ok_and(|t| t.is_dir())
+        {
+            continue;
+        }
+
+        // Other users' variables can't be read, so derive them from the profile
+        let local = entry.path().join("AppData").join("Local");
+        let vars = HashMap::from([
+            ("USERPROFILE".to_string(), entry.path().display().to_string()),
+            ("LOCALAPPDATA".to_string(), local.display().to_string()),
+            ("APPDATA".to_string(), entry.path().join("AppData").join("Roaming").display().to_string()),
+            ("TEMP".to_string(), local.join("Temp").display().to_string()),
+        ]);
+        users.push((name, vars));
+    }
+
+    users
+}
+
+fn expand(template: &Template, vars: &HashMap<String, String>, user: Option<&str>) -> Vec<KnownLocation> {
+    let lookup = |name: &str| {
+        // Overrides only exist for other users; the current one uses the real environment
+        if vars.is_empty() {
+            env_var_ignore_case(name)
+        } else {
+            vars.get(&name.to_ascii_uppercase()).cloned().or_else(|| match name.to_ascii_uppercase().as_str() {
+                "SYSTEMROOT" | "SYSTEMDRIVE" => env_var_ignore_case(name),
+                _ => None,
+            })
+        }
+    };
+
+    let path = match expand_env_vars(template.path, lookup) {
+        Some(path) => PathBuf::from(path),
+        None => return Vec::new(),
+    };
+
+    expand_wildcards(&path)
+        .into_iter()
+        .map(|path| KnownLocation {
+            label: template.label,
+            path,
+            category: template.category,
+            prefix: template.prefix,
+            user: user.map(str::to_string),
+            app: None,
+            min_age: None,
+            exclude: Vec::new(),
+        })
+        .collect()
+}
+
+pub fn locations() -> Vec<KnownLocation> {
+    let mut locations = Vec::new();
+    let users = user_environments();
+    let several = users.len() > 1;
+
+    for (user, vars) in &users {
+        for template in USER_TEMPLATES {
+            locations.extend(expand(template, vars, several.then_some(user.as_str())));
+        }
+    }
+
+    for template in SYSTEM_TEMPLATES {
+        locations.extend(expand(template, &HashMap::new(), None));
+    }
+
+    locations
+}
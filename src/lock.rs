@@ -0,0 +1,172 @@
+//! One run per scan root at a time, so a manual run and a scheduled one can't
+//! race each other deleting the same files.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use crate::paths::user_cache_dir;
+
+/// An exclusive lock on a scan root, held until dropped.
+#[derive(Debug)]
+pub struct RootLock {
+    _file: File,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another run holds the lock; its process id, if it could be read
+    Held { pid: Option<u32> },
+    Io(io::Error),
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Held { pid: Some(pid) } => write!(f, "another hica run (process {}) is working on it", pid),
+            LockError::Held { pid: None } => write!(f, "another hica run is working on it"),
+            LockError::Io(e) => write!(f, "could not lock it: {}", e),
+        }
+    }
+}
+
+/// Where locks are kept: a directory every user shares, so a run as root and
+/// one as the user, from cron or by hand, see each other's locks. That's
+/// `/run/lock` (or `/var/lock`) where there is one, `ProgramData` on Windows,
+/// and the temp directory otherwise.
+fn lock_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("ProgramData")
+            .map(|dir| PathBuf::from(dir).join("hica").join("locks"))
+            .or_else(|| user_cache_dir().map(|dir| dir.join("hica").join("locks")));
+    }
+    ["/run/lock", "/var/lock"]
+        .iter()
+        .map(PathBuf::from)
+        .find(|dir| dir.is_dir())
+        .or_else(|| Some(std::env::temp_dir()))
+}
+
+/// FNV-1a, which unlike the standard hasher gives the same value in every
+/// build, so runs from different hica versions agree on a root's lock.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// The lock file for `root`: `hica-<hash of the canonical path>.lock` in the
+/// shared lock directory.
+fn lock_path(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let hash = stable_hash(root.to_string_lossy().as_bytes());
+    lock_dir().map(|dir| dir.join(format!("hica-{:016x}.lock", hash)))
+}
+
+fn open(path: &Path) -> Result<File, LockError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(LockError::Io)?;
+    }
+    // Not truncated on opening, so a waiting run can still read the holder's id
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true).truncate(false);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        // Anyone can create files in a shared lock directory, so a link
+        // planted there must not lead the lock to some other file
+        options.custom_flags(libc::O_NOFOLLOW).mode(0o644);
+    }
+    match options.open(path) {
+        // Another user's lock file can still be locked through a read-only handle
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            options.write(false).create(false);
+            options.open(path).map_err(LockError::Io)
+        }
+        result => result.map_err(LockError::Io),
+    }
+}
+
+/// Whether this process may record itself in the lock file: only in one of
+/// its own, which nothing else links to.
+fn is_own(file: &File) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        file.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.nlink() == 1 && Some(metadata.uid()) == crate::platform::current_user_id())
+    }
+    #[cfg(not(unix))]
+    {
+        file.metadata().is_ok_and(|metadata| metadata.is_file())
+    }
+}
+
+/// Records this process as the holder, for the message another run shows.
+fn claim(mut file: File) -> Result<RootLock, LockError> {
+    if is_own(&file) {
+        file.set_len(0)
+            .and_then(|_| file.rewind())
+            .and_then(|_| write!(file, "{}", std::process::id()))
+            .map_err(LockError::Io)?;
+    }
+    Ok(RootLock { _file: file })
+}
+
+fn no_lock_dir() -> LockError {
+    LockError::Io(io::Error::new(io::ErrorKind::NotFound, "no directory for lock files"))
+}
+
+impl RootLock {
+    /// Locks `root`, failing right away if another run holds it.
+    pub fn try_acquire(root: &Path) -> Result<RootLock, LockError> {
+        RootLock::try_lock_file(&lock_path(root).ok_or_else(no_lock_dir)?)
+    }
+
+    fn try_lock_file(path: &Path) -> Result<RootLock, LockError> {
+        let mut file = open(path)?;
+        match file.try_lock() {
+            Ok(()) => claim(file),
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                Err(LockError::Held { pid: pid.trim().parse().ok() })
+            }
+            Err(TryLockError::Error(e)) => Err(LockError::Io(e)),
+        }
+    }
+
+    /// Locks `root`, waiting for another run holding it to finish.
+    pub async fn acquire(root: &Path) -> Result<RootLock, LockError> {
+        let file = open(&lock_path(root).ok_or_else(no_lock_dir)?)?;
+        let file = tokio::task::spawn_blocking(move || file.lock().map(|()| file))
+            .await
+            .map_err(|e| LockError::Io(io::Error::other(e)))?
+            .map_err(LockError::Io)?;
+        claim(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_root_can_only_be_locked_once() {
+        let dir = std::env::temp_dir().join(format!("hica-lock-{}", std::process::id()));
+        let path = dir.join("root.lock");
+        let lock = RootLock::try_lock_file(&path).unwrap();
+        let second = RootLock::try_lock_file(&path);
+        drop(lock);
+        let after = RootLock::try_lock_file(&path);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(second, Err(LockError::Held { pid: Some(pid) }) if pid == std::process::id()));
+        assert!(after.is_ok());
+    }
+
+    #[test]
+    fn lock_names_are_stable() {
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -1,377 +1,2307 @@
-use clap::Parser;
+use hica::{agent, anonymize, archive, bench, browse, browser, checkpoint, classify, clean, cleaners, compare, config, dedupe, doctor, du, duration, errors, export, i18n, interrupt, junk, jvm, locations, lock, mounts, notify, package, paths, pause, platform, plugins, policy, progress, protect, remote, report, scan, schedule, sign, size, snapshots, template, theme, vcs, walker};
+use hica::tr;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use archive::ArchiveTarget;
+use browser::{running_processes, Browser};
+use clean::{delete_cache_files, CleanHooks, CleanOptions, CleanSummary, DeleteLimits};
+use config::Config;
+use dedupe::find_duplicates;
+use errors::{Operation, PathError};
+use locations::KnownLocation;
+use lock::{LockError, RootLock};
+use notify::RunReport;
+use protect::{is_dangerous_root, ProtectedPaths};
+use remote::Remote;
+use classify::{CacheCategory, Risk};
+use report::{print_age_histogram, print_apps, print_comparison, print_browser_profiles, print_container_storage, print_crash_dumps, print_disk_usage, print_junk, print_offenders, print_trash, print_duplicates, print_jvm_caches, print_compiler_caches, print_python_caches, print_roots, print_sample, print_tree, print_volume_summary, category_with_color, risk_with_color, json_line, prometheus_metrics, result_json, summary_json, write_atomically};
+use scan::{cloned_data, scan_cache_files, size_totals, CacheFile, Partial, ScanOptions, ScanStats, Staleness, StopReason};
+use schedule::{Backend, Interval, Schedule};
+use sign::Signer;
+use template::Template;
+use walker::Walker;
+use size::format_size_with_color;
 use colored::Colorize;
-use futures::future::BoxFuture;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use tokio::fs;
+use std::time::{Duration, Instant, SystemTime};
 
-fn format_size_with_color(size: u64) -> colored::ColoredString {
-    let units = ["B", "KB", "MB", "GB", "TB"];
-    let mut size_value = size as f64;
-    let mut unit_index = 0;
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Config file to use (default: hica/config.toml in the user config directory)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Show a desktop notification when the run finishes
+    #[arg(long, global = true)]
+    notify: bool,
+
+    /// Language of the output (default: from LC_ALL, LC_MESSAGES or LANG)
+    #[arg(long, global = true, value_enum)]
+    lang: Option<i18n::Lang>,
+
+    /// Print sizes in 1000-based units (kB, MB, GB), as disk tools do
+    #[arg(long, global = true, conflicts_with = "bytes")]
+    si: bool,
+
+    /// Print sizes as exact byte counts
+    #[arg(long, global = true)]
+    bytes: bool,
+
+    /// Run at the lowest CPU and disk priority, e.g. for scheduled scans
+    #[arg(long, global = true)]
+    nice: bool,
+
+    /// How to show progress: a bar, or JSON events on stderr for wrapping tools
+    #[arg(long, global = true, value_enum, default_value_t = progress::ProgressFormat::Bar)]
+    progress: progress::ProgressFormat,
+
+    /// Write a plain progress line every few seconds instead of drawing a bar, as when stderr isn't a terminal
+    #[arg(long, global = true, conflicts_with = "progress")]
+    no_progress: bool,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Parser)]
+enum Commands {
+    /// Detect cache files
+    Detect(Box<DetectArgs>),
+    /// Clean the caches of a specific tool (cargo, docker, npm, pip, ...)
+    Clean(CleanArgs),
+    /// Show the operating system's well-known cache locations
+    System(SystemArgs),
+    /// Show cache usage per application
+    Apps(AppsArgs),
+    /// Show the disk usage of a directory, with how much of it is cache
+    Du(DuArgs),
+    /// Run hica periodically with the system scheduler
+    #[command(subcommand)]
+    Schedule(ScheduleCommand),
+    /// Manage the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Explain how paths are detected and classified
+    Classify(ClassifyArgs),
+    /// Serve scans and cleanups to other programs over a local socket
+    Serve(ServeArgs),
+    /// Check the signature of a report written with --sign
+    Verify(VerifyArgs),
+    /// Scan a path and save the cache found to a file, to look into elsewhere with `hica import`
+    Export(ExportArgs),
+    /// Report on a scan saved with `hica export`, filtered like `hica detect`
+    Import(ImportArgs),
+    /// Show where two scans differ, per category and per directory, such as those of two machines
+    Compare(CompareArgs),
+    /// Scan and post the summary to the `[agent] endpoint` in the config, once or every so often
+    Agent(AgentArgs),
+    /// Check the config, the paths to scan and what this machine offers, as a first stop when something's wrong
+    Doctor(DoctorArgs),
+    /// Time a few ways of walking a directory tree, to see which suits this machine
+    #[command(hide = true)]
+    Bench(BenchArgs),
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+    /// Signed report to check
+    report: PathBuf,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    /// Socket to listen on (default: hica.sock in $XDG_RUNTIME_DIR)
+    #[arg(long)]
+    socket: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ClassifyArgs {
+    /// Paths to explain (they don't need to exist)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Validate the config file, reporting every problem with its line number
+    Check,
+    /// Write a commented example config file
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Args)]
+struct DetectArgs {
+    /// Path to scan (default: current directory)
+    path: Option<PathBuf>,
+
+    /// Delete the detected cache files without prompting
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Allow deleting when the scan path is a filesystem root or a home directory
+    #[arg(long)]
+    i_know_what_im_doing: bool,
+
+    /// Stop deleting once this much has been freed (e.g. 10GB)
+    #[arg(long, value_name = "SIZE", value_parser = size::parse_size)]
+    max_delete_size: Option<u64>,
+
+    /// Stop deleting after this many files
+    #[arg(long, value_name = "COUNT")]
+    max_delete_count: Option<u64>,
+
+    /// Gzip log files instead of deleting them, in place or into DIR
+    #[arg(long, value_name = "DIR", num_args = 0..=1)]
+    archive: Option<Option<PathBuf>>,
+
+    /// Remove directories that are left empty after deleting
+    #[arg(long)]
+    prune_empty_dirs: bool,
+
+    /// Report sets of cache files with identical contents
+    #[arg(long)]
+    dedupe: bool,
+
+    /// Only flag files not used for at least this long (e.g. 90d, 6w, 12h)
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    older_than: Option<Duration>,
+
+    /// Only flag stale files, unused for longer than `[stale] after` in the config (default 30 days)
+    #[arg(long, conflicts_with = "older_than")]
+    stale: bool,
+
+    /// Count reads as use too, by looking at access times where available
+    #[arg(long)]
+    atime: bool,
+
+    /// Only flag files in these categories (e.g. trash,crashdump)
+    #[arg(long, value_enum, value_name = "CATEGORY", value_delimiter = ',')]
+    category: Vec<CacheCategory>,
+
+    /// Only flag files matched at least this confidently: `safe` for known cache locations only,
+    /// `likely` to add cache extensions and directory names, `risky` (the default) for everything
+    #[arg(long, value_enum, value_name = "LEVEL", default_value_t = Risk::Risky)]
+    risk: Risk,
+
+    /// Also delete files in Maven's local repository (~/.m2/repository)
+    #[arg(long)]
+    include_maven: bool,
+
+    /// Leave the caches of browsers that are currently running alone
+    #[arg(long)]
+    skip_in_use: bool,
+
+    /// Stop scanning after this long and report the partial results (e.g. 5m)
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Stop listing files after this many, to bound memory on huge trees
+    #[arg(long, value_name = "COUNT")]
+    max_files: Option<usize>,
+
+    /// Pick up an interrupted scan of the same path where it left off
+    #[arg(long)]
+    resume: bool,
+
+    /// Wait for another run on the same path to finish, instead of exiting
+    #[arg(long)]
+    wait: bool,
+
+    /// Scan every user's home directory, with a breakdown per user (needs root)
+    #[arg(long, conflicts_with_all = ["path", "archive", "resume", "format", "print0", "metrics_file", "result_file"])]
+    all_users: bool,
+
+    /// Only flag files owned by this user, by name or id (default: yourself)
+    #[arg(long, value_name = "USER", conflicts_with = "all_users")]
+    owner: Option<String>,
+
+    /// Flag files whoever owns them
+    #[arg(long, conflicts_with_all = ["owner", "all_users"])]
+    any_owner: bool,
+
+    /// Skip files and directories ignored by the .gitignore files of enclosing repositories
+    #[arg(long)]
+    respect_gitignore: bool,
+
+    /// Scan NFS, SMB, SSHFS and other network shares too, instead of skipping them
+    #[arg(long)]
+    include_network: bool,
+
+    /// How to list directories: blocking threads are usually much faster than async file operations
+    #[arg(long, value_enum, default_value_t = Walker::Auto)]
+    walker: Walker,
+
+    /// Directories the threads walker lists at once (default: one per CPU, at most 16)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Show why each file in the full list was flagged and categorized
+    #[arg(long)]
+    explain: bool,
+
+    /// Output format; anything but `text` only reports and never deletes
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// How many of the directories holding the most cache to rank (0 hides them)
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top_dirs: usize,
+
+    /// Browse the cache found directory by directory, and mark what to delete
+    #[arg(long, conflicts_with_all = ["all_users", "format", "format_template", "print0", "summary_only", "yes", "ci"])]
+    browse: bool,
+
+    /// Print only the totals, with no file list, prompts or deleting
+    #[arg(long)]
+    summary_only: bool,
+
+    /// Show where the caches are as a directory tree, with the total below each directory
+    #[arg(long, conflicts_with_all = ["all_users", "format", "print0", "ci"])]
+    tree: bool,
+
+    /// Also total the caches per directory directly in the scanned path, such as each project under ~/code
+    #[arg(long, value_enum, value_name = "GROUP", conflicts_with_all = ["all_users", "format", "print0", "ci"])]
+    group_by: Option<GroupBy>,
+
+    /// With --group-by root, also show where the caches in this directory are and its largest files
+    #[arg(long, value_name = "DIR", requires = "group_by")]
+    expand: Vec<PathBuf>,
+
+    /// Print only the paths of matching files, separated by NUL bytes (for `xargs -0`)
+    #[arg(long, conflicts_with = "format")]
+    print0: bool,
+
+    /// Print one line per matching file in this shape, e.g. "{path}\t{size_bytes}\t{category}"
+    #[arg(long, value_name = "TEMPLATE", value_parser = Template::parse, conflicts_with_all = ["format", "print0", "all_users", "sign"])]
+    format_template: Option<Template>,
+
+    /// Also write Prometheus metrics to this file, e.g. for node_exporter's textfile collector
+    #[arg(long, value_name = "FILE")]
+    metrics_file: Option<PathBuf>,
+
+    /// After deleting, write every deleted file and every failure to this JSON file
+    #[arg(long, value_name = "FILE")]
+    result_file: Option<PathBuf>,
+
+    /// Append an HMAC of the report, keyed with `[sign] key_file`, so later edits show up in `hica verify`
+    #[arg(long, conflicts_with_all = ["print0", "all_users"])]
+    sign: bool,
+
+    /// Replace home directories and hash user names in the report, for sharing it publicly
+    #[arg(long, conflicts_with_all = ["print0", "all_users"])]
+    anonymize: bool,
+
+    /// Cleanup step for CI runners: delete Build and Package caches without prompts or colors,
+    /// never touch browser data, stay within --timeout (default 10m), and print the result as JSON
+    #[arg(long, conflicts_with_all = ["all_users", "format", "print0", "summary_only", "archive", "dedupe"])]
+    ci: bool,
+}
+
+/// How long a `--ci` run may take, scanning and deleting, unless `--timeout` says otherwise.
+const CI_TIME_BUDGET: Duration = Duration::from_secs(10 * 60);
+
+impl DetectArgs {
+    /// Turns `--ci` into the settings it stands for.
+    fn apply_ci_preset(&mut self) {
+        if !self.ci {
+            return;
+        }
+        self.yes = true;
+        if self.category.is_empty() {
+            self.category = vec![CacheCategory::Build, CacheCategory::Package];
+        }
+        self.timeout.get_or_insert(CI_TIME_BUDGET);
+        colored::control::set_override(false);
+    }
+    
+    /// When files count as stale in this run.
+    fn staleness(&self, config: &Config) -> Staleness {
+        Staleness { use_atime: self.atime || config.staleness.use_atime, ..config.staleness }
+    }
+    
+    /// How long files must have gone unused to be flagged, if at all.
+    fn min_age(&self, config: &Config) -> Option<Duration> {
+        self.older_than.or(self.stale.then_some(config.staleness.after))
+    }
+    
+    /// Whether `--category` and `--risk` let `file` through.
+    fn wants(&self, file: &CacheFile) -> bool {
+        (self.category.is_empty() || self.category.contains(&file.category)) && file.risk <= self.risk
+    }
+    
+    /// Whether the output is meant for other programs, which rules out
+    /// prompts, and deleting outside `--ci`.
+    fn machine_output(&self) -> bool {
+        self.format != OutputFormat::Text || self.print0 || self.format_template.is_some() || self.ci
+    }
+    
+    /// Whether the run writes a report for elsewhere, which `--sign` and
+    /// `--anonymize` apply to.
+    fn exports_report(&self) -> bool {
+        self.machine_output() || self.metrics_file.is_some() || self.result_file.is_some()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum GroupBy {
+    /// The directories directly in the scanned path
+    Root,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Prometheus,
+    /// One JSON object per file, streamed as the scan finds them
+    Jsonl,
+    /// The totals per category and volume as one JSON object
+    Json,
+}
+
+#[derive(Args)]
+struct CleanArgs {
+    /// Tool whose caches to clean (omit to list the available cleaners)
+    tool: Option<String>,
+
+    /// Also remove locations that aren't cleaned by default
+    #[arg(long)]
+    all: bool,
+
+    /// Delete without prompting
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct SystemArgs {
+    /// Delete the contents of the listed locations
+    #[arg(long)]
+    clean: bool,
+
+    /// Delete without prompting
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct DuArgs {
+    /// Directory to measure (default: current directory)
+    path: Option<PathBuf>,
+
+    /// How many entries to list
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// File to save the scan to (`-` for stdout)
+    file: PathBuf,
+
+    /// Path to scan (default: current directory)
+    path: Option<PathBuf>,
+
+    /// Skip files and directories ignored by the .gitignore files of enclosing repositories
+    #[arg(long)]
+    respect_gitignore: bool,
+
+    /// Scan NFS, SMB, SSHFS and other network shares too, instead of skipping them
+    #[arg(long)]
+    include_network: bool,
+
+    /// Stop scanning after this long and save the partial results (e.g. 5m)
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Stop listing files after this many, to bound memory on huge trees
+    #[arg(long, value_name = "COUNT")]
+    max_files: Option<usize>,
+
+    /// How to list directories
+    #[arg(long, value_enum, default_value_t = Walker::Auto)]
+    walker: Walker,
+
+    /// Directories the threads walker lists at once (default: one per CPU, at most 16)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+}
+
+#[derive(Args)]
+struct ImportArgs {
+    /// Scan saved with `hica export` (`-` for stdin)
+    file: PathBuf,
+
+    /// Only include files not used for at least this long before now (e.g. 90d, 6w, 12h)
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    older_than: Option<Duration>,
+
+    /// Only include stale files, unused for longer than `[stale] after` in the config (default 30 days)
+    #[arg(long, conflicts_with = "older_than")]
+    stale: bool,
+
+    /// Count reads as use too, by looking at access times where the scan recorded them
+    #[arg(long)]
+    atime: bool,
+
+    /// Only include files in these categories (e.g. build,package)
+    #[arg(long, value_enum, value_name = "CATEGORY", value_delimiter = ',')]
+    category: Vec<CacheCategory>,
+
+    /// Only include files matched at least this confidently
+    #[arg(long, value_enum, value_name = "LEVEL", default_value_t = Risk::Risky)]
+    risk: Risk,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Print only the paths of matching files, separated by NUL bytes, as a deletion plan for `xargs -0`
+    #[arg(long, conflicts_with = "format")]
+    print0: bool,
+
+    /// Print one line per matching file in this shape, e.g. "{path}\t{size_bytes}\t{category}"
+    #[arg(long, value_name = "TEMPLATE", value_parser = Template::parse, conflicts_with_all = ["format", "print0"])]
+    format_template: Option<Template>,
+
+    /// How many of the directories holding the most cache to rank (0 hides them)
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top_dirs: usize,
+
+    /// Show where the caches are as a directory tree, with the total below each directory
+    #[arg(long, conflicts_with_all = ["format", "print0", "format_template"])]
+    tree: bool,
+}
+
+impl ImportArgs {
+    /// Whether the filters let `file` through.
+    fn wants(&self, file: &CacheFile, config: &Config) -> bool {
+        let min_age = self.older_than.or(self.stale.then_some(config.staleness.after));
+        (self.category.is_empty() || self.category.contains(&file.category))
+            && file.risk <= self.risk
+            && min_age.is_none_or(|min_age| file.age(self.atime || config.staleness.use_atime) >= min_age)
+    }
+}
+
+#[derive(Args)]
+struct CompareArgs {
+    /// The scan to compare against: a --format json or jsonl report, or a `hica export` file
+    a: PathBuf,
+
+    /// The scan to compare, in any of the same forms
+    b: PathBuf,
+
+    /// How many of the directories that changed the most to list
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    top: usize,
+}
+
+#[derive(Args)]
+struct AgentArgs {
+    /// Path to scan (default: your home directory)
+    path: Option<PathBuf>,
+
+    /// Keep running, scanning and reporting this often (e.g. 6h), instead of once
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    every: Option<Duration>,
+
+    /// Stop each scan after this long and report the partial results (e.g. 30m)
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse_duration)]
+    timeout: Option<Duration>,
+}
+
+#[derive(Args)]
+struct DoctorArgs {
+    /// Paths to check for scanning (default: your home directory)
+    paths: Vec<PathBuf>,
+
+    /// Also check where logs archived with --archive DIR would go
+    #[arg(long, value_name = "DIR")]
+    archive: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    /// Directory to walk (default: current directory)
+    path: Option<PathBuf>,
+
+    /// Numbers of jobs to try the parallel strategies with
+    #[arg(long, value_delimiter = ',', default_value = "1,4,16", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Vec<u16>,
+
+    /// Walks per strategy, of which the fastest counts
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u16).range(1..))]
+    runs: u16,
+}
+
+#[derive(Args)]
+struct AppsArgs {
+    /// How many applications to list
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+
+    /// Delete the caches of these applications (by bundle ID or name)
+    #[arg(long, value_name = "APP", num_args = 1..)]
+    clean: Vec<String>,
+
+    /// Delete without prompting
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Register a periodic run, e.g. `hica schedule install -- detect ~/Downloads --older-than 30d --yes`
+    Install(ScheduleInstallArgs),
+    /// Unregister a periodic run
+    Remove(ScheduleRemoveArgs),
+}
+
+#[derive(Args)]
+struct ScheduleInstallArgs {
+    /// Name of the schedule, to tell several apart
+    #[arg(long, default_value = "default")]
+    name: String,
+
+    /// How often to run
+    #[arg(long, value_enum, default_value_t = Interval::Daily)]
+    every: Interval,
+
+    /// Scheduler to register with (default: the platform's usual one)
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+
+    /// The hica command to run, after `--`
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    command: Vec<String>,
+}
+
+#[derive(Args)]
+struct ScheduleRemoveArgs {
+    /// Name of the schedule to remove
+    #[arg(long, default_value = "default")]
+    name: String,
+
+    /// Scheduler it was registered with (default: the platform's usual one)
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+}
+
+/// Points at each package manager's own clean command for its download cache,
+/// and leaves root-owned caches alone unless hica runs with privileges.
+async fn hold_back_package_caches(cache_files: &mut Vec<CacheFile>) {
+    let privileged = platform::is_privileged();
+    
+    for cache in package::PACKAGE_CACHES {
+        let files: Vec<&CacheFile> = cache_files
+            .iter()
+            .filter(|f| f.category == CacheCategory::Package && package::package_cache(&f.path).is_some_and(|c| c.manager == cache.manager))
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+        
+        let held_back = cache.needs_root && !privileged;
+        println!("\n{} {} files ({}) are {}'s download cache; the supported way to clean it is `{}`{}",
+            "[Note]".blue(),
+            files.len().to_string().cyan(),
+            format_size_with_color(size_totals(files).await.size),
+            cache.manager,
+            cache.advice().yellow(),
+            if held_back { ", so they'll be left alone without root" } else { "" }
+        );
+        
+        if held_back {
+            cache_files.retain(|f| package::package_cache(&f.path).is_none_or(|c| c.manager != cache.manager));
+        }
+    }
+}
+
+/// Leaves files in the trash to `hica clean trash`, since deleting them one by
+/// one would leave the trash's own bookkeeping behind.
+async fn hold_back_trash(cache_files: &mut Vec<CacheFile>) {
+    let (trash, rest): (Vec<CacheFile>, Vec<CacheFile>) = std::mem::take(cache_files)
+        .into_iter()
+        .partition(|f| f.category == CacheCategory::Trash);
+    *cache_files = rest;
+    if !trash.is_empty() {
+        println!("\n{} {}",
+            "[Note]".blue(),
+            tr!("trash.held-back", trash.len().to_string().cyan(), format_size_with_color(size_totals(&trash).await.size))
+        );
+    }
+}
+
+/// Leaves Maven's local repository alone, since it may hold artifacts that
+/// were installed locally rather than downloaded.
+async fn hold_back_maven(cache_files: &mut Vec<CacheFile>) {
+    let (maven, rest): (Vec<CacheFile>, Vec<CacheFile>) = std::mem::take(cache_files)
+        .into_iter()
+        .partition(|f| jvm::is_maven_repository(&f.path));
+    *cache_files = rest;
+    if !maven.is_empty() {
+        println!("\n{} {}",
+            "[Note]".blue(),
+            tr!("maven.held-back", maven.len().to_string().cyan(), format_size_with_color(size_totals(&maven).await.size))
+        );
+    }
+}
+
+/// Warns about browser caches whose browser is running, since deleting a live
+/// profile's cache can corrupt it, and drops them if `skip` is set.
+fn warn_running_browsers(cache_files: &mut Vec<CacheFile>, skip: bool) {
+    let browser_of = |file: &CacheFile| match file.category {
+        CacheCategory::Browser => Browser::from_path(&file.path),
+        _ => None,
+    };
+    
+    let mut browsers: Vec<Browser> = cache_files.iter().filter_map(browser_of).collect();
+    if browsers.is_empty() {
+        return;
+    }
+    browsers.sort_by_key(|b| b.as_str());
+    browsers.dedup();
+    
+    let processes = running_processes();
+    let running: Vec<Browser> = browsers.into_iter().filter(|b| b.is_running(&processes)).collect();
+    
+    for browser in &running {
+        println!("\n{} {}", "[Warning!]".red().bold(), tr!("browser.running", browser.as_str().yellow()));
+    }
+    
+    if skip && !running.is_empty() {
+        let before = cache_files.len();
+        cache_files.retain(|f| !browser_of(f).is_some_and(|b| running.contains(&b)));
+        println!("{} {}", "[Note]".blue(), tr!("browser.skipping", (before - cache_files.len()).to_string().cyan()));
+    } else if !running.is_empty() {
+        println!("{}", tr!("browser.close-first"));
+    }
+}
+
+async fn clean_tool(args: &CleanArgs, config: &Config) {
+    let name = match &args.tool {
+        Some(name) => name,
+        None => {
+            println!("{}", tr!("clean.available").blue().bold());
+            for cleaner in cleaners::registry() {
+                let size: u64 = cleaner.targets().iter().filter(|t| t.default).map(|t| t.size()).sum();
+                println!("  {} - {} ({})", cleaner.name().cyan(), cleaner.description(), format_size_with_color(size));
+            }
+            return;
+        }
+    };
+    
+    let cleaner = match cleaners::find(name) {
+        Some(cleaner) => cleaner,
+        None => {
+            let names: Vec<&str> = cleaners::registry().iter().map(|c| c.name()).collect();
+            eprintln!("{} {}", "[Error!]".red(), tr!("clean.unknown-tool", name, names.join(", ")));
+            std::process::exit(1);
+        }
+    };
+    
+    let targets = cleaner.targets();
+    if targets.is_empty() {
+        println!("{} {}", "[OK!]".green(), tr!("clean.none-found", cleaner.name()));
+        return;
+    }
+    
+    println!("{}", tr!("clean.locations", cleaner.name()).blue().bold());
+    let mut selected = Vec::new();
+    for target in &targets {
+        let size = target.size();
+        let included = target.default || args.all;
+        println!("  {} ({}){}\n    {}\n    {}",
+            target.label.yellow(),
+            format_size_with_color(size),
+            if included { String::new() } else { format!(" {}", tr!("clean.needs-all")).dimmed().to_string() },
+            target.describe(),
+            target.note.dimmed()
+        );
+        if included {
+            selected.push((target, size));
+        }
+    }
+    
+    // Protected targets are left out before the prompt and the hooks count them
+    let protected = ProtectedPaths::new(&config.protected);
+    selected.retain(|(target, _)| {
+        let kept = target.path().is_some_and(|path| protected.is_protected(path));
+        if kept {
+            println!("  {} {}", "[Protected]".blue(), tr!("clean.skipped", target.describe()));
+        }
+        !kept
+    });
+    
+    let total: u64 = selected.iter().map(|(_, size)| size).sum();
+    if selected.is_empty() {
+        println!("\n{} {}", "[OK!]".green(), tr!("clean.nothing-selected"));
+        return;
+    }
+    
+    let question = tr!("prompt.delete-targets", selected.len(), format_size_with_color(total));
+    if !args.yes && !confirm_deletion(question.red().bold(), selected.len(), total, (0, 0), config) {
+        println!("\n{} {}", "[OK!]".green(), tr!("delete.canceled"));
+        return;
+    }
+    
+    if let Err(e) = config.clean_hooks.before(None, selected.len(), total).await {
+        eprintln!("{} {}", "[Error!]".red(), e);
+        std::process::exit(1);
+    }
+    let mut report = RunReport { found: selected.len() as u64, found_size: total, ..Default::default() };
+    for (target, size) in selected {
+        match target.clean().await {
+            Ok(_) => {
+                println!("  {} {}", "[OK!]".green(), tr!("clean.cleaned", target.describe()));
+                report.deleted += 1;
+                report.freed += size;
+            }
+            Err(e) => {
+                println!("  {} {}", "[Failed!]".red(), tr!("clean.failed", target.describe(), e.to_string().red()));
+                report.errors.push(format!("failed to clean {}: {}", target.describe(), e));
+            }
+        }
+    }
+    
+    config.clean_hooks.after(None, report.deleted, report.freed, report.errors.len()).await;
+    
+    println!("\n{} {}", "[OK!]".green(), tr!("delete.freed", format_size_with_color(report.freed)));
+    send_report(config, report, args.yes).await;
+}
+
+async fn system_locations(args: &SystemArgs, config: &Config) {
+    let locations = locations::known_locations();
+    if locations.is_empty() {
+        println!("{} {}", "[OK!]".green(), tr!("system.none"));
+        return;
+    }
+    
+    println!("{}", tr!("system.title").blue().bold());
+    let mut total = 0;
+    for location in &locations {
+        let size = location.size();
+        total += size;
+        println!("  {}{} [{}] ({})\n    {}",
+            location.label.yellow(),
+            location.user.as_deref().map(|user| format!(" ({})", user)).unwrap_or_default(),
+            category_with_color(location.category),
+            format_size_with_color(size),
+            location.path.display()
+        );
+    }
+    println!("\n{} {}",
+        "[OK!]".green(),
+        tr!("system.total", locations.len().to_string().cyan(), format_size_with_color(total))
+    );
+    
+    if !args.clean {
+        return;
+    }
+    if !args.yes && !prompt_yes_no(tr!("prompt.delete-locations").red().bold()) {
+        println!("\n{} {}", "[OK!]".green(), tr!("delete.canceled"));
+        return;
+    }
+    
+    let protected = ProtectedPaths::new(&config.protected);
+    let summary = clean_locations(&locations, total, &protected, &config.clean_hooks).await;
+    send_report(config, RunReport::from_summary(locations.len(), total, summary), args.yes).await;
+}
+
+/// Empties the locations, of `size` bytes in all, between the clean hooks;
+/// `deleted_count` in the result counts locations emptied completely. Those
+/// protected as a whole are skipped before the pre-clean hook is told what
+/// goes. Exits without touching anything if the pre-clean hook fails.
+async fn clean_locations(locations: &[KnownLocation], size: u64, protected: &ProtectedPaths, hooks: &CleanHooks) -> CleanSummary {
+    let (kept, locations): (Vec<&KnownLocation>, Vec<&KnownLocation>) = locations
+        .iter()
+        .partition(|location| protected.is_protected(&location.path));
+    for location in &kept {
+        println!("  {} {}", "[Protected]".blue(), tr!("clean.skipped", location.path.display()));
+    }
+    let size = size.saturating_sub(kept.iter().map(|location| location.size()).sum());
+    
+    if let Err(e) = hooks.before(None, locations.len(), size).await {
+        eprintln!("{} {}", "[Error!]".red(), e);
+        std::process::exit(1);
+    }
+    let mut summary = CleanSummary::default();
+    let _graceful = interrupt::graceful();
+    for (cleaned, location) in locations.iter().enumerate() {
+        if interrupt::interrupted() {
+            println!("\n{} {}", "[Interrupted]".yellow().bold(), tr!("clean.left-alone", (locations.len() - cleaned).to_string().cyan()));
+            break;
+        }
+        let (location_freed, failed) = location.clean(protected).await;
+        summary.deleted_size += location_freed;
+        
+        if failed > 0 {
+            summary.errors.push(PathError {
+                operation: Operation::Delete,
+                path: location.path.clone(),
+                message: tr!("clean.in-use", failed),
+            });
+        } else {
+            summary.deleted_count += 1;
+        }
+    }
+    hooks.after(None, summary.deleted_count, summary.deleted_size, summary.errors.len()).await;
+    
+    println!("\n{} {}", "[OK!]".green(), tr!("delete.freed", format_size_with_color(summary.deleted_size)));
+    errors::print_summary(&summary.errors);
+    summary
+}
+
+/// Reports the outcome of a cleanup: as a desktop notification if enabled, and
+/// to the configured webhook if the run was unattended.
+async fn send_report(config: &Config, mut report: RunReport, unattended: bool) {
+    if config.desktop_notify {
+        let title = tr!("notify.freed", size::format_size(report.freed));
+        let mut body = tr!("notify.body", report.deleted, report.found);
+        if !report.errors.is_empty() {
+            body.push_str(&tr!("notify.errors", report.errors.len()));
+        }
+        notify_desktop(&title, &body);
+    }
+    
+    if !unattended {
+        return;
+    }
+    
+    report.command = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+    if let Some(url) = &config.webhook_url
+        && let Err(e) = notify::post_webhook(url, config.webhook_format, &report).await
+    {
+        eprintln!("{} Failed to notify {}: {}", "[Warning!]".red().bold(), url, e);
+    }
+    if config.email.smtp_url.is_some()
+        && let Err(e) = notify::send_email(&config.email, &report).await
+    {
+        eprintln!("{} {}", "[Warning!]".red().bold(), tr!("email.failed", e));
+    }
+}
+
+fn notify_desktop(title: &str, body: &str) {
+    if let Err(e) = notify::desktop_notification(title, body) {
+        eprintln!("{} {}", "[Warning!]".red().bold(), e);
+    }
+}
+
+/// Measures the whole tree, not just its caches, showing which entries take
+/// the space and how much of each is cache.
+async fn disk_usage(args: &DuArgs) {
+    let path = args.path.clone().unwrap_or_else(|| PathBuf::from("."));
+    eprintln!("{} {}", "[Scan:]".yellow(), tr!("du.start", path.display()));
+    
+    let pb = if progress::shows_bar() { ProgressBar::new_spinner() } else { ProgressBar::hidden() };
+    pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] {pos} files measured").unwrap());
+    let _graceful = interrupt::graceful();
+    let usage = tokio::task::spawn_blocking({
+        let (path, pb) = (path.clone(), pb.clone());
+        move || {
+            let mut events = progress::ProgressEvents::default();
+            let usage = du::disk_usage(&path, |files| {
+                if files % 1000 == 0 {
+                    pb.set_position(files);
+                    events.update(progress::ProgressEvent { phase: "measuring", files, ..progress::ProgressEvent::default() });
+                }
+            });
+            events.finish();
+            usage
+        }
+    })
+    .await
+    .expect("the disk usage walk panicked");
+    pb.finish_and_clear();
+    
+    print_disk_usage(&path, &usage, args.top);
+    if usage.interrupted {
+        println!("{} {}", "[Interrupted]".yellow().bold(), tr!("du.partial"));
+    }
+    errors::print_summary(&usage.errors);
+}
+
+/// Scans a path like `hica detect` and saves everything it finds, unfiltered,
+/// so `hica import` can filter it any way later.
+async fn export_scan(args: &ExportArgs) {
+    let path = args.path.clone().unwrap_or_else(|| PathBuf::from("."));
+    eprintln!("{} {}", "[Scan:]".yellow(), tr!("scan.start", path.display()));
+    let options = ScanOptions {
+        respect_gitignore: args.respect_gitignore,
+        timeout: args.timeout,
+        max_files: args.max_files,
+        walker: args.walker,
+        jobs: args.jobs.map(usize::from),
+        include_network: args.include_network,
+        ..ScanOptions::default()
+    };
+    let scan = scan_cache_files(&path, &options, |_| {}).await;
+    if let Some(partial) = &scan.partial {
+        print_partial(partial, args.timeout.unwrap_or_default(), true);
+    }
+    
+    // Git can only be asked here, on the machine the repositories are on
+    let mut tracked = vcs::TrackedFiles::default();
+    let files: Vec<CacheFile> = scan.cache_files.into_iter().filter(|file| !tracked.is_tracked(&file.path)).collect();
+    let export = export::Export {
+        host: platform::hostname(),
+        root: std::path::absolute(&path).unwrap_or(path),
+        taken: SystemTime::now(),
+        files,
+    };
+    if let Err(e) = export.save(&args.file) {
+        eprintln!("{} Failed to write {}: {}", "[Error!]".red(), args.file.display(), e);
+        std::process::exit(1);
+    }
+    let size = export.files.iter().map(|file| file.size).sum();
+    eprintln!("{} {}", "[OK!]".green(), tr!("export.saved", export.files.len().to_string().cyan(), format_size_with_color(size), args.file.display()));
+    errors::print_summary(&scan.errors);
+}
+
+async fn import_scan(args: &ImportArgs, config: &Config) {
+    let export = export::Export::load(&args.file).unwrap_or_else(|e| {
+        eprintln!("{} Failed to read {}: {}", "[Error!]".red(), args.file.display(), e);
+        std::process::exit(1);
+    });
+    report_export(export, args, config).await;
+}
+
+/// Scans another machine over SSH and reports on it like `hica import`.
+async fn detect_remote(remote: &Remote, args: &DetectArgs, config: &Config) {
+    if args.yes || args.browse || args.ci || args.archive.is_some() {
+        eprintln!("{} {}", "[Error!]".red(), tr!("remote.report-only"));
+        std::process::exit(2);
+    }
+    eprintln!("{} {}", "[Remote:]".yellow(), tr!("remote.start", remote.path.as_str().cyan(), remote.host.as_str().cyan()));
+    let mut export_args = Vec::new();
+    for (set, flag) in [(args.respect_gitignore, "--respect-gitignore"), (args.include_network, "--include-network")] {
+        if set {
+            export_args.push(flag.to_string());
+        }
+    }
+    if let Some(timeout) = args.timeout {
+        export_args.push(format!("--timeout={}s", timeout.as_secs().max(1)));
+    }
+    if let Some(max_files) = args.max_files {
+        export_args.push(format!("--max-files={}", max_files));
+    }
+    if let Some(jobs) = args.jobs {
+        export_args.push(format!("--jobs={}", jobs));
+    }
+    if let Some(walker) = args.walker.to_possible_value() {
+        export_args.push(format!("--walker={}", walker.get_name()));
+    }
+    
+    let export = tokio::task::block_in_place(|| remote::scan(remote, &export_args)).unwrap_or_else(|e| {
+        eprintln!("{} {}", "[Error!]".red(), e);
+        std::process::exit(1);
+    });
+    let import = ImportArgs {
+        file: PathBuf::from(format!("{}:{}", remote.host, remote.path)),
+        older_than: args.older_than,
+        stale: args.stale,
+        atime: args.atime,
+        category: args.category.clone(),
+        risk: args.risk,
+        format: args.format,
+        print0: args.print0,
+        format_template: args.format_template.clone(),
+        top_dirs: args.top_dirs,
+        tree: args.tree,
+    };
+    report_export(export, &import, config).await;
+}
+
+/// Reports on an exported scan. The files are on another machine, so it
+/// never deletes; `--print0` lists what to delete there instead.
+async fn report_export(export: export::Export, args: &ImportArgs, config: &Config) {
+    let cache_files: Vec<CacheFile> = export.files.into_iter().filter(|file| args.wants(file, config)).collect();
+    let root = export.root;
+    let staleness = Staleness { use_atime: args.atime || config.staleness.use_atime, ..config.staleness };
+    
+    match args.format {
+        OutputFormat::Text if args.print0 => cache_files.iter().for_each(|file| print_path0(&file.path)),
+        OutputFormat::Text if args.format_template.is_some() => {
+            let template = args.format_template.as_ref().expect("checked above");
+            for file in &cache_files {
+                println!("{}", template.render(file, &root));
+            }
+        }
+        OutputFormat::Jsonl => {
+            for file in &cache_files {
+                println!("{}", json_line(file, &root));
+            }
+        }
+        OutputFormat::Json => {
+            println!("{}", summary_json(&cache_files, &root, &[], None, &[], &ScanStats::default(), &staleness).await);
+        }
+        OutputFormat::Prometheus => print!("{}", prometheus_metrics(&cache_files, &root).await),
+        OutputFormat::Text => {
+            let age = SystemTime::now().duration_since(export.taken).unwrap_or_default();
+            println!("{} {}", "[Import:]".yellow(), tr!("import.source", paths::display(&root), export.host.cyan(), duration::format_duration(age)));
+            let size = cache_files.iter().map(|file| file.size).sum();
+            println!("\n{} {}", "[OK!]".green(), tr!("scan.found", cache_files.len().to_string().cyan(), format_size_with_color(size)));
+            if cache_files.is_empty() {
+                return;
+            }
+            
+            println!("\n{}", tr!("summary.categories").blue().bold());
+            for category in CacheCategory::ALL {
+                let files: Vec<&CacheFile> = cache_files.iter().filter(|file| file.category == category).collect();
+                if files.is_empty() {
+                    continue;
+                }
+                let stale: Vec<&CacheFile> = files.iter().copied().filter(|file| file.is_stale(&staleness)).collect();
+                println!("  {}: {}, {}",
+                    category.as_str().cyan(),
+                    tr!("summary.files", files.len().to_string().cyan(), format_size_with_color(files.iter().map(|file| file.size).sum())),
+                    tr!("summary.stale", stale.len().to_string().cyan(), format_size_with_color(stale.iter().map(|file| file.size).sum()))
+                );
+            }
+            print_offenders(&cache_files, &root, args.top_dirs);
+            print_apps(&cache_files, 10).await;
+            print_age_histogram(&cache_files, staleness.use_atime).await;
+            if args.tree {
+                print_tree(&cache_files, &root);
+            }
+            println!("\n{} {}", "[Note]".blue(), tr!("import.plan-hint"));
+        }
+    }
+}
+
+fn compare_scans(args: &CompareArgs) {
+    let load = |file: &Path| compare::load(file).unwrap_or_else(|e| {
+        eprintln!("{} Failed to read {}: {}", "[Error!]".red(), file.display(), e);
+        std::process::exit(1);
+    });
+    print_comparison(&load(&args.a), &load(&args.b), args.top);
+}
+
+/// Scans and posts the summary to the agent endpoint, once or every
+/// `--every` until interrupted. A report that fails to send is given up on;
+/// the next one carries the totals as they are by then.
+async fn run_agent(args: &AgentArgs, config: &Config) {
+    let Some(endpoint) = &config.agent_endpoint else {
+        eprintln!("{} {}", "[Error!]".red(), tr!("agent.no-endpoint"));
+        std::process::exit(2);
+    };
+    let Some(path) = args.path.clone().or_else(paths::home_dir) else {
+        eprintln!("{} Could not find your home directory; pass a path to scan", "[Error!]".red());
+        std::process::exit(2);
+    };
+    let token = config.agent_token_file.as_ref().map(|file| match std::fs::read_to_string(file) {
+        Ok(token) => token.trim().to_string(),
+        Err(e) => {
+            eprintln!("{} Failed to read {}: {}", "[Error!]".red(), file.display(), e);
+            std::process::exit(1);
+        }
+    });
+    let machine_id = agent::machine_id(config.agent_machine_id.as_deref());
+    let staleness = config.staleness;
+    let options = ScanOptions { timeout: args.timeout, max_ops_per_second: config.nice_ops_per_second, ..ScanOptions::default() };
+    
+    let mut failed = false;
+    loop {
+        eprintln!("{} {}", "[Scan:]".yellow(), tr!("scan.start", path.display()));
+        let mounts = mounts::mounts();
+        let scan = scan_cache_files(&path, &options, |_| {}).await;
+        let mut tracked = vcs::TrackedFiles::default();
+        let cache_files: Vec<CacheFile> = scan.cache_files.into_iter().filter(|file| !tracked.is_tracked(&file.path)).collect();
+        if interrupt::interrupted() {
+            break;
+        }
+        
+        let summary = summary_json(&cache_files, &path, &mounts, scan.partial.as_ref(), &scan.errors, &scan.stats, &staleness).await;
+        let payload = agent::payload(&machine_id, &platform::hostname(), &summary);
+        failed = match notify::post_json(endpoint, &payload, token.as_deref()).await {
+            Ok(()) => {
+                eprintln!("{} {}", "[OK!]".green(), tr!("agent.sent", format_size_with_color(size_totals(&cache_files).await.size), endpoint));
+                false
+            }
+            Err(e) => {
+                eprintln!("{} {}", "[Warning!]".red().bold(), tr!("agent.failed", endpoint, e));
+                true
+            }
+        };
+        
+        let Some(every) = args.every else { break };
+        let next = Instant::now() + every;
+        while Instant::now() < next && !interrupt::interrupted() {
+            tokio::time::sleep(Duration::from_secs(1).min(next.saturating_duration_since(Instant::now()))).await;
+        }
+        if interrupt::interrupted() {
+            break;
+        }
+    }
+    if failed && args.every.is_none() {
+        std::process::exit(1);
+    }
+}
+
+/// Walks the tree with each strategy and number of jobs, printing the fastest
+/// of a few runs of each. The first walk also warms the filesystem cache for
+/// the others, so it isn't timed.
+async fn run_bench(args: &BenchArgs) {
+    let path = args.path.clone().unwrap_or_else(|| PathBuf::from("."));
+    eprintln!("{} Walking {} once to warm the filesystem cache", "[Bench]".yellow(), path.display());
+    let (reference, _) = bench::run(&path, bench::Strategy::SequentialAsync, 1).await;
+    println!("\n{} directories, {} files ({}), fastest of {} runs each:",
+        reference.directories.to_string().cyan(),
+        reference.files.to_string().cyan(),
+        format_size_with_color(reference.bytes),
+        args.runs
+    );
+    println!("  {:<18} {:>5} {:>10} {:>12}", "strategy", "jobs", "time", "files/s");
+    
+    let mut fastest: Option<(bench::Strategy, u16, Duration)> = None;
+    for strategy in bench::Strategy::ALL {
+        let jobs = if strategy.is_parallel() { args.jobs.clone() } else { vec![1] };
+        for jobs in jobs {
+            let mut best = Duration::MAX;
+            for _ in 0..args.runs {
+                if interrupt::interrupted() {
+                    return;
+                }
+                let (count, elapsed) = bench::run(&path, strategy, jobs as usize).await;
+                if count.files != reference.files {
+                    eprintln!("{} {} found {} files rather than {}; is the tree changing?", "[Warning!]".red(), strategy.as_str(), count.files, reference.files);
+                }
+                best = best.min(elapsed);
+            }
+            println!("  {:<18} {:>5} {:>9.2}s {:>12.0}",
+                strategy.as_str(),
+                jobs,
+                best.as_secs_f64(),
+                reference.files as f64 / best.as_secs_f64().max(0.001)
+            );
+            if fastest.is_none_or(|(_, _, time)| best < time) {
+                fastest = Some((strategy, jobs, best));
+            }
+        }
+    }
+    if let Some((strategy, jobs, _)) = fastest {
+        println!("\n{} Fastest here: {} with {} {}", "[OK!]".green(), strategy.as_str().cyan(), jobs, if jobs == 1 { "job" } else { "jobs" });
+    }
+}
+
+async fn app_caches(args: &AppsArgs, config: &Config) {
+    let mut by_app: Vec<(String, Vec<KnownLocation>, u64)> = Vec::new();
+    for location in locations::app_caches() {
+        let app = location.app.clone().unwrap_or_default();
+        let size = location.size();
+        match by_app.iter_mut().find(|(name, _, _)| *name == app) {
+            Some((_, locations, total)) => {
+                locations.push(location);
+                *total += size;
+            }
+            None => by_app.push((app, vec![location], size)),
+        }
+    }
     
-    while size_value >= 1024.0 && unit_index < units.len() - 1 {
-        size_value /= 1024.0;
-        unit_index += 1;
+    if by_app.is_empty() {
+        println!("{} {}", "[OK!]".green(), tr!("apps.none"));
+        return;
     }
+    by_app.sort_by_key(|(_, _, size)| std::cmp::Reverse(*size));
     
-    let formatted = format!("{:.1} {}", size_value, units[unit_index]);
+    if args.clean.is_empty() {
+        println!("{}", tr!("apps.title").blue().bold());
+        for (app, locations, size) in by_app.iter().take(args.top) {
+            println!("  {} ({})", app.yellow(), format_size_with_color(*size));
+            for location in locations {
+                println!("    {}", location.path.display());
+            }
+        }
+        if by_app.len() > args.top {
+            println!("  {}", tr!("list.more", (by_app.len() - args.top).to_string().cyan()));
+        }
+        return;
+    }
     
-    match units[unit_index] {
-        "TB" => formatted.red(),
-        "GB" => formatted.yellow(), // Use yellow instead of orange since orange() doesn't exist
-        "MB" => formatted.green(),
-        "KB" => formatted.blue(),
-        "B" => formatted.purple(),
-        _ => formatted.cyan(), // default color as fallback
+    let mut selected = Vec::new();
+    let mut selected_size = 0;
+    for name in &args.clean {
+        match by_app.iter().position(|(app, _, _)| app.eq_ignore_ascii_case(name)) {
+            Some(index) => {
+                let (app, locations, size) = by_app.swap_remove(index);
+                println!("  {} ({})", app.yellow(), format_size_with_color(size));
+                selected.extend(locations);
+                selected_size += size;
+            }
+            None => println!("{} {}", "[Note]".blue(), tr!("apps.not-found", name)),
+        }
     }
+    
+    if selected.is_empty()
+        || (!args.yes && !prompt_yes_no(tr!("prompt.delete-apps").red().bold()))
+    {
+        println!("\n{} {}", "[OK!]".green(), tr!("delete.canceled"));
+        return;
+    }
+    
+    let summary = clean_locations(&selected, selected_size, &ProtectedPaths::new(&config.protected), &config.clean_hooks).await;
+    send_report(config, RunReport::from_summary(selected.len(), selected_size, summary), args.yes).await;
 }
 
-fn create_progress_bar() -> ProgressBar {
-    let pb = ProgressBar::new(0);
-    pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} files scanned")
-        .unwrap()
-        .progress_chars("=> "));
-    pb
-}
-
-#[derive(Parser)]
-#[command(author, version, about, long_about = None)]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
-}
-
-#[derive(Parser)]
-enum Commands {
-    /// Detect cache files
-    Detect {
-        /// Path to scan (default: current directory)
-        path: Option<PathBuf>,
-    },
-}
-
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
-enum CacheCategory {
-    Browser,
-    System,
-    Application,
-    Log,
-    Temporary,
-    Backup,
-    Other,
-}
-
-impl CacheCategory {
-    fn as_str(&self) -> &'static str {
-        match self {
-            CacheCategory::Browser => "Browser",
-            CacheCategory::System => "System",
-            CacheCategory::Application => "Application",
-            CacheCategory::Log => "Log",
-            CacheCategory::Temporary => "Temporary",
-            CacheCategory::Backup => "Backup",
-            CacheCategory::Other => "Other",
-        }
-    }
-}
-
-#[derive(Debug)]
-struct CacheFile {
-    path: PathBuf,
-    size: u64,
-    category: CacheCategory,
-}
-
-impl CacheFile {
-    async fn new(path: PathBuf) -> Option<Self> {
-        match fs::metadata(&path).await {
-            Ok(metadata) if metadata.is_file() => {
-                // Classify the cache file
-                let category = classify_cache_file(&path).unwrap_or(CacheCategory::Other);
-                
-                Some(CacheFile {
-                    path,
-                    size: metadata.len(),
-                    category,
-                })
+fn manage_schedule(command: &ScheduleCommand, config_path: Option<&Path>) {
+    let result = match command {
+        ScheduleCommand::Install(args) => {
+            if !args.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                eprintln!("{} Schedule names may only contain letters, digits, `-` and `_`", "[Error!]".red());
+                std::process::exit(1);
+            }
+            if !args.command.iter().any(|arg| arg == "--yes" || arg == "-y") {
+                println!("{} The command has no --yes, so scheduled runs will only report and never delete",
+                    "[Note]".blue()
+                );
+            }
+            
+            let exe = std::env::current_exe()
+                .map(|exe| exe.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| "hica".to_string());
+            let mut command_line = vec![exe];
+            if let Some(path) = config_path {
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+                command_line.extend(["--config".to_string(), path.to_string_lossy().into_owned()]);
             }
-            _ => None,
+            command_line.extend(args.command.iter().cloned());
+            
+            let schedule = Schedule { name: args.name.clone(), interval: args.every, command: command_line };
+            schedule::install(&schedule, args.backend.unwrap_or_else(Backend::detect))
+                .map(|installed| format!("Installed {}", installed))
+        }
+        ScheduleCommand::Remove(args) => schedule::remove(&args.name, args.backend.unwrap_or_else(Backend::detect))
+            .map(|removed| format!("Removed {}", removed)),
+    };
+    
+    match result {
+        Ok(message) => println!("{} {}", "[OK!]".green(), message),
+        Err(e) => {
+            eprintln!("{} {}", "[Error!]".red(), e);
+            std::process::exit(1);
         }
     }
 }
 
-fn classify_cache_file(path: &Path) -> Option<CacheCategory> {
-    let file_name = match path.file_name() {
-        Some(os_str) => match os_str.to_str() {
-            Some(name) => name.to_lowercase(),
-            None => return None,
-        },
-        None => return None,
+fn check_config(path: Option<&Path>) {
+    let (path, config, errors) = match Config::check(path) {
+        Ok(checked) => checked,
+        Err(e) => {
+            eprintln!("{} {}", "[Error!]".red(), e);
+            std::process::exit(1);
+        }
     };
     
-    let path_str = path.to_string_lossy().to_lowercase();
-    
-    // Check for browser cache patterns
-    let browser_patterns = ["chrome", "firefox", "edge", "safari", "browser", "mozilla"];
-    if browser_patterns.iter().any(|&pattern| path_str.contains(pattern)) {
-        return Some(CacheCategory::Browser);
+    for error in &errors {
+        println!("{} {}:{}: {}", "[Error!]".red(), path.display(), error.line, error.message);
+    }
+    for entry in &config.protected {
+        let expanded = paths::expand_home(entry);
+        if entry.contains(['/', '\\']) && !expanded.exists() {
+            println!("{} protected path {} does not exist", "[Note]".blue(), expanded.display());
+        }
     }
     
-    // Check for log files
-    if file_name.ends_with(".log") || path_str.contains("log") {
-        return Some(CacheCategory::Log);
+    if !errors.is_empty() {
+        println!("\n{} {} has {} errors", "[Failed!]".red(), path.display(), errors.len().to_string().cyan());
+        std::process::exit(1);
+    }
+    println!("{} {} is valid", "[OK!]".green(), path.display());
+}
+
+/// Prints what `hica doctor` finds, exiting with 1 if anything failed.
+fn run_doctor(args: &DoctorArgs, config: Option<&Path>) {
+    let roots = match args.paths.is_empty() {
+        true => paths::home_dir().into_iter().collect(),
+        false => args.paths.clone(),
+    };
+    let mut sections = vec![(tr!("doctor.config"), doctor::check_config(config))];
+    for root in &roots {
+        sections.push((tr!("doctor.scanning", root.display()), doctor::check_root(root, args.archive.as_deref())));
     }
+    sections.push((tr!("doctor.platform"), doctor::check_platform()));
     
-    // Check for temporary files
-    let temp_patterns = [".tmp", ".temp", ".swp", ".swo", ".crdownload", ".part", "tmp", "temp"];
-    if temp_patterns.iter().any(|&pattern| file_name.contains(pattern) || path_str.contains(pattern)) {
-        return Some(CacheCategory::Temporary);
+    let mut failed = 0;
+    for (title, findings) in &sections {
+        println!("{}", title.blue().bold());
+        for finding in findings {
+            let tag = match finding.status {
+                doctor::Status::Ok => "[OK!]".green(),
+                doctor::Status::Note => "[Note]".blue(),
+                doctor::Status::Warning => "[Warning!]".red().bold(),
+                doctor::Status::Failed => "[Failed!]".red(),
+            };
+            println!("  {} {}", tag, finding.message);
+            failed += usize::from(finding.status == doctor::Status::Failed);
+        }
+        println!();
     }
+    if failed > 0 {
+        println!("{} {}", "[Failed!]".red(), tr!("doctor.failed", failed.to_string().cyan()));
+        std::process::exit(1);
+    }
+    println!("{} {}", "[OK!]".green(), tr!("doctor.ok"));
+}
+
+fn init_config(path: Option<&Path>, force: bool) {
+    let path = match path.map(Path::to_path_buf).or_else(config::default_path) {
+        Some(path) => path,
+        None => {
+            eprintln!("{} Could not determine the config directory, pass --config", "[Error!]".red());
+            std::process::exit(1);
+        }
+    };
     
-    // Check for backup files
-    let backup_patterns = [".bak", ".backup", ".old", "backup"];
-    if backup_patterns.iter().any(|&pattern| file_name.contains(pattern) || path_str.contains(pattern)) {
-        return Some(CacheCategory::Backup);
+    if path.exists() && !force {
+        eprintln!("{} {} already exists, pass --force to overwrite it", "[Error!]".red(), path.display());
+        std::process::exit(1);
     }
     
-    // Check for system cache patterns
-    let system_patterns = ["system", ".cache", "cache"];
-    if system_patterns.iter().any(|&pattern| path_str.contains(pattern)) {
-        return Some(CacheCategory::System);
+    let written = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
     }
+    .and_then(|_| std::fs::write(&path, config::TEMPLATE));
     
-    // Check for application cache patterns
-    let app_patterns = ["app", "application", ".app"];
-    if app_patterns.iter().any(|&pattern| path_str.contains(pattern)) {
-        return Some(CacheCategory::Application);
+    match written {
+        Ok(_) => println!("{} Wrote {}", "[OK!]".green(), path.display()),
+        Err(e) => {
+            eprintln!("{} Failed to write {}: {}", "[Error!]".red(), path.display(), e);
+            std::process::exit(1);
+        }
     }
+}
+
+/// Walks through the detection and category checks for each path, to make
+/// false positives and negatives easy to track down.
+fn explain_paths(args: &ClassifyArgs, config: &Config) {
+    let protected = ProtectedPaths::new(&config.protected);
     
-    // Default to Other
-    Some(CacheCategory::Other)
+    for (i, path) in args.paths.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", paths::display(path).yellow());
+        
+        match (classify::cache_file_match(path), plugins::classify(path)) {
+            (Some((reason, risk)), _) => println!("  Cache file: {} ({}), {}", "yes".green(), reason, risk_with_color(risk)),
+            (None, Some(plugins::Verdict::Keep { reason })) => println!("  Cache file: {} (kept by {})", "no".red(), reason),
+            (None, _) => println!("  Cache file: {} (no extension, directory or name rule matched)", "no".red()),
+        }
+        
+        // A plugin's category stands instead of the built-in checks
+        if let Some(plugins::Verdict::Cache { category, .. }) = plugins::classify(path) {
+            println!("  Category: {}", category_with_color(category));
+            if protected.is_protected(path) {
+                println!("  {} never deleted", "[Protected]".blue());
+            }
+            continue;
+        }
+        println!("  Category checks, in order:");
+        let mut decided = false;
+        for (category, reason) in classify::category_checks(path) {
+            let outcome = match (decided, reason) {
+                (true, _) => "not checked".dimmed().to_string(),
+                (false, Some(reason)) => {
+                    decided = true;
+                    format!("{} ({})", "matched".green(), reason)
+                }
+                (false, None) => "no match".dimmed().to_string(),
+            };
+            println!("    {:<12} {}", category.as_str(), outcome);
+        }
+        
+        if let Some((category, _)) = classify::explain_category(path) {
+            println!("  Category: {}", category_with_color(category));
+        }
+        if protected.is_protected(path) {
+            println!("  {} never deleted", "[Protected]".blue());
+        }
+    }
 }
 
-fn is_cache_file(path: &Path) -> bool {
-    let cache_extensions = [
-        ".cache", ".tmp", ".temp", ".swp", ".swo", ".bak", 
-        ".log", ".old", ".backup", ".crdownload", ".part",
-    ];
+/// Writes the path's raw bytes followed by a NUL, so any file name survives.
+fn print_path0(path: &Path) {
+    use std::io::Write;
     
-    let cache_directories = [
-        "cache", "caches", ".cache", "temp", ".temp", "tmp", ".tmp",
-        "logs", ".logs", "backup", ".backup", "old", ".old",
-    ];
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
     
-    let file_name = match path.file_name() {
-        Some(os_str) => match os_str.to_str() {
-            Some(name) => name,
-            None => return false,
-        },
-        None => return false,
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(&bytes).and_then(|_| stdout.write_all(b"\0"));
+}
+
+/// Prints a yes/no question and reads the answer, defaulting to "no".
+fn prompt_yes_no(question: colored::ColoredString) -> bool {
+    println!("\n{}", question);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).expect("Failed to read input");
+    i18n::lang().is_yes(&input)
+}
+
+/// Asks before deleting `count` files or locations totaling `size`: a plain
+/// yes or no, unless `risky` of them (totaling `risky_size`) matched loosely
+/// or the total is over `confirm_size`, which calls for typing the count.
+fn confirm_deletion(question: colored::ColoredString, count: usize, size: u64, risky: (usize, u64), config: &Config) -> bool {
+    let (risky, risky_size) = risky;
+    let large = config.confirm_size.filter(|&limit| size > limit);
+    if risky == 0 && large.is_none() {
+        return prompt_yes_no(question);
+    }
+    
+    if risky > 0 {
+        println!("\n{} {}", "[Risky]".red().bold(), tr!("confirm.risky", risky.to_string().cyan(), format_size_with_color(risky_size)));
+    }
+    if let Some(limit) = large {
+        println!("\n{} {}", "[Large]".red().bold(), tr!("confirm.large", format_size_with_color(size), format_size_with_color(limit)));
+    }
+    println!("\n{}", tr!("confirm.type", count.to_string().cyan()).red().bold());
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).expect("Failed to read input");
+    input.trim() == count.to_string()
+}
+
+/// Files that only matched loosely, and their total size.
+fn risky_files<'a>(cache_files: impl IntoIterator<Item = &'a CacheFile>) -> (usize, u64) {
+    cache_files
+        .into_iter()
+        .filter(|f| f.risk == Risk::Risky)
+        .fold((0, 0), |(count, size), f| (count + 1, size + f.size))
+}
+
+/// Warns that a scan ran out of time, listing where it didn't get to. With
+/// machine formats the warning goes to stderr, to keep stdout parseable.
+fn print_partial(partial: &Partial, timeout: Duration, machine_output: bool) {
+    let warning = match partial.reason {
+        StopReason::Timeout => tr!("scan.partial", duration::format_duration(timeout), partial.unreached.len()),
+        StopReason::FileLimit(max) => tr!("scan.file-limit", max, partial.unreached.len()),
+        StopReason::Interrupted => tr!("scan.interrupted", partial.unreached.len()),
     };
+    let mut lines = vec![format!("\n{} {}", "[Partial]".yellow().bold(), warning)];
+    lines.extend(partial.unreached.iter().take(10).map(|dir| format!("  {}", dir.display())));
+    if partial.unreached.len() > 10 {
+        lines.push(format!("  ... and {} more", (partial.unreached.len() - 10).to_string().cyan()));
+    }
     
-    let parent_name = match path.parent() {
-        Some(parent) => match parent.file_name() {
-            Some(os_str) => match os_str.to_str() {
-                Some(name) => name,
-                None => return false,
-            },
-            None => return false,
+    for line in lines {
+        if machine_output {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// The user whose files `detect` flags: `--owner`, or whoever runs hica
+/// unless `--any-owner` is given. `None` flags files whoever owns them.
+fn owner_filter(args: &DetectArgs) -> Option<u32> {
+    if args.any_owner {
+        return None;
+    }
+    match &args.owner {
+        Some(user) => match platform::user_id(user) {
+            Some(uid) => Some(uid),
+            None => {
+                eprintln!("{} Unknown user `{}`", "[Error!]".red(), user);
+                std::process::exit(1);
+            }
         },
-        None => return false,
-    };
+        None => platform::current_user_id(),
+    }
+}
+
+/// `detect --all-users`: scans every user's home, reports per user, and
+/// deletes each user's files separately. Only files a user owns are kept, so
+/// a symlink planted in one home can't get root to delete somebody else's.
+async fn detect_all_users(args: &DetectArgs, config: &Config) {
+    if !platform::is_privileged() {
+        eprintln!("{} --all-users needs root (an elevated prompt on Windows) to read every home directory", "[Error!]".red());
+        std::process::exit(1);
+    }
+    let homes = paths::user_homes();
+    if homes.is_empty() {
+        eprintln!("{} No user home directories found", "[Error!]".red());
+        std::process::exit(1);
+    }
     
-    // Check by extension
-    for ext in cache_extensions.iter() {
-        if file_name.to_lowercase().ends_with(ext) {
-            return true;
+    let scan_options = ScanOptions {
+        respect_gitignore: args.respect_gitignore,
+        timeout: args.timeout,
+        max_files: args.max_files,
+        max_ops_per_second: config.nice_ops_per_second,
+        walker: args.walker,
+        jobs: args.jobs.map(usize::from),
+        include_network: args.include_network,
+        ..ScanOptions::default()
+    };
+    let staleness = args.staleness(config);
+    let min_age = args.min_age(config);
+    let mut locks = Vec::new();
+    let mut users: Vec<(String, PathBuf, Vec<CacheFile>)> = Vec::new();
+    let mut errors = Vec::new();
+    for (user, home) in homes {
+        if interrupt::interrupted() {
+            break;
         }
+        if !args.summary_only {
+            locks.push(lock_root(&home, args.wait).await);
+        }
+        
+        eprintln!("{} {}", "[Scan:]".yellow(), tr!("scan.start", home.display()));
+        let scan = scan_cache_files(&home, &scan_options, |_| {}).await;
+        if let Some(partial) = &scan.partial {
+            print_partial(partial, args.timeout.unwrap_or_default(), false);
+        }
+        errors.extend(scan.errors);
+        
+        let home_owner = platform::owner(&home);
+        let mut tracked = vcs::TrackedFiles::default();
+        let files: Vec<CacheFile> = scan.cache_files
+            .into_iter()
+            .filter(|f| args.wants(f))
+            .filter(|f| min_age.is_none_or(|min_age| f.age(staleness.use_atime) >= min_age))
+            .filter(|f| home_owner.is_none() || platform::owner(&f.path) == home_owner)
+            .filter(|f| !tracked.is_tracked(&f.path))
+            .collect();
+        users.push((user, home, files));
     }
     
-    // Check by directory name
-    for dir in cache_directories.iter() {
-        if parent_name.to_lowercase() == *dir {
-            return true;
+    println!("\n{}", tr!("summary.users").blue().bold());
+    for (user, home, files) in &users {
+        println!("  {} ({}): {}",
+            user.cyan(),
+            home.display(),
+            tr!("summary.files", files.len().to_string().cyan(), format_size_with_color(size_totals(files).await.size))
+        );
+        let mut categories: Vec<CacheCategory> = files.iter().map(|f| f.category).collect();
+        categories.sort_by_key(|c| c.as_str());
+        categories.dedup();
+        for category in categories {
+            let matching: Vec<&CacheFile> = files.iter().filter(|f| f.category == category).collect();
+            let stale: Vec<&CacheFile> = matching.iter().copied().filter(|f| f.is_stale(&staleness)).collect();
+            println!("    {}: {}, {}",
+                category.as_str(),
+                tr!("summary.files", matching.len().to_string().cyan(), format_size_with_color(size_totals(matching).await.size)),
+                tr!("summary.stale", stale.len().to_string().cyan(), format_size_with_color(size_totals(stale).await.size))
+            );
         }
     }
     
-    // Check for common cache file patterns
-    let cache_patterns = [
-        "cache", "temp", "tmp", "log", "backup", "old", ".swp", ".swo",
-        "crdownload", "part", "~$", ".ds_store",
-    ];
+    let found = users.iter().map(|(_, _, files)| files.len()).sum::<usize>();
+    let found_size = users.iter().flat_map(|(_, _, files)| files).map(|f| f.size).sum::<u64>();
+    println!("\n{} {}", "[OK!]".green(), tr!("scan.found", found.to_string().cyan(), format_size_with_color(found_size)));
+    if args.summary_only || interrupt::interrupted() || found == 0 {
+        errors::print_summary(&errors);
+        return;
+    }
+    
+    if !args.i_know_what_im_doing {
+        println!("\n{} --all-users cleans entire home directories", "[Warning!]".red().bold());
+        println!("{} {}", "[Refused!]".red(), tr!("root.refused"));
+        errors::print_summary(&errors);
+        return;
+    }
     
-    for pattern in cache_patterns.iter() {
-        if file_name.to_lowercase().contains(pattern) {
-            return true;
+    let protected = ProtectedPaths::new(&config.protected);
+    for (_, _, files) in &mut users {
+        hold_back_trash(files).await;
+        if !args.include_maven {
+            hold_back_maven(files).await;
         }
+        warn_running_browsers(files, args.skip_in_use);
+    }
+    let everyone = || users.iter().flat_map(|(_, _, files)| files);
+    let (count, size) = (everyone().count(), everyone().map(|f| f.size).sum());
+    if !args.yes && !confirm_deletion(tr!("prompt.delete").red().bold(), count, size, risky_files(everyone()), config) {
+        println!("\n{} {}", "[OK!]".green(), tr!("delete.canceled"));
+        errors::print_summary(&errors);
+        return;
     }
     
-    false
-}
-
-// Define a boxed future type for recursive async function
-type WalkDirFuture<'a> = BoxFuture<'a, Vec<PathBuf>>;
-
-async fn async_walk_dir(path: &Path) -> Vec<PathBuf> {
-    async_walk_dir_inner(path).await
+    let mut total = CleanSummary::default();
+    let mut hook_failed = false;
+    for (user, home, files) in users {
+        if files.is_empty() || interrupt::interrupted() {
+            continue;
+        }
+        println!("\n{} {}", "[User]".blue().bold(), user.cyan());
+        let options = CleanOptions {
+            root: home,
+            limits: DeleteLimits {
+                max_size: args.max_delete_size.or(config.max_delete_size).map(|max| max.saturating_sub(total.deleted_size)),
+                max_count: args.max_delete_count.or(config.max_delete_count).map(|max| max.saturating_sub(total.deleted_count)),
+                deadline: None,
+            },
+            archive: None,
+            prune_empty_dirs: args.prune_empty_dirs,
+            retry: config.retry,
+            hooks: config.clean_hooks.clone(),
+            quiet: false,
+        };
+        let summary = match delete_cache_files(files, &protected, &options).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                // The users before were cleaned, so their results still get reported
+                eprintln!("{} {}", "[Error!]".red(), e);
+                hook_failed = true;
+                break;
+            }
+        };
+        total.deleted_count += summary.deleted_count;
+        total.deleted_size += summary.deleted_size;
+        total.deleted.extend(summary.deleted);
+        total.errors.extend(summary.errors);
+    }
+    errors.extend(total.errors.iter().cloned());
+    errors::print_summary(&errors);
+    send_report(config, RunReport::from_summary(found, found_size, total), args.yes).await;
+    if hook_failed {
+        std::process::exit(1);
+    }
 }
 
-// Helper function with boxed future to handle recursion
-fn async_walk_dir_inner(path: &Path) -> WalkDirFuture<'_> {
-    Box::pin(async move {
-        let mut files = Vec::new();
-        
-        if let Ok(mut dir_entries) = fs::read_dir(path).await {
-            // Use async iteration with proper Result<Option<DirEntry>> handling
-            while let Ok(Some(entry)) = dir_entries.next_entry().await {
-                let entry_path = entry.path();
-                
-                if let Ok(metadata) = fs::metadata(&entry_path).await {
-                    if metadata.is_dir() {
-                        // Recursively walk subdirectories with boxed future
-                        let mut sub_files = async_walk_dir_inner(&entry_path).await;
-                        files.append(&mut sub_files);
-                    } else if metadata.is_file() {
-                        files.push(entry_path);
-                    }
-                }
-            }
+/// Locks the scan root against other runs, waiting for them if `wait` is set
+/// and exiting otherwise.
+async fn lock_root(path: &Path, wait: bool) -> RootLock {
+    let held = match RootLock::try_acquire(path) {
+        Ok(lock) => return lock,
+        Err(held @ LockError::Held { .. }) => held,
+        Err(e) => {
+            eprintln!("{} {} {}", "[Error!]".red(), path.display(), e);
+            std::process::exit(1);
         }
-        
-        files
-    })
+    };
+    if !wait {
+        eprintln!("{} {}: {}; pass --wait to wait for it", "[Busy]".yellow().bold(), path.display(), held);
+        std::process::exit(1);
+    }
+    
+    eprintln!("{} {}: {}", "[Waiting]".yellow(), path.display(), held);
+    match RootLock::acquire(path).await {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{} {} {}", "[Error!]".red(), path.display(), e);
+            std::process::exit(1);
+        }
+    }
 }
 
-async fn scan_cache_files(path: &Path) -> Vec<CacheFile> {
-    let mut cache_files = Vec::new();
+async fn detect_cache_files(path: &Path, args: &DetectArgs, config: &Config) {
+    let started = Instant::now();
+    let owner = owner_filter(args);
+    let key = signing_key(args, config);
+    if args.anonymize {
+        if !args.exports_report() {
+            eprintln!("{} --anonymize applies to reports, so it needs --format, --format-template, --metrics-file, --result-file or --ci", "[Error!]".red());
+            std::process::exit(2);
+        }
+        anonymize::enable();
+    }
+    if args.archive.is_some() && platform::find_program("gzip").is_none() {
+        eprintln!("{} --archive compresses logs with gzip, which isn't installed", "[Error!]".red());
+        std::process::exit(2);
+    }
+    let mut out = ReportOutput::new(key);
+    
+    // Runs that may delete keep others off the same path until they're done
+    let _lock = if (args.machine_output() && !args.ci) || args.summary_only {
+        None
+    } else {
+        Some(lock_root(path, args.wait).await)
+    };
     
-    println!("{} Traversing directory structure...", "[Running!]".yellow());
+    eprintln!("{} {}", "[Scan:]".yellow(), tr!("scan.start", path.display()));
     
-    // Asynchronously get all files
-    let all_files = async_walk_dir(path).await;
-    let total_files = all_files.len() as u64;
+    let mounts = mounts::mounts();
+    let scan_options = ScanOptions {
+        respect_gitignore: args.respect_gitignore,
+        timeout: args.timeout,
+        max_files: args.max_files,
+        checkpoint: checkpoint::Checkpoint::default_path(path),
+        resume: args.resume,
+        max_ops_per_second: config.nice_ops_per_second,
+        walker: args.walker,
+        jobs: args.jobs.map(usize::from),
+        include_network: args.include_network,
+        ..ScanOptions::default()
+    };
+    let staleness = args.staleness(config);
+    let min_age = args.min_age(config);
+    let owned = |file: &CacheFile| owner.is_none() || platform::owner(&file.path).is_none_or(|uid| Some(uid) == owner);
+    let mut tracked = vcs::TrackedFiles::default();
+    let scan = scan_cache_files(path, &scan_options, |file| {
+        if min_age.is_some_and(|min_age| file.age(staleness.use_atime) < min_age) || !owned(file) || !args.wants(file) {
+            return;
+        }
+        let streaming = args.format == OutputFormat::Jsonl || args.print0 || args.format_template.is_some();
+        if streaming && tracked.is_tracked(&file.path) {
+            return;
+        }
+        if args.format == OutputFormat::Jsonl {
+            out.print(&format!("{}\n", json_line(file, &mounts::volume_of(&mounts, &file.path))));
+        } else if args.print0 {
+            print_path0(&file.path);
+        } else if let Some(template) = &args.format_template {
+            println!("{}", template.render(file, &mounts::volume_of(&mounts, &file.path)));
+        }
+    }).await;
+    let mut cache_files = scan.cache_files;
+    cache_files.retain(|f| args.wants(f));
     
-    // Create progress bar
-    let pb = create_progress_bar();
-    pb.set_length(total_files);
+    let (mine, others): (Vec<CacheFile>, Vec<CacheFile>) = cache_files.into_iter().partition(owned);
+    cache_files = mine;
+    if !others.is_empty() && !args.machine_output() {
+        println!("\n{} {}",
+            "[Note]".blue(),
+            tr!("scan.other-owners", others.len().to_string().cyan(), format_size_with_color(size_totals(&others).await.size))
+        );
+    }
     
-    // Process files asynchronously with progress updates
-    for (i, file_path) in all_files.into_iter().enumerate() {
-        pb.set_position((i + 1) as u64);
+    if let Some(min_age) = min_age {
+        let (stale, active): (Vec<CacheFile>, Vec<CacheFile>) = cache_files
+            .into_iter()
+            .partition(|f| f.age(staleness.use_atime) >= min_age);
+        cache_files = stale;
         
-        if is_cache_file(&file_path) {
-            if let Some(cache_file) = CacheFile::new(file_path).await {
-                cache_files.push(cache_file);
-            }
+        if !active.is_empty() && !args.machine_output() {
+            println!("\n{} {}",
+                "[Note]".blue(),
+                tr!("scan.ignored-recent",
+                    active.len().to_string().cyan(),
+                    tr!(if staleness.use_atime { "scan.recent-used" } else { "scan.recent-modified" }),
+                    format_size_with_color(size_totals(&active).await.size)
+                )
+            );
         }
     }
     
-    pb.finish_with_message("Scan completed");
+    // Whatever its name, a file under version control is part of the project
+    let (versioned, unversioned): (Vec<CacheFile>, Vec<CacheFile>) = cache_files
+        .into_iter()
+        .partition(|f| tracked.is_tracked(&f.path));
+    cache_files = unversioned;
     
-    cache_files
-}
-
-async fn detect_cache_files(path: &Path) {
-    println!("{} Scanning for cache files in {}", "[Scan:]".yellow(), path.display());
+    if !args.machine_output() {
+        for repository in &tracked.unlisted {
+            println!("\n{} {}", "[Warning!]".red().bold(), tr!("git.unlisted", repository.display()));
+        }
+        if !versioned.is_empty() {
+            println!("\n{} {}", "[Protected]".blue(), tr!("git.tracked", versioned.len().to_string().cyan()));
+            for file in versioned.iter().take(5) {
+                println!("  {}", paths::display(&file.path));
+            }
+            if versioned.len() > 5 {
+                println!("  {}", tr!("list.more", (versioned.len() - 5).to_string().cyan()));
+            }
+        }
+    }
+    
+    if args.format == OutputFormat::Prometheus || args.metrics_file.is_some() {
+        let metrics = prometheus_metrics(&cache_files, path).await;
+        if let Some(file) = &args.metrics_file {
+            out.write_file(file, &metrics, sign::Style::Comment);
+        }
+        if args.format == OutputFormat::Prometheus {
+            out.print(&metrics);
+        }
+    }
+    if args.format == OutputFormat::Json {
+        out.print(&format!("{}\n", summary_json(&cache_files, path, &mounts, scan.partial.as_ref(), &scan.errors, &scan.stats, &staleness).await));
+    }
+    if let Some(partial) = &scan.partial {
+        print_partial(partial, args.timeout.unwrap_or_default(), args.machine_output());
+    }
+    if args.ci {
+        let summary = summary_json(&cache_files, path, &mounts, scan.partial.as_ref(), &scan.errors, &scan.stats, &staleness).await;
+        let deadline = args.timeout.map(|budget| started + budget);
+        return clean_for_ci(cache_files, path, args, config, summary, deadline, out).await;
+    }
+    if args.machine_output() {
+        out.finish(if args.format == OutputFormat::Prometheus { sign::Style::Comment } else { sign::Style::Json });
+        return;
+    }
+    let mut errors = scan.errors;
     
-    let cache_files = scan_cache_files(path).await;
-    let total_size: u64 = cache_files.iter().map(|f| f.size).sum();
+    let totals = size_totals(&cache_files).await;
     
-    println!("\n{} Found {} cache files totaling {}", 
+    println!("\n{} {}", 
         "[OK!]".green(), 
-        cache_files.len().to_string().cyan(), 
-        format_size_with_color(total_size)
+        tr!("scan.found", cache_files.len().to_string().cyan(), format_size_with_color(totals.size))
     );
+    let cloned = cloned_data(&cache_files).await;
+    if cloned.count > 0 {
+        println!("{} {}", "[Note]".blue(), tr!("summary.cloned", cloned.count.to_string().cyan(), format_size_with_color(cloned.shared)));
+    }
+    if !cache_files.is_empty()
+        && let Some(covering) = snapshots::covering(path, &mounts)
+    {
+        println!("{} {}", "[Note]".blue(), tr!("summary.snapshots", covering.count.to_string().cyan(), covering.dir.display()));
+    }
+    
+    // Unless it goes on to delete unattended, the run ends or waits for the user here
+    if config.desktop_notify && (!args.yes || args.summary_only || cache_files.is_empty() || interrupt::interrupted()) {
+        notify_desktop(
+            &tr!("scan.finished"),
+            &tr!("scan.found", cache_files.len(), size::format_size(totals.size)),
+        );
+    }
     
     if !cache_files.is_empty() {
         // Group files by category
-        let mut categories = std::collections::HashMap::new();
+        let mut categories: HashMap<CacheCategory, Vec<&CacheFile>> = HashMap::new();
         for file in &cache_files {
-            categories.entry(file.category).or_insert_with(|| {
-                (0, 0u64) // (count, size)
-            }).0 += 1;
-            categories.entry(file.category).and_modify(|(_count, size)| {
-                *size += file.size;
-            });
+            categories.entry(file.category).or_default().push(file);
         }
         
         // Print category summary
-        println!("\n{}", "Category Summary: ".blue().bold());
-        for (category, (count, size)) in categories {
-            println!("  {}: {} files ({})", 
-                category.as_str().cyan(), 
-                count.to_string().cyan(), 
-                format_size_with_color(size)
+        println!("\n{}", tr!("summary.categories").blue().bold());
+        let mut below_threshold: Vec<&CacheFile> = Vec::new();
+        let mut rows = Vec::new();
+        for (category, files) in categories {
+            let size = size_totals(files.iter().copied()).await.size;
+            let threshold = config.thresholds.iter().find(|(c, _)| *c == category).map(|(_, threshold)| *threshold);
+            if threshold.is_some_and(|threshold| size < threshold) {
+                below_threshold.extend(files);
+            } else {
+                rows.push((category.as_str().cyan(), files));
+            }
+        }
+        if !below_threshold.is_empty() {
+            rows.push((tr!("summary.below-threshold").dimmed(), below_threshold));
+        }
+        for (label, files) in rows {
+            let stale: Vec<&CacheFile> = files.iter().copied().filter(|f| f.is_stale(&staleness)).collect();
+            println!("  {}: {}, {}", 
+                label, 
+                tr!("summary.files", files.len().to_string().cyan(), format_size_with_color(size_totals(files).await.size)),
+                tr!("summary.stale", stale.len().to_string().cyan(), format_size_with_color(size_totals(stale).await.size))
+            );
+        }
+        if min_age.is_none() {
+            println!("  {}", tr!("summary.stale-hint", duration::format_duration(staleness.after)).dimmed());
+        }
+        print_container_storage();
+        print_trash().await;
+        print_offenders(&cache_files, path, args.top_dirs);
+        
+        print_browser_profiles(&cache_files, 10).await;
+        print_apps(&cache_files, 10).await;
+        print_python_caches(&cache_files).await;
+        print_jvm_caches(&cache_files).await;
+        print_compiler_caches(&cache_files).await;
+        print_crash_dumps(&cache_files, 10).await;
+        let junk = junk::find(&cache_files).await;
+        print_junk(&junk).await;
+        print_volume_summary(&cache_files, &mounts).await;
+        if args.group_by == Some(GroupBy::Root) {
+            print_roots(&cache_files, path, &args.expand, 10).await;
+        }
+        if args.tree {
+            print_tree(&cache_files, path);
+        }
+        if args.summary_only || interrupt::interrupted() {
+            errors::print_summary(&errors);
+            return;
+        }
+        
+        hold_back_package_caches(&mut cache_files).await;
+        hold_back_trash(&mut cache_files).await;
+        if !args.include_maven {
+            hold_back_maven(&mut cache_files).await;
+        }
+        
+        print_age_histogram(&cache_files, staleness.use_atime).await;
+        
+        if args.dedupe {
+            print_duplicates(&find_duplicates(&cache_files).await, 10);
+        }
+        
+        if totals.pinned_count > 0 {
+            println!("\n{} {}",
+                "[Note]".blue(),
+                tr!("delete.pinned", totals.pinned_count.to_string().cyan(), format_size_with_color(totals.pinned_size))
             );
         }
         
-        // Prompt to show full file list
-        println!("\n{}", "Do you want to see the full list of cache files? (y/N)".yellow());
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).expect("Failed to read input");
+        let protected = ProtectedPaths::new(&config.protected);
+        let protected_count = cache_files.iter().filter(|f| protected.is_protected(&f.path)).count();
+        if protected_count > 0 {
+            println!("\n{} {}", "[Protected]".blue(), tr!("protected.count", protected_count.to_string().cyan()));
+        }
         
-        if input.trim().eq_ignore_ascii_case("y") {
-            println!("\n{}", "Cache files: ".blue().bold());
+        // Prompt to show full file list
+        if !args.yes && !args.browse && prompt_yes_no(tr!("prompt.list").yellow()) {
+            println!("\n{}", tr!("list.title").blue().bold());
             for file in &cache_files {
-                println!("  {} ({}) [{}]\n    {}", 
-                    file.path.file_name().unwrap().to_str().unwrap().yellow(),
+                println!("  {} ({}) [{}, {}]\n    {}", 
+                    file.path.file_name().map(paths::escape).unwrap_or_default().color(theme::theme().name),
                     format_size_with_color(file.size),
-                    file.category.as_str().magenta(),
-                    file.path.display()
+                    category_with_color(file.category),
+                    risk_with_color(file.risk),
+                    paths::display(&file.path)
                 );
+                let metadata = platform::apple_metadata(&file.path);
+                if !metadata.is_empty() {
+                    println!("    {}", format!("macOS metadata: {}", metadata.join(", ")).dimmed());
+                }
+                if args.explain {
+                    let flagged = classify::cache_file_reason(&file.path).unwrap_or_default();
+                    let category = classify::explain_category(&file.path).map(|(_, reason)| reason).unwrap_or_default();
+                    println!("    {}", format!("flagged by {}, {} by {}", flagged, file.category.as_str(), category).dimmed());
+                }
             }
         }
         
-        // Prompt to delete cache files
-        println!("\n{}", "Do you want to delete these cache files? (y/N)".red().bold());
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).expect("Failed to read input");
-        
-        if input.trim().eq_ignore_ascii_case("y") {
-            let mut deleted_count = 0;
-            let mut deleted_size = 0;
-            
-            println!("\n{} Deleting cache files...", "🗑️".red());
+        if is_dangerous_root(path) {
+            println!("\n{} {}", "[Warning!]".red().bold(), tr!("root.warning", path.display()));
+            print_sample(&cache_files, &protected, 10);
             
-            for file in cache_files {
-                match fs::remove_file(&file.path).await {
-                    Ok(_) => {
-                        println!("  {} Deleted {}", "[OK!]".green(), file.path.display());
-                        deleted_count += 1;
-                        deleted_size += file.size;
-                    }
-                    Err(e) => {
-                        println!("  {} Failed to delete {}: {}", 
-                            "[Failed!]".red(), 
-                            file.path.display(), 
-                            e.to_string().red()
-                        );
-                    }
-                }
+            if !args.i_know_what_im_doing {
+                println!("\n{} {}", "[Refused!]".red(), tr!("root.refused"));
+                errors::print_summary(&errors);
+                return;
             }
-            
-            println!("\n{} Deleted {} files totaling {}", 
-                "[OK!]".green(), 
-                deleted_count.to_string().cyan(), 
-                format_size_with_color(deleted_size)
-            );
+        }
+        
+        warn_running_browsers(&mut cache_files, args.skip_in_use);
+        
+        // Prompt to delete cache files
+        let size = cache_files.iter().map(|f| f.size).sum();
+        let to_delete = if args.browse {
+            let marked = browse::run(&cache_files, path).unwrap_or_else(|e| {
+                eprintln!("{} Failed to browse in the terminal: {}", "[Error!]".red(), e);
+                std::process::exit(1);
+            });
+            // The queue was confirmed in the browser; only risky or large deletions ask again
+            let marked_size = marked.iter().map(|file| file.size).sum();
+            let risky = risky_files(&marked);
+            let stricter = risky.0 > 0 || config.confirm_size.is_some_and(|limit| marked_size > limit);
+            (!marked.is_empty() && (!stricter || confirm_deletion(tr!("prompt.delete-marked").red().bold(), marked.len(), marked_size, risky, config))).then_some(marked)
+        } else if args.yes || confirm_deletion(tr!("prompt.delete").red().bold(), cache_files.len(), size, risky_files(&cache_files), config) {
+            Some(cache_files)
         } else {
-            println!("\n{} Deletion canceled", "[OK!]".green());
+            // Junk is safe to delete even when the rest isn't, unless it was held back since
+            let remaining: HashSet<&Path> = cache_files.iter().map(|file| file.path.as_path()).collect();
+            let junk: Vec<CacheFile> = junk.into_iter().map(|(file, _)| file).filter(|file| remaining.contains(file.path.as_path())).collect();
+            let junk_size = junk.iter().map(|file| file.size).sum();
+            (!junk.is_empty() && prompt_yes_no(tr!("prompt.delete-junk", junk.len(), size::format_size(junk_size)).yellow())).then_some(junk)
+        };
+        if let Some(cache_files) = to_delete {
+            let options = CleanOptions {
+                root: path.to_path_buf(),
+                limits: DeleteLimits {
+                    max_size: args.max_delete_size.or(config.max_delete_size),
+                    max_count: args.max_delete_count.or(config.max_delete_count),
+                    deadline: None,
+                },
+                archive: args.archive.clone().map(|dir| match dir {
+                    Some(dir) => ArchiveTarget::Directory(dir),
+                    None => ArchiveTarget::InPlace,
+                }),
+                prune_empty_dirs: args.prune_empty_dirs,
+                retry: config.retry,
+                hooks: config.clean_hooks.clone(),
+                quiet: false,
+            };
+            let found = cache_files.len();
+            let found_size = cache_files.iter().map(|f| f.size).sum();
+            let summary = match delete_cache_files(cache_files, &protected, &options).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    eprintln!("{} {}", "[Error!]".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            if let Some(file) = &args.result_file {
+                out.write_file(file, &format!("{}\n", result_json(path, &summary)), sign::Style::Json);
+            }
+            errors.extend(summary.errors.iter().cloned());
+            errors::print_summary(&errors);
+            send_report(config, RunReport::from_summary(found, found_size, summary), args.yes).await;
+            return;
+        }
+        println!("\n{} {}", "[OK!]".green(), tr!("delete.canceled"));
+    }
+    errors::print_summary(&errors);
+}
+
+/// The key `--sign` asks for, from the file `[sign] key_file` names. A run
+/// that can't sign as asked exits rather than write an unsigned report.
+fn signing_key(args: &DetectArgs, config: &Config) -> Option<Vec<u8>> {
+    if !args.sign {
+        return None;
+    }
+    if !args.exports_report() {
+        eprintln!("{} --sign signs reports, so it needs --format, --metrics-file, --result-file or --ci", "[Error!]".red());
+        std::process::exit(2);
+    }
+    let Some(file) = &config.signing_key_file else {
+        eprintln!("{} --sign needs `key_file` under [sign] in the config file", "[Error!]".red());
+        std::process::exit(2);
+    };
+    match sign::load_key(file) {
+        Ok(key) => Some(key),
+        Err(e) => {
+            eprintln!("{} Failed to read the signing key {}: {}", "[Error!]".red(), file.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// A report on stdout, signed as it's written when `--sign` is given, and
+/// the report files written next to it.
+struct ReportOutput {
+    signer: Option<Signer>,
+    key: Option<Vec<u8>>,
+}
+
+impl ReportOutput {
+    fn new(key: Option<Vec<u8>>) -> Self {
+        ReportOutput { signer: key.as_deref().map(Signer::new), key }
+    }
+    
+    /// Replaces `file` with `contents`, signed on its own if reports are.
+    /// A report that can't be written fails the run.
+    fn write_file(&self, file: &Path, contents: &str, style: sign::Style) {
+        let mut contents = contents.to_string();
+        if let Some(key) = &self.key {
+            let mut signer = Signer::new(key);
+            signer.update(contents.as_bytes());
+            contents.push_str(&sign::signature_line(style, &signer));
+        }
+        if let Err(e) = write_atomically(file, &contents) {
+            eprintln!("{} Failed to write {}: {}", "[Error!]".red(), file.display(), e);
+            std::process::exit(1);
+        }
+    }
+    
+    fn print(&mut self, text: &str) {
+        print!("{}", text);
+        if let Some(signer) = &mut self.signer {
+            signer.update(text.as_bytes());
+        }
+    }
+
+    /// Ends the report with its signature, if it's signed.
+    fn finish(self, style: sign::Style) {
+        if let Some(signer) = &self.signer {
+            print!("{}", sign::signature_line(style, signer));
+        }
+    }
+}
+
+/// Checks a signed report against the configured key, exiting with 1 if it
+/// doesn't match.
+fn verify_report(args: &VerifyArgs, config: &Config) {
+    let Some(file) = &config.signing_key_file else {
+        eprintln!("{} Verifying needs `key_file` under [sign] in the config file", "[Error!]".red());
+        std::process::exit(2);
+    };
+    let key = sign::load_key(file).unwrap_or_else(|e| {
+        eprintln!("{} Failed to read the signing key {}: {}", "[Error!]".red(), file.display(), e);
+        std::process::exit(2);
+    });
+    let report = std::fs::read(&args.report).unwrap_or_else(|e| {
+        eprintln!("{} Failed to read {}: {}", "[Error!]".red(), args.report.display(), e);
+        std::process::exit(2);
+    });
+    match sign::verify(&report, &key) {
+        Ok(()) => println!("{} {} is signed and unchanged", "[OK!]".green(), args.report.display()),
+        Err(e) => {
+            eprintln!("{} {}: {}", "[Tampered!]".red().bold(), args.report.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The rest of a `--ci` run: deletes what's left of `cache_files` after
+/// holding back browser data and whatever needs a person to decide, with no
+/// prompts and only until `deadline`, then prints one JSON object with the
+/// scan's `summary` and what the cleanup did.
+async fn clean_for_ci(
+    mut cache_files: Vec<CacheFile>,
+    path: &Path,
+    args: &DetectArgs,
+    config: &Config,
+    summary: String,
+    deadline: Option<Instant>,
+    mut out: ReportOutput,
+) {
+    let found = cache_files.len();
+    let found_size = cache_files.iter().map(|f| f.size).sum();
+    
+    let privileged = platform::is_privileged();
+    cache_files.retain(|f| f.category != CacheCategory::Browser && browser::locate(&f.path).is_none());
+    cache_files.retain(|f| f.category != CacheCategory::Package || package::package_cache(&f.path).is_none_or(|cache| privileged || !cache.needs_root));
+    cache_files.retain(|f| f.category != CacheCategory::Trash && (args.include_maven || !jvm::is_maven_repository(&f.path)));
+    let held_back = found - cache_files.len();
+    
+    if !cache_files.is_empty() && is_dangerous_root(path) && !args.i_know_what_im_doing {
+        eprintln!("{} {}", "[Refused!]".red(), tr!("root.refused"));
+        std::process::exit(1);
+    }
+    
+    let options = CleanOptions {
+        root: path.to_path_buf(),
+        limits: DeleteLimits {
+            max_size: args.max_delete_size.or(config.max_delete_size),
+            max_count: args.max_delete_count.or(config.max_delete_count),
+            deadline,
+        },
+        archive: None,
+        prune_empty_dirs: args.prune_empty_dirs,
+        retry: config.retry,
+        hooks: config.clean_hooks.clone(),
+        quiet: true,
+    };
+    let result = match delete_cache_files(cache_files, &ProtectedPaths::new(&config.protected), &options).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("{} {}", "[Error!]".red(), e);
+            std::process::exit(1);
         }
+    };
+    if let Some(file) = &args.result_file {
+        out.write_file(file, &format!("{}\n", result_json(path, &result)), sign::Style::Json);
     }
+    
+    let errors: Vec<String> = result.errors.iter().map(PathError::to_json).collect();
+    out.print(&format!(
+        "{{\"summary\":{},\"deleted\":{},\"freed\":{},\"held_back\":{},\"out_of_time\":{},\"errors\":[{}]}}\n",
+        summary, result.deleted_count, result.deleted_size, held_back, result.out_of_time, errors.join(",")
+    ));
+    out.finish(sign::Style::Json);
+    send_report(config, RunReport::from_summary(found, found_size, result), true).await;
 }
 
+#[cfg(unix)]
+async fn serve_api(args: &ServeArgs, config: Config) {
+    let socket = args.socket.clone().unwrap_or_else(hica::serve::default_socket);
+    eprintln!("{} Listening on {}", "[Serve:]".yellow(), socket.display());
+    if let Err(e) = hica::serve::serve(&socket, config).await {
+        eprintln!("{} {}", "[Error!]".red(), e);
+        std::process::exit(1);
+    }
+}
 
+#[cfg(not(unix))]
+async fn serve_api(_args: &ServeArgs, _config: Config) {
+    eprintln!("{} `hica serve` needs Unix domain sockets, which this platform lacks", "[Error!]".red());
+    std::process::exit(1);
+}
 
-#[tokio::main]
-async fn main() {
+fn main() {
     let cli = Cli::parse();
+    // Linux keeps priorities per thread, and threads take theirs from the one
+    // that starts them, so this has to come before the runtime's workers
+    if cli.nice
+        && let Err(e) = platform::lower_priority()
+    {
+        eprintln!("{} Could not lower hica's priority: {}", "[Warning!]".red(), e);
+    }
+    
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().expect("failed to start the async runtime");
+    runtime.block_on(run(cli));
+}
+
+async fn run(cli: Cli) {
+    i18n::set_lang(cli.lang.unwrap_or_else(i18n::Lang::from_env));
+    size::set_units(match (cli.si, cli.bytes) {
+        (true, _) => size::Units::Si,
+        (_, true) => size::Units::Bytes,
+        _ => size::Units::Binary,
+    });
+    progress::set_format(progress::effective_format(cli.progress, cli.no_progress));
+    
+    // Neither needs, nor should fail on, the current config
+    match cli.command {
+        Commands::Config(ConfigCommand::Check) => return check_config(cli.config.as_deref()),
+        Commands::Config(ConfigCommand::Init { force }) => return init_config(cli.config.as_deref(), force),
+        Commands::Doctor(args) => return run_doctor(&args, cli.config.as_deref()),
+        _ => {}
+    }
+    
+    let mut config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{} Invalid config: {}", "[Error!]".red(), e);
+            std::process::exit(1);
+        }
+    };
+    theme::set_theme(config.theme.clone());
+    config.desktop_notify |= cli.notify;
+    
+    // Plugins live next to the config file in use, and only commands that
+    // classify files start them
+    let classifies = match &cli.command {
+        Commands::Detect(args) => args.all_users || args.path.as_deref().and_then(Remote::parse).is_none(),
+        Commands::Classify(_) | Commands::Serve(_) | Commands::Export(_) | Commands::Agent(_) => true,
+        _ => false,
+    };
+    if classifies && let Some(dir) = cli.config.clone().or_else(config::default_path).and_then(|file| file.parent().map(|dir| dir.join("plugins"))) {
+        let (loaded, failures) = plugins::load(&dir);
+        for failure in failures {
+            eprintln!("{} Skipping plugin {}", "[Warning!]".red(), failure);
+        }
+        plugins::install(loaded);
+    }
+    if let Some(script) = &config.policy_script {
+        match policy::Policy::start(script) {
+            Ok(policy) => policy::install(policy),
+            Err(e) => {
+                eprintln!("{} Could not start the policy script {}: {}", "[Error!]".red(), script.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if !cli.nice {
+        // The cap only applies to runs that asked to stay in the background
+        config.nice_ops_per_second = None;
+    }
+    
+    if let Err(e) = pause::catch_signals() {
+        eprintln!("{} Could not set up pausing with SIGUSR1 and SIGUSR2: {}", "[Warning!]".red(), e);
+    }
+    // `serve` shuts itself down on Ctrl-C
+    if !matches!(cli.command, Commands::Serve(_)) {
+        interrupt::catch();
+    }
     
     match cli.command {
-        Commands::Detect { path } => {
-            let scan_path = path.unwrap_or_else(|| PathBuf::from("."));
-            detect_cache_files(&scan_path).await;
+        Commands::Detect(args) if args.all_users => detect_all_users(&args, &config).await,
+        Commands::Detect(args) if let Some(remote) = args.path.as_deref().and_then(Remote::parse) => {
+            detect_remote(&remote, &args, &config).await;
+        }
+        Commands::Detect(mut args) => {
+            args.apply_ci_preset();
+            let scan_path = args.path.clone().unwrap_or_else(|| PathBuf::from("."));
+            detect_cache_files(&scan_path, &args, &config).await;
         }
+        Commands::Clean(args) => clean_tool(&args, &config).await,
+        Commands::System(args) => system_locations(&args, &config).await,
+        Commands::Apps(args) => app_caches(&args, &config).await,
+        Commands::Du(args) => disk_usage(&args).await,
+        Commands::Schedule(command) => manage_schedule(&command, cli.config.as_deref()),
+        Commands::Classify(args) => explain_paths(&args, &config),
+        Commands::Serve(args) => serve_api(&args, config).await,
+        Commands::Verify(args) => verify_report(&args, &config),
+        Commands::Export(args) => export_scan(&args).await,
+        Commands::Import(args) => import_scan(&args, &config).await,
+        Commands::Compare(args) => compare_scans(&args),
+        Commands::Agent(args) => run_agent(&args, &config).await,
+        Commands::Bench(args) => run_bench(&args).await,
+        Commands::Config(_) | Commands::Doctor(_) => unreachable!("handled before loading the config"),
+    }
+    
+    if interrupt::interrupted() {
+        std::process::exit(130);
     }
 }
@@ -0,0 +1,305 @@
+//! `--walker mft`: lists a whole NTFS volume from its master file table, the
+//! way Everything does, instead of reading one directory after another.
+//!
+//! `FSCTL_ENUM_USN_DATA` hands out the name and parent of every file on the
+//! volume in large batches, which takes seconds where walking the directories
+//! of a full drive takes minutes. It needs an elevated prompt to open the
+//! volume. The records carry no sizes, so the scan only reads the metadata of
+//! the files whose paths make them cache.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// A file or directory of the volume, at the path the scan sees it under.
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// Lists everything below `root` from the master file table of its volume.
+/// Fails, with why, off Windows, off NTFS or without the rights to open the
+/// volume.
+pub fn list(root: &Path) -> Result<Vec<Entry>, String> {
+    #[cfg(windows)]
+    {
+        let absolute = std::path::absolute(root).map_err(|e| e.to_string())?;
+        let letter = match absolute.components().next() {
+            Some(std::path::Component::Prefix(prefix)) => match prefix.kind() {
+                std::path::Prefix::Disk(letter) | std::path::Prefix::VerbatimDisk(letter) => letter as char,
+                _ => return Err("it's only read for paths on a drive letter".to_string()),
+            },
+            _ => return Err("the path has no drive letter".to_string()),
+        };
+        let records = windows::read_records(letter)?;
+        Ok(entries_below(&records, &PathBuf::from(format!("{}:\\", letter)), &absolute, root))
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = root;
+        Err("the master file table is only read on Windows".to_string())
+    }
+}
+
+/// One file or directory as the volume's change journal describes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub id: u64,
+    pub parent: u64,
+    /// The name as UTF-16, as NTFS stores it
+    pub name: Vec<u16>,
+    pub is_dir: bool,
+}
+
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+/// The part of a file reference that indexes the MFT, without the sequence number.
+const INDEX_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+/// The MFT index of a volume's root directory.
+const ROOT_INDEX: u64 = 5;
+/// Deeper chains of parents than this can only be a corrupt table.
+const MAX_DEPTH: usize = 1024;
+
+/// Reads the `USN_RECORD_V2`s in a buffer `FSCTL_ENUM_USN_DATA` returned,
+/// after its leading file reference. Records of other versions are skipped.
+pub fn parse_records(bytes: &[u8], records: &mut Vec<Record>) {
+    let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+
+    let mut offset = 0;
+    while offset + 60 <= bytes.len() {
+        let length = u32_at(offset) as usize;
+        if length < 60 || offset + length > bytes.len() {
+            break;
+        }
+        let name_length = u16_at(offset + 56) as usize;
+        let name_offset = u16_at(offset + 58) as usize;
+        if u16_at(offset + 4) == 2 && name_offset + name_length <= length {
+            let name = bytes[offset + name_offset..offset + name_offset + name_length]
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            records.push(Record {
+                id: u64_at(offset + 8),
+                parent: u64_at(offset + 16),
+                name,
+                is_dir: u32_at(offset + 52) & FILE_ATTRIBUTE_DIRECTORY != 0,
+            });
+        }
+        offset += length;
+    }
+}
+
+fn name_of(record: &Record) -> OsString {
+    #[cfg(windows)]
+    {
+        std::os::windows::ffi::OsStringExt::from_wide(&record.name)
+    }
+    #[cfg(not(windows))]
+    {
+        OsString::from(String::from_utf16_lossy(&record.name))
+    }
+}
+
+/// The path of the directory `id` below `volume_root`, remembering it and
+/// every directory on the way in `known`. `None` for directories that hang
+/// off something the table didn't list, like the volume's own metadata.
+fn directory_path(id: u64, by_id: &HashMap<u64, &Record>, known: &mut HashMap<u64, Option<PathBuf>>, volume_root: &Path) -> Option<PathBuf> {
+    let mut chain = Vec::new();
+    let mut current = id;
+    let base = loop {
+        if let Some(path) = known.get(&current) {
+            break path.clone();
+        }
+        if current & INDEX_MASK == ROOT_INDEX {
+            break Some(volume_root.to_path_buf());
+        }
+        match by_id.get(&current) {
+            Some(record) if chain.len() < MAX_DEPTH && record.parent != current => {
+                chain.push(current);
+                current = record.parent;
+            }
+            _ => break None,
+        }
+    };
+
+    let mut path = base;
+    for id in chain.into_iter().rev() {
+        path = path.map(|path| path.join(name_of(by_id[&id])));
+        known.insert(id, path.clone());
+    }
+    path
+}
+
+/// The records below `absolute_root`, with their paths rebuilt from their
+/// parents and put under `scan_path`, the way the scan was asked for it.
+/// Matching ignores case, like NTFS does.
+pub fn entries_below(records: &[Record], volume_root: &Path, absolute_root: &Path, scan_path: &Path) -> Vec<Entry> {
+    let lowercase = |path: &Path| -> Vec<String> {
+        path.components().map(|component| component.as_os_str().to_string_lossy().to_lowercase()).collect()
+    };
+    let root = lowercase(absolute_root);
+    let by_id: HashMap<u64, &Record> = records.iter().map(|record| (record.id, record)).collect();
+    let mut known = HashMap::new();
+
+    let mut entries = Vec::new();
+    for record in records {
+        let Some(parent) = directory_path(record.parent, &by_id, &mut known, volume_root) else {
+            continue;
+        };
+        let path = parent.join(name_of(record));
+        let components = lowercase(&path);
+        if components.len() <= root.len() || components[..root.len()] != root[..] {
+            continue;
+        }
+        let below: PathBuf = path.components().skip(root.len()).collect();
+        entries.push(Entry { path: scan_path.join(below), is_dir: record.is_dir });
+    }
+    entries
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::io;
+
+    use super::{parse_records, Record};
+
+    const GENERIC_READ: u32 = 0x8000_0000;
+    const FILE_SHARE_READ: u32 = 0x1;
+    const FILE_SHARE_WRITE: u32 = 0x2;
+    const OPEN_EXISTING: u32 = 3;
+    const INVALID_HANDLE_VALUE: isize = -1;
+    const FSCTL_ENUM_USN_DATA: u32 = 0x0009_00B3;
+    const ERROR_HANDLE_EOF: i32 = 38;
+
+    #[repr(C)]
+    struct MftEnumDataV0 {
+        start_file_reference_number: u64,
+        low_usn: i64,
+        high_usn: i64,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn CreateFileW(name: *const u16, access: u32, share: u32, security: *mut c_void, disposition: u32, flags: u32, template: isize) -> isize;
+        fn DeviceIoControl(device: isize, code: u32, input: *const c_void, input_size: u32, output: *mut c_void, output_size: u32, returned: *mut u32, overlapped: *mut c_void) -> i32;
+        fn GetVolumeInformationW(root: *const u16, name: *mut u16, name_size: u32, serial: *mut u32, max_component: *mut u32, flags: *mut u32, fs_name: *mut u16, fs_name_size: u32) -> i32;
+        fn CloseHandle(handle: isize) -> i32;
+    }
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Closes the volume however reading it ends.
+    struct Volume(isize);
+
+    impl Drop for Volume {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from CreateFileW and is closed only here
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// Every record in the master file table of drive `letter`.
+    pub fn read_records(letter: char) -> Result<Vec<Record>, String> {
+        let mut fs_name = [0u16; 32];
+        // SAFETY: the root is NUL-terminated, and the buffers are as large as passed
+        let found = unsafe {
+            GetVolumeInformationW(wide(&format!("{}:\\", letter)).as_ptr(), std::ptr::null_mut(), 0, std::ptr::null_mut(), std::ptr::null_mut(), std::ptr::null_mut(), fs_name.as_mut_ptr(), fs_name.len() as u32)
+        };
+        let fs_name = String::from_utf16_lossy(&fs_name[..fs_name.iter().position(|&c| c == 0).unwrap_or(fs_name.len())]);
+        if found == 0 || fs_name != "NTFS" {
+            return Err(format!("{}: isn't an NTFS volume", letter));
+        }
+
+        // SAFETY: the name is NUL-terminated; the other arguments are plain values
+        let handle = unsafe {
+            CreateFileW(wide(&format!("\\\\.\\{}:", letter)).as_ptr(), GENERIC_READ, FILE_SHARE_READ | FILE_SHARE_WRITE, std::ptr::null_mut(), OPEN_EXISTING, 0, 0)
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(format!("couldn't open {}: ({}); it needs an elevated prompt", letter, io::Error::last_os_error()));
+        }
+        let volume = Volume(handle);
+
+        let mut input = MftEnumDataV0 { start_file_reference_number: 0, low_usn: 0, high_usn: i64::MAX };
+        // u64s keep the buffer aligned for the records in it
+        let mut buffer = vec![0u64; 64 * 1024];
+        let mut records = Vec::new();
+        loop {
+            let mut returned = 0u32;
+            // SAFETY: input and output point to live buffers of the sizes passed
+            let ok = unsafe {
+                DeviceIoControl(
+                    volume.0,
+                    FSCTL_ENUM_USN_DATA,
+                    &input as *const MftEnumDataV0 as *const c_void,
+                    std::mem::size_of::<MftEnumDataV0>() as u32,
+                    buffer.as_mut_ptr() as *mut c_void,
+                    (buffer.len() * 8) as u32,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            if ok == 0 {
+                let error = io::Error::last_os_error();
+                if error.raw_os_error() == Some(ERROR_HANDLE_EOF) {
+                    return Ok(records);
+                }
+                return Err(format!("couldn't read the master file table of {}: ({})", letter, error));
+            }
+            // SAFETY: DeviceIoControl wrote `returned` bytes to the start of the buffer
+            let bytes = unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, returned as usize) };
+            if bytes.len() < 8 {
+                return Ok(records);
+            }
+            input.start_file_reference_number = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+            parse_records(&bytes[8..], &mut records);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_bytes(id: u64, parent: u64, name: &str, is_dir: bool) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let length = (60 + name.len()).next_multiple_of(8);
+        let mut bytes = vec![0u8; length];
+        bytes[0..4].copy_from_slice(&(length as u32).to_le_bytes());
+        bytes[4..6].copy_from_slice(&2u16.to_le_bytes());
+        bytes[8..16].copy_from_slice(&id.to_le_bytes());
+        bytes[16..24].copy_from_slice(&parent.to_le_bytes());
+        bytes[52..56].copy_from_slice(&(if is_dir { FILE_ATTRIBUTE_DIRECTORY } else { 0x20 }).to_le_bytes());
+        bytes[56..58].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes[58..60].copy_from_slice(&60u16.to_le_bytes());
+        bytes[60..60 + name.len()].copy_from_slice(&name);
+        bytes
+    }
+
+    #[test]
+    fn rebuilds_paths_below_the_scan_root() {
+        let root = 0x0005_0000_0000_0005;
+        let buffer: Vec<u8> = [
+            record_bytes(0x10, root, "Users", true),
+            record_bytes(0x11, 0x10, "Alice", true),
+            record_bytes(0x12, 0x11, "debug.log", false),
+            record_bytes(0x13, root, "pagefile.sys", false),
+            record_bytes(0x14, 0x99, "orphan.tmp", false),
+        ]
+        .concat();
+        let mut records = Vec::new();
+        parse_records(&buffer, &mut records);
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[2], Record { id: 0x12, parent: 0x11, name: "debug.log".encode_utf16().collect(), is_dir: false });
+
+        let entries = entries_below(&records, Path::new("/vol"), Path::new("/vol/users"), Path::new("~/"));
+        assert_eq!(entries, [
+            Entry { path: PathBuf::from("~/Alice"), is_dir: true },
+            Entry { path: PathBuf::from("~/Alice/debug.log"), is_dir: false },
+        ]);
+    }
+}
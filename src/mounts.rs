@@ -0,0 +1,174 @@
+//! The mounted filesystems, so scans can stay off the ones that hold nothing
+//! to clean, and off network shares unless asked.
+
+use std::path::{Path, PathBuf};
+
+/// Kernel and virtual filesystems whose "files" are interfaces, not data.
+const SPECIAL_FILESYSTEMS: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devfs", "devpts", "cgroup", "cgroup2", "securityfs",
+    "debugfs", "tracefs", "pstore", "bpf", "configfs", "fusectl", "mqueue", "hugetlbfs",
+    "autofs", "binfmt_misc", "efivarfs", "selinuxfs", "nsfs", "rpc_pipefs",
+];
+
+/// Filesystems on another machine: slow to walk, and their "caches" may be
+/// someone else's data.
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "afpfs", "webdav", "davfs", "9p", "ceph", "glusterfs",
+    "fuse.sshfs", "fuse.glusterfs", "fuse.rclone", "fuse.s3fs", "afs",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mount {
+    pub point: PathBuf,
+    pub fs_type: String,
+    pub read_only: bool,
+}
+
+impl Mount {
+    /// Why a scan shouldn't descend into this mount, if it shouldn't.
+    pub fn skip_reason(&self) -> Option<String> {
+        if SPECIAL_FILESYSTEMS.contains(&self.fs_type.as_str()) {
+            Some(format!("special filesystem ({})", self.fs_type))
+        } else if self.read_only && self.point != Path::new("/") {
+            // A read-only root is macOS's sealed system volume, which the
+            // writable data volume is firmlinked into, so it can't be skipped
+            Some(format!("read-only {} filesystem", self.fs_type))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the filesystem is a share on another machine (NFS, SMB, SSHFS and the like).
+    pub fn is_network(&self) -> bool {
+        NETWORK_FILESYSTEMS.contains(&self.fs_type.as_str())
+    }
+}
+
+/// The mounted filesystems, in mount order. Empty where they can't be listed.
+pub fn mounts() -> Vec<Mount> {
+    if cfg!(target_os = "linux") {
+        std::fs::read_to_string("/proc/self/mounts")
+            .map(|table| parse_proc_mounts(&table))
+            .unwrap_or_default()
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("mount")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| parse_mount_output(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The mount `path` lives on: the most recent one with the longest mount point
+/// containing it.
+pub fn mount_of<'a>(mounts: &'a [Mount], path: &Path) -> Option<&'a Mount> {
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.point))
+        .max_by_key(|mount| mount.point.components().count())
+}
+
+/// The volume `path` lives on: its mount point, or its drive root where
+/// mounts can't be listed (such as `C:\` on Windows).
+pub fn volume_of(mounts: &[Mount], path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match mount_of(mounts, &absolute) {
+        Some(mount) => mount.point.clone(),
+        None => absolute.ancestors().last().unwrap_or(&absolute).to_path_buf(),
+    }
+}
+
+/// Undoes the octal escapes (`\040` for a space) in `/proc/self/mounts`.
+fn unescape_octal(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 4).filter(|digits| bytes[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match escape {
+            Some(digits) => {
+                result.push(digits.iter().fold(0u8, |value, d| value.wrapping_mul(8) + (d - b'0')));
+                i += 4;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Parses the Linux mount table: `device point type options dump pass`.
+pub fn parse_proc_mounts(table: &str) -> Vec<Mount> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let point = unescape_octal(fields.next()?);
+            let fs_type = fields.next()?.to_string();
+            let read_only = fields.next()?.split(',').any(|option| option == "ro");
+            Some(Mount { point: PathBuf::from(point), fs_type, read_only })
+        })
+        .collect()
+}
+
+/// Parses macOS `mount` output: `device on /point (type, option, ...)`.
+pub fn parse_mount_output(output: &str) -> Vec<Mount> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (point, details) = rest.rsplit_once(" (")?;
+            let mut options = details.trim_end_matches(')').split(", ");
+            let fs_type = options.next()?.to_string();
+            let read_only = options.any(|option| option == "read-only");
+            Some(Mount { point: PathBuf::from(point), fs_type, read_only })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_linux_mount_table() {
+        let table = "proc /proc proc rw,relatime 0 0\n\
+                     /dev/vda / ext4 rw,relatime 0 0\n\
+                     /dev/vdb /mnt/My\\040Disk ext4 ro,nosuid 0 0\n\
+                     nas:/export /mnt/nas nfs4 rw,relatime 0 0\n";
+        let mounts = parse_proc_mounts(table);
+
+        assert_eq!(mounts.len(), 4);
+        assert!(mounts[3].is_network() && !mounts[1].is_network());
+        assert_eq!(mounts[0].skip_reason().as_deref(), Some("special filesystem (proc)"));
+        assert_eq!(mounts[1].skip_reason(), None);
+        assert_eq!(mounts[2].point, PathBuf::from("/mnt/My Disk"));
+        assert!(mounts[2].read_only);
+
+        assert_eq!(mount_of(&mounts, Path::new("/mnt/My Disk/x")).unwrap().fs_type, "ext4");
+        assert_eq!(mount_of(&mounts, Path::new("/proc/1/maps")).unwrap().fs_type, "proc");
+        assert_eq!(mount_of(&mounts, Path::new("/home/alice")).unwrap().point, PathBuf::from("/"));
+    }
+
+    #[test]
+    fn parses_macos_mount_output() {
+        let output = "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n\
+                      devfs on /dev (devfs, local, nobrowse)\n\
+                      /dev/disk3s5 on /System/Volumes/Data (apfs, local, journaled, nobrowse)\n";
+        let mounts = parse_mount_output(output);
+
+        assert_eq!(mounts.len(), 3);
+        assert!(mounts[0].read_only);
+        assert_eq!(mounts[0].skip_reason(), None);
+        assert!(mounts[1].skip_reason().is_some());
+        assert_eq!(mounts[2].skip_reason(), None);
+    }
+}
@@ -0,0 +1,578 @@
+//! Reporting the outcome of runs, to a chat webhook, by email or on the desktop.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::classify::CacheCategory;
+use crate::clean::CleanSummary;
+use crate::platform::hostname;
+use crate::report::json_string;
+use crate::scan::CacheFile;
+use crate::size::format_size;
+use crate::tr;
+
+/// The payload shape a webhook expects.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum WebhookFormat {
+    /// hica's own JSON object with every field
+    #[default]
+    Json,
+    /// A Slack message with blocks, and the same as `{"text": ...}` for
+    /// compatible incoming webhooks that don't show blocks
+    Slack,
+    /// A Discord message with an embed
+    Discord,
+}
+
+impl WebhookFormat {
+    pub fn parse(name: &str) -> Option<WebhookFormat> {
+        match name {
+            "json" => Some(WebhookFormat::Json),
+            "slack" => Some(WebhookFormat::Slack),
+            "discord" => Some(WebhookFormat::Discord),
+            _ => None,
+        }
+    }
+}
+
+/// Where and how the summary of unattended runs is emailed, from `[email]`.
+#[derive(Debug, Default, Clone)]
+pub struct EmailSettings {
+    /// `smtps://host[:port]` for TLS, or `smtp://host[:port]`, which is
+    /// upgraded with STARTTLS where the server offers it
+    pub smtp_url: Option<String>,
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub user: Option<String>,
+    /// Holds the SMTP password, so it needn't be in the config itself
+    pub password_file: Option<PathBuf>,
+}
+
+/// The outcome of one cleaning run.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// The hica command line that ran, without the binary
+    pub command: String,
+    /// Files (or locations, for `clean`, `system` and `apps`) selected for deletion
+    pub found: u64,
+    pub found_size: u64,
+    pub deleted: u64,
+    pub freed: u64,
+    pub errors: Vec<String>,
+    /// Files deleted and their size per category, largest first, where the
+    /// run deleted files rather than whole locations
+    pub categories: Vec<(CacheCategory, u64, u64)>,
+}
+
+/// How many errors a chat message lists before summarising the rest.
+const MAX_LISTED_ERRORS: usize = 10;
+
+impl RunReport {
+    pub fn from_summary(found: usize, found_size: u64, summary: CleanSummary) -> RunReport {
+        RunReport {
+            command: String::new(),
+            found: found as u64,
+            found_size,
+            deleted: summary.deleted_count,
+            freed: summary.deleted_size,
+            errors: summary.errors.iter().map(ToString::to_string).collect(),
+            categories: by_category(&summary.deleted),
+        }
+    }
+
+    /// One line saying what the run did.
+    fn headline(&self, host: &str) -> String {
+        format!("hica on {}: `{}` deleted {} of {} found, freeing {}", host, self.command, self.deleted, self.found, format_size(self.freed))
+    }
+
+    /// The errors as a bulleted list, up to `MAX_LISTED_ERRORS` of them.
+    fn listed_errors(&self) -> String {
+        let mut text: Vec<String> = self.errors.iter().take(MAX_LISTED_ERRORS).map(|error| format!("• {}", error)).collect();
+        if self.errors.len() > MAX_LISTED_ERRORS {
+            text.push(format!("… and {} more", self.errors.len() - MAX_LISTED_ERRORS));
+        }
+        text.join("\n")
+    }
+
+    fn slack_blocks(&self, host: &str) -> String {
+        let mrkdwn = |text: &str| format!("{{\"type\":\"mrkdwn\",\"text\":{}}}", json_string(text));
+        let mut blocks = vec![
+            format!("{{\"type\":\"header\",\"text\":{{\"type\":\"plain_text\",\"text\":{}}}}}", json_string(&format!("hica on {} freed {}", host, format_size(self.freed)))),
+            format!("{{\"type\":\"section\",\"text\":{}}}", mrkdwn(&format!("Deleted *{}* of {} found ({})", self.deleted, self.found, format_size(self.found_size)))),
+        ];
+        // A section holds at most 10 fields
+        for chunk in self.categories.chunks(10) {
+            let fields: Vec<String> = chunk.iter().map(|&(category, files, bytes)| mrkdwn(&format!("*{}*\n{} ({} files)", category.as_str(), format_size(bytes), files))).collect();
+            blocks.push(format!("{{\"type\":\"section\",\"fields\":[{}]}}", fields.join(",")));
+        }
+        if !self.errors.is_empty() {
+            let heading = format!("*{} errors*\n", self.errors.len());
+            let text = format!("{}{}", heading, slack_text(&self.listed_errors(), 3000 - heading.chars().count()));
+            blocks.push(format!("{{\"type\":\"section\",\"text\":{}}}", mrkdwn(&text)));
+        }
+        // Both kinds of text hold at most 3000 characters
+        blocks.push(format!("{{\"type\":\"context\",\"elements\":[{}]}}", mrkdwn(&format!("`hica {}`", slack_text(&self.command, 3000 - 7)))));
+        blocks.join(",")
+    }
+
+    fn discord_embed(&self, host: &str) -> String {
+        let field = |name: &str, value: &str, inline: bool| format!("{{\"name\":{},\"value\":{},\"inline\":{}}}", json_string(name), json_string(&truncate(value, 1024)), inline);
+        // An embed holds at most 25 fields, one of which may be the errors
+        let mut fields: Vec<String> = self.categories.iter().take(24).map(|&(category, files, bytes)| field(category.as_str(), &format!("{} ({} files)", format_size(bytes), files), true)).collect();
+        if !self.errors.is_empty() {
+            fields.push(field(&format!("{} errors", self.errors.len()), &self.listed_errors(), false));
+        }
+        // Green, or red when something failed
+        let color = if self.errors.is_empty() { 0x2E_B8_6E } else { 0xE0_3E_3E };
+        format!(
+            "{{\"title\":{},\"description\":{},\"color\":{},\"fields\":[{}],\"footer\":{{\"text\":{}}}}}",
+            json_string(&format!("hica on {} freed {}", host, format_size(self.freed))),
+            json_string(&format!("Deleted **{}** of {} found ({})", self.deleted, self.found, format_size(self.found_size))),
+            color,
+            fields.join(","),
+            json_string(&truncate(&format!("hica {}", self.command), 2048))
+        )
+    }
+
+    /// The subject and plain text body of the summary email.
+    pub fn email_text(&self, host: &str) -> (String, String) {
+        let mut subject = tr!("email.subject", host, format_size(self.freed));
+        if !self.errors.is_empty() {
+            subject.push_str(&tr!("email.subject-errors", self.errors.len()));
+        }
+        let mut body = tr!(
+            "email.body",
+            self.command,
+            self.found,
+            format_size(self.found_size),
+            self.deleted,
+            format_size(self.freed),
+            self.errors.len()
+        );
+        if !self.errors.is_empty() {
+            body.push('\n');
+            for error in &self.errors {
+                body.push_str(&format!("- {}\n", error));
+            }
+        }
+        body.push_str(&format!("\n{}\n", tr!("email.attached")));
+        (subject, body)
+    }
+
+    pub fn payload(&self, format: WebhookFormat, host: &str) -> String {
+        match format {
+            WebhookFormat::Json => {
+                let errors: Vec<String> = self.errors.iter().map(|e| json_string(e)).collect();
+                format!(
+                    "{{\"host\":{},\"command\":{},\"found\":{},\"found_bytes\":{},\"deleted\":{},\"freed_bytes\":{},\"errors\":[{}]}}",
+                    json_string(host),
+                    json_string(&self.command),
+                    self.found,
+                    self.found_size,
+                    self.deleted,
+                    self.freed,
+                    errors.join(",")
+                )
+            }
+            WebhookFormat::Slack => {
+                let mut text = self.headline(host);
+                if !self.errors.is_empty() {
+                    text.push_str(&format!(", with {} errors:\n{}", self.errors.len(), self.listed_errors()));
+                }
+                format!("{{\"text\":{},\"blocks\":[{}]}}", json_string(&slack_text(&text, usize::MAX)), self.slack_blocks(host))
+            }
+            WebhookFormat::Discord => format!("{{\"username\":\"hica\",\"embeds\":[{}]}}", self.discord_embed(host)),
+        }
+    }
+}
+
+/// Files and bytes per category, largest first.
+fn by_category(files: &[CacheFile]) -> Vec<(CacheCategory, u64, u64)> {
+    let mut categories: Vec<(CacheCategory, u64, u64)> = CacheCategory::ALL
+        .iter()
+        .map(|&category| {
+            let files = files.iter().filter(|file| file.category == category);
+            (category, files.clone().count() as u64, files.map(|file| file.size).sum())
+        })
+        .filter(|&(_, files, _)| files > 0)
+        .collect();
+    categories.sort_by_key(|&(_, _, bytes)| std::cmp::Reverse(bytes));
+    categories
+}
+
+/// `text`, cut to at most `max` characters.
+fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max.saturating_sub(1)) {
+        Some((end, _)) if text[end..].chars().count() > 1 => format!("{}…", &text[..end]),
+        _ => text.to_string(),
+    }
+}
+
+/// `text` for Slack, which reads `&`, `<` and `>` as markup, escaped and cut
+/// to at most `max` characters without splitting an escape.
+fn slack_text(text: &str, max: usize) -> String {
+    let width = |c: char| match c {
+        '&' => 5,
+        '<' | '>' => 4,
+        _ => 1,
+    };
+    let fits = text.chars().map(width).sum::<usize>() <= max;
+    let mut escaped = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        // Leave room for the ellipsis
+        if !fits && used + width(c) >= max {
+            escaped.push('…');
+            break;
+        }
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+        used += width(c);
+    }
+    escaped
+}
+
+/// POSTs the report to `url` with the system `curl`.
+pub async fn post_webhook(url: &str, format: WebhookFormat, report: &RunReport) -> Result<(), String> {
+    post_json(url, &report.payload(format, &hostname()), None).await
+}
+
+/// Quotes `text` as a string of a curl config file.
+fn curl_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r"))
+}
+
+/// POSTs one line of JSON to `url` with the system `curl`, with `token` as a
+/// bearer token. The URL, headers and body go to curl on stdin, as a config
+/// file, so neither the token nor a webhook URL's secret shows up in the
+/// process list.
+pub async fn post_json(url: &str, payload: &str, token: Option<&str>) -> Result<(), String> {
+    let mut config = format!("url = {}\n", curl_string(url));
+    config.push_str(&format!("header = {}\n", curl_string("Content-Type: application/json")));
+    if let Some(token) = token {
+        config.push_str(&format!("header = {}\n", curl_string(&format!("Authorization: Bearer {}", token))));
+    }
+    config.push_str(&format!("data-binary = {}\n", curl_string(payload)));
+
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "30", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run curl: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes()).await.map_err(|e| e.to_string())?;
+    }
+
+    let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Encodes `data` as base64, in lines of 76 characters as MIME wants them.
+fn base64_lines(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for (i, chunk) in data.chunks(3).enumerate() {
+        if i > 0 && i % 19 == 0 {
+            out.push_str("\r\n");
+        }
+        let n = chunk.iter().enumerate().fold(0u32, |n, (j, &byte)| n | u32::from(byte) << (16 - 8 * j));
+        for j in 0..4 {
+            out.push(if j <= chunk.len() { ALPHABET[(n >> (18 - 6 * j) & 63) as usize] as char } else { '=' });
+        }
+    }
+    out
+}
+
+/// `subject` as a header value: as is when it's plain ASCII, or else as RFC
+/// 2047 encoded words, each short enough for a line of its own.
+fn email_subject(subject: &str) -> String {
+    if subject.is_ascii() {
+        return subject.to_string();
+    }
+    // 45 bytes make 60 base64 characters, 72 with the markers around them
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, c) in subject.char_indices() {
+        if i + c.len_utf8() - start > 45 {
+            words.push(&subject[start..i]);
+            start = i;
+        }
+    }
+    words.push(&subject[start..]);
+    words
+        .iter()
+        .map(|word| format!("=?UTF-8?B?{}?=", base64_lines(word.as_bytes())))
+        .collect::<Vec<_>>()
+        .join("\r\n ")
+}
+
+/// `time` as an RFC 5322 date, in UTC.
+fn email_date(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    let (days, rest) = (seconds / 86400, seconds % 86400);
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    format!(
+        "{}, {} {} {} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        rest / 3600,
+        rest / 60 % 60,
+        rest % 60
+    )
+}
+
+/// The summary email, with the JSON report attached.
+fn email_message(from: &str, to: &[String], report: &RunReport, host: &str, date: SystemTime) -> String {
+    let (subject, body) = report.email_text(host);
+    let boundary = format!("hica-{}", date.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_nanos()));
+    let headers = [
+        format!("From: {}", from),
+        format!("To: {}", to.join(", ")),
+        format!("Subject: {}", email_subject(&subject)),
+        format!("Date: {}", email_date(date)),
+        "MIME-Version: 1.0".to_string(),
+        format!("Content-Type: multipart/mixed; boundary=\"{}\"", boundary),
+    ];
+    format!(
+        "{}\r\n\r\n--{b}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}\r\n\
+         --{b}\r\nContent-Type: application/json; name=\"hica-report.json\"\r\n\
+         Content-Disposition: attachment; filename=\"hica-report.json\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n--{b}--\r\n",
+        headers.join("\r\n"),
+        body.replace('\n', "\r\n"),
+        base64_lines(report.payload(WebhookFormat::Json, host).as_bytes()),
+        b = boundary
+    )
+}
+
+/// Writes `contents` to a new file in the temp directory that only the
+/// current user can read, never reusing or following what's already there.
+/// On Windows, the temp directory is the user's own.
+fn private_temp_file(name: &str, contents: &str) -> std::io::Result<PathBuf> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let path = std::env::temp_dir().join(format!("hica-{}-{}-{}", name, std::process::id(), nanos));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    if let Err(e) = std::io::Write::write_all(&mut file, contents.as_bytes()) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Emails the report with the system `curl`. The message goes to curl on
+/// stdin, and the credentials through a curl config file only the current
+/// user can read.
+pub async fn send_email(settings: &EmailSettings, report: &RunReport) -> Result<(), String> {
+    let url = settings.smtp_url.as_deref().ok_or("`email.smtp_url` isn't set")?;
+    let from = settings.from.as_deref().ok_or("`email.from` isn't set")?;
+    if settings.to.is_empty() {
+        return Err("`email.to` lists no one".to_string());
+    }
+    let mut config = String::new();
+    if let Some(user) = &settings.user {
+        let password = match &settings.password_file {
+            Some(file) => std::fs::read_to_string(file).map_err(|e| format!("could not read {}: {}", file.display(), e))?.trim().to_string(),
+            None => String::new(),
+        };
+        config.push_str(&format!("user = {}\n", curl_string(&format!("{}:{}", user, password))));
+    }
+
+    let host = hostname();
+    let message = email_message(from, &settings.to, report, &host, SystemTime::now());
+    let config_file = private_temp_file("email", &config).map_err(|e| format!("could not write the curl config: {}", e))?;
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error", "--max-time", "60", "--mail-from", from]).arg("--config").arg(&config_file);
+    for to in &settings.to {
+        command.args(["--mail-rcpt", to]);
+    }
+    // Never send a password in the clear; without one, take TLS where offered
+    command.arg(if settings.user.is_some() { "--ssl-reqd" } else { "--ssl" });
+    // The path of an SMTP URL is the name hica greets the server with, and
+    // without one, curl would make one up
+    let url = match url.split_once("://").is_some_and(|(_, rest)| rest.contains('/')) {
+        true => url.to_string(),
+        false => format!("{}/{}", url, host),
+    };
+    command.args(["--upload-file", "-", "--url", &url]);
+    let sent = async {
+        let mut child = command.stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped()).spawn().map_err(|e| format!("failed to run curl: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(message.as_bytes()).await.map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+        match output.status.success() {
+            true => Ok(()),
+            false => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        }
+    }
+    .await;
+    let _ = std::fs::remove_file(&config_file);
+    sent
+}
+
+/// Shows a native desktop notification with the platform's own tool:
+/// `notify-send` on Linux, `osascript` on macOS and PowerShell on Windows.
+/// The tool is left running in the background, so this never blocks.
+pub fn desktop_notification(title: &str, body: &str) -> Result<(), String> {
+    let mut command = if cfg!(windows) {
+        let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; \
+             $n.ShowBalloonTip(10000, {}, {}, 'Info'); Start-Sleep -Seconds 10; $n.Dispose()",
+            quote(title),
+            quote(body)
+        );
+        let mut command = std::process::Command::new("powershell");
+        command.args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let script = format!("display notification {} with title {}", json_string(body), json_string(title));
+        let mut command = std::process::Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = std::process::Command::new("notify-send");
+        command.args(["--app-name=hica", title, body]);
+        command
+    };
+
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+        .map_err(|e| format!("failed to show a notification: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Json;
+
+    fn report() -> RunReport {
+        RunReport {
+            command: "detect /tmp --yes".to_string(),
+            found: 3,
+            found_size: 3072,
+            deleted: 2,
+            freed: 2048,
+            errors: vec!["failed to delete /tmp/a \"b\": Permission denied".to_string()],
+            categories: vec![(CacheCategory::Build, 1, 1536), (CacheCategory::Log, 1, 512)],
+        }
+    }
+
+    #[test]
+    fn renders_json_payload() {
+        assert_eq!(
+            report().payload(WebhookFormat::Json, "box"),
+            r#"{"host":"box","command":"detect /tmp --yes","found":3,"found_bytes":3072,"deleted":2,"freed_bytes":2048,"errors":["failed to delete /tmp/a \"b\": Permission denied"]}"#
+        );
+    }
+
+    #[test]
+    fn renders_email() {
+        assert_eq!(base64_lines(b"hica"), "aGljYQ==");
+        assert_eq!(base64_lines(&[0; 60]).lines().map(str::len).collect::<Vec<_>>(), [76, 4]);
+        assert_eq!(email_date(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)), "Tue, 14 Nov 2023 22:13:20 +0000");
+
+        let message = email_message("hica@box", &["ops@example.com".to_string()], &report(), "box", UNIX_EPOCH);
+        assert!(message.starts_with("From: hica@box\r\nTo: ops@example.com\r\nSubject: hica on box: freed 2.0 KB, 1 errors\r\n"));
+        assert!(message.contains("Freed:    2.0 KB\r\n"));
+        assert!(message.contains("filename=\"hica-report.json\""));
+        assert!(message.ends_with("--hica-0--\r\n"));
+    }
+
+    #[test]
+    fn encodes_non_ascii_subjects() {
+        assert_eq!(email_subject("hica on box: freed 2.0 KB"), "hica on box: freed 2.0 KB");
+        assert_eq!(email_subject("hica auf Büro: 2.0 KB freigegeben"), "=?UTF-8?B?aGljYSBhdWYgQsO8cm86IDIuMCBLQiBmcmVpZ2VnZWJlbg==?=");
+
+        let long = email_subject(&format!("hica auf {}: 2.0 KB freigegeben", "ü".repeat(40)));
+        assert_eq!(long.lines().count(), 3);
+        assert!(long.lines().all(|line| line.trim().len() <= 75 && line.trim().starts_with("=?UTF-8?B?")));
+    }
+
+    #[test]
+    fn writes_private_temp_files() {
+        let path = private_temp_file("test", "user = \"a:b\"\n").unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        #[cfg(unix)]
+        let mode = std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "user = \"a:b\"\n");
+        #[cfg(unix)]
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn renders_slack_payload() {
+        let payload = report().payload(WebhookFormat::Slack, "box");
+        assert!(payload.starts_with(r#"{"text":"hica on box: `detect /tmp --yes` deleted 2 of 3 found, freeing 2.0 KB, with 1 errors:\n• "#));
+
+        let payload = Json::parse(&payload).unwrap();
+        let blocks = payload.get("blocks").and_then(Json::as_array).unwrap();
+        let fields = blocks[2].get("fields").and_then(Json::as_array).unwrap();
+        assert_eq!(fields[0].get("text").and_then(Json::as_str), Some("*Build*\n1.5 KB (1 files)"));
+
+        let mut report = report();
+        report.command = format!("detect <{}> --yes", "x".repeat(4000));
+        report.errors = vec!["failed to delete /tmp/<@channel> & co: Permission denied".to_string()];
+        let payload = Json::parse(&report.payload(WebhookFormat::Slack, "box")).unwrap();
+        let blocks = payload.get("blocks").and_then(Json::as_array).unwrap();
+        let errors = blocks[3].get("text").and_then(|text| text.get("text")).and_then(Json::as_str).unwrap();
+        assert!(errors.ends_with("/tmp/&lt;@channel&gt; &amp; co: Permission denied"), "{}", errors);
+        let context = blocks[4].get("elements").and_then(Json::as_array).unwrap()[0].get("text").and_then(Json::as_str).unwrap();
+        assert!(context.starts_with("`hica detect &lt;xxx"), "{}", context);
+        assert_eq!(context.chars().count(), 3000);
+        assert!(!payload.get("text").and_then(Json::as_str).unwrap().contains('<'));
+
+        assert_eq!(slack_text("abcdef", 4), "abc…");
+        assert_eq!(slack_text("a&b", 6), "a…");
+        assert_eq!(slack_text("a&b", 7), "a&amp;b");
+    }
+
+    #[test]
+    fn renders_discord_payload() {
+        let payload = Json::parse(&report().payload(WebhookFormat::Discord, "box")).unwrap();
+        let embed = &payload.get("embeds").and_then(Json::as_array).unwrap()[0];
+        assert_eq!(embed.get("title").and_then(Json::as_str), Some("hica on box freed 2.0 KB"));
+        let fields = embed.get("fields").and_then(Json::as_array).unwrap();
+        assert_eq!(fields.iter().filter_map(|field| field.get("name").and_then(Json::as_str)).collect::<Vec<_>>(), ["Build", "Log", "1 errors"]);
+
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abcd", 4), "abcd");
+    }
+}
@@ -0,0 +1,88 @@
+//! System package managers' download caches, which are best cleaned by the
+//! package manager itself.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::classify::lowercase_name;
+
+pub struct PackageCache {
+    pub manager: &'static str,
+    /// Runs of path components that mark the cache, matched anywhere in a path.
+    patterns: &'static [&'static [&'static str]],
+    /// The command that cleans the cache the supported way.
+    pub clean_command: &'static [&'static str],
+    /// Whether the cache is owned by root.
+    pub needs_root: bool,
+}
+
+impl PackageCache {
+    pub fn advice(&self) -> String {
+        let command = self.clean_command.join(" ");
+        if self.needs_root {
+            format!("sudo {}", command)
+        } else {
+            command
+        }
+    }
+
+    /// Where the cache lives on this machine.
+    pub fn location(&self) -> Option<PathBuf> {
+        let path = match self.manager {
+            "brew" => crate::paths::user_cache_dir()?.join("Homebrew"),
+            _ => self.patterns[0].iter().fold(PathBuf::from("/"), |path, c| path.join(c)),
+        };
+        Some(path)
+    }
+}
+
+pub const PACKAGE_CACHES: &[PackageCache] = &[
+    PackageCache {
+        manager: "apt",
+        patterns: &[&["var", "cache", "apt", "archives"]],
+        clean_command: &["apt-get", "clean"],
+        needs_root: true,
+    },
+    PackageCache {
+        manager: "dnf",
+        patterns: &[&["var", "cache", "dnf"]],
+        clean_command: &["dnf", "clean", "packages"],
+        needs_root: true,
+    },
+    PackageCache {
+        manager: "yum",
+        patterns: &[&["var", "cache", "yum"]],
+        clean_command: &["yum", "clean", "packages"],
+        needs_root: true,
+    },
+    PackageCache {
+        manager: "pacman",
+        patterns: &[&["var", "cache", "pacman", "pkg"]],
+        clean_command: &["pacman", "-Sc", "--noconfirm"],
+        needs_root: true,
+    },
+    PackageCache {
+        manager: "brew",
+        // ~/Library/Caches/Homebrew on macOS, ~/.cache/Homebrew on Linux
+        patterns: &[&["caches", "homebrew"], &[".cache", "homebrew"]],
+        clean_command: &["brew", "cleanup", "--prune=all"],
+        needs_root: false,
+    },
+];
+
+/// The package manager whose download cache `path` is in, if any.
+pub fn package_cache(path: &Path) -> Option<&'static PackageCache> {
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(lowercase_name(name)),
+            _ => None,
+        })
+        .collect();
+
+    PACKAGE_CACHES.iter().find(|cache| {
+        cache
+            .patterns
+            .iter()
+            .any(|pattern| components.windows(pattern.len()).any(|window| window == *pattern))
+    })
+}
@@ -0,0 +1,199 @@
+use std::borrow::Cow;
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+/// The current user's home directory, if it can be determined.
+pub fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Directories of the machine's users' homes, with the user names they're
+/// named after: `/home/*` on Linux, `/Users/*` on macOS and
+/// `C:\Users\*` on Windows, leaving out shared and template profiles.
+pub fn user_homes() -> Vec<(String, PathBuf)> {
+    const NOT_USERS: &[&str] = &["shared", "public", "default", "default user", "all users", "guest", "lost+found"];
+
+    let parent = if cfg!(windows) {
+        let drive = env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        PathBuf::from(format!("{}\\Users", drive))
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from("/Users")
+    } else {
+        PathBuf::from("/home")
+    };
+
+    let mut homes: Vec<(String, PathBuf)> = std::fs::read_dir(&parent)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+        .filter(|(name, _)| !name.starts_with('.') && !NOT_USERS.contains(&name.to_lowercase().as_str()))
+        .collect();
+    homes.sort();
+    homes
+}
+
+/// Expands a leading `~` to the home directory, leaving other paths untouched.
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return PathBuf::from(path),
+    };
+
+    match home_dir() {
+        Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The per-user cache directory: `$XDG_CACHE_HOME` or `~/.cache` on Linux,
+/// `~/Library/Caches` on macOS and `%LOCALAPPDATA%` on Windows.
+pub fn user_cache_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return env::var_os("LOCALAPPDATA").filter(|dir| !dir.is_empty()).map(PathBuf::from);
+    }
+    if cfg!(target_os = "macos") {
+        return home_dir().map(|home| home.join("Library").join("Caches"));
+    }
+
+    env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".cache")))
+}
+
+/// Expands Windows-style `%NAME%` references using `lookup`, with `%%` for a
+/// literal percent sign. Returns `None` if any variable is undefined, so a
+/// location is never guessed from a half-expanded path.
+pub fn expand_env_vars(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find('%')?;
+        let name = &after[..end];
+
+        if name.is_empty() {
+            result.push('%');
+        } else {
+            result.push_str(&lookup(name)?);
+        }
+        rest = &after[end + 1..];
+    }
+
+    result.push_str(rest);
+    Some(result)
+}
+
+/// Looks up an environment variable the way Windows does, ignoring case.
+pub fn env_var_ignore_case(name: &str) -> Option<String> {
+    env::vars()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// Expands `*` components of a path to every matching subdirectory, e.g. each
+/// browser profile in `User Data/*/Cache`. Paths without wildcards are
+/// returned as they are.
+pub fn expand_wildcards(path: &Path) -> Vec<PathBuf> {
+    let mut results = vec![PathBuf::new()];
+
+    for component in path.components() {
+        if component == Component::Normal("*".as_ref()) {
+            results = results
+                .iter()
+                .filter_map(|base| std::fs::read_dir(base).ok())
+                .flatten()
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+                .map(|entry| entry.path())
+                .collect();
+        } else {
+            for result in &mut results {
+                result.push(component);
+            }
+        }
+    }
+
+    results.sort();
+    results
+}
+
+/// `name` as printable text that still tells it apart from other names: bytes
+/// that aren't valid UTF-8 become `\xNN` and control characters are escaped,
+/// where `display()` would turn the former into `�` and let the latter break
+/// up a listing. Backslashes are doubled on Unix, so a name can't pass for an
+/// escape; on Windows they only ever separate components. This is for the
+/// terminal: machine formats quote `to_string_lossy()` themselves.
+pub fn escape(name: &OsStr) -> Cow<'_, str> {
+    if let Some(text) = name.to_str()
+        && !text.chars().any(|c| c.is_control() || (c == '\\' && cfg!(unix)))
+    {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::new();
+    for chunk in name.as_encoded_bytes().utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '\\' if cfg!(unix) => escaped.push_str("\\\\"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// `path` as printable text, escaped like [`escape`] does.
+pub fn display(path: &Path) -> Cow<'_, str> {
+    escape(path.as_os_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_unprintable_names() {
+        assert_eq!(display(Path::new("/tmp/caché.log")), "/tmp/caché.log");
+        assert_eq!(display(Path::new("/tmp/a\nb\u{1b}.log")), r"/tmp/a\nb\u{1b}.log");
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            assert_eq!(escape(OsStr::from_bytes(b"cache-\xff\xfe.tmp")), r"cache-\xFF\xFE.tmp");
+            assert_eq!(escape(OsStr::new(r"a\nb")), r"a\\nb");
+        }
+    }
+
+    #[test]
+    fn expands_percent_variables() {
+        let lookup = |name: &str| match name.to_ascii_uppercase().as_str() {
+            "LOCALAPPDATA" => Some(r"C:\Users\alice\AppData\Local".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_env_vars(r"%LocalAppData%\Temp", lookup).as_deref(),
+            Some(r"C:\Users\alice\AppData\Local\Temp")
+        );
+        assert_eq!(expand_env_vars("100%% sure", lookup).as_deref(), Some("100% sure"));
+        assert_eq!(expand_env_vars(r"%TEMP%\x", lookup), None);
+        assert_eq!(expand_env_vars("%unterminated", lookup), None);
+    }
+}
@@ -0,0 +1,67 @@
+//! Pausing a long run to let a more important job have the disk, without
+//! losing its progress: SIGUSR1 pauses scans and deletions, SIGUSR2 resumes
+//! them. `hica serve` also offers `pause` and `resume` methods.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::interrupt;
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// How often a paused run checks whether it may go on.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn pause() {
+    PAUSED.store(true, Ordering::SeqCst);
+}
+
+pub fn resume() {
+    PAUSED.store(false, Ordering::SeqCst);
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// Waits while the run is paused. Ctrl-C ends the wait, so a paused run can
+/// still wrap up.
+pub async fn wait_while_paused() {
+    while is_paused() && !interrupt::interrupted() {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Pauses on SIGUSR1 and resumes on SIGUSR2 for the rest of the run.
+#[cfg(unix)]
+pub fn catch_signals() -> std::io::Result<()> {
+    use colored::Colorize;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    use crate::tr;
+
+    let mut pauses = signal(SignalKind::user_defined1())?;
+    let mut resumes = signal(SignalKind::user_defined2())?;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(()) = pauses.recv() => {
+                    pause();
+                    eprintln!("\n{} {}", "[Paused]".yellow().bold(), tr!("pause.paused", std::process::id()));
+                }
+                Some(()) = resumes.recv() => {
+                    resume();
+                    eprintln!("\n{} {}", "[Resumed]".green(), tr!("pause.resumed"));
+                }
+                else => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// There are no user signals to catch outside Unix.
+#[cfg(not(unix))]
+pub fn catch_signals() -> std::io::Result<()> {
+    Ok(())
+}
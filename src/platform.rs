@@ -0,0 +1,638 @@
+//! Small platform queries that need OS-specific calls.
+
+/// Whether hica runs with administrator rights (root on Unix).
+pub fn is_privileged() -> bool {
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid has no preconditions and cannot fail
+        unsafe { libc::geteuid() == 0 }
+    }
+    #[cfg(windows)]
+    {
+        // `net session` only succeeds from an elevated prompt
+        std::process::Command::new("net")
+            .arg("session")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        false
+    }
+}
+
+/// This machine's host name, for telling reports from several machines apart.
+pub fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        // SAFETY: the buffer is valid for its whole length, and the result is
+        // read only up to the first NUL
+        if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } == 0 {
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..end]).into_owned();
+        }
+    }
+
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Where `program` is on the `PATH`, if anywhere.
+pub fn find_program(program: &str) -> Option<std::path::PathBuf> {
+    let names: Vec<String> = match cfg!(windows) {
+        true => ["exe", "cmd", "bat"].iter().map(|ext| format!("{}.{}", program, ext)).collect(),
+        false => vec![program.to_string()],
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| crate::plugins::is_executable(path))
+}
+
+/// The user id owning `path` itself, not what a symlink points to. `None`
+/// where ownership isn't a plain user id, as on Windows.
+pub fn owner(path: &std::path::Path) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        std::fs::symlink_metadata(path).ok().map(|metadata| metadata.uid())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// The id of the user hica runs as, where users have plain ids.
+pub fn current_user_id() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid has no preconditions and cannot fail
+        Some(unsafe { libc::geteuid() })
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Looks up a user id by name, or takes a numeric one as it is.
+pub fn user_id(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse() {
+        return Some(uid);
+    }
+    #[cfg(unix)]
+    {
+        let name = std::ffi::CString::new(name).ok()?;
+        // SAFETY: `name` is NUL-terminated; the entry is read before any other
+        // passwd lookup could overwrite it
+        let entry = unsafe { libc::getpwnam(name.as_ptr()) };
+        if entry.is_null() {
+            None
+        } else {
+            // SAFETY: checked for null above
+            Some(unsafe { (*entry).pw_uid })
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+pub fn free_space(path: &std::path::Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: statvfs is plain old data, for which all zeroes is valid
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL-terminated and `stats` is a valid statvfs to fill
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+            return None;
+        }
+        #[allow(clippy::unnecessary_cast)]
+        Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Files and directories on the filesystem holding `path`, from its count of
+/// used inodes. Filesystems that allocate inodes on demand, like Btrfs, don't
+/// keep one.
+pub fn used_inodes(path: &std::path::Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: statvfs is plain old data, for which all zeroes is valid
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL-terminated and `stats` is a valid statvfs to fill
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 || stats.f_files == 0 {
+            return None;
+        }
+        #[allow(clippy::unnecessary_cast)]
+        Some((stats.f_files as u64).saturating_sub(stats.f_ffree as u64))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Whether a file is an online-only placeholder of a cloud-synced one: a
+/// OneDrive or Dropbox Files On-Demand file on Windows, or an iCloud Drive
+/// file evicted from the Mac. Its data isn't on this disk, and reading it
+/// downloads it.
+pub fn is_placeholder(metadata: &std::fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+        const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x4_0000;
+        const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x40_0000;
+        metadata.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS) != 0
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt;
+
+        const SF_DATALESS: u32 = 0x4000_0000;
+        metadata.st_flags() & SF_DATALESS != 0
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Bytes of `path` that other files share, as reflink copies (`cp
+/// --reflink`, Btrfs and XFS snapshots) or APFS clones do, so deleting it
+/// frees only the rest. `None` where the filesystem or platform can't say.
+pub fn shared_bytes(path: &std::path::Path) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        fiemap::shared_bytes(&std::fs::File::open(path).ok()?)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::MetadataExt;
+
+        let allocated = std::fs::metadata(path).ok()?.blocks() * 512;
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: attrlist is plain old data, for which all zeroes is valid
+        let mut request: libc::attrlist = unsafe { std::mem::zeroed() };
+        request.bitmapcount = libc::ATTR_BIT_MAP_COUNT;
+        request.forkattr = libc::ATTR_CMNEXT_PRIVATESIZE;
+        /// The length of the reply, then the bytes no clone shares
+        #[repr(C, packed(4))]
+        struct Reply {
+            length: u32,
+            private_size: libc::off_t,
+        }
+        let mut reply = Reply { length: 0, private_size: 0 };
+        // SAFETY: `path` is NUL-terminated, and the request and reply are as large as passed
+        let result = unsafe {
+            libc::getattrlist(
+                path.as_ptr(),
+                (&mut request as *mut libc::attrlist).cast(),
+                (&mut reply as *mut Reply).cast(),
+                std::mem::size_of::<Reply>(),
+                libc::FSOPT_ATTR_CMN_EXTENDED,
+            )
+        };
+        if result != 0 || (reply.length as usize) < std::mem::size_of::<Reply>() {
+            return None;
+        }
+        let private_size = reply.private_size;
+        Some(allocated.saturating_sub(private_size.max(0) as u64))
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Reads which extents of a file other files share with the `FS_IOC_FIEMAP`
+/// ioctl, which libc has no bindings for.
+#[cfg(target_os = "linux")]
+mod fiemap {
+    use std::os::fd::AsRawFd;
+
+    const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+    /// Extents asked for per call
+    const BATCH: usize = 64;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Extent {
+        logical: u64,
+        physical: u64,
+        length: u64,
+        reserved64: [u64; 2],
+        flags: u32,
+        reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    struct Request {
+        start: u64,
+        length: u64,
+        flags: u32,
+        mapped_extents: u32,
+        extent_count: u32,
+        reserved: u32,
+        extents: [Extent; BATCH],
+    }
+
+    pub fn shared_bytes(file: &std::fs::File) -> Option<u64> {
+        let mut shared = 0;
+        let mut start = 0;
+        loop {
+            let mut request = Request {
+                start,
+                length: u64::MAX - start,
+                // Not FIEMAP_FLAG_SYNC: that would flush every scanned file's
+                // dirty pages, and data not yet written out isn't shared anyway
+                flags: 0,
+                mapped_extents: 0,
+                extent_count: BATCH as u32,
+                reserved: 0,
+                extents: [Extent::default(); BATCH],
+            };
+            // SAFETY: the request has room for the `extent_count` extents it asks for
+            if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut request as *mut Request) } != 0 {
+                return None;
+            }
+            let extents = &request.extents[..(request.mapped_extents as usize).min(BATCH)];
+            let Some(last) = extents.last() else { return Some(shared) };
+            shared += extents.iter().filter(|extent| extent.flags & FIEMAP_EXTENT_SHARED != 0).map(|extent| extent.length).sum::<u64>();
+            if last.flags & FIEMAP_EXTENT_LAST != 0 {
+                return Some(shared);
+            }
+            start = last.logical + last.length;
+        }
+    }
+}
+
+/// Moves hica into the background: the lowest CPU priority, and idle or
+/// throttled disk IO where the platform has it, so a scan doesn't make the
+/// machine sluggish.
+pub fn lower_priority() -> std::io::Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        // The background band lowers CPU priority and throttles IO together
+        // SAFETY: setpriority only changes this process's scheduling
+        if unsafe { libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        // SAFETY: setpriority only changes this process's scheduling
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        #[cfg(target_os = "linux")]
+        {
+            // ioprio_set(IOPRIO_WHO_PROCESS, self, IOPRIO_CLASS_IDLE), as `ionice -c 3` does
+            const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+            const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+            // SAFETY: the syscall takes plain integers and only affects this process
+            if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, IOPRIO_CLASS_IDLE << 13) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        // Background mode lowers CPU, IO and memory priority together
+        const PROCESS_MODE_BACKGROUND_BEGIN: u32 = 0x0010_0000;
+        #[link(name = "kernel32")]
+        unsafe extern "system" {
+            fn GetCurrentProcess() -> isize;
+            fn SetPriorityClass(process: isize, class: u32) -> i32;
+        }
+        // SAFETY: the pseudo handle of the current process is always valid
+        if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        Ok(())
+    }
+}
+
+/// Names of the extended attributes of `path`, where the platform has them
+/// (Linux and macOS). Empty elsewhere.
+pub fn xattr_names(path: &std::path::Path) -> std::io::Result<Vec<std::ffi::CString>> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        let path = xattr::c_path(path)?;
+        let mut buffer = vec![0u8; 1024];
+        loop {
+            // SAFETY: `path` is NUL-terminated and `buffer` is valid for its length
+            let len = unsafe { xattr::list(&path, &mut buffer) };
+            if len >= 0 {
+                buffer.truncate(len as usize);
+                break;
+            }
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ERANGE) {
+                return Err(error);
+            }
+            buffer.resize(buffer.len() * 4, 0);
+        }
+        Ok(buffer
+            .split(|&b| b == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| std::ffi::CString::new(name).ok())
+            .collect())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = path;
+        Ok(Vec::new())
+    }
+}
+
+/// Copies the extended attributes of `from` onto `to`. Attributes the
+/// destination's filesystem refuses, like `security.*` ones without
+/// privileges, are skipped.
+pub fn copy_xattrs(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        let names = xattr_names(from)?;
+        let (from, to) = (xattr::c_path(from)?, xattr::c_path(to)?);
+        for name in names {
+            // SAFETY: both paths and `name` are NUL-terminated, and the value
+            // buffer is sized by the call before
+            unsafe {
+                let len = xattr::get(&from, &name, &mut []);
+                if len < 0 {
+                    continue;
+                }
+                let mut value = vec![0u8; len as usize];
+                let len = xattr::get(&from, &name, &mut value);
+                if len < 0 {
+                    continue;
+                }
+                value.truncate(len as usize);
+                let _ = xattr::set(&to, &name, &value);
+            }
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (from, to);
+        Ok(())
+    }
+}
+
+/// The macOS metadata attached to `path` that's worth pointing out before
+/// deleting it: Gatekeeper's quarantine and provenance marks, Finder info,
+/// resource forks and Spotlight metadata.
+pub fn apple_metadata(path: &std::path::Path) -> Vec<&'static str> {
+    let names = xattr_names(path).unwrap_or_default();
+    let has = |prefix: &str| names.iter().any(|name| name.to_bytes().starts_with(prefix.as_bytes()));
+
+    [
+        ("com.apple.quarantine", "quarantined"),
+        ("com.apple.provenance", "provenance"),
+        ("com.apple.FinderInfo", "Finder info"),
+        ("com.apple.ResourceFork", "resource fork"),
+        ("com.apple.metadata:", "Spotlight metadata"),
+    ]
+    .into_iter()
+    .filter(|(prefix, _)| has(prefix))
+    .map(|(_, label)| label)
+    .collect()
+}
+
+/// Size of the resource fork of `path` on macOS, which the file's length
+/// leaves out but deleting it frees.
+pub fn resource_fork_size(path: &std::path::Path) -> u64 {
+    if cfg!(target_os = "macos") {
+        std::fs::metadata(path.join("..namedfork").join("rsrc")).map_or(0, |m| m.len())
+    } else {
+        0
+    }
+}
+
+/// The files running programs have open and the directories they work in,
+/// as far as this user can see them. Only known on Linux, from `/proc`.
+pub fn open_paths() -> std::collections::HashSet<std::path::PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        let processes = std::fs::read_dir("/proc").into_iter().flatten().flatten().map(|entry| entry.path());
+        processes
+            .filter(|process| process.file_name().is_some_and(|name| name.to_string_lossy().bytes().all(|b| b.is_ascii_digit())))
+            .flat_map(|process| {
+                let fds = std::fs::read_dir(process.join("fd")).into_iter().flatten().flatten().map(|entry| entry.path());
+                fds.chain(std::iter::once(process.join("cwd"))).collect::<Vec<_>>()
+            })
+            .filter_map(|link| std::fs::read_link(link).ok())
+            .filter(|target| target.is_absolute())
+            .collect()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        std::collections::HashSet::new()
+    }
+}
+
+/// Why a file can't be deleted even with the right permissions: the macOS
+/// user or system immutable flag (`chflags uchg` or Finder's "Locked").
+pub fn locked_reason(path: &std::path::Path) -> Option<&'static str> {
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt;
+
+        const UF_IMMUTABLE: u32 = 0x0000_0002;
+        const SF_IMMUTABLE: u32 = 0x0002_0000;
+        let flags = std::fs::symlink_metadata(path).ok()?.st_flags();
+        if flags & SF_IMMUTABLE != 0 {
+            Some("it has the system immutable flag `schg`, which root can only clear in single-user mode")
+        } else if flags & UF_IMMUTABLE != 0 {
+            Some("it's locked; unlock it in Finder's Get Info or with `chflags nouchg`")
+        } else {
+            None
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Bytes held by the Recycle Bins of all drives, as Explorer counts them.
+/// `None` outside Windows, where trash lives in plain directories.
+pub fn recycle_bin_size() -> Option<u64> {
+    #[cfg(windows)]
+    {
+        #[cfg_attr(target_arch = "x86", repr(C, packed(1)))]
+        #[cfg_attr(not(target_arch = "x86"), repr(C))]
+        struct ShQueryRbInfo {
+            size: u32,
+            bytes: i64,
+            items: i64,
+        }
+        #[link(name = "shell32")]
+        unsafe extern "system" {
+            fn SHQueryRecycleBinW(root: *const u16, info: *mut ShQueryRbInfo) -> i32;
+        }
+        let mut info = ShQueryRbInfo { size: std::mem::size_of::<ShQueryRbInfo>() as u32, bytes: 0, items: 0 };
+        // SAFETY: a null root asks about every drive, and `info` has its size set
+        if unsafe { SHQueryRecycleBinW(std::ptr::null(), &mut info) } != 0 {
+            return None;
+        }
+        Some(info.bytes.max(0) as u64)
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// `path` in the form the filesystem calls accept however long it is. On
+/// Windows, that's the extended-length `\\?\` form: without it, paths over
+/// `MAX_PATH` (260 characters), common deep inside `node_modules` caches,
+/// fail to stat or delete. Elsewhere, `path` as it is.
+pub fn long_path(path: &std::path::Path) -> std::borrow::Cow<'_, std::path::Path> {
+    #[cfg(windows)]
+    {
+        let Ok(absolute) = std::path::absolute(path) else {
+            return std::borrow::Cow::Borrowed(path);
+        };
+        match absolute.to_str().and_then(extended_length) {
+            Some(extended) => std::borrow::Cow::Owned(extended.into()),
+            None => std::borrow::Cow::Borrowed(path),
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        std::borrow::Cow::Borrowed(path)
+    }
+}
+
+/// Prefixes an absolute Windows path with `\\?\` (`\\?\UNC\` for a network
+/// share), which also turns off the parsing that would otherwise resolve `.`,
+/// `..` and `/` in it, so those are normalized first. `None` for paths that
+/// are relative or already have a prefix.
+#[cfg(any(windows, test))]
+fn extended_length(path: &str) -> Option<String> {
+    let path = path.replace('/', "\\");
+    if path.starts_with("\\\\?\\") || path.starts_with("\\\\.\\") {
+        return None;
+    }
+    let (prefix, rest) = if let Some(share) = path.strip_prefix("\\\\") {
+        ("\\\\?\\UNC\\", share)
+    } else if path.as_bytes().get(1) == Some(&b':') && path.as_bytes().get(2) == Some(&b'\\') {
+        ("\\\\?\\", path.as_str())
+    } else {
+        return None;
+    };
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in rest.split('\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                // Never above the drive or the share
+                let root = if prefix.ends_with("UNC\\") { 2 } else { 1 };
+                if components.len() > root {
+                    components.pop();
+                }
+            }
+            name => components.push(name),
+        }
+    }
+    let mut extended = format!("{}{}", prefix, components.join("\\"));
+    if components.len() == 1 {
+        extended.push('\\');
+    }
+    Some(extended)
+}
+
+/// The extended attribute calls, which take an extra position and options
+/// argument on macOS.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod xattr {
+    use std::ffi::{CStr, CString};
+
+    pub fn c_path(path: &std::path::Path) -> std::io::Result<CString> {
+        use std::os::unix::ffi::OsStrExt;
+
+        CString::new(path.as_os_str().as_bytes()).map_err(std::io::Error::other)
+    }
+
+    pub unsafe fn list(path: &CStr, buffer: &mut [u8]) -> isize {
+        #[cfg(target_os = "linux")]
+        return unsafe { libc::listxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len()) };
+        #[cfg(target_os = "macos")]
+        return unsafe { libc::listxattr(path.as_ptr(), buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+    }
+
+    pub unsafe fn get(path: &CStr, name: &CStr, value: &mut [u8]) -> isize {
+        #[cfg(target_os = "linux")]
+        return unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), value.as_mut_ptr().cast(), value.len()) };
+        #[cfg(target_os = "macos")]
+        return unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), value.as_mut_ptr().cast(), value.len(), 0, 0) };
+    }
+
+    pub unsafe fn set(path: &CStr, name: &CStr, value: &[u8]) -> i32 {
+        #[cfg(target_os = "linux")]
+        return unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0) };
+        #[cfg(target_os = "macos")]
+        return unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0, 0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_extended_length_paths() {
+        assert_eq!(extended_length(r"C:\Users\alice\node_modules").as_deref(), Some(r"\\?\C:\Users\alice\node_modules"));
+        assert_eq!(extended_length(r"C:/Users/alice/./a/../b").as_deref(), Some(r"\\?\C:\Users\alice\b"));
+        assert_eq!(extended_length(r"C:\").as_deref(), Some(r"\\?\C:\"));
+        assert_eq!(extended_length(r"\\server\share\cache\..\..").as_deref(), Some(r"\\?\UNC\server\share"));
+        assert_eq!(extended_length(r"\\?\C:\already"), None);
+        assert_eq!(extended_length(r"relative\path"), None);
+    }
+
+    #[test]
+    fn plain_copies_share_nothing() {
+        let file = std::env::temp_dir().join(format!("hica-shared-{}", std::process::id()));
+        std::fs::write(&file, vec![1u8; 64 * 1024]).unwrap();
+        let shared = shared_bytes(&file);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(shared.unwrap_or(0), 0);
+    }
+}
@@ -0,0 +1,341 @@
+//! Classifier plugins: executables in the `plugins` directory next to the
+//! config file, which tell hica about the caches of applications it has no
+//! patterns for.
+//!
+//! A plugin speaks JSON, one object per line, on stdin and stdout. hica
+//! starts it once per run and asks `{"request":"describe"}`, to which it
+//! answers with its name and the directories it knows about:
+//! `{"name":"acme","roots":["~/Library/Application Support/Acme"]}`. For
+//! each file below one of those, hica asks
+//! `{"request":"classify","path":".."}` and the plugin answers either
+//! `{"cache":true,"category":"application","risk":"safe","reason":".."}`,
+//! `{"cache":false}` to keep a file the built-in patterns would flag, or
+//! `{}` to leave it to them. The plugin should exit when its stdin closes.
+//!
+//! A plugin that takes longer than ten seconds to answer is stopped and asked
+//! nothing more. Plugins, and the directory they're in, must belong to you or
+//! to root and not be writable by anyone else, since they run with your
+//! rights on every scan.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::classify::{CacheCategory, Risk};
+use crate::json::Json;
+use crate::paths::expand_home;
+use crate::report::json_string;
+
+/// What a plugin says about a file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Cache { category: CacheCategory, risk: Risk, reason: String },
+    /// Not cache, whatever the built-in patterns say
+    Keep { reason: String },
+}
+
+/// How long a plugin gets to answer a request
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A running helper program, asked one line of JSON at a time.
+pub(crate) struct Connection {
+    child: Child,
+    stdin: ChildStdin,
+    /// Answer lines, read by a thread of their own so a request can time out;
+    /// an empty line means the program closed its stdout
+    answers: Receiver<io::Result<String>>,
+    /// How long an answer may take, if there's a limit
+    timeout: Option<Duration>,
+}
+
+impl Connection {
+    pub(crate) fn start(path: &Path, timeout: Option<Duration>) -> Result<Connection, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        let (sender, answers) = mpsc::channel();
+        std::thread::spawn(move || loop {
+            let mut line = String::new();
+            let result = stdout.read_line(&mut line).map(|_| line);
+            let done = !matches!(&result, Ok(line) if !line.is_empty());
+            if sender.send(result).is_err() || done {
+                break;
+            }
+        });
+        Ok(Connection { child, stdin, answers, timeout })
+    }
+
+    /// Sends one request line and reads the answer line. A program that
+    /// doesn't answer in time is killed.
+    pub(crate) fn ask(&mut self, request: &str) -> Result<Json, String> {
+        writeln!(self.stdin, "{}", request).and_then(|()| self.stdin.flush()).map_err(|e| e.to_string())?;
+        let answer = match self.timeout {
+            Some(timeout) => self.answers.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => {
+                    let _ = self.child.kill();
+                    let _ = self.child.wait();
+                    format!("it didn't answer within {} seconds", timeout.as_secs())
+                }
+                RecvTimeoutError::Disconnected => "it exited".to_string(),
+            })?,
+            None => self.answers.recv().map_err(|_| "it exited".to_string())?,
+        };
+        match answer {
+            Ok(line) if line.is_empty() => Err("it exited".to_string()),
+            Ok(line) => Json::parse(line.trim()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+pub struct Plugin {
+    pub name: String,
+    pub roots: Vec<PathBuf>,
+    /// None once the plugin has failed, after which it's asked nothing more
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Plugin {
+    /// Starts the executable at `path` and asks what it covers.
+    pub fn start(path: &Path) -> Result<Plugin, String> {
+        let mut connection = Connection::start(path, Some(PLUGIN_TIMEOUT))?;
+        let description = connection.ask("{\"request\":\"describe\",\"hica\":1}")?;
+        let name = description.get("name").and_then(Json::as_str).ok_or("the description has no `name`")?.to_string();
+        let roots = description
+            .get("roots")
+            .and_then(Json::as_array)
+            .ok_or("the description has no `roots`")?
+            .iter()
+            .filter_map(Json::as_str)
+            .map(expand_home)
+            .collect();
+        Ok(Plugin { name, roots, connection: Mutex::new(Some(connection)) })
+    }
+
+    /// Asks the plugin about `path`, if it's below one of its roots.
+    fn classify(&self, path: &Path) -> Result<Option<Verdict>, String> {
+        // Roots are absolute, and the plugin shouldn't have to know where hica runs
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        if !self.roots.iter().any(|root| path.starts_with(root)) {
+            return Ok(None);
+        }
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(live) = connection.as_mut() else {
+            return Ok(None);
+        };
+        let request = format!("{{\"request\":\"classify\",\"path\":{}}}", json_string(&path.to_string_lossy()));
+        let answer = match live.ask(&request).and_then(|answer| verdict(&self.name, &answer)) {
+            Ok(answer) => answer,
+            Err(e) => {
+                *connection = None;
+                return Err(e);
+            }
+        };
+        Ok(answer)
+    }
+}
+
+/// Reads an answer to a classify request.
+fn verdict(plugin: &str, answer: &Json) -> Result<Option<Verdict>, String> {
+    let reason = answer.get("reason").and_then(Json::as_str).map_or_else(|| format!("plugin `{}`", plugin), |reason| format!("{} (plugin `{}`)", reason, plugin));
+    match answer.get("cache").and_then(Json::as_bool) {
+        None => Ok(None),
+        Some(false) => Ok(Some(Verdict::Keep { reason })),
+        Some(true) => {
+            let category = match answer.get("category").and_then(Json::as_str) {
+                Some(name) => CacheCategory::from_str(name, true).map_err(|_| format!("unknown category `{}`", name))?,
+                None => CacheCategory::Application,
+            };
+            let risk = match answer.get("risk").and_then(Json::as_str) {
+                Some(name) => Risk::from_str(name, true).map_err(|_| format!("unknown risk `{}`", name))?,
+                None => Risk::Safe,
+            };
+            Ok(Some(Verdict::Cache { category, risk, reason }))
+        }
+    }
+}
+
+static PLUGINS: OnceLock<Vec<Plugin>> = OnceLock::new();
+/// The last verdict, since a file is classified once to flag it and again
+/// to pick its category
+static LAST: Mutex<Option<(PathBuf, Option<Verdict>)>> = Mutex::new(None);
+
+/// Starts every executable in `dir`, returning the plugins that answered
+/// and a message for each that didn't.
+pub fn load(dir: &Path) -> (Vec<Plugin>, Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (Vec::new(), Vec::new());
+    };
+    if let Err(e) = trusted(dir) {
+        return (Vec::new(), vec![format!("{}: {}", dir.display(), e)]);
+    }
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| is_executable(path)).collect();
+    paths.sort();
+
+    let (mut plugins, mut failures) = (Vec::new(), Vec::new());
+    for path in paths {
+        match trusted(&path).and_then(|()| Plugin::start(&path)) {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => failures.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    (plugins, failures)
+}
+
+/// Refuses a plugin, or the directory of plugins, that someone other than
+/// the user running hica or root could have put there or changed.
+fn trusted(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+        if metadata.uid() != 0 && Some(metadata.uid()) != crate::platform::current_user_id() {
+            return Err("refusing it, since it belongs to another user".to_string());
+        }
+        if metadata.mode() & 0o022 != 0 {
+            return Err("refusing it, since other users can change it".to_string());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+pub fn is_executable(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+    }
+}
+
+/// Runs `wait`, which waits on a helper program, handing the worker thread's
+/// other tasks to the rest of the runtime meanwhile: scans classify files on
+/// the runtime's worker threads.
+pub(crate) fn off_the_runtime<T>(wait: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) if runtime.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => tokio::task::block_in_place(wait),
+        _ => wait(),
+    }
+}
+
+/// Sets the plugins classification asks for the rest of the run.
+pub fn install(plugins: Vec<Plugin>) {
+    let _ = PLUGINS.set(plugins);
+}
+
+/// The first verdict of a plugin on `path`. A plugin that fails is
+/// reported once and asked nothing more.
+pub fn classify(path: &Path) -> Option<Verdict> {
+    let plugins = PLUGINS.get().filter(|plugins| !plugins.is_empty())?;
+    if let Some((last, verdict)) = &*LAST.lock().unwrap_or_else(|e| e.into_inner())
+        && last == path
+    {
+        return verdict.clone();
+    }
+    let ask = || plugins.iter().find_map(|plugin| match plugin.classify(path) {
+        Ok(verdict) => verdict,
+        Err(e) => {
+            eprintln!("{} Plugin `{}` failed and is skipped from now on: {}", "[Warning!]".red().bold(), plugin.name, e);
+            None
+        }
+    });
+    let verdict = off_the_runtime(ask);
+    *LAST.lock().unwrap_or_else(|e| e.into_inner()) = Some((path.to_path_buf(), verdict.clone()));
+    verdict
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_verdicts() {
+        let answer = |text: &str| verdict("acme", &Json::parse(text).unwrap());
+        assert_eq!(answer("{}"), Ok(None));
+        assert_eq!(answer(r#"{"cache":false}"#), Ok(Some(Verdict::Keep { reason: "plugin `acme`".into() })));
+        assert_eq!(
+            answer(r#"{"cache":true,"category":"build","risk":"likely","reason":"render cache"}"#),
+            Ok(Some(Verdict::Cache { category: CacheCategory::Build, risk: Risk::Likely, reason: "render cache (plugin `acme`)".into() }))
+        );
+        assert!(answer(r#"{"cache":true,"category":"bogus"}"#).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn talks_to_a_plugin() {
+        let dir = std::env::temp_dir().join(format!("hica-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("acme");
+        let here = std::env::current_dir().unwrap();
+        std::fs::write(&script, format!(concat!(
+            "#!/bin/sh\n",
+            "read -r line; echo '{{\"name\":\"acme\",\"roots\":[\"/opt/acme\",{}]}}'\n",
+            "while read -r line; do case \"$line\" in *'\"path\":\"/'*render*) echo '{{\"cache\":true}}';; *) echo '{{}}';; esac; done\n",
+        ), json_string(&here.to_string_lossy()))).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+
+        let (plugins, failures) = load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(failures.is_empty(), "{:?}", failures);
+        assert_eq!(plugins[0].roots, [PathBuf::from("/opt/acme"), here]);
+        assert_eq!(plugins[0].classify(Path::new("/srv/render.bin")), Ok(None));
+        assert_eq!(plugins[0].classify(Path::new("/opt/acme/render.bin")), Ok(Some(Verdict::Cache {
+            category: CacheCategory::Application,
+            risk: Risk::Safe,
+            reason: "plugin `acme`".into(),
+        })));
+        assert_eq!(plugins[0].classify(Path::new("/opt/acme/data.db")), Ok(None));
+        // Relative paths are below the roots they resolve to, and sent resolved
+        assert!(matches!(plugins[0].classify(Path::new("render.bin")), Ok(Some(Verdict::Cache { .. }))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_slow_and_untrusted_plugins() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("hica-plugins-refused-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("slow");
+        std::fs::write(&script, "#!/bin/sh\nread -r line; sleep 5\n").unwrap();
+        std::fs::set_permissions(&script, PermissionsExt::from_mode(0o755)).unwrap();
+
+        let mut connection = Connection::start(&script, Some(Duration::from_millis(200))).unwrap();
+        let started = std::time::Instant::now();
+        assert!(connection.ask("{}").unwrap_err().contains("didn't answer"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        std::fs::set_permissions(&script, PermissionsExt::from_mode(0o777)).unwrap();
+        let (plugins, failures) = load(&dir);
+        assert!(plugins.is_empty());
+        assert!(failures[0].contains("other users can change it"), "{:?}", failures);
+
+        std::fs::set_permissions(&script, PermissionsExt::from_mode(0o755)).unwrap();
+        std::fs::set_permissions(&dir, PermissionsExt::from_mode(0o777)).unwrap();
+        let (plugins, failures) = load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(plugins.is_empty());
+        assert!(failures[0].starts_with(&dir.display().to_string()), "{:?}", failures);
+    }
+}
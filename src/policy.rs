@@ -0,0 +1,224 @@
+//! Policy scripts: a program named by `[policy] script`, which has the last
+//! word on every cache file a scan finds. hica embeds no interpreter: the
+//! script is run as an external program, with the interpreter its `#!` line
+//! picks, so a policy can be written in Lua, Rhai, Python or plain shell
+//! alike. Without `#!` lines, Windows can't run it, so policies are Unix-only
+//! and a configured one stops hica there.
+//!
+//! Like a plugin, a policy speaks JSON, one object per line, on stdin and
+//! stdout, and is started once per run. When a scan starts it's told
+//! `{"event":"scan-start","path":".."}`, and when it ends
+//! `{"event":"scan-end","path":"..","examined":..,"files":..,"bytes":..}`
+//! with the cache found; any answer to those will do. For each cache file
+//! it's asked
+//! `{"request":"file","path":"..","size":..,"category":"..","risk":"..","modified":..}`,
+//! with times as Unix seconds, and answers `{}` to keep the file as it is,
+//! `{"skip":true}` to leave it out, or a new `category` or `risk`.
+//!
+//! A script that fails, by exiting, answering garbage or taking more than ten
+//! seconds to answer, is stopped, and every file from then on is left out
+//! rather than kept: a scan shouldn't delete what the policy never saw.
+
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::classify::{CacheCategory, Risk};
+use crate::json::Json;
+use crate::plugins::{off_the_runtime, Connection};
+use crate::report::json_string;
+use crate::scan::{CacheFile, ScanStats};
+
+/// What a policy did with a file.
+#[derive(Debug, Clone, PartialEq)]
+enum Decision {
+    Skip,
+    Change { category: Option<CacheCategory>, risk: Option<Risk> },
+}
+
+/// Reads an answer to a file request.
+fn decision(answer: &Json) -> Result<Decision, String> {
+    if answer.get("skip").and_then(Json::as_bool) == Some(true) {
+        return Ok(Decision::Skip);
+    }
+    let category = match answer.get("category").and_then(Json::as_str) {
+        Some(name) => Some(CacheCategory::from_str(name, true).map_err(|_| format!("unknown category `{}`", name))?),
+        None => None,
+    };
+    let risk = match answer.get("risk").and_then(Json::as_str) {
+        Some(name) => Some(Risk::from_str(name, true).map_err(|_| format!("unknown risk `{}`", name))?),
+        None => None,
+    };
+    Ok(Decision::Change { category, risk })
+}
+
+fn file_request(file: &CacheFile) -> String {
+    let time = |time: Option<SystemTime>| {
+        time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map_or_else(|| "null".to_string(), |elapsed| elapsed.as_secs().to_string())
+    };
+    format!(
+        "{{\"request\":\"file\",\"path\":{},\"size\":{},\"category\":\"{}\",\"risk\":\"{}\",\"modified\":{},\"accessed\":{}}}",
+        json_string(&file.path.to_string_lossy()),
+        file.size,
+        file.category.as_str().to_lowercase(),
+        file.risk.as_str(),
+        time(file.modified),
+        time(file.accessed)
+    )
+}
+
+/// How long a policy script gets to answer a request
+const POLICY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Policy {
+    script: String,
+    /// None once the script has failed, after which every file is left out
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Policy {
+    pub fn start(script: &Path) -> Result<Policy, String> {
+        if cfg!(not(unix)) {
+            return Err("policy scripts need Unix, where the `#!` line picks their interpreter".to_string());
+        }
+        let connection = Connection::start(script, Some(POLICY_TIMEOUT))?;
+        Ok(Policy { script: script.display().to_string(), connection: Mutex::new(Some(connection)) })
+    }
+
+    /// Sends `request`, dropping the script if it fails.
+    fn ask(&self, request: &str) -> Option<Json> {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        match off_the_runtime(|| connection.as_mut().map(|live| live.ask(request))) {
+            Some(Ok(answer)) => Some(answer),
+            Some(Err(e)) => {
+                *connection = None;
+                self.failed(&e);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn failed(&self, error: &str) {
+        eprintln!("{} Policy script {} failed, so the scan leaves out every file from now on: {}", "[Warning!]".red().bold(), self.script, error);
+    }
+
+    /// The file as the script would have it, or None if it's left out,
+    /// which every file is once the script has failed.
+    fn review(&self, mut file: CacheFile) -> Option<CacheFile> {
+        let answer = self.ask(&file_request(&file))?;
+        match decision(&answer) {
+            Ok(Decision::Skip) => None,
+            Ok(Decision::Change { category, risk }) => {
+                file.category = category.unwrap_or(file.category);
+                file.risk = risk.unwrap_or(file.risk);
+                Some(file)
+            }
+            Err(e) => {
+                *self.connection.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                self.failed(&e);
+                None
+            }
+        }
+    }
+}
+
+static POLICY: OnceLock<Policy> = OnceLock::new();
+
+/// Sets the policy scans consult for the rest of the run.
+pub fn install(policy: Policy) {
+    let _ = POLICY.set(policy);
+}
+
+/// Passes a cache file the scan found through the policy, if there is one.
+pub fn review(file: CacheFile) -> Option<CacheFile> {
+    match POLICY.get() {
+        Some(policy) => policy.review(file),
+        None => Some(file),
+    }
+}
+
+pub fn scan_started(path: &Path) {
+    if let Some(policy) = POLICY.get() {
+        policy.ask(&format!("{{\"event\":\"scan-start\",\"path\":{}}}", json_string(&path.to_string_lossy())));
+    }
+}
+
+pub fn scan_finished(path: &Path, stats: &ScanStats, cache_files: &[CacheFile]) {
+    if let Some(policy) = POLICY.get() {
+        policy.ask(&format!(
+            "{{\"event\":\"scan-end\",\"path\":{},\"examined\":{},\"files\":{},\"bytes\":{}}}",
+            json_string(&path.to_string_lossy()),
+            stats.files,
+            cache_files.len(),
+            cache_files.iter().map(|file| file.size).sum::<u64>()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn reads_decisions() {
+        let answer = |text: &str| decision(&Json::parse(text).unwrap());
+        assert_eq!(answer("{}"), Ok(Decision::Change { category: None, risk: None }));
+        assert_eq!(answer(r#"{"skip":true,"risk":"safe"}"#), Ok(Decision::Skip));
+        assert_eq!(answer(r#"{"category":"log","risk":"risky"}"#), Ok(Decision::Change { category: Some(CacheCategory::Log), risk: Some(Risk::Risky) }));
+        assert!(answer(r#"{"risk":"maybe"}"#).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_a_script() {
+        let script = std::env::temp_dir().join(format!("hica-policy-{}", std::process::id()));
+        std::fs::write(&script, concat!(
+            "#!/bin/sh\n",
+            "while read -r line; do case \"$line\" in *keep.me*) echo '{\"skip\":true}';; *.log*) echo '{\"category\":\"log\"}';; *) echo '{}';; esac; done\n",
+        )).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        let policy = Policy::start(&script).unwrap();
+
+        let file = |path: &str| CacheFile {
+            path: PathBuf::from(path),
+            size: 10,
+            category: CacheCategory::Temporary,
+            risk: Risk::Likely,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+        assert!(policy.review(file("/srv/keep.me")).is_none());
+        assert_eq!(policy.review(file("/srv/app.log.1")).map(|file| file.category), Some(CacheCategory::Log));
+        assert_eq!(policy.review(file("/srv/a.tmp")).map(|file| file.category), Some(CacheCategory::Temporary));
+        std::fs::remove_file(&script).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn leaves_out_files_once_the_script_fails() {
+        let script = std::env::temp_dir().join(format!("hica-policy-failing-{}", std::process::id()));
+        std::fs::write(&script, "#!/bin/sh\nread -r line; echo '{}'; read -r line; echo 'garbage'\n").unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        let policy = Policy::start(&script).unwrap();
+
+        let file = CacheFile {
+            path: PathBuf::from("/srv/a.tmp"),
+            size: 10,
+            category: CacheCategory::Temporary,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+        let answers = [policy.review(file.clone()), policy.review(file.clone()), policy.review(file)];
+        std::fs::remove_file(&script).unwrap();
+        assert!(answers[0].is_some());
+        assert!(answers[1..].iter().all(Option::is_none));
+    }
+}
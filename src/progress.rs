@@ -0,0 +1,213 @@
+//! How long-running work shows its progress: an indicatif bar for people,
+//! plain lines for logs (`--no-progress`, or whenever stderr isn't a
+//! terminal), or JSON events on stderr for programs that draw their own
+//! (`--progress json`).
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::report::json_string;
+use crate::size::format_size;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum ProgressFormat {
+    /// A progress bar on the terminal
+    #[default]
+    Bar,
+    /// One JSON object per line on stderr
+    Json,
+    /// A plain line on stderr every few seconds, for logs
+    Plain,
+}
+
+static FORMAT: OnceLock<ProgressFormat> = OnceLock::new();
+
+/// Sets the progress format for the rest of the run; only the first call counts.
+pub fn set_format(format: ProgressFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn format() -> ProgressFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// The format a run uses: plain lines instead of a bar with `--no-progress`
+/// or when stderr goes to a log rather than a terminal, where a bar can't be
+/// drawn.
+pub fn effective_format(requested: ProgressFormat, no_progress: bool) -> ProgressFormat {
+    match requested {
+        ProgressFormat::Bar if no_progress || !std::io::stderr().is_terminal() => ProgressFormat::Plain,
+        requested => requested,
+    }
+}
+
+/// Whether progress is drawn as a bar, rather than written as events.
+pub fn shows_bar() -> bool {
+    format() == ProgressFormat::Bar
+}
+
+/// How often JSON events are written at most, so a fast walk doesn't flood the reader.
+const EVENT_INTERVAL: Duration = Duration::from_millis(100);
+/// How often plain progress lines are written at most.
+const LINE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A snapshot of some work's progress.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressEvent {
+    /// `resuming`, `walking` or `deleting`
+    pub phase: &'static str,
+    /// Files processed so far
+    pub files: u64,
+    /// Files there are to process, when known up front
+    pub total: Option<u64>,
+    /// Cache files found so far, while scanning
+    pub found: Option<u64>,
+    /// Bytes found while scanning, or freed while deleting
+    pub bytes: u64,
+    /// The file processed last
+    pub current: Option<PathBuf>,
+}
+
+impl ProgressEvent {
+    /// `{"phase":..,"files":..,"total":..,"found":..,"bytes":..,"current":..,"done":..}`,
+    /// leaving out `total` and `found` when they don't apply.
+    fn to_json(&self, done: bool) -> String {
+        let mut fields = vec![format!("\"phase\":\"{}\"", self.phase), format!("\"files\":{}", self.files)];
+        if let Some(total) = self.total {
+            fields.push(format!("\"total\":{}", total));
+        }
+        if let Some(found) = self.found {
+            fields.push(format!("\"found\":{}", found));
+        }
+        fields.push(format!("\"bytes\":{}", self.bytes));
+        fields.push(format!(
+            "\"current\":{}",
+            self.current.as_ref().map_or("null".to_string(), |path| json_string(&path.to_string_lossy()))
+        ));
+        fields.push(format!("\"done\":{}", done));
+        format!("{{{}}}", fields.join(","))
+    }
+
+    /// `[Progress] walking: 1520 files, 38 cache files found (9.0 MB)`, with
+    /// `(done)` on the last line of a phase.
+    fn to_line(&self, done: bool) -> String {
+        let mut line = format!("[Progress] {}: {}", self.phase, self.files);
+        if let Some(total) = self.total {
+            line.push_str(&format!("/{}", total));
+        }
+        line.push_str(" files");
+        match self.found {
+            Some(found) => line.push_str(&format!(", {} cache files found ({})", found, format_size(self.bytes))),
+            None if self.bytes > 0 => line.push_str(&format!(", {} freed", format_size(self.bytes))),
+            None => {}
+        }
+        if done {
+            line.push_str(" (done)");
+        }
+        line
+    }
+}
+
+/// How often a `Throughput` takes a sample.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+/// Weight of the newest sample, so the rate follows changes over a few
+/// seconds rather than jumping with every burst of small files.
+const SMOOTHING: f64 = 0.2;
+
+/// A rate of something growing, such as bytes looked at, smoothed over the
+/// last few seconds.
+#[derive(Default)]
+pub struct Throughput {
+    last: Option<(Instant, u64)>,
+    per_sec: f64,
+}
+
+impl Throughput {
+    /// Takes in the running `total` and returns the rate per second.
+    pub fn update(&mut self, total: u64) -> f64 {
+        let now = Instant::now();
+        match self.last {
+            None => self.last = Some((now, total)),
+            Some((at, before)) if now - at >= SAMPLE_INTERVAL => {
+                let rate = total.saturating_sub(before) as f64 / (now - at).as_secs_f64();
+                self.per_sec = if self.per_sec == 0.0 { rate } else { SMOOTHING * rate + (1.0 - SMOOTHING) * self.per_sec };
+                self.last = Some((now, total));
+            }
+            Some(_) => {}
+        }
+        self.per_sec
+    }
+}
+
+/// Writes progress events to stderr in the run's format: JSON at most one
+/// per `EVENT_INTERVAL`, plain lines at most one per `LINE_INTERVAL`, and
+/// always the last one, marked done. Writes nothing while a bar is shown.
+#[derive(Default)]
+pub struct ProgressEvents {
+    started: Option<Instant>,
+    written: Option<Instant>,
+    latest: Option<ProgressEvent>,
+}
+
+impl ProgressEvents {
+    pub fn update(&mut self, event: ProgressEvent) {
+        let interval = match format() {
+            ProgressFormat::Bar => return,
+            ProgressFormat::Json => EVENT_INTERVAL,
+            ProgressFormat::Plain => LINE_INTERVAL,
+        };
+        let started = *self.started.get_or_insert_with(Instant::now);
+        // Plain lines wait out a first interval, so quick work logs just its result
+        let since = match format() {
+            ProgressFormat::Plain => Some(self.written.unwrap_or(started)),
+            _ => self.written,
+        };
+        if since.is_none_or(|since| since.elapsed() >= interval) {
+            write(&event, false);
+            self.written = Some(Instant::now());
+        }
+        self.latest = Some(event);
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(event) = self.latest.take() {
+            write(&event, true);
+        }
+    }
+}
+
+fn write(event: &ProgressEvent, done: bool) {
+    match format() {
+        ProgressFormat::Bar => {}
+        ProgressFormat::Json => eprintln!("{}", event.to_json(done)),
+        ProgressFormat::Plain => eprintln!("{}", event.to_line(done)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_fields_that_dont_apply() {
+        let event = ProgressEvent {
+            phase: "deleting",
+            files: 3,
+            total: Some(10),
+            bytes: 2048,
+            current: Some(PathBuf::from("/tmp/a \"b\".log")),
+            ..ProgressEvent::default()
+        };
+        assert_eq!(
+            event.to_json(true),
+            r#"{"phase":"deleting","files":3,"total":10,"bytes":2048,"current":"/tmp/a \"b\".log","done":true}"#
+        );
+        assert_eq!(event.to_line(false), "[Progress] deleting: 3/10 files, 2.0 KB freed");
+        let event = ProgressEvent { phase: "walking", files: 1520, found: Some(38), bytes: 1024, ..ProgressEvent::default() };
+        assert_eq!(event.to_line(true), "[Progress] walking: 1520 files, 38 cache files found (1.0 KB) (done)");
+    }
+}
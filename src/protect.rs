@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::gitignore::VCS_DIRS;
+use crate::paths::{expand_home, home_dir};
+
+/// Always protected, whatever the config says, along with version control
+/// metadata (`VCS_DIRS` anywhere, and git directories by their contents).
+/// Bare names match any path component.
+/// Container storage is only ever cleaned through the engine (`hica clean docker`),
+/// and Spotlight's index and the FSEvents log are rebuilt expensively if touched.
+/// Snapshots are history, not cache.
+const DEFAULT_PROTECTED: &[&str] = &[
+    "~/.ssh", "~/.gnupg", "/etc", ".zfs", ".snapshot", ".snapshots",
+    "/var/lib/docker", "/var/lib/containers", "~/.local/share/docker", "~/.local/share/containers",
+    ".Spotlight-V100", ".fseventsd", "com.apple.Spotlight", "com.apple.metadata.mdworker",
+];
+
+/// Paths that are reported but never deleted.
+pub struct ProtectedPaths {
+    roots: Vec<PathBuf>,
+    names: Vec<OsString>,
+    /// Whether each directory looked at is a git directory
+    git_dirs: Mutex<HashMap<PathBuf, bool>>,
+}
+
+/// Whether `dir` holds a git repository's metadata under whatever name: a
+/// bare repository, a `--separate-git-dir`, or the git directory of a linked
+/// worktree, which `commondir` points back from.
+fn is_git_dir(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && ((dir.join("objects").is_dir() && dir.join("refs").is_dir()) || dir.join("commondir").is_file())
+}
+
+impl ProtectedPaths {
+    pub fn new(extra: &[String]) -> Self {
+        let mut roots = Vec::new();
+        let mut names = Vec::new();
+
+        for entry in DEFAULT_PROTECTED.iter().chain(VCS_DIRS).copied().chain(extra.iter().map(String::as_str)) {
+            if entry.contains(['/', '\\']) || entry.starts_with('~') {
+                let root = expand_home(entry);
+                if let Ok(canonical) = root.canonicalize()
+                    && canonical != root
+                {
+                    roots.push(canonical);
+                }
+                roots.push(root);
+            } else {
+                names.push(OsString::from(entry));
+            }
+        }
+
+        ProtectedPaths { roots, names, git_dirs: Mutex::new(HashMap::new()) }
+    }
+
+    /// Checks both the path as given and with symlinks resolved, so a link
+    /// into a protected directory doesn't slip through.
+    pub fn is_protected(&self, path: &Path) -> bool {
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let canonical = path.canonicalize().ok();
+
+        std::iter::once(absolute.as_path())
+            .chain(canonical.as_deref())
+            .any(|candidate| self.matches(candidate))
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+            || path.components().any(|component| match component {
+                Component::Normal(name) => self.names.iter().any(|n| n == name),
+                _ => false,
+            })
+            || self.in_git_dir(path)
+    }
+
+    fn in_git_dir(&self, path: &Path) -> bool {
+        let mut git_dirs = self.git_dirs.lock().unwrap_or_else(|e| e.into_inner());
+        path.ancestors().skip(1).any(|dir| *git_dirs.entry(dir.to_path_buf()).or_insert_with(|| is_git_dir(dir)))
+    }
+}
+
+/// Whether `path` is a filesystem or drive root, or someone's entire home
+/// directory, where a blanket cleanup is almost certainly a mistake.
+pub fn is_dangerous_root(path: &Path) -> bool {
+    let path = match path.canonicalize() {
+        Ok(path) => path,
+        Err(_) => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    };
+
+    let parent = match path.parent() {
+        Some(parent) => parent,
+        // `/` on Unix, `C:\` on Windows
+        None => return true,
+    };
+
+    if home_dir().is_some_and(|home| home.canonicalize().unwrap_or(home) == path) {
+        return true;
+    }
+
+    // `/root`, or a direct child of `/home`, `/Users` or `C:\Users`
+    let is_root = |p: &Path| p.parent().is_none();
+    let parent_name = parent.file_name().and_then(|name| name.to_str());
+    (is_root(parent) && path.file_name().is_some_and(|name| name == "root"))
+        || (parent.parent().is_some_and(is_root)
+            && parent_name.is_some_and(|name| name.eq_ignore_ascii_case("home") || name.eq_ignore_ascii_case("users")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protects_repositories_under_any_name() {
+        let dir = std::env::temp_dir().join(format!("hica-protect-{}", std::process::id()));
+        let bare = dir.join("project.git");
+        let worktree = dir.join("gitdirs").join("feature");
+        std::fs::create_dir_all(bare.join("objects").join("pack")).unwrap();
+        std::fs::create_dir_all(bare.join("refs")).unwrap();
+        std::fs::create_dir_all(bare.join("logs")).unwrap();
+        std::fs::write(bare.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(worktree.join("HEAD"), "ref: refs/heads/feature\n").unwrap();
+        std::fs::write(worktree.join("commondir"), "../..\n").unwrap();
+        std::fs::create_dir_all(dir.join("app").join("logs")).unwrap();
+
+        let protected = ProtectedPaths::new(&[]);
+        let results = [
+            protected.is_protected(&bare.join("logs").join("HEAD")),
+            protected.is_protected(&bare.join("objects").join("pack").join("tmp_pack_1")),
+            protected.is_protected(&worktree.join("index.lock")),
+            protected.is_protected(&dir.join("app").join(".git")),
+            protected.is_protected(&dir.join("app").join(".hg").join("cache").join("x")),
+            protected.is_protected(&dir.join("app").join("logs").join("app.log")),
+        ];
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(results, [true, true, true, true, true, false]);
+    }
+}
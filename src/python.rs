@@ -0,0 +1,82 @@
+//! The caches of Python tools: package downloads kept by pip, conda and
+//! Poetry, and the bytecode and tool caches left in projects.
+
+use std::path::{Component, Path};
+
+use crate::classify::{lowercase_name, CacheCategory};
+
+/// Conda installs, whose `pkgs` directory keeps downloaded packages.
+const CONDA_INSTALLS: &[&str] = &["miniconda3", "anaconda3", "miniforge3", "mambaforge", "miniconda", "anaconda", ".conda"];
+
+/// Caches tools keep in a project, by directory name.
+const PROJECT_CACHES: &[(&str, &str)] = &[
+    ("__pycache__", "bytecode"),
+    (".pytest_cache", "pytest"),
+    (".mypy_cache", "mypy"),
+    (".ruff_cache", "ruff"),
+];
+
+/// A Python tool's cache a path is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PythonCache {
+    /// `pip`, `conda`, `poetry`, `bytecode`, `pytest`, `mypy` or `ruff`
+    pub tool: &'static str,
+    /// Package for downloads, Build for what a project's tools regenerate
+    pub category: CacheCategory,
+}
+
+/// The Python tool whose cache `path` is in: pip's HTTP and wheel cache,
+/// conda's `pkgs`, Poetry's `cache` and `artifacts` (not its virtualenvs),
+/// bytecode, and the caches of pytest, mypy and ruff.
+pub fn python_cache(path: &Path) -> Option<PythonCache> {
+    let components: Vec<String> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(lowercase_name(name)),
+            _ => None,
+        })
+        .collect();
+    let (_, dirs) = components.split_last()?;
+    let package = |tool| Some(PythonCache { tool, category: CacheCategory::Package });
+    let build = |tool| Some(PythonCache { tool, category: CacheCategory::Build });
+
+    if let Some((_, tool)) = PROJECT_CACHES.iter().find(|(dir, _)| dirs.iter().any(|d| d == dir)) {
+        return build(tool);
+    }
+
+    for (i, pair) in dirs.windows(2).enumerate() {
+        let (parent, dir) = (pair[0].as_str(), pair[1].as_str());
+        let child = dirs.get(i + 2).map(String::as_str);
+        // `~/.cache/pip`, `~/Library/Caches/pip`, `%LOCALAPPDATA%\pip\Cache`
+        if (matches!(parent, ".cache" | "caches") && dir == "pip") || (parent == "pip" && dir == "cache") {
+            return package("pip");
+        }
+        if CONDA_INSTALLS.contains(&parent) && dir == "pkgs" {
+            return package("conda");
+        }
+        // `~/.cache/pypoetry/cache`, or `%LOCALAPPDATA%\pypoetry\Cache\cache`
+        let poetry_root = (matches!(parent, ".cache" | "caches") && dir == "pypoetry") || (parent == "pypoetry" && dir == "cache");
+        if poetry_root && matches!(child, Some("cache" | "artifacts")) {
+            return package("poetry");
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_python_tool_caches() {
+        let tool = |path: &str| python_cache(Path::new(path)).map(|cache| (cache.tool, cache.category));
+        assert_eq!(tool("/home/alice/.cache/pip/http-v2/a/b/c"), Some(("pip", CacheCategory::Package)));
+        assert_eq!(tool("C:/Users/alice/AppData/Local/pip/Cache/wheels/a/b.whl"), Some(("pip", CacheCategory::Package)));
+        assert_eq!(tool("/home/alice/miniconda3/pkgs/numpy-1.26.4.conda"), Some(("conda", CacheCategory::Package)));
+        assert_eq!(tool("/home/alice/.cache/pypoetry/artifacts/ab/cd/requests.whl"), Some(("poetry", CacheCategory::Package)));
+        assert_eq!(tool("/home/alice/.cache/pypoetry/virtualenvs/app-py3.12/bin/python"), None);
+        assert_eq!(tool("/home/alice/app/src/__pycache__/main.cpython-312.pyc"), Some(("bytecode", CacheCategory::Build)));
+        assert_eq!(tool("/home/alice/app/.pytest_cache/v/cache/lastfailed"), Some(("pytest", CacheCategory::Build)));
+        assert_eq!(tool("/home/alice/app/src/main.py"), None);
+    }
+}
@@ -0,0 +1,110 @@
+//! Scans of other machines over SSH, for `hica detect user@host:/path`.
+//!
+//! The remote side runs `hica export -` and the scan comes back over the
+//! connection, so nothing but SSH needs to be set up. Where hica isn't
+//! installed, this very binary is copied over for the one scan, provided
+//! the remote machine runs the same system on the same architecture.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::export::Export;
+
+/// A path on another machine, as `[user@]host:path`.
+#[derive(Debug, PartialEq)]
+pub struct Remote {
+    /// Whatever ssh takes as the destination
+    pub host: String,
+    pub path: String,
+}
+
+impl Remote {
+    /// Reads `[user@]host:path` the way scp does: a colon before any slash.
+    /// Local paths that exist, and Windows drive letters, aren't remote.
+    pub fn parse(spec: &Path) -> Option<Remote> {
+        let spec = spec.to_str()?;
+        let (host, path) = spec.split_once(':')?;
+        if host.len() < 2 || host.contains(['/', '\\']) || Path::new(spec).exists() {
+            return None;
+        }
+        let path = if path.is_empty() { "." } else { path };
+        Some(Remote { host: host.to_string(), path: path.to_string() })
+    }
+}
+
+/// Quotes `arg` for the remote shell.
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// The remote system as `uname -sm` puts it, if this binary can run there.
+fn local_uname() -> Option<String> {
+    let os = match std::env::consts::OS {
+        "linux" => "Linux",
+        "macos" => "Darwin",
+        "freebsd" => "FreeBSD",
+        _ => return None,
+    };
+    let arch = match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("macos", "aarch64") => "arm64",
+        (_, arch) => arch,
+    };
+    Some(format!("{} {}", os, arch))
+}
+
+fn ssh(host: &str, command: &str) -> Command {
+    let mut ssh = Command::new("ssh");
+    ssh.arg("--").arg(host).arg(command).stderr(Stdio::inherit());
+    ssh
+}
+
+/// Runs `command` and decodes the export it prints, or returns its exit code
+/// if it failed.
+fn run_export(mut command: Command) -> Result<Result<Export, i32>, String> {
+    let mut child = command.stdout(Stdio::piped()).spawn().map_err(|e| format!("could not run ssh: {}", e))?;
+    let mut bytes = Vec::new();
+    child.stdout.take().expect("stdout is piped").read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Ok(Err(status.code().unwrap_or(255)));
+    }
+    Export::decode(&bytes).map(Ok)
+}
+
+/// Scans `remote` with `hica export`, passing it `args`, and returns the scan.
+pub fn scan(remote: &Remote, args: &[String]) -> Result<Export, String> {
+    let export = std::iter::once(quote(&remote.path)).chain(args.iter().map(|arg| quote(arg))).collect::<Vec<_>>().join(" ");
+    match run_export(ssh(&remote.host, &format!("hica export - {}", export)))? {
+        Ok(scan) => return Ok(scan),
+        // 127 is the shell's "command not found"
+        Err(127) => {}
+        Err(code) => return Err(format!("the scan on {} failed with exit status {}", remote.host, code)),
+    }
+
+    let uname = ssh(&remote.host, "uname -sm").stdout(Stdio::piped()).output().map_err(|e| format!("could not run ssh: {}", e))?;
+    let uname = String::from_utf8_lossy(&uname.stdout).trim().to_string();
+    if local_uname().is_none_or(|local| local != uname) {
+        return Err(format!("hica isn't installed on {}, and this one can't run on its system ({})", remote.host, uname));
+    }
+    let binary = std::env::current_exe().map_err(|e| e.to_string())?;
+    let helper = format!(r#"f=$(mktemp) && cat > "$f" && chmod +x "$f" && "$f" export - {}; s=$?; rm -f "$f"; exit $s"#, export);
+    let mut command = ssh(&remote.host, &helper);
+    command.stdin(std::fs::File::open(&binary).map_err(|e| format!("could not read {}: {}", binary.display(), e))?);
+    run_export(command)?.map_err(|code| format!("the scan on {} failed with exit status {}", remote.host, code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_remote_paths_from_local_ones() {
+        assert_eq!(Remote::parse(Path::new("admin@web1:/var/cache")), Some(Remote { host: "admin@web1".into(), path: "/var/cache".into() }));
+        assert_eq!(Remote::parse(Path::new("web1:")), Some(Remote { host: "web1".into(), path: ".".into() }));
+        assert_eq!(Remote::parse(Path::new("/home/a:b")), None);
+        assert_eq!(Remote::parse(Path::new("C:\\Users")), None);
+        assert_eq!(Remote::parse(Path::new("./web1:x")), None);
+        assert_eq!(quote("it's"), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,896 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use colored::{ColoredString, Colorize};
+
+use crate::anonymize;
+use crate::apps;
+use crate::browser;
+use crate::classify::{CacheCategory, Risk};
+use crate::clean::CleanSummary;
+use crate::cleaners;
+use crate::compare::{category_deltas, path_deltas, Side};
+use crate::compiler;
+use crate::crash::crashed_program;
+use crate::dedupe::DuplicateSet;
+use crate::du::DiskUsage;
+use crate::errors::PathError;
+use crate::jvm::jvm_cache;
+use crate::mounts::{volume_of, Mount};
+use crate::paths;
+use crate::platform::free_space;
+use crate::protect::ProtectedPaths;
+use crate::python::python_cache;
+use crate::junk::JunkKind;
+use crate::scan::{size_totals, CacheFile, Partial, ScanStats, Staleness};
+use crate::size::format_size_with_color;
+use crate::theme::theme;
+use crate::tr;
+
+/// Prints the largest files that would be deleted, so the user can sanity check
+/// a cleanup before it happens.
+pub fn print_sample(cache_files: &[CacheFile], protected: &ProtectedPaths, count: usize) {
+    let mut sample: Vec<&CacheFile> = cache_files
+        .iter()
+        .filter(|f| !protected.is_protected(&f.path))
+        .collect();
+    sample.sort_by_key(|f| std::cmp::Reverse(f.size));
+    
+    println!("\n{}", "Largest files that would be deleted: ".blue().bold());
+    for file in sample.iter().take(count) {
+        println!("  {} [{}, {}] {}",
+            format_size_with_color(file.size),
+            category_with_color(file.category),
+            risk_with_color(file.risk),
+            paths::display(&file.path)
+        );
+    }
+    if sample.len() > count {
+        println!("  ... and {} more", (sample.len() - count).to_string().cyan());
+    }
+}
+
+/// A risk level, colored by how careful it calls for being.
+pub fn risk_with_color(risk: Risk) -> ColoredString {
+    let index = match risk {
+        Risk::Safe => 0,
+        Risk::Likely => 1,
+        Risk::Risky => 2,
+    };
+    risk.as_str().color(theme().risks[index])
+}
+
+/// A category name in the theme's category color.
+pub fn category_with_color(category: CacheCategory) -> ColoredString {
+    category.as_str().color(theme().category)
+}
+
+pub fn print_duplicates(sets: &[DuplicateSet], count: usize) {
+    let wasted: u64 = sets.iter().map(|set| set.wasted()).sum();
+    
+    println!("\n{}", "Duplicate Files: ".blue().bold());
+    if sets.is_empty() {
+        println!("  No duplicates found");
+        return;
+    }
+    
+    for set in sets.iter().take(count) {
+        println!("  {} copies of {} ({} wasted)",
+            set.paths.len().to_string().cyan(),
+            format_size_with_color(set.size),
+            format_size_with_color(set.wasted())
+        );
+        for path in &set.paths {
+            println!("    {}", paths::display(path));
+        }
+    }
+    if sets.len() > count {
+        println!("  ... and {} more sets", (sets.len() - count).to_string().cyan());
+    }
+    println!("  {} duplicate sets wasting {}",
+        sets.len().to_string().cyan(),
+        format_size_with_color(wasted)
+    );
+}
+
+/// Upper bounds of the age histogram buckets, in days.
+const AGE_BUCKETS: [(u64, &str); 3] = [(7, "< 7 days"), (30, "7-30 days"), (90, "30-90 days")];
+
+/// Prints how the cache size is spread over file ages, to help pick an
+/// `--older-than` cutoff.
+pub async fn print_age_histogram(cache_files: &[CacheFile], use_atime: bool) {
+    let mut buckets: Vec<Vec<&CacheFile>> = vec![Vec::new(); AGE_BUCKETS.len() + 1];
+    for file in cache_files {
+        let days = file.age(use_atime).as_secs() / (24 * 60 * 60);
+        let index = AGE_BUCKETS
+            .iter()
+            .position(|(limit, _)| days < *limit)
+            .unwrap_or(AGE_BUCKETS.len());
+        buckets[index].push(file);
+    }
+    
+    let mut sizes = Vec::new();
+    for bucket in &buckets {
+        sizes.push(size_totals(bucket.iter().copied()).await.size);
+    }
+    let largest = sizes.iter().copied().max().unwrap_or(0).max(1);
+    
+    println!("\n{}", tr!("summary.ages").blue().bold());
+    let labels = AGE_BUCKETS.iter().map(|(_, label)| *label).chain(std::iter::once("> 90 days"));
+    for ((label, bucket), size) in labels.zip(&buckets).zip(&sizes) {
+        let bar = "#".repeat((size * 30 / largest) as usize);
+        println!("  {:<10} {:<30} {}",
+            label,
+            bar.cyan(),
+            tr!("summary.files", bucket.len().to_string().cyan(), format_size_with_color(*size))
+        );
+    }
+}
+
+/// Prints the space container engines could reclaim, which never shows up as
+/// plain cache files.
+pub fn print_container_storage() {
+    for name in ["docker", "podman"] {
+        let targets = match cleaners::find(name) {
+            Some(cleaner) => cleaner.targets(),
+            None => continue,
+        };
+        if targets.is_empty() {
+            continue;
+        }
+        
+        let size: u64 = targets.iter().filter(|t| t.default).map(|t| t.size()).sum();
+        println!("  {} ({}): {} reclaimable, run `hica clean {}`",
+            CacheCategory::Container.as_str().cyan(),
+            name,
+            format_size_with_color(size),
+            name
+        );
+    }
+}
+
+/// How long measuring the trash may hold up a report.
+const TRASH_SIZE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prints the size of the trash, which is reported with the caches but only
+/// emptied through `hica clean trash`. A trash too big to measure in
+/// `TRASH_SIZE_TIMEOUT` is left out rather than hold up the report.
+pub async fn print_trash() {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    // A thread of its own, which is left to finish unnoticed if it takes too long
+    std::thread::spawn(move || sender.send(cleaners::trash_size()));
+    let size = match tokio::time::timeout(TRASH_SIZE_TIMEOUT, receiver).await {
+        Ok(Ok(size)) if size > 0 => size,
+        _ => return,
+    };
+    println!("  {}: {}", CacheCategory::Trash.as_str().cyan(), tr!("summary.trash", format_size_with_color(size)));
+}
+
+/// The files of `category` grouped by `source`, largest first.
+async fn totals_by_source(
+    cache_files: &[CacheFile],
+    category: CacheCategory,
+    source: impl Fn(&CacheFile) -> Option<String>,
+) -> Vec<(Option<String>, usize, u64)> {
+    group_by_source(cache_files.iter().filter(|f| f.category == category), source).await
+}
+
+/// Files grouped by `source`, largest first.
+async fn group_by_source<'a>(
+    cache_files: impl Iterator<Item = &'a CacheFile>,
+    source: impl Fn(&CacheFile) -> Option<String>,
+) -> Vec<(Option<String>, usize, u64)> {
+    let mut sources: Vec<(Option<String>, Vec<&CacheFile>)> = Vec::new();
+    for file in cache_files {
+        let name = source(file);
+        match sources.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, files)) => files.push(file),
+            None => sources.push((name, vec![file])),
+        }
+    }
+    
+    let mut totals = Vec::new();
+    for (name, files) in sources {
+        let size = size_totals(files.iter().copied()).await.size;
+        totals.push((name, files.len(), size));
+    }
+    totals.sort_by_key(|(_, _, size)| std::cmp::Reverse(*size));
+    totals
+}
+
+/// Crash dumps grouped by the program that left them, with `None` for dumps
+/// that don't say.
+async fn crash_dumps_by_program(cache_files: &[CacheFile]) -> Vec<(Option<String>, usize, u64)> {
+    totals_by_source(cache_files, CacheCategory::CrashDump, |file| crashed_program(&file.path)).await
+}
+
+/// Prints a breakdown under `title`, the largest `count` sources first.
+fn print_sources(title: String, sources: &[(Option<String>, usize, u64)], unknown: String, count: usize) {
+    if sources.is_empty() {
+        return;
+    }
+    
+    println!("\n{}", title.blue().bold());
+    for (source, files, size) in sources.iter().take(count) {
+        println!("  {}: {}",
+            source.as_deref().unwrap_or(&unknown).cyan(),
+            tr!("summary.files", files.to_string().cyan(), format_size_with_color(*size))
+        );
+    }
+    if sources.len() > count {
+        println!("  ... and {} more", (sources.len() - count).to_string().cyan());
+    }
+}
+
+/// Prints which programs the crash dumps came from, since a few forgotten
+/// dumps of one program can outweigh every other cache.
+pub async fn print_crash_dumps(cache_files: &[CacheFile], count: usize) {
+    let programs: Vec<_> = crash_dumps_by_program(cache_files)
+        .await
+        .into_iter()
+        .map(|(program, files, bytes)| (program.map(|program| paths::escape(program.as_ref()).into_owned()), files, bytes))
+        .collect();
+    print_sources(tr!("summary.crash-dumps"), &programs, tr!("summary.crash-unknown"), count);
+}
+
+/// Prints the junk among the cache files by kind.
+pub async fn print_junk(junk: &[(CacheFile, JunkKind)]) {
+    let mut kinds = Vec::new();
+    for kind in JunkKind::ALL {
+        let files: Vec<&CacheFile> = junk.iter().filter(|(_, k)| *k == kind).map(|(file, _)| file).collect();
+        if !files.is_empty() {
+            kinds.push((Some(kind.as_str().to_string()), files.len(), size_totals(files.iter().copied()).await.size));
+        }
+    }
+    print_sources(tr!("summary.junk"), &kinds, String::new(), kinds.len());
+}
+
+/// Prints the browser caches per browser profile.
+pub async fn print_browser_profiles(cache_files: &[CacheFile], count: usize) {
+    let profiles = totals_by_source(cache_files, CacheCategory::Browser, |file| {
+        browser::locate(&file.path).map(|location| location.describe())
+    }).await;
+    print_sources(tr!("summary.browser-profiles"), &profiles, tr!("summary.browser-unknown"), count);
+}
+
+/// Prints the application caches per app, for the apps hica can tell.
+pub async fn print_apps(cache_files: &[CacheFile], count: usize) {
+    let apps = totals_by_source(cache_files, CacheCategory::Application, |file| apps::app_of(&file.path)).await;
+    if apps.iter().all(|(app, _, _)| app.is_none()) {
+        return;
+    }
+    print_sources(tr!("summary.apps"), &apps, tr!("summary.apps-other"), count);
+}
+
+/// Prints the caches of the tools `tool` can tell, per tool.
+async fn print_tool_caches(cache_files: &[CacheFile], title: String, tool: impl Fn(&CacheFile) -> Option<String>) {
+    let tools = group_by_source(cache_files.iter().filter(|f| tool(f).is_some()), &tool).await;
+    print_sources(title, &tools, String::new(), tools.len());
+}
+
+/// Prints the caches of Python tools per tool, across the Package and Build
+/// categories they're counted in.
+pub async fn print_python_caches(cache_files: &[CacheFile]) {
+    print_tool_caches(cache_files, tr!("summary.python"), |file| python_cache(&file.path).map(|cache| cache.tool.to_string())).await;
+}
+
+/// Prints the caches of JVM build tools per tool.
+pub async fn print_jvm_caches(cache_files: &[CacheFile]) {
+    print_tool_caches(cache_files, tr!("summary.jvm"), |file| jvm_cache(&file.path).map(|cache| cache.tool.to_string())).await;
+}
+
+/// Prints what ccache and sccache take up next to the size each trims itself
+/// to, since a compiler cache under its limit is working as intended.
+pub async fn print_compiler_caches(cache_files: &[CacheFile]) {
+    let source = |file: &CacheFile| compiler::compiler_cache(&file.path).map(str::to_string);
+    let tools = group_by_source(cache_files.iter().filter(|f| source(f).is_some()), source).await;
+    if tools.is_empty() {
+        return;
+    }
+
+    println!("\n{}", tr!("summary.compiler").blue().bold());
+    for (tool, files, size) in &tools {
+        let tool = tool.as_deref().unwrap_or_default();
+        let usage = tr!("summary.files", files.to_string().cyan(), format_size_with_color(*size));
+        match compiler::size_limit(tool) {
+            Some(limit) => println!("  {}: {}", tool.cyan(), tr!("summary.compiler-limit", usage, format_size_with_color(limit))),
+            None => println!("  {}: {}", tool.cyan(), tr!("summary.compiler-unlimited", usage)),
+        }
+    }
+}
+
+/// Quotes a string as a JSON string literal.
+pub fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// One detected file as a JSON object on a single line, with the volume it
+/// lives on.
+pub fn json_line(file: &CacheFile, volume: &Path) -> String {
+    let seconds = |time: Option<SystemTime>| {
+        time.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or("null".to_string(), |elapsed| elapsed.as_secs().to_string())
+    };
+    
+    format!(
+        "{{\"path\":{},\"size\":{},\"category\":\"{}\",\"risk\":\"{}\",\"modified\":{},\"accessed\":{},\"volume\":{}}}",
+        json_string(&anonymize::path(&file.path)),
+        file.size,
+        file.category.as_str().to_lowercase(),
+        file.risk.as_str(),
+        seconds(file.modified),
+        seconds(file.accessed),
+        json_string(&anonymize::path(volume))
+    )
+}
+
+/// The files grouped by the volume they live on, in volume order.
+fn group_by_volume<'a>(cache_files: &'a [CacheFile], mounts: &[Mount]) -> Vec<(PathBuf, Vec<&'a CacheFile>)> {
+    let mut volumes: Vec<(PathBuf, Vec<&CacheFile>)> = Vec::new();
+    for file in cache_files {
+        let volume = volume_of(mounts, &file.path);
+        match volumes.iter_mut().find(|(point, _)| *point == volume) {
+            Some((_, files)) => files.push(file),
+            None => volumes.push((volume, vec![file])),
+        }
+    }
+    volumes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    volumes
+}
+
+/// Prints how much each volume would get back, with its free space where
+/// known, since freeing space only helps on the volume that's running out.
+pub async fn print_volume_summary(cache_files: &[CacheFile], mounts: &[Mount]) {
+    let volumes = group_by_volume(cache_files, mounts);
+    
+    println!("\n{}", tr!("summary.volumes").blue().bold());
+    for (volume, files) in &volumes {
+        let free = match free_space(volume) {
+            Some(free) => format!(", {}", tr!("summary.free", format_size_with_color(free))),
+            None => String::new(),
+        };
+        println!("  {}: {}{}",
+            volume.display().to_string().cyan(),
+            tr!("summary.files", files.len().to_string().cyan(), format_size_with_color(size_totals(files.iter().copied()).await.size)),
+            free
+        );
+    }
+}
+
+/// The directory directly in `root` that `path` is in, or `None` for files
+/// right in `root`.
+fn root_entry(path: &Path, root: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut components = relative.components();
+    let first = components.next()?;
+    components.next()?;
+    Some(paths::escape(first.as_os_str()).into_owned())
+}
+
+/// The entry of `root` an `--expand` argument means, given either as its
+/// name or as a path below `root`.
+fn expanded_entry(dir: &Path, root: &Path) -> Option<String> {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    relative
+        .components()
+        .find(|component| matches!(component, std::path::Component::Normal(_)))
+        .map(|component| paths::escape(component.as_os_str()).into_owned())
+}
+
+/// Prints the caches per directory directly in `root`, such as each project
+/// under `~/code`, and then the details of the ones in `expand`: where in
+/// them the caches are and their largest files.
+pub async fn print_roots(cache_files: &[CacheFile], root: &Path, expand: &[PathBuf], count: usize) {
+    let entries = group_by_source(cache_files.iter(), |file| root_entry(&file.path, root)).await;
+    print_sources(tr!("summary.roots", paths::display(root)), &entries, tr!("summary.roots-direct"), count);
+
+    for dir in expand {
+        let Some(name) = expanded_entry(dir, root) else {
+            continue;
+        };
+        let files: Vec<&CacheFile> = cache_files
+            .iter()
+            .filter(|file| root_entry(&file.path, root).as_deref() == Some(name.as_str()))
+            .collect();
+        if files.is_empty() {
+            println!("\n{} {}", "[Note]".blue(), tr!("summary.roots-none", name));
+            continue;
+        }
+
+        let entry = root.join(&name);
+        let subdirs = group_by_source(files.iter().copied(), |file| root_entry(&file.path, &entry)).await;
+        print_sources(tr!("summary.roots-expanded", name), &subdirs, tr!("summary.roots-direct"), count);
+        let mut largest = files;
+        largest.sort_by_key(|file| std::cmp::Reverse(file.size));
+        for file in largest.iter().take(count) {
+            println!("    {} [{}] {}",
+                format_size_with_color(file.size),
+                category_with_color(file.category),
+                paths::display(&file.path)
+            );
+        }
+        if largest.len() > count {
+            println!("    ... and {} more", (largest.len() - count).to_string().cyan());
+        }
+    }
+}
+
+/// A directory in the tree of cache locations, with the cache below it.
+#[derive(Default)]
+struct TreeNode {
+    files: usize,
+    size: u64,
+    /// Size per category, in the order they were first seen
+    categories: Vec<(CacheCategory, u64)>,
+    children: BTreeMap<String, TreeNode>,
+}
+
+impl TreeNode {
+    fn insert(&mut self, dirs: &[String], file: &CacheFile) {
+        self.files += 1;
+        self.size += file.size;
+        match self.categories.iter_mut().find(|(category, _)| *category == file.category) {
+            Some((_, size)) => *size += file.size,
+            None => self.categories.push((file.category, file.size)),
+        }
+        if let Some((dir, rest)) = dirs.split_first() {
+            self.children.entry(dir.clone()).or_default().insert(rest, file);
+        }
+    }
+
+    /// The category most of the cache below is in.
+    fn main_category(&self) -> CacheCategory {
+        self.categories.iter().max_by_key(|(_, size)| *size).map_or(CacheCategory::Other, |(category, _)| *category)
+    }
+}
+
+/// Directories under this share of the total are summed up rather than shown.
+const TREE_MIN_PERCENT: u64 = 1;
+/// Most directories shown at each level.
+const TREE_MAX_CHILDREN: usize = 10;
+
+fn cache_tree(cache_files: &[CacheFile], root: &Path) -> TreeNode {
+    let mut tree = TreeNode::default();
+    for file in cache_files {
+        let parent = file.path.parent().unwrap_or(&file.path);
+        let relative = parent.strip_prefix(root).unwrap_or(parent);
+        let dirs: Vec<String> = relative
+            .components()
+            .map(|component| paths::escape(component.as_os_str()).into_owned())
+            .collect();
+        tree.insert(&dirs, file);
+    }
+    tree
+}
+
+/// Prints the cache files as a tree of the directories holding them, with
+/// the total below each, so the few directories holding most of it stand out.
+/// A directory whose cache is all in one subdirectory is shown joined with it.
+pub fn print_tree(cache_files: &[CacheFile], root: &Path) {
+    let tree = cache_tree(cache_files, root);
+    println!("\n{}", tr!("summary.tree").blue().bold());
+    println!("  {}: {}", paths::display(root).cyan(), tr!("summary.files", tree.files.to_string().cyan(), format_size_with_color(tree.size)));
+    let min_size = (tree.size * TREE_MIN_PERCENT / 100).max(1);
+    print_tree_level(&tree, "  ", min_size);
+}
+
+fn print_tree_level(node: &TreeNode, indent: &str, min_size: u64) {
+    let mut children: Vec<(&String, &TreeNode)> = node.children.iter().collect();
+    children.sort_by_key(|(_, child)| std::cmp::Reverse(child.size));
+    let shown = children
+        .iter()
+        .take(TREE_MAX_CHILDREN)
+        .take_while(|(_, child)| child.size >= min_size)
+        .count();
+    let rest = &children[shown..];
+
+    for (i, (name, child)) in children[..shown].iter().enumerate() {
+        let last = i + 1 == shown && rest.is_empty();
+        let mut name = name.to_string();
+        let mut child = *child;
+        while let Some((below, grandchild)) = child.children.iter().next()
+            && child.children.len() == 1
+            && grandchild.files == child.files
+        {
+            name = format!("{}/{}", name, below);
+            child = grandchild;
+        }
+        println!("{}{} {}: {}",
+            indent,
+            if last { "└──" } else { "├──" },
+            name,
+            tr!("summary.files", child.files.to_string().cyan(), format_size_with_color(child.size))
+        );
+        print_tree_level(child, &format!("{}{}", indent, if last { "    " } else { "│   " }), min_size);
+    }
+    if !rest.is_empty() {
+        let files = rest.iter().map(|(_, child)| child.files).sum::<usize>();
+        let size = rest.iter().map(|(_, child)| child.size).sum::<u64>();
+        println!("{}└── {}: {}",
+            indent,
+            tr!("summary.tree-rest", rest.len().to_string().cyan()),
+            tr!("summary.files", files.to_string().cyan(), format_size_with_color(size))
+        );
+    }
+}
+
+/// Subdirectories holding at least this share of a directory are broken out of it.
+const OFFENDER_CHILD_PERCENT: u64 = 10;
+/// A directory with most of its cache in this few subdirectories is broken
+/// down into them. One spread over more, like a package cache, is one offender.
+const OFFENDER_MAX_BREAKDOWN: usize = 3;
+
+/// A directory among those holding the most cache.
+#[derive(Debug, PartialEq)]
+struct Offender {
+    dir: PathBuf,
+    size: u64,
+    category: CacheCategory,
+}
+
+/// Goes down the tree from `dir` to where its cache is spread out, noting
+/// each such directory.
+fn find_offenders(node: &TreeNode, dir: PathBuf, offenders: &mut Vec<Offender>) {
+    let big: Vec<(&String, &TreeNode)> = node.children
+        .iter()
+        .filter(|(_, child)| child.size * 100 >= node.size * OFFENDER_CHILD_PERCENT)
+        .collect();
+    let big_size: u64 = big.iter().map(|(_, child)| child.size).sum();
+
+    if !big.is_empty() && big.len() <= OFFENDER_MAX_BREAKDOWN && big_size * 2 > node.size {
+        for (name, child) in big {
+            find_offenders(child, dir.join(name), offenders);
+        }
+    } else if node.size > 0 {
+        offenders.push(Offender { dir, size: node.size, category: node.main_category() });
+    }
+}
+
+/// The directories holding the most cache, largest first.
+fn offenders(cache_files: &[CacheFile], root: &Path) -> Vec<Offender> {
+    let mut offenders = Vec::new();
+    find_offenders(&cache_tree(cache_files, root), root.to_path_buf(), &mut offenders);
+    offenders.sort_by_key(|offender| std::cmp::Reverse(offender.size));
+    offenders
+}
+
+/// Prints the `count` directories holding the most cache, with their share
+/// of it and the category most of it is in, such as a package manager's
+/// store or one browser profile.
+pub fn print_offenders(cache_files: &[CacheFile], root: &Path, count: usize) {
+    let total: u64 = cache_files.iter().map(|file| file.size).sum();
+    if count == 0 || total == 0 {
+        return;
+    }
+    println!("\n{}", tr!("summary.offenders").blue().bold());
+    for offender in offenders(cache_files, root).iter().take(count) {
+        println!("  {}: {}",
+            paths::display(&offender.dir).cyan(),
+            tr!("summary.offender", format_size_with_color(offender.size), offender.size * 100 / total, category_with_color(offender.category))
+        );
+    }
+}
+
+/// Width of the bars in `hica du`.
+const USAGE_BAR: u64 = 30;
+
+/// Prints a tree's disk usage, the `count` largest entries of its root first,
+/// each with a bar whose red part is cache.
+pub fn print_disk_usage(root: &Path, usage: &DiskUsage, count: usize) {
+    let largest = usage.entries.first().map_or(0, |(_, entry)| entry.bytes).max(1);
+    let share = |part: u64, whole: u64| (part * 100).checked_div(whole).unwrap_or(0);
+
+    println!("\n{}", tr!("du.title", paths::display(root)).blue().bold());
+    for (path, entry) in usage.entries.iter().take(count) {
+        let width = entry.bytes * USAGE_BAR / largest;
+        let cache = (entry.cache_bytes * USAGE_BAR / largest).min(width);
+        let name = path.file_name().map_or_else(|| paths::display(path), paths::escape);
+        println!("  {:>10} {}{}{} {}{}",
+            format_size_with_color(entry.bytes),
+            "#".repeat(cache as usize).red(),
+            "#".repeat((width - cache) as usize).blue(),
+            " ".repeat((USAGE_BAR - width) as usize),
+            name,
+            if path.is_dir() { std::path::MAIN_SEPARATOR_STR } else { "" }
+        );
+        if entry.cache_bytes > 0 {
+            println!("  {:>10} {}", "", tr!("du.cache", format_size_with_color(entry.cache_bytes), share(entry.cache_bytes, entry.bytes)).dimmed());
+        }
+    }
+    if usage.entries.len() > count {
+        println!("  ... and {} more", (usage.entries.len() - count).to_string().cyan());
+    }
+
+    println!("\n{} {}",
+        "[OK!]".green(),
+        tr!("du.total",
+            usage.total.files.to_string().cyan(),
+            format_size_with_color(usage.total.bytes),
+            format_size_with_color(usage.total.cache_bytes),
+            share(usage.total.cache_bytes, usage.total.bytes)
+        )
+    );
+}
+
+/// A change in size, red for growth and green for shrinking.
+fn size_delta(before: u64, after: u64) -> ColoredString {
+    let change = crate::size::format_size(before.abs_diff(after));
+    if after >= before { format!("+{}", change).red() } else { format!("-{}", change).green() }
+}
+
+/// Prints where the cache of `b` differs from that of `a`: per category,
+/// then the `count` directories that explain most of it.
+pub fn print_comparison(a: &Side, b: &Side, count: usize) {
+    let describe = |side: &Side| {
+        let details: Vec<String> = side.host.iter().cloned().chain(side.root.iter().map(|root| paths::display(root).to_string())).collect();
+        if details.is_empty() { side.label.cyan().to_string() } else { format!("{} ({})", side.label.cyan(), details.join(", ")) }
+    };
+    println!("{} {}", "[Compare:]".yellow(), tr!("compare.title", describe(a), describe(b)));
+
+    println!("\n{}", tr!("compare.categories").blue().bold());
+    let categories = category_deltas(a, b);
+    if categories.is_empty() {
+        println!("  {}", tr!("compare.same").dimmed());
+    }
+    for (category, before, after) in categories {
+        println!("  {}: {} → {} ({}), {}",
+            category.as_str().cyan(),
+            format_size_with_color(before.bytes),
+            format_size_with_color(after.bytes),
+            size_delta(before.bytes, after.bytes),
+            tr!("compare.files", before.files.to_string().cyan(), after.files.to_string().cyan())
+        );
+    }
+
+    println!("\n{}", tr!("compare.paths").blue().bold());
+    match path_deltas(a, b) {
+        Some(dirs) if dirs.is_empty() => println!("  {}", tr!("compare.same").dimmed()),
+        Some(dirs) => {
+            for (dir, before, after) in dirs.iter().take(count) {
+                println!("  {}: {} → {} ({})", paths::display(dir).cyan(), format_size_with_color(*before), format_size_with_color(*after), size_delta(*before, *after));
+            }
+            if dirs.len() > count {
+                println!("  ... and {} more", (dirs.len() - count).to_string().cyan());
+            }
+        }
+        None => println!("  {}", tr!("compare.no-paths").dimmed()),
+    }
+
+    println!("\n{} {}", "[OK!]".green(), tr!("compare.total",
+        format_size_with_color(a.total.bytes),
+        format_size_with_color(b.total.bytes),
+        size_delta(a.total.bytes, b.total.bytes)
+    ));
+}
+
+/// Escapes a Prometheus label value.
+fn label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Renders per-category cache totals in the Prometheus text exposition format,
+/// for scraping directly or through node_exporter's textfile collector. Every
+/// category is listed, so series don't vanish when a category is cleaned out.
+pub async fn prometheus_metrics(cache_files: &[CacheFile], root: &Path) -> String {
+    let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    let path = label_value(&anonymize::path(&root));
+    
+    let mut bytes = String::new();
+    let mut files = String::new();
+    for category in CacheCategory::ALL {
+        let matching: Vec<&CacheFile> = cache_files.iter().filter(|f| f.category == category).collect();
+        let labels = format!("path=\"{}\",category=\"{}\"", path, category.as_str().to_lowercase());
+        bytes.push_str(&format!("hica_cache_bytes{{{}}} {}\n", labels, size_totals(matching.iter().copied()).await.size));
+        files.push_str(&format!("hica_cache_files{{{}}} {}\n", labels, matching.len()));
+    }
+    
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    
+    format!(
+        "# HELP hica_cache_bytes Size of the detected cache files.\n# TYPE hica_cache_bytes gauge\n{}\
+         # HELP hica_cache_files Number of detected cache files.\n# TYPE hica_cache_files gauge\n{}\
+         # HELP hica_scan_timestamp_seconds When the scan finished.\n# TYPE hica_scan_timestamp_seconds gauge\n\
+         hica_scan_timestamp_seconds{{path=\"{}\"}} {}\n",
+        bytes, files, path, now
+    )
+}
+
+/// The totals of a scan as a single JSON object, for dashboards. The schema is
+/// versioned, and every category is always present:
+///
+/// `{"version":1,"path":..,"timestamp":..,
+/// "total":{"files":..,"bytes":..,"stale":{"files":..,"bytes":..}},
+/// "categories":{"browser":{"files":..,"bytes":..,"stale":{..}},..},
+/// "volumes":[{"volume":..,"files":..,"bytes":..,"free":..},..],
+/// "crash_dumps":[{"program":..,"files":..,"bytes":..},..],
+/// "partial":false,"unreached":[..]}`
+pub async fn summary_json(cache_files: &[CacheFile], root: &Path, mounts: &[Mount], partial: Option<&Partial>, errors: &[PathError], stats: &ScanStats, staleness: &Staleness) -> String {
+    let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    let counts = |files: usize, bytes: u64| format!("\"files\":{},\"bytes\":{}", files, bytes);
+    let with_stale = async |files: Vec<&CacheFile>| {
+        let stale: Vec<&CacheFile> = files.iter().copied().filter(|f| f.is_stale(staleness)).collect();
+        format!("{},\"stale\":{{{}}}",
+            counts(files.len(), size_totals(files.iter().copied()).await.size),
+            counts(stale.len(), size_totals(stale.iter().copied()).await.size)
+        )
+    };
+    
+    let mut categories = Vec::new();
+    for category in CacheCategory::ALL {
+        let matching: Vec<&CacheFile> = cache_files.iter().filter(|f| f.category == category).collect();
+        categories.push(format!("\"{}\":{{{}}}", category.as_str().to_lowercase(), with_stale(matching).await));
+    }
+    
+    let mut volumes = Vec::new();
+    for (volume, files) in group_by_volume(cache_files, mounts) {
+        volumes.push(format!("{{\"volume\":{},{},\"free\":{}}}",
+            json_string(&anonymize::path(&volume)),
+            counts(files.len(), size_totals(files.iter().copied()).await.size),
+            free_space(&volume).map_or("null".to_string(), |free| free.to_string())
+        ));
+    }
+    
+    let crash_dumps: Vec<String> = crash_dumps_by_program(cache_files)
+        .await
+        .into_iter()
+        .map(|(program, files, bytes)| format!("{{\"program\":{},{}}}",
+            program.as_deref().map_or("null".to_string(), json_string),
+            counts(files, bytes)
+        ))
+        .collect();
+    
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    
+    let unreached: Vec<String> = partial
+        .map(|partial| partial.unreached.iter().map(|dir| json_string(&anonymize::path(dir))).collect())
+        .unwrap_or_default();
+    let errors: Vec<String> = errors.iter().map(PathError::to_json).collect();
+    
+    format!(
+        "{{\"version\":1,\"path\":{},\"timestamp\":{},\"total\":{{{}}},\"categories\":{{{}}},\"volumes\":[{}],\"crash_dumps\":[{}],\"partial\":{},\"unreached\":[{}],\"errors\":[{}],\"stats\":{}}}",
+        json_string(&anonymize::path(&root)),
+        now,
+        with_stale(cache_files.iter().collect()).await,
+        categories.join(","),
+        volumes.join(","),
+        crash_dumps.join(","),
+        partial.is_some(),
+        unreached.join(","),
+        errors.join(","),
+        stats.to_json()
+    )
+}
+
+/// What a cleanup did, file by file, for orchestration tools to check and
+/// record: every file deleted, and every failure with its error.
+pub fn result_json(root: &Path, summary: &CleanSummary) -> String {
+    let root = std::path::absolute(root).unwrap_or_else(|_| root.to_path_buf());
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let deleted: Vec<String> = summary
+        .deleted
+        .iter()
+        .map(|file| format!("{{\"path\":{},\"size\":{},\"category\":\"{}\"}}",
+            json_string(&anonymize::path(&file.path)),
+            file.size,
+            file.category.as_str().to_lowercase()
+        ))
+        .collect();
+    let failed: Vec<String> = summary.errors.iter().map(PathError::to_json).collect();
+
+    format!(
+        "{{\"version\":1,\"path\":{},\"timestamp\":{},\"deleted_count\":{},\"freed\":{},\"out_of_time\":{},\"deleted\":[{}],\"failed\":[{}]}}",
+        json_string(&anonymize::path(&root)),
+        now,
+        summary.deleted_count,
+        summary.deleted_size,
+        summary.out_of_time,
+        deleted.join(","),
+        failed.join(",")
+    )
+}
+
+/// Writes `contents` to `path` through a temporary file and a rename, so a
+/// collector reading the file never sees it half-written.
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_cache_up_the_tree() {
+        let file = |path: &str, size| CacheFile {
+            path: PathBuf::from(path),
+            size,
+            category: CacheCategory::Other,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+        let files = [file("/home/a/.cache/x/1", 10), file("/home/a/.cache/y/2", 5), file("/home/a/build/3.o", 1)];
+
+        let tree = cache_tree(&files, Path::new("/home/a"));
+        assert_eq!((tree.files, tree.size), (3, 16));
+        let cache = &tree.children[".cache"];
+        assert_eq!((cache.files, cache.size), (2, 15));
+        assert_eq!(cache.children["x"].size, 10);
+        assert_eq!(tree.children["build"].size, 1);
+    }
+
+    #[test]
+    fn ranks_directories_where_their_cache_spreads_out() {
+        let file = |path: String, size, category| CacheFile {
+            path: PathBuf::from(path),
+            size,
+            category,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+        let mut files: Vec<CacheFile> = (0..20).map(|i| file(format!("/h/.cache/yarn/v6/pkg{}/a.tgz", i), 30, CacheCategory::Package)).collect();
+        files.push(file("/h/.config/chrome/Default/Cache/Cache_Data/f_1".into(), 200, CacheCategory::Browser));
+        files.push(file("/h/.config/chrome/Default/Cache/Cache_Data/f_2".into(), 200, CacheCategory::Browser));
+        files.push(file("/h/notes.tmp".into(), 1, CacheCategory::Temporary));
+
+        assert_eq!(offenders(&files, Path::new("/h")), [
+            Offender { dir: "/h/.cache/yarn/v6".into(), size: 600, category: CacheCategory::Package },
+            Offender { dir: "/h/.config/chrome/Default/Cache/Cache_Data".into(), size: 400, category: CacheCategory::Browser },
+        ]);
+    }
+
+    #[test]
+    fn finds_the_entry_of_the_root_a_file_is_in() {
+        let root = Path::new("/code");
+        assert_eq!(root_entry(Path::new("/code/hica/target/x.o"), root).as_deref(), Some("hica"));
+        assert_eq!(root_entry(Path::new("/code/notes.tmp"), root), None);
+        assert_eq!(expanded_entry(Path::new("/code/hica/target"), root).as_deref(), Some("hica"));
+        assert_eq!(expanded_entry(Path::new("./hica"), root).as_deref(), Some("hica"));
+    }
+
+    #[test]
+    fn keeps_names_as_they_are_in_json() {
+        let file = CacheFile {
+            path: "/tmp/a\\nb\nc.tmp".into(),
+            size: 1,
+            category: CacheCategory::Temporary,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: None,
+            accessed: None,
+        };
+
+        let line = crate::json::Json::parse(&json_line(&file, Path::new("/"))).unwrap();
+        assert_eq!(line.get("path").and_then(crate::json::Json::as_str), Some("/tmp/a\\nb\nc.tmp"));
+    }
+}
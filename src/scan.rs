@@ -0,0 +1,1142 @@
+use colored::Colorize;
+use futures::future::BoxFuture;
+use futures::stream::{self, Stream, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::fs;
+
+use crate::checkpoint::Checkpoint;
+use crate::classify::{cache_file_match, classify_cache_file, is_cache_file, CacheCategory, Risk};
+use crate::errors::{self, Operation, PathError};
+use crate::gitignore::{IgnoreStack, VCS_DIRS};
+use crate::interrupt;
+use crate::mounts::{self, Mount};
+use crate::pause;
+use crate::policy;
+use crate::platform::{is_placeholder, long_path};
+use crate::progress::{self, ProgressEvent, ProgressEvents};
+use crate::tr;
+use crate::mft;
+use crate::snapshots::SNAPSHOT_DIRS;
+use crate::walker::{self, DirReader, Walker};
+
+/// A spinner, or a bar with an ETA when there's an estimate of how many
+/// files the scan will come across.
+fn create_progress_bar(estimate: Option<u64>) -> ProgressBar {
+    let (pb, template) = match estimate {
+        Some(estimate) => (
+            ProgressBar::new(estimate),
+            "{spinner:.green} [{elapsed_precise}] [{bar:30.green/blue}] {pos}/~{len} files scanned ({per_sec:0}, ETA {eta}), {msg}",
+        ),
+        None => (ProgressBar::new_spinner(), "{spinner:.green} [{elapsed_precise}] {pos} files scanned ({per_sec:0}), {msg}"),
+    };
+    pb.set_style(ProgressStyle::with_template(template).unwrap().progress_chars("=> "));
+    pb
+}
+
+/// How many files a scan of `path` will come across, when it's a whole
+/// filesystem: about as many as the filesystem has inodes in use.
+fn estimated_files(path: &Path) -> Option<u64> {
+    let root = path.canonicalize().ok()?;
+    if mounts::volume_of(&mounts::mounts(), &root) != root {
+        return None;
+    }
+    crate::platform::used_inodes(&root)
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub category: CacheCategory,
+    /// How sure the match that flagged it is.
+    pub risk: Risk,
+    /// `(device, inode)` of files with several hard links, so data shared
+    /// between them is only counted once.
+    pub link_id: Option<(u64, u64)>,
+    pub modified: Option<SystemTime>,
+    /// Last access time, where the platform and mount options record it.
+    pub accessed: Option<SystemTime>,
+}
+
+/// When a cache file counts as stale rather than in active use.
+#[derive(Debug, Clone, Copy)]
+pub struct Staleness {
+    /// Unused for at least this long
+    pub after: Duration,
+    /// Count reads as use too, going by access times where available
+    pub use_atime: bool,
+}
+
+impl Default for Staleness {
+    fn default() -> Self {
+        Staleness { after: Duration::from_secs(30 * 24 * 60 * 60), use_atime: false }
+    }
+}
+
+impl CacheFile {
+    fn new(path: PathBuf, metadata: &std::fs::Metadata, risk: Risk) -> Self {
+        // Classify the cache file
+        let category = classify_cache_file(&path).unwrap_or(CacheCategory::Other);
+        let size = metadata.len() + crate::platform::resource_fork_size(&path);
+        
+        CacheFile {
+            path,
+            size,
+            category,
+            risk,
+            link_id: link_id(metadata),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+        }
+    }
+    
+    /// How long ago the file was last written, or also read when `use_atime`
+    /// is set. Unknown times count as brand new, so they're never stale.
+    pub fn age(&self, use_atime: bool) -> Duration {
+        let accessed = if use_atime { self.accessed } else { None };
+        let last_used = match (self.modified, accessed) {
+            (Some(modified), Some(accessed)) => modified.max(accessed),
+            (modified, accessed) => match modified.or(accessed) {
+                Some(time) => time,
+                None => return Duration::ZERO,
+            },
+        };
+        
+        SystemTime::now().duration_since(last_used).unwrap_or(Duration::ZERO)
+    }
+    
+    /// Whether the file has gone unused long enough to be stale, which makes
+    /// it the safest part of a cache to delete.
+    pub fn is_stale(&self, staleness: &Staleness) -> bool {
+        self.age(staleness.use_atime) >= staleness.after
+    }
+}
+
+/// Number of hard links to the file, or 1 where the platform doesn't say.
+pub fn link_count(metadata: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        1
+    }
+}
+
+/// `(device, inode)` of a file with several hard links, to count its data once.
+pub fn link_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    if link_count(metadata) <= 1 {
+        return None;
+    }
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Sizes of a set of cache files with hard-linked data counted once.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SizeTotals {
+    /// Each distinct file counted once, however many of its links were found
+    pub size: u64,
+    /// Files whose data is also linked from outside the set, so deleting them frees nothing
+    pub pinned_count: usize,
+    pub pinned_size: u64,
+}
+
+/// Cache files that share data with reflink copies, snapshots or APFS clones.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClonedData {
+    pub count: usize,
+    /// Bytes of theirs that stay on disk when they're deleted
+    pub shared: u64,
+}
+
+/// Reads how much of each file other files share, which deleting it won't
+/// free. That reads every file's extents, so it's done once per scan rather
+/// than with every total.
+pub async fn cloned_data(files: &[CacheFile]) -> ClonedData {
+    let files: Vec<(PathBuf, u64)> = files.iter()
+        .filter(|file| file.size > 0 && file.link_id.is_none())
+        .map(|file| (long_path(&file.path).into_owned(), file.size))
+        .collect();
+    tokio::task::spawn_blocking(move || {
+        let mut cloned = ClonedData::default();
+        for (path, size) in files {
+            let shared = crate::platform::shared_bytes(&path).unwrap_or(0).min(size);
+            if shared > 0 {
+                cloned.count += 1;
+                cloned.shared += shared;
+            }
+        }
+        cloned
+    })
+    .await
+    .unwrap_or_default()
+}
+
+pub async fn size_totals<'a>(files: impl IntoIterator<Item = &'a CacheFile>) -> SizeTotals {
+    let mut totals = SizeTotals::default();
+    let mut linked = std::collections::HashMap::new();
+    
+    for file in files {
+        match file.link_id {
+            Some(id) => linked.entry(id).or_insert_with(Vec::new).push(file),
+            None => totals.size += file.size,
+        }
+    }
+    
+    for links in linked.values() {
+        let file = links[0];
+        totals.size += file.size;
+        
+        let total_links = match fs::metadata(long_path(&file.path)).await {
+            Ok(metadata) => link_count(&metadata),
+            Err(_) => continue,
+        };
+        if total_links > links.len() as u64 {
+            totals.pinned_count += links.len();
+            totals.pinned_size += file.size;
+        }
+    }
+    
+    totals
+}
+
+/// What a scan leaves out, and how it goes about it.
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// Files and directories to leave out, relative to the scan path or absolute
+    pub exclude: Vec<PathBuf>,
+    /// Leave out cache files smaller than this many bytes
+    pub min_size: u64,
+    /// Skip whatever the `.gitignore` files of enclosing repositories ignore
+    pub respect_gitignore: bool,
+    /// Stop after this long and report what was found so far
+    pub timeout: Option<Duration>,
+    /// Stop listing files after this many, so a pathological tree can't
+    /// exhaust memory
+    pub max_files: Option<usize>,
+    /// Where to save progress now and then, so an interrupted scan can resume
+    pub checkpoint: Option<PathBuf>,
+    /// Pick up from the checkpoint, if it's of the same tree
+    pub resume: bool,
+    /// Most filesystem operations per second, to keep the disk free for others
+    pub max_ops_per_second: Option<u32>,
+    /// How directories are listed
+    pub walker: Walker,
+    /// Directories the threads walker lists at once (default: one per CPU)
+    pub jobs: Option<usize>,
+    /// Walk network shares too, rather than skipping them
+    pub include_network: bool,
+}
+
+/// How often a scan saves its progress.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a scan found, and what it didn't get to.
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub cache_files: Vec<CacheFile>,
+    /// Set when the scan stopped early, so its results cover only part of the tree
+    pub partial: Option<Partial>,
+    /// Files and directories that couldn't be looked at
+    pub errors: Vec<PathError>,
+    pub stats: ScanStats,
+}
+
+/// How much a scan went through and how long it took, to tell a slow
+/// filesystem or a slower hica from a bigger tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScanStats {
+    pub elapsed: Duration,
+    /// Directories listed
+    pub directories: u64,
+    /// Files looked at, and their size
+    pub files: u64,
+    pub bytes: u64,
+    /// Files flagged as cache
+    pub matched: u64,
+}
+
+impl ScanStats {
+    pub fn files_per_second(&self) -> f64 {
+        self.files as f64 / self.elapsed.as_secs_f64().max(0.001)
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(0.001)
+    }
+
+    /// `{"elapsed_ms":..,"directories":..,"files":..,"bytes":..,"matched":..,"files_per_second":..}`
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"elapsed_ms\":{},\"directories\":{},\"files\":{},\"bytes\":{},\"matched\":{},\"files_per_second\":{}}}",
+            self.elapsed.as_millis(),
+            self.directories,
+            self.files,
+            self.bytes,
+            self.matched,
+            self.files_per_second().round()
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Partial {
+    pub reason: StopReason,
+    /// Directories the walk never entered
+    pub unreached: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Timeout,
+    /// `--max-files` was reached
+    FileLimit(usize),
+    /// Ctrl-C, once `interrupt::catch` has taken it over
+    Interrupted,
+}
+
+/// How far along a scan is, as passed to `Scanner::on_progress`.
+#[derive(Debug, Clone, Default)]
+pub struct ScanProgress {
+    pub phase: ScanPhase,
+    /// Files looked at so far, and their size
+    pub examined: u64,
+    pub examined_bytes: u64,
+    /// Cache files found so far, and their size
+    pub found: u64,
+    pub found_bytes: u64,
+    /// The file looked at last
+    pub current: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScanPhase {
+    /// Looking again at the files a resumed scan's checkpoint had found
+    Resuming,
+    /// Walking the tree
+    #[default]
+    Walking,
+}
+
+impl ScanPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanPhase::Resuming => "resuming",
+            ScanPhase::Walking => "walking",
+        }
+    }
+}
+
+/// Something worth telling the user while a scan runs, as passed to
+/// `Scanner::on_note`.
+#[derive(Debug)]
+pub enum ScanNote {
+    /// A mount below the scan path holds nothing to clean and is skipped
+    SkippedMount { point: PathBuf, reason: String },
+    /// The scan path itself is on such a mount, so nothing is scanned
+    SkippedRoot { reason: String },
+    /// A network share, at or below the scan path, is skipped as it wasn't included
+    SkippedNetwork { point: PathBuf, fs_type: String },
+    /// The scan picks up from a checkpoint with this many directories done
+    Resumed { done: usize },
+    /// Resuming was asked for, but there's no checkpoint of this path
+    NoCheckpoint,
+    /// Progress can't be saved; the scan goes on regardless
+    CheckpointFailed { file: PathBuf, error: std::io::Error },
+    /// Matching files that are online-only placeholders of cloud storage were left out
+    Placeholders { count: u64, size: u64 },
+    /// The master file table can't be read, so the threads walker lists directories instead
+    WalkerFallback { reason: String },
+}
+
+type Callback<'a, T> = Box<dyn FnMut(&T) + Send + 'a>;
+
+/// Scans a directory tree for cache files, for embedding hica in other tools:
+///
+/// ```no_run
+/// # async fn example() {
+/// let scan = hica::Scanner::new("/home/alice")
+///     .exclude("projects")
+///     .min_size(1024)
+///     .on_progress(|progress| eprintln!("{} files scanned", progress.examined))
+///     .run()
+///     .await;
+/// println!("{} cache files", scan.cache_files.len());
+/// # }
+/// ```
+pub struct Scanner<'a> {
+    path: PathBuf,
+    options: ScanOptions,
+    on_progress: Option<Callback<'a, ScanProgress>>,
+    on_found: Option<Callback<'a, CacheFile>>,
+    on_note: Option<Callback<'a, ScanNote>>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(path: impl Into<PathBuf>) -> Scanner<'a> {
+        Scanner {
+            path: path.into(),
+            options: ScanOptions::default(),
+            on_progress: None,
+            on_found: None,
+            on_note: None,
+        }
+    }
+    
+    /// Replaces all the options at once.
+    pub fn options(mut self, options: ScanOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
+    /// Leaves out a file or directory, relative to the scan path or absolute.
+    pub fn exclude(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.exclude.push(path.into());
+        self
+    }
+    
+    /// Leaves out cache files smaller than `bytes`.
+    pub fn min_size(mut self, bytes: u64) -> Self {
+        self.options.min_size = bytes;
+        self
+    }
+    
+    pub fn respect_gitignore(mut self, respect: bool) -> Self {
+        self.options.respect_gitignore = respect;
+        self
+    }
+    
+    /// Walks NFS, SMB, SSHFS and other network shares instead of skipping them.
+    pub fn include_network(mut self, include: bool) -> Self {
+        self.options.include_network = include;
+        self
+    }
+    
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+    
+    pub fn max_files(mut self, max: usize) -> Self {
+        self.options.max_files = Some(max);
+        self
+    }
+    
+    /// Called as each file is looked at.
+    pub fn on_progress(mut self, callback: impl FnMut(&ScanProgress) + Send + 'a) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+    
+    /// Called with each cache file as soon as it's found.
+    pub fn on_found(mut self, callback: impl FnMut(&CacheFile) + Send + 'a) -> Self {
+        self.on_found = Some(Box::new(callback));
+        self
+    }
+    
+    /// Called with notes about the scan, which are otherwise dropped.
+    pub fn on_note(mut self, callback: impl FnMut(&ScanNote) + Send + 'a) -> Self {
+        self.on_note = Some(Box::new(callback));
+        self
+    }
+    
+    /// Runs the scan as a stream of the cache files it finds, as it finds
+    /// them, for consuming results before the walk is done. Dropping the
+    /// stream stops the scan. This takes the place of `on_found`; use `run`
+    /// for the whole `Scan`, including whether it was partial.
+    pub fn stream(self) -> impl Stream<Item = CacheFile> + 'a {
+        let (sender, receiver) = futures::channel::mpsc::unbounded();
+        let scan = self
+            .on_found(move |file| {
+                let _ = sender.unbounded_send(file.clone());
+            })
+            .run();
+        // The receiver ends once the scan is done and drops the sender
+        let finished = stream::once(scan).filter_map(|_| async { None });
+        stream::select(receiver, finished)
+    }
+}
+
+/// Directories the walk doesn't descend into, and the ignore rules it keeps.
+struct Walk<'a> {
+    /// Mounts and excluded paths, as the walk will see them
+    skip: Vec<PathBuf>,
+    respect_gitignore: bool,
+    deadline: Option<Instant>,
+    max_files: Option<usize>,
+    min_size: u64,
+    /// Directories listed so far
+    directories: AtomicU64,
+    /// Files looked at so far, and their size
+    listed: AtomicUsize,
+    listed_bytes: AtomicU64,
+    /// Cache files found so far, and their size
+    found: AtomicU64,
+    found_bytes: AtomicU64,
+    /// Cloud placeholders that would have matched, and their size in the cloud
+    placeholders: AtomicU64,
+    placeholder_bytes: AtomicU64,
+    /// Directories left out because the walk had to stop
+    unreached: Mutex<Vec<PathBuf>>,
+    /// Set once the walk had to stop with something left out, files or directories
+    stopped: AtomicBool,
+    errors: Mutex<Vec<PathError>>,
+    /// The scan path, which checkpoints are relative to
+    root: PathBuf,
+    checkpointing: Option<Mutex<Checkpointing>>,
+    throttle: Option<Throttle>,
+    /// The backend, resolved, and how many directories it may list at once
+    walker: Walker,
+    listings: tokio::sync::Semaphore,
+    on_progress: Option<Mutex<Callback<'a, ScanProgress>>>,
+    on_found: Option<Mutex<Callback<'a, CacheFile>>>,
+    on_note: Option<Mutex<Callback<'a, ScanNote>>>,
+}
+
+impl Walk<'_> {
+    fn file_limit_reached(&self) -> bool {
+        self.max_files.is_some_and(|max| self.listed.load(Ordering::Relaxed) >= max)
+    }
+    
+    /// Whether the walk has to stop, out of time, over the file limit or
+    /// interrupted.
+    fn must_stop(&self) -> bool {
+        self.file_limit_reached() || self.deadline.is_some_and(|deadline| Instant::now() >= deadline) || interrupt::interrupted()
+    }
+    
+    /// Waits for the next filesystem operation to be allowed.
+    async fn throttle(&self) {
+        pause::wait_while_paused().await;
+        if let Some(throttle) = &self.throttle {
+            throttle.wait().await;
+        }
+    }
+    
+    /// Counts a file the walk came across, returning it if it's cache. Without
+    /// metadata, which only a master file table listing leaves out for files
+    /// that aren't cache, the file counts without its size.
+    fn examine(&self, path: PathBuf, metadata: Option<&std::fs::Metadata>, phase: ScanPhase) -> Option<CacheFile> {
+        let size = metadata.map_or(0, |metadata| metadata.len());
+        let examined = self.listed.fetch_add(1, Ordering::Relaxed) as u64 + 1;
+        let examined_bytes = self.listed_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        let current = self.on_progress.as_ref().map(|_| path.clone());
+        let mut matched = metadata.zip(cache_file_match(&path));
+        // An online-only placeholder frees nothing here, and touching it
+        // would download it or, deleted, remove it from the cloud too
+        if let Some((metadata, _)) = &matched
+            && is_placeholder(metadata)
+        {
+            self.placeholders.fetch_add(1, Ordering::Relaxed);
+            self.placeholder_bytes.fetch_add(metadata.len(), Ordering::Relaxed);
+            matched = None;
+        }
+        let cache_file = matched
+            .map(|(metadata, (_, risk))| CacheFile::new(path, metadata, risk))
+            .filter(|file| file.size >= self.min_size)
+            .and_then(policy::review);
+        
+        if let Some(file) = &cache_file {
+            self.found.fetch_add(1, Ordering::Relaxed);
+            self.found_bytes.fetch_add(file.size, Ordering::Relaxed);
+            if let Some(on_found) = &self.on_found {
+                (on_found.lock().unwrap())(file);
+            }
+        }
+        if let (Some(on_progress), Some(current)) = (&self.on_progress, current) {
+            (on_progress.lock().unwrap())(&ScanProgress {
+                phase,
+                examined,
+                examined_bytes,
+                found: self.found.load(Ordering::Relaxed),
+                found_bytes: self.found_bytes.load(Ordering::Relaxed),
+                current,
+            });
+        }
+        cache_file
+    }
+    
+    fn error(&self, operation: Operation, path: &Path, error: &std::io::Error) {
+        if errors::is_reportable(error) {
+            self.errors.lock().unwrap().push(PathError::new(operation, path, error));
+        }
+    }
+    
+    fn note(&self, note: ScanNote) {
+        if let Some(on_note) = &self.on_note {
+            (on_note.lock().unwrap())(&note);
+        }
+    }
+    
+    /// Records a finished directory in the checkpoint, saving it now and
+    /// then. Returns whether the directory was recorded.
+    fn complete_dir(&self, dir: PathBuf, files: Vec<PathBuf>) -> bool {
+        let Some(checkpointing) = &self.checkpointing else { return false };
+        let mut checkpointing = checkpointing.lock().unwrap();
+        let recorded = checkpointing.checkpoint.complete(dir, files);
+        if checkpointing.saved.elapsed() >= CHECKPOINT_INTERVAL {
+            let failure = checkpointing.save();
+            drop(checkpointing);
+            if let Some(note) = failure {
+                self.note(note);
+            }
+        }
+        recorded
+    }
+}
+
+/// Spaces operations out evenly to stay under a rate.
+struct Throttle {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl Throttle {
+    fn new(per_second: u32) -> Throttle {
+        Throttle {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+    
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+}
+
+/// A checkpoint being kept up to date as directories are finished.
+struct Checkpointing {
+    checkpoint: Checkpoint,
+    file: PathBuf,
+    saved: Instant,
+    failed: bool,
+}
+
+impl Checkpointing {
+    /// Saves the checkpoint, returning a note the first time that fails.
+    fn save(&mut self) -> Option<ScanNote> {
+        self.saved = Instant::now();
+        match self.checkpoint.save(&self.file) {
+            Err(error) if !self.failed => {
+                self.failed = true;
+                Some(ScanNote::CheckpointFailed { file: self.file.clone(), error })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Define a boxed future type for recursive async function. Besides the cache
+// files, it tells whether the directory was walked completely and checkpointed.
+type WalkDirFuture<'a> = BoxFuture<'a, (Vec<CacheFile>, bool)>;
+
+/// Subdirectories of one directory the threads walker is in at once. How
+/// many are listed at the same time is up to `Walk::listings`.
+const SUBDIRS_AT_ONCE: usize = 8;
+
+async fn async_walk_dir(path: &Path, walk: &Walk<'_>) -> Vec<CacheFile> {
+    let ignores = match (walk.respect_gitignore, path.canonicalize()) {
+        (true, Ok(absolute)) => Some((IgnoreStack::for_dir(&absolute), absolute)),
+        _ => None,
+    };
+    async_walk_dir_inner(path, walk, ignores).await.0
+}
+
+// Helper function with boxed future to handle recursion. `ignores` carries the
+// gitignore rules in effect along with the directory's absolute path, which
+// they're matched against.
+fn async_walk_dir_inner<'a>(path: &'a Path, walk: &'a Walk<'_>, ignores: Option<(IgnoreStack, PathBuf)>) -> WalkDirFuture<'a> {
+    Box::pin(async move {
+        let mut files = Vec::new();
+        let relative = path.strip_prefix(&walk.root).unwrap_or(path).to_path_buf();
+        
+        if walk.must_stop() {
+            walk.stopped.store(true, Ordering::Relaxed);
+            walk.unreached.lock().unwrap().push(path.to_path_buf());
+            return (files, false);
+        }
+        // A resumed scan starts out with the files of finished directories
+        if let Some(checkpointing) = &walk.checkpointing
+            && checkpointing.lock().unwrap().checkpoint.is_done(&relative)
+        {
+            return (files, true);
+        }
+        
+        let mut whole = true;
+        let mut cache_here = Vec::new();
+        // Subdirectories the threads walker goes through together, once this one is listed
+        let mut subdirs = Vec::new();
+        
+        walk.throttle().await;
+        let listing = {
+            let _slot = walk.listings.acquire().await.expect("the listing semaphore is never closed");
+            DirReader::open(walk.walker, path).await
+        };
+        if let Err(e) = &listing {
+            walk.error(Operation::List, path, e);
+        }
+        if let Ok(mut dir_entries) = listing {
+            walk.directories.fetch_add(1, Ordering::Relaxed);
+            loop {
+                let entry = match dir_entries.next_entry().await {
+                    Ok(Some(entry)) => entry,
+                    Ok(None) => break,
+                    Err(e) => {
+                        walk.error(Operation::List, path, &e);
+                        break;
+                    }
+                };
+                // Joined to `path` rather than taken from the entry, so reported
+                // paths never carry the extended-length prefix
+                let entry_path = path.join(entry.file_name());
+                
+                // Once stopped, the rest of the directory is only listed, and
+                // only to note the subdirectories that won't be entered
+                if walk.must_stop() {
+                    walk.stopped.store(true, Ordering::Relaxed);
+                    if entry.is_dir().await {
+                        walk.unreached.lock().unwrap().push(entry_path);
+                    }
+                    whole = false;
+                    continue;
+                }
+                
+                walk.throttle().await;
+                let name = entry.file_name();
+                let metadata = entry.metadata(&entry_path).await;
+                if let Err(e) = &metadata {
+                    walk.error(Operation::Stat, &entry_path, e);
+                }
+                if let Ok(metadata) = metadata {
+                    let absolute = ignores.as_ref().map(|(_, dir)| dir.join(&name));
+                    if let (Some((stack, _)), Some(absolute)) = (&ignores, &absolute)
+                        && stack.is_ignored(absolute, metadata.is_dir())
+                    {
+                        continue;
+                    }
+                    
+                    if metadata.is_dir() {
+                        let is_skipped = name.to_str().is_some_and(|name| VCS_DIRS.contains(&name) || SNAPSHOT_DIRS.contains(&name));
+                        if is_skipped || walk.skip.contains(&entry_path) {
+                            continue;
+                        }
+                        let sub_ignores = ignores.as_ref().zip(absolute).map(|((stack, _), absolute)| (stack.with_dir(&absolute), absolute));
+                        if walk.walker == Walker::Threads {
+                            subdirs.push((entry_path, sub_ignores));
+                            continue;
+                        }
+                        // Recursively walk subdirectories with boxed future
+                        let (mut sub_files, sub_whole) = async_walk_dir_inner(&entry_path, walk, sub_ignores).await;
+                        files.append(&mut sub_files);
+                        whole &= sub_whole;
+                    } else if metadata.is_file() && !walk.skip.contains(&entry_path) {
+                        if walk.checkpointing.is_some() && is_cache_file(&entry_path) {
+                            cache_here.push(relative.join(&name));
+                        }
+                        files.extend(walk.examine(entry_path, Some(&metadata), ScanPhase::Walking));
+                    }
+                }
+            }
+        }
+        
+        let walks: Vec<WalkDirFuture> = subdirs
+            .iter()
+            .map(|(subdir, sub_ignores)| async_walk_dir_inner(subdir, walk, sub_ignores.clone()))
+            .collect();
+        let mut walked = stream::iter(walks).buffered(SUBDIRS_AT_ONCE);
+        while let Some((mut sub_files, sub_whole)) = walked.next().await {
+            files.append(&mut sub_files);
+            whole &= sub_whole;
+        }
+        
+        // Only a directory whose subdirectories were all recorded can be
+        // skipped as a whole on resume
+        if walk.checkpointing.is_some() {
+            whole = whole && walk.complete_dir(relative, cache_here);
+        }
+        (files, whole)
+    })
+}
+
+/// Goes through what a master file table listing found below the scan path.
+/// File names alone tell which files are cache, so only those are read from
+/// disk; the rest count without their sizes.
+async fn walk_listing(entries: Vec<mft::Entry>, walk: &Walk<'_>) -> Vec<CacheFile> {
+    let mut files = Vec::new();
+    for entry in entries {
+        if walk.must_stop() {
+            walk.stopped.store(true, Ordering::Relaxed);
+            walk.unreached.lock().unwrap().push(walk.root.clone());
+            break;
+        }
+        let below = entry.path.strip_prefix(&walk.root).unwrap_or(&entry.path);
+        let in_skipped = below.components().any(|component| {
+            component.as_os_str().to_str().is_some_and(|name| VCS_DIRS.contains(&name) || SNAPSHOT_DIRS.contains(&name))
+        });
+        if in_skipped || walk.skip.iter().any(|skipped| entry.path.starts_with(skipped)) {
+            continue;
+        }
+        if entry.is_dir {
+            walk.directories.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+        
+        let metadata = match cache_file_match(&entry.path) {
+            Some(_) => {
+                walk.throttle().await;
+                match fs::metadata(long_path(&entry.path)).await {
+                    Ok(metadata) => Some(metadata),
+                    Err(e) => {
+                        walk.error(Operation::Stat, &entry.path, &e);
+                        continue;
+                    }
+                }
+            }
+            None => None,
+        };
+        files.extend(walk.examine(entry.path, metadata.as_ref(), ScanPhase::Walking));
+    }
+    files
+}
+
+/// Why the walk stays off `mount`, if it does.
+fn skip_note(mount: &Mount, include_network: bool) -> Option<ScanNote> {
+    match mount.skip_reason() {
+        Some(reason) => Some(ScanNote::SkippedMount { point: mount.point.clone(), reason }),
+        None if mount.is_network() && !include_network => {
+            Some(ScanNote::SkippedNetwork { point: mount.point.clone(), fs_type: mount.fs_type.clone() })
+        }
+        None => None,
+    }
+}
+
+/// Mounts below `path` that the walk stays off, as it will see them, with
+/// why; or why `path` itself is on such a mount.
+fn skipped_mounts(path: &Path, include_network: bool) -> Result<Vec<(PathBuf, ScanNote)>, ScanNote> {
+    let mounts = mounts::mounts();
+    let root = match path.canonicalize() {
+        Ok(root) => root,
+        Err(_) => return Ok(Vec::new()),
+    };
+    
+    if let Some(note) = mounts::mount_of(&mounts, &root).and_then(|mount| skip_note(mount, include_network)) {
+        return Err(match note {
+            ScanNote::SkippedMount { reason, .. } => ScanNote::SkippedRoot { reason },
+            note => note,
+        });
+    }
+    
+    let mut skip = Vec::new();
+    let mut skipped_points: Vec<&Path> = Vec::new();
+    for mount in &mounts {
+        let Some(note) = skip_note(mount, include_network) else { continue };
+        // Mounts inside one that's already skipped are never reached anyway
+        if skipped_points.iter().any(|point| mount.point.starts_with(point)) {
+            continue;
+        }
+        if let Ok(relative) = mount.point.strip_prefix(&root)
+            && !relative.as_os_str().is_empty()
+        {
+            skip.push((path.join(relative), note));
+            skipped_points.push(&mount.point);
+        }
+    }
+    
+    Ok(skip)
+}
+
+/// An excluded path as the walk will see it, below `path`.
+fn excluded_path(path: &Path, root: Option<&Path>, excluded: &Path) -> PathBuf {
+    if excluded.is_relative() {
+        return path.join(excluded);
+    }
+    match root.and_then(|root| excluded.strip_prefix(root).ok()) {
+        Some(relative) => path.join(relative),
+        None => excluded.to_path_buf(),
+    }
+}
+
+impl Scanner<'_> {
+    /// Walks the tree, then examines the files found, reporting progress along
+    /// the way.
+    pub async fn run(mut self) -> Scan {
+        let started = Instant::now();
+        let path = self.path.as_path();
+        let options = &self.options;
+        let root = path.canonicalize().ok();
+        policy::scan_started(path);
+        let mut note = |note: ScanNote| {
+            if let Some(on_note) = &mut self.on_note {
+                on_note(&note);
+            }
+        };
+        
+        let mut skip = match skipped_mounts(path, options.include_network) {
+            Ok(skip) => skip.into_iter().map(|(dir, skipped)| {
+                note(skipped);
+                dir
+            }).collect::<Vec<_>>(),
+            Err(skipped) => {
+                note(skipped);
+                return Scan::default();
+            }
+        };
+        skip.extend(options.exclude.iter().map(|excluded| excluded_path(path, root.as_deref(), excluded)));
+        
+        let checkpointing = options.checkpoint.clone().zip(root).map(|(file, root)| {
+            let resumed = if options.resume { Checkpoint::load(&file).filter(|c| c.root == root) } else { None };
+            match &resumed {
+                Some(checkpoint) => note(ScanNote::Resumed { done: checkpoint.done_count() }),
+                None if options.resume => note(ScanNote::NoCheckpoint),
+                None => {}
+            }
+            Mutex::new(Checkpointing {
+                checkpoint: resumed.unwrap_or_else(|| Checkpoint::new(root)),
+                file,
+                saved: Instant::now(),
+                failed: false,
+            })
+        });
+        
+        let mut walker = options.walker.resolve(options.max_ops_per_second.is_some());
+        let mut listing = None;
+        if walker == Walker::Mft {
+            // .gitignore files only prune a walk that reads directories one by one
+            let listed = if options.respect_gitignore {
+                Err("--respect-gitignore needs the directories read one by one".to_string())
+            } else {
+                let path = path.to_path_buf();
+                tokio::task::spawn_blocking(move || mft::list(&path)).await.unwrap_or_else(|e| Err(e.to_string()))
+            };
+            match listed {
+                Ok(entries) => listing = Some(entries),
+                Err(reason) => {
+                    note(ScanNote::WalkerFallback { reason });
+                    walker = Walker::Threads;
+                }
+            }
+        }
+        
+        // Asynchronously get all files
+        let walk = Walk {
+            skip,
+            respect_gitignore: options.respect_gitignore,
+            deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+            max_files: options.max_files,
+            min_size: options.min_size,
+            directories: AtomicU64::new(0),
+            listed: AtomicUsize::new(0),
+            listed_bytes: AtomicU64::new(0),
+            found: AtomicU64::new(0),
+            found_bytes: AtomicU64::new(0),
+            placeholders: AtomicU64::new(0),
+            placeholder_bytes: AtomicU64::new(0),
+            unreached: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false),
+            errors: Mutex::new(Vec::new()),
+            root: path.to_path_buf(),
+            checkpointing,
+            throttle: options.max_ops_per_second.map(Throttle::new),
+            walker,
+            listings: tokio::sync::Semaphore::new(options.jobs.unwrap_or_else(walker::default_jobs).max(1)),
+            on_progress: self.on_progress.take().map(Mutex::new),
+            on_found: self.on_found.take().map(Mutex::new),
+            on_note: self.on_note.take().map(Mutex::new),
+        };
+        
+        // A resumed scan looks at what its checkpoint found first, as those
+        // directories won't be walked again. A master file table listing
+        // covers them anyway.
+        let resumed: Vec<PathBuf> = match &walk.checkpointing {
+            Some(checkpointing) if listing.is_none() => checkpointing.lock().unwrap().checkpoint.files().iter().map(|file| path.join(file)).collect(),
+            _ => Vec::new(),
+        };
+        let mut cache_files = Vec::new();
+        for file_path in resumed {
+            walk.throttle().await;
+            if let Ok(metadata) = fs::metadata(long_path(&file_path)).await
+                && metadata.is_file()
+            {
+                cache_files.extend(walk.examine(file_path, Some(&metadata), ScanPhase::Resuming));
+            }
+        }
+        
+        cache_files.extend(match listing {
+            Some(entries) => walk_listing(entries, &walk).await,
+            None => async_walk_dir(path, &walk).await,
+        });
+        
+        let reason = match walk.max_files {
+            _ if interrupt::interrupted() => StopReason::Interrupted,
+            Some(max) if walk.file_limit_reached() => StopReason::FileLimit(max),
+            _ => StopReason::Timeout,
+        };
+        let unreached = std::mem::take(&mut *walk.unreached.lock().unwrap());
+        // Stopped in a directory of only files, the walk left out files but no directories
+        let stopped = walk.stopped.load(Ordering::Relaxed) || !unreached.is_empty() || reason == StopReason::Interrupted;
+        let partial = stopped.then_some(Partial { reason, unreached });
+        
+        // A partial scan can be finished later with --resume; a complete one is done with
+        if let Some(checkpointing) = &walk.checkpointing {
+            let mut checkpointing = checkpointing.lock().unwrap();
+            if partial.is_some() {
+                if let Some(failure) = checkpointing.save() {
+                    drop(checkpointing);
+                    walk.note(failure);
+                }
+            } else {
+                let _ = std::fs::remove_file(&checkpointing.file);
+            }
+        }
+        let placeholders = walk.placeholders.load(Ordering::Relaxed);
+        if placeholders > 0 {
+            walk.note(ScanNote::Placeholders { count: placeholders, size: walk.placeholder_bytes.load(Ordering::Relaxed) });
+        }
+        let errors = std::mem::take(&mut *walk.errors.lock().unwrap());
+        let stats = ScanStats {
+            elapsed: started.elapsed(),
+            directories: walk.directories.load(Ordering::Relaxed),
+            files: walk.listed.load(Ordering::Relaxed) as u64,
+            bytes: walk.listed_bytes.load(Ordering::Relaxed),
+            matched: walk.found.load(Ordering::Relaxed),
+        };
+        policy::scan_finished(path, &stats, &cache_files);
+        Scan { cache_files, partial, errors, stats }
+    }
+}
+
+/// Prints how much the scan went through and how fast, on stderr like the
+/// other status lines.
+fn print_stats(stats: &ScanStats) {
+    eprintln!("{} {}",
+        "[Stats]".blue(),
+        tr!("scan.stats",
+            stats.directories,
+            stats.files,
+            crate::size::format_size(stats.bytes),
+            format!("{:.1}s", stats.elapsed.as_secs_f64()),
+            stats.files_per_second().round(),
+            crate::size::format_size(stats.bytes_per_second() as u64),
+            stats.matched
+        )
+    );
+}
+
+/// Prints a scan note the way the CLI shows them.
+fn print_note(path: &Path, note: &ScanNote) {
+    match note {
+        ScanNote::SkippedMount { point, reason } => {
+            eprintln!("{} {}", "[Note]".blue(), tr!("scan.skipped-mount", point.display(), reason));
+        }
+        ScanNote::SkippedRoot { reason } => {
+            eprintln!("{} {}", "[Note]".blue(), tr!("scan.skipped-root", path.display(), reason));
+        }
+        ScanNote::SkippedNetwork { point, fs_type } => {
+            eprintln!("{} {}", "[Note]".blue(), tr!("scan.skipped-network", point.display(), fs_type));
+        }
+        ScanNote::Resumed { done } => eprintln!("{} {}", "[Resume]".yellow(), tr!("scan.resuming", done)),
+        ScanNote::NoCheckpoint => eprintln!("{} {}", "[Note]".blue(), tr!("scan.no-checkpoint", path.display())),
+        ScanNote::CheckpointFailed { file, error } => {
+            eprintln!("{} {}", "[Warning!]".red(), tr!("scan.checkpoint-failed", file.display(), error));
+        }
+        ScanNote::Placeholders { count, size } => {
+            eprintln!("{} {}", "[Note]".blue(), tr!("scan.placeholders", count, crate::size::format_size(*size)));
+        }
+        ScanNote::WalkerFallback { reason } => eprintln!("{} {}", "[Note]".blue(), tr!("scan.walker-fallback", reason)),
+    }
+}
+
+/// Scans `path` the way the CLI does, with notes on stderr and a progress bar
+/// or, in logs and with `--progress json`, progress events.
+pub async fn scan_cache_files(path: &Path, options: &ScanOptions, on_found: impl FnMut(&CacheFile) + Send) -> Scan {
+    eprintln!("{} {}", "[Running!]".yellow(), tr!("scan.traversing"));
+    
+    let _graceful = interrupt::graceful();
+    let pb = if progress::shows_bar() { create_progress_bar(estimated_files(path)) } else { ProgressBar::hidden() };
+    let throughput = Mutex::new(progress::Throughput::default());
+    let events = Mutex::new(ProgressEvents::default());
+    let scan = Scanner::new(path)
+        .options(options.clone())
+        .on_found(on_found)
+        .on_note(|note| print_note(path, note))
+        .on_progress({
+            let pb = pb.clone();
+            let events = &events;
+            let throughput = &throughput;
+            move |progress| {
+                events.lock().unwrap().update(ProgressEvent {
+                    phase: progress.phase.as_str(),
+                    files: progress.examined,
+                    found: Some(progress.found),
+                    bytes: progress.found_bytes,
+                    current: Some(progress.current.clone()),
+                    ..ProgressEvent::default()
+                });
+                if pb.length().is_some_and(|len| progress.examined > len) {
+                    pb.set_length(progress.examined);
+                }
+                pb.set_position(progress.examined);
+                let rate = throughput.lock().unwrap().update(progress.examined_bytes);
+                pb.set_message(tr!("scan.progress", progress.found, crate::size::format_size(rate as u64)));
+            }
+        })
+        .run()
+        .await;
+    pb.finish_with_message("Scan completed");
+    events.lock().unwrap().finish();
+    print_stats(&scan.stats);
+    
+    scan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_excluded_paths_against_the_scan_path() {
+        let root = Path::new("/home/alice");
+        assert_eq!(excluded_path(Path::new("."), Some(root), Path::new("projects")), PathBuf::from("./projects"));
+        assert_eq!(excluded_path(Path::new("."), Some(root), Path::new("/home/alice/tmp")), PathBuf::from("./tmp"));
+        assert_eq!(excluded_path(Path::new("."), Some(root), Path::new("/srv")), PathBuf::from("/srv"));
+    }
+
+    #[tokio::test]
+    async fn stopping_among_files_makes_the_scan_partial() {
+        let dir = std::env::temp_dir().join(format!("hica-scan-budget-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.join(format!("{}.tmp", i)), "x").unwrap();
+        }
+        let limited = Scanner::new(&dir).max_files(2).run().await;
+        let complete = Scanner::new(&dir).max_files(5).run().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let partial = limited.partial.expect("the scan stopped before the last file");
+        assert_eq!(partial.reason, StopReason::FileLimit(2));
+        assert!(partial.unreached.is_empty());
+        assert_eq!(limited.stats.files, 2);
+        assert!(complete.partial.is_none());
+    }
+}
@@ -0,0 +1,354 @@
+//! Registering periodic hica runs with the platform's scheduler.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::ValueEnum;
+
+use crate::paths::home_dir;
+use crate::platform::is_privileged;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Interval {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Backend {
+    Systemd,
+    Launchd,
+    Cron,
+    Schtasks,
+}
+
+impl Backend {
+    /// The scheduler this platform normally uses.
+    pub fn detect() -> Backend {
+        if cfg!(windows) {
+            Backend::Schtasks
+        } else if cfg!(target_os = "macos") {
+            Backend::Launchd
+        } else if Path::new("/run/systemd/system").is_dir() {
+            Backend::Systemd
+        } else {
+            Backend::Cron
+        }
+    }
+}
+
+/// A scheduled hica run.
+pub struct Schedule {
+    pub name: String,
+    pub interval: Interval,
+    /// The full command line, starting with the hica binary.
+    pub command: Vec<String>,
+}
+
+impl Schedule {
+    fn unit_name(&self) -> String {
+        format!("hica-{}", self.name)
+    }
+}
+
+fn systemd_dir() -> Option<PathBuf> {
+    if is_privileged() {
+        Some(PathBuf::from("/etc/systemd/system"))
+    } else {
+        let config = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => home_dir()?.join(".config"),
+        };
+        Some(config.join("systemd").join("user"))
+    }
+}
+
+fn launchd_path(name: &str) -> Option<PathBuf> {
+    Some(
+        home_dir()?
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("io.github.edwardjoke.hica.{}.plist", name)),
+    )
+}
+
+/// Quotes an argument for a systemd `ExecStart=` line.
+fn systemd_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '"', '\\', '\'', '%', '$']) {
+        return arg.to_string();
+    }
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+/// Quotes an argument for `/bin/sh`, as cron runs commands through it.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@+".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn systemd_units(schedule: &Schedule) -> (String, String) {
+    let command: Vec<String> = schedule.command.iter().map(|arg| systemd_quote(arg)).collect();
+    let service = format!(
+        "[Unit]\nDescription=hica scheduled run ({name})\n\n[Service]\nType=oneshot\nExecStart={command}\nNice=10\nIOSchedulingClass=idle\n",
+        name = schedule.name,
+        command = command.join(" "),
+    );
+
+    let calendar = match schedule.interval {
+        Interval::Hourly => "hourly",
+        Interval::Daily => "daily",
+        Interval::Weekly => "weekly",
+    };
+    let timer = format!(
+        "[Unit]\nDescription=Run hica {name} {calendar}\n\n[Timer]\nOnCalendar={calendar}\nPersistent=true\nRandomizedDelaySec=15min\n\n[Install]\nWantedBy=timers.target\n",
+        name = schedule.name,
+    );
+
+    (service, timer)
+}
+
+pub fn launchd_plist(schedule: &Schedule) -> String {
+    let arguments: String = schedule
+        .command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+
+    let interval = match schedule.interval {
+        Interval::Hourly => "        <key>Minute</key>\n        <integer>0</integer>\n",
+        Interval::Daily => "        <key>Hour</key>\n        <integer>3</integer>\n        <key>Minute</key>\n        <integer>0</integer>\n",
+        Interval::Weekly => "        <key>Weekday</key>\n        <integer>0</integer>\n        <key>Hour</key>\n        <integer>3</integer>\n        <key>Minute</key>\n        <integer>0</integer>\n",
+    };
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n    <key>Label</key>\n    <string>io.github.edwardjoke.hica.{name}</string>\n\
+         \x20   <key>ProgramArguments</key>\n    <array>\n{arguments}    </array>\n\
+         \x20   <key>StartCalendarInterval</key>\n    <dict>\n{interval}    </dict>\n\
+         \x20   <key>LowPriorityIO</key>\n    <true/>\n\
+         </dict>\n</plist>\n",
+        name = xml_escape(&schedule.name),
+    )
+}
+
+/// The crontab line, tagged so `schedule remove` can find it again.
+pub fn cron_line(schedule: &Schedule) -> String {
+    let when = match schedule.interval {
+        Interval::Hourly => "0 * * * *",
+        Interval::Daily => "0 3 * * *",
+        Interval::Weekly => "0 3 * * 0",
+    };
+    // cron turns a bare `%` into a newline, even inside quotes
+    let command: Vec<String> = schedule.command.iter().map(|arg| shell_quote(arg).replace('%', "\\%")).collect();
+    format!("{} {} {}", when, command.join(" "), cron_marker(&schedule.name))
+}
+
+fn cron_marker(name: &str) -> String {
+    format!("# hica:{}", name)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{} {}` failed ({})", program, args.join(" "), status))
+    }
+}
+
+fn read_crontab() -> String {
+    Command::new("crontab")
+        .arg("-l")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .unwrap_or_default()
+}
+
+fn write_crontab(contents: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run crontab: {}", e))?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("crontab failed ({})", status))
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("failed to write {}: {}", path.display(), e))
+}
+
+/// Writes and registers the schedule, returning where it was installed.
+pub fn install(schedule: &Schedule, backend: Backend) -> Result<String, String> {
+    let unit = schedule.unit_name();
+
+    match backend {
+        Backend::Systemd => {
+            let dir = systemd_dir().ok_or("could not determine the systemd unit directory")?;
+            let (service, timer) = systemd_units(schedule);
+            write_file(&dir.join(format!("{}.service", unit)), &service)?;
+            write_file(&dir.join(format!("{}.timer", unit)), &timer)?;
+
+            let timer_name = format!("{}.timer", unit);
+            if is_privileged() {
+                run("systemctl", &["daemon-reload"])?;
+                run("systemctl", &["enable", "--now", &timer_name])?;
+            } else {
+                run("systemctl", &["--user", "daemon-reload"])?;
+                run("systemctl", &["--user", "enable", "--now", &timer_name])?;
+            }
+            Ok(format!("systemd timer {} in {}", timer_name, dir.display()))
+        }
+        Backend::Launchd => {
+            let path = launchd_path(&schedule.name).ok_or("could not determine the LaunchAgents directory")?;
+            write_file(&path, &launchd_plist(schedule))?;
+            run("launchctl", &["load", "-w", &path.to_string_lossy()])?;
+            Ok(format!("launchd agent {}", path.display()))
+        }
+        Backend::Cron => {
+            let marker = cron_marker(&schedule.name);
+            let mut lines: Vec<String> = read_crontab()
+                .lines()
+                .filter(|line| !line.ends_with(&marker))
+                .map(str::to_string)
+                .collect();
+            lines.push(cron_line(schedule));
+            write_crontab(&(lines.join("\n") + "\n"))?;
+            Ok("the user's crontab".to_string())
+        }
+        Backend::Schtasks => {
+            let quoted: Vec<String> = schedule
+                .command
+                .iter()
+                .map(|arg| if arg.contains(' ') { format!("\"{}\"", arg) } else { arg.clone() })
+                .collect();
+            let every = match schedule.interval {
+                Interval::Hourly => "HOURLY",
+                Interval::Daily => "DAILY",
+                Interval::Weekly => "WEEKLY",
+            };
+            run("schtasks", &["/Create", "/F", "/SC", every, "/ST", "03:00", "/TN", &unit, "/TR", &quoted.join(" ")])?;
+            Ok(format!("scheduled task {}", unit))
+        }
+    }
+}
+
+/// Unregisters and deletes a schedule installed with [`install`].
+pub fn remove(name: &str, backend: Backend) -> Result<String, String> {
+    let unit = format!("hica-{}", name);
+
+    match backend {
+        Backend::Systemd => {
+            let dir = systemd_dir().ok_or("could not determine the systemd unit directory")?;
+            let timer_name = format!("{}.timer", unit);
+            let paths = ["service", "timer"].map(|suffix| dir.join(format!("{}.{}", unit, suffix)));
+            if !paths.iter().any(|path| path.exists()) {
+                return Err(format!("no schedule named `{}`", name));
+            }
+            let scope: &[&str] = if is_privileged() { &[] } else { &["--user"] };
+
+            // The timer may already be gone; removing the files is what matters
+            let _ = run("systemctl", &[scope, &["disable", "--now", &timer_name]].concat());
+            for path in paths {
+                if path.exists() {
+                    std::fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {}", path.display(), e))?;
+                }
+            }
+            let _ = run("systemctl", &[scope, &["daemon-reload"]].concat());
+            Ok(format!("systemd timer {}", timer_name))
+        }
+        Backend::Launchd => {
+            let path = launchd_path(name).ok_or("could not determine the LaunchAgents directory")?;
+            if !path.exists() {
+                return Err(format!("no schedule named `{}`", name));
+            }
+            let _ = run("launchctl", &["unload", "-w", &path.to_string_lossy()]);
+            std::fs::remove_file(&path).map_err(|e| format!("failed to remove {}: {}", path.display(), e))?;
+            Ok(format!("launchd agent {}", path.display()))
+        }
+        Backend::Cron => {
+            let marker = cron_marker(name);
+            let crontab = read_crontab();
+            if !crontab.lines().any(|line| line.ends_with(&marker)) {
+                return Err(format!("no schedule named `{}`", name));
+            }
+            let lines: Vec<&str> = crontab.lines().filter(|line| !line.ends_with(&marker)).collect();
+            write_crontab(&(lines.join("\n") + "\n"))?;
+            Ok("the crontab entry".to_string())
+        }
+        Backend::Schtasks => {
+            run("schtasks", &["/Delete", "/F", "/TN", &unit])?;
+            Ok(format!("scheduled task {}", unit))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> Schedule {
+        Schedule {
+            name: "nightly".to_string(),
+            interval: Interval::Daily,
+            command: vec!["/usr/bin/hica".into(), "detect".into(), "/home/alice/My Downloads".into(), "--yes".into()],
+        }
+    }
+
+    #[test]
+    fn renders_systemd_units() {
+        let (service, timer) = systemd_units(&schedule());
+        assert!(service.contains("ExecStart=/usr/bin/hica detect \"/home/alice/My Downloads\" --yes\n"));
+        assert!(timer.contains("OnCalendar=daily\n"));
+    }
+
+    #[test]
+    fn renders_tagged_cron_line() {
+        assert_eq!(
+            cron_line(&schedule()),
+            "0 3 * * * /usr/bin/hica detect '/home/alice/My Downloads' --yes # hica:nightly"
+        );
+        let mut schedule = schedule();
+        schedule.command.push("--format-template=%path".into());
+        assert!(cron_line(&schedule).ends_with(" --yes '--format-template=\\%path' # hica:nightly"));
+    }
+
+    #[test]
+    fn removing_a_missing_systemd_schedule_fails() {
+        if systemd_dir().is_some() {
+            assert_eq!(remove("hica-test-never-installed", Backend::Systemd), Err("no schedule named `hica-test-never-installed`".to_string()));
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! `hica serve`: scans and cleanups driven over a local Unix socket, so GUI
+//! front-ends and editor plugins can build on hica without parsing its output.
+//!
+//! The protocol is JSON-RPC 2.0 with one request per line, each answered with
+//! one response line. Scans run in the background: `scan` starts one and
+//! returns its id, `progress` polls it, `results` pages through what it found,
+//! `delete` cleans it up once the client confirms, and `cancel` stops it and
+//! forgets it. `pause` and `resume` hold up and continue all scans and
+//! deletions, as SIGUSR1 and SIGUSR2 do.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::AbortHandle;
+
+use crate::browser::{running_processes, Browser};
+use crate::classify::CacheCategory;
+use crate::clean::{delete_cache_files, CleanOptions, DeleteLimits};
+use crate::config::Config;
+use crate::json::Json;
+use crate::jvm::is_maven_repository;
+use crate::lock::RootLock;
+use crate::mounts::{mounts, volume_of};
+use crate::package::package_cache;
+use crate::pause;
+use crate::platform::is_privileged;
+use crate::protect::{is_dangerous_root, ProtectedPaths};
+use crate::report::{json_line, json_string};
+use crate::scan::{Scan, ScanOptions, ScanProgress, Scanner};
+use crate::vcs::TrackedFiles;
+
+/// Where `hica serve` listens unless told otherwise: `hica.sock` in
+/// `$XDG_RUNTIME_DIR`, or a per-user socket in the temporary directory.
+pub fn default_socket() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("hica.sock"),
+        // SAFETY: getuid has no preconditions and cannot fail
+        None => std::env::temp_dir().join(format!("hica-{}.sock", unsafe { libc::getuid() })),
+    }
+}
+
+enum State {
+    Scanning(AbortHandle),
+    Done(Scan),
+    Deleting,
+    Deleted,
+}
+
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            State::Scanning(_) => "scanning",
+            State::Done(_) => "done",
+            State::Deleting => "deleting",
+            State::Deleted => "deleted",
+        }
+    }
+}
+
+struct Job {
+    path: PathBuf,
+    progress: Arc<Mutex<ScanProgress>>,
+    state: State,
+}
+
+struct Server {
+    config: Config,
+    jobs: Mutex<HashMap<u64, Job>>,
+    next_id: Mutex<u64>,
+}
+
+/// A failed request: a JSON-RPC error code and a message for the client.
+struct Failure(i32, String);
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+/// The request was well-formed, but the scan isn't in a state to serve it
+const SCAN_ERROR: i32 = -32000;
+
+fn invalid_params(message: impl Into<String>) -> Failure {
+    Failure(INVALID_PARAMS, message.into())
+}
+
+/// Serves requests on `socket` until interrupted, then removes the socket.
+/// Only the current user may connect.
+pub async fn serve(socket: &Path, config: Config) -> io::Result<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("another hica is already serving on {}", socket.display())));
+        }
+        // Left behind by a server that didn't shut down cleanly
+        std::fs::remove_file(socket)?;
+    }
+    if let Some(dir) = socket.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    // Create the socket owner-only from the start, rather than fixing it up
+    // after others could have connected
+    // SAFETY: umask has no preconditions and cannot fail
+    let previous = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(socket);
+    unsafe { libc::umask(previous) };
+    let listener = listener?;
+
+    let server = Arc::new(Server { config, jobs: Mutex::new(HashMap::new()), next_id: Mutex::new(1) });
+    let result = loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(server.clone(), stream));
+                }
+                Err(e) => break Err(e),
+            },
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        }
+    };
+
+    let _ = std::fs::remove_file(socket);
+    result
+}
+
+async fn handle_connection(server: Arc<Server>, stream: UnixStream) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = respond(&server, &line).await;
+        if writer.write_all(format!("{}\n", response).as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Answers one request line with one response line.
+async fn respond(server: &Arc<Server>, line: &str) -> String {
+    let (id, outcome) = match Json::parse(line) {
+        Ok(request) => {
+            let id = request.get("id").map_or("null".to_string(), id_json);
+            (id, dispatch(server, &request).await)
+        }
+        Err(e) => ("null".to_string(), Err(Failure(PARSE_ERROR, e))),
+    };
+
+    match outcome {
+        Ok(result) => format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"result\":{}}}", id, result),
+        Err(Failure(code, message)) => format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"error\":{{\"code\":{},\"message\":{}}}}}",
+            id, code, json_string(&message)
+        ),
+    }
+}
+
+/// Echoes a request id back; JSON-RPC ids are strings or numbers.
+fn id_json(id: &Json) -> String {
+    match id {
+        Json::String(s) => json_string(s),
+        Json::Number(n) if n.fract() == 0.0 => format!("{}", *n as i64),
+        Json::Number(n) => n.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+async fn dispatch(server: &Arc<Server>, request: &Json) -> Result<String, Failure> {
+    let method = request
+        .get("method")
+        .and_then(Json::as_str)
+        .ok_or_else(|| Failure(INVALID_REQUEST, "missing `method`".to_string()))?;
+    let params = request.get("params").cloned().unwrap_or(Json::Object(Vec::new()));
+
+    match method {
+        "scan" => start_scan(server, &params),
+        "progress" => progress(server, &params),
+        "results" => results(server, &params),
+        "delete" => delete(server, &params).await,
+        "cancel" => cancel(server, &params),
+        "pause" => {
+            pause::pause();
+            Ok("{\"paused\":true}".to_string())
+        }
+        "resume" => {
+            pause::resume();
+            Ok("{\"paused\":false}".to_string())
+        }
+        _ => Err(Failure(METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+    }
+}
+
+fn scan_id(params: &Json) -> Result<u64, Failure> {
+    params.get("scan").and_then(Json::as_u64).ok_or_else(|| invalid_params("`scan` must be a scan id"))
+}
+
+fn unknown_scan(id: u64) -> Failure {
+    Failure(SCAN_ERROR, format!("no scan {}", id))
+}
+
+/// `scan {path, exclude?, min_size?, respect_gitignore?}` starts a scan in the
+/// background and returns `{"scan": id}`.
+fn start_scan(server: &Arc<Server>, params: &Json) -> Result<String, Failure> {
+    let path = PathBuf::from(params.get("path").and_then(Json::as_str).ok_or_else(|| invalid_params("`path` must be a string"))?);
+    if !path.is_dir() {
+        return Err(invalid_params(format!("{} is not a directory", path.display())));
+    }
+
+    let mut options = ScanOptions {
+        max_ops_per_second: server.config.nice_ops_per_second,
+        ..ScanOptions::default()
+    };
+    if let Some(exclude) = params.get("exclude") {
+        let exclude = exclude.as_array().ok_or_else(|| invalid_params("`exclude` must be an array of paths"))?;
+        for item in exclude {
+            options.exclude.push(PathBuf::from(item.as_str().ok_or_else(|| invalid_params("`exclude` must be an array of paths"))?));
+        }
+    }
+    if let Some(min_size) = params.get("min_size") {
+        options.min_size = min_size.as_u64().ok_or_else(|| invalid_params("`min_size` must be a number of bytes"))?;
+    }
+    if let Some(respect) = params.get("respect_gitignore") {
+        options.respect_gitignore = respect.as_bool().ok_or_else(|| invalid_params("`respect_gitignore` must be a boolean"))?;
+    }
+    if let Some(include) = params.get("include_network") {
+        options.include_network = include.as_bool().ok_or_else(|| invalid_params("`include_network` must be a boolean"))?;
+    }
+
+    let id = {
+        let mut next_id = server.next_id.lock().unwrap();
+        *next_id += 1;
+        *next_id - 1
+    };
+    let progress = Arc::new(Mutex::new(ScanProgress::default()));
+
+    // Hold the lock until the job is in place, so the task can't finish first
+    let mut jobs = server.jobs.lock().unwrap();
+    let task = tokio::spawn({
+        let server = server.clone();
+        let path = path.clone();
+        let progress = progress.clone();
+        async move {
+            let scan = Scanner::new(path)
+                .options(options)
+                .on_progress(move |update| *progress.lock().unwrap() = update.clone())
+                .run()
+                .await;
+
+            // Whatever its name, a file under version control is part of the project
+            let mut tracked = TrackedFiles::default();
+            let cache_files = scan.cache_files.into_iter().filter(|f| !tracked.is_tracked(&f.path)).collect();
+
+            if let Some(job) = server.jobs.lock().unwrap().get_mut(&id) {
+                job.state = State::Done(Scan { cache_files, ..scan });
+            }
+        }
+    });
+    jobs.insert(id, Job { path, progress, state: State::Scanning(task.abort_handle()) });
+
+    Ok(format!("{{\"scan\":{}}}", id))
+}
+
+/// `progress {scan}` returns the scan's state, whether work is paused, and
+/// the counts so far.
+fn progress(server: &Server, params: &Json) -> Result<String, Failure> {
+    let id = scan_id(params)?;
+    let jobs = server.jobs.lock().unwrap();
+    let job = jobs.get(&id).ok_or_else(|| unknown_scan(id))?;
+    let progress = job.progress.lock().unwrap();
+
+    Ok(format!(
+        "{{\"state\":\"{}\",\"paused\":{},\"examined\":{},\"found\":{},\"found_bytes\":{}}}",
+        job.state.name(), pause::is_paused(), progress.examined, progress.found, progress.found_bytes
+    ))
+}
+
+/// `results {scan, offset?, limit?}` returns a finished scan's cache files,
+/// in the same form as `detect --format jsonl`, with the overall totals.
+fn results(server: &Server, params: &Json) -> Result<String, Failure> {
+    let id = scan_id(params)?;
+    let offset = params.get("offset").map_or(Some(0), Json::as_u64).ok_or_else(|| invalid_params("`offset` must be a number"))? as usize;
+    let limit = params.get("limit").map_or(Some(u64::MAX), Json::as_u64).ok_or_else(|| invalid_params("`limit` must be a number"))? as usize;
+
+    let jobs = server.jobs.lock().unwrap();
+    let job = jobs.get(&id).ok_or_else(|| unknown_scan(id))?;
+    let State::Done(scan) = &job.state else {
+        return Err(Failure(SCAN_ERROR, format!("scan {} is {}, not done", id, job.state.name())));
+    };
+
+    let mounts = mounts();
+    let files: Vec<String> = scan
+        .cache_files
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|file| json_line(file, &volume_of(&mounts, &file.path)))
+        .collect();
+    let unreached: Vec<String> = scan
+        .partial
+        .iter()
+        .flat_map(|partial| &partial.unreached)
+        .map(|dir| json_string(&dir.to_string_lossy()))
+        .collect();
+    let errors: Vec<String> = scan.errors.iter().map(|e| e.to_json()).collect();
+
+    Ok(format!(
+        "{{\"total\":{},\"bytes\":{},\"partial\":{},\"unreached\":[{}],\"errors\":[{}],\"files\":[{}]}}",
+        scan.cache_files.len(),
+        scan.cache_files.iter().map(|f| f.size).sum::<u64>(),
+        scan.partial.is_some(),
+        unreached.join(","),
+        errors.join(","),
+        files.join(",")
+    ))
+}
+
+/// `delete {scan, confirm}` deletes what a finished scan found, with the same
+/// safeguards as `detect`. There's nobody to ask, so files that `detect` would
+/// warn about are left alone: root-owned package caches without root, and the
+/// caches of running browsers.
+async fn delete(server: &Server, params: &Json) -> Result<String, Failure> {
+    let id = scan_id(params)?;
+    if params.get("confirm").and_then(Json::as_bool) != Some(true) {
+        return Err(invalid_params("deleting needs `\"confirm\": true`"));
+    }
+
+    let (root, scan, _lock) = {
+        let mut jobs = server.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id).ok_or_else(|| unknown_scan(id))?;
+        if !matches!(job.state, State::Done(_)) {
+            return Err(Failure(SCAN_ERROR, format!("scan {} is {}, not done", id, job.state.name())));
+        }
+        if is_dangerous_root(&job.path) {
+            return Err(Failure(SCAN_ERROR, format!("refusing to delete files found under {}", job.path.display())));
+        }
+        // Another run, such as a scheduled one, may be cleaning the same path
+        let lock = RootLock::try_acquire(&job.path).map_err(|e| Failure(SCAN_ERROR, format!("{}: {}", job.path.display(), e)))?;
+        let State::Done(scan) = std::mem::replace(&mut job.state, State::Deleting) else {
+            unreachable!("checked above");
+        };
+        (job.path.clone(), scan, lock)
+    };
+
+    let mut cache_files = scan.cache_files.clone();
+    let found = cache_files.len();
+    let privileged = is_privileged();
+    cache_files.retain(|f| f.category != CacheCategory::Package || package_cache(&f.path).is_none_or(|cache| privileged || !cache.needs_root));
+    let processes = running_processes();
+    cache_files.retain(|f| f.category != CacheCategory::Browser || !Browser::from_path(&f.path).is_some_and(|b| b.is_running(&processes)));
+    // The trash is emptied through the desktop, with `hica clean trash`, and
+    // Maven's repository may hold artifacts that can't be downloaded again
+    cache_files.retain(|f| f.category != CacheCategory::Trash && !is_maven_repository(&f.path));
+    let held_back = found - cache_files.len();
+
+    let options = CleanOptions {
+        root,
+        limits: DeleteLimits {
+            max_size: server.config.max_delete_size,
+            max_count: server.config.max_delete_count,
+            deadline: None,
+        },
+        archive: None,
+        prune_empty_dirs: false,
+        retry: server.config.retry,
+        hooks: server.config.clean_hooks.clone(),
+        quiet: false,
+    };
+    let summary = match delete_cache_files(cache_files, &ProtectedPaths::new(&server.config.protected), &options).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            // Nothing was deleted, so the scan can be deleted again once the hook is fixed
+            if let Some(job) = server.jobs.lock().unwrap().get_mut(&id) {
+                job.state = State::Done(scan);
+            }
+            return Err(Failure(SCAN_ERROR, e));
+        }
+    };
+
+    if let Some(job) = server.jobs.lock().unwrap().get_mut(&id) {
+        job.state = State::Deleted;
+    }
+
+    let errors: Vec<String> = summary.errors.iter().map(|e| e.to_json()).collect();
+    Ok(format!(
+        "{{\"deleted\":{},\"freed\":{},\"held_back\":{},\"errors\":[{}]}}",
+        summary.deleted_count, summary.deleted_size, held_back, errors.join(",")
+    ))
+}
+
+/// `cancel {scan}` stops a running scan, and forgets any scan's results.
+fn cancel(server: &Server, params: &Json) -> Result<String, Failure> {
+    let id = scan_id(params)?;
+    let mut jobs = server.jobs.lock().unwrap();
+    match jobs.get(&id).map(|job| &job.state) {
+        None => return Err(unknown_scan(id)),
+        Some(State::Deleting) => return Err(Failure(SCAN_ERROR, format!("scan {} is being deleted", id))),
+        Some(State::Scanning(task)) => task.abort(),
+        Some(_) => {}
+    }
+    jobs.remove(&id);
+
+    Ok("{\"canceled\":true}".to_string())
+}
+
@@ -0,0 +1,110 @@
+use colored::Colorize;
+use std::sync::OnceLock;
+
+/// How sizes are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Units {
+    /// 1024-based, e.g. `4.2 GB` for 4.2 GiB
+    #[default]
+    Binary,
+    /// 1000-based, as most disk tools and drive labels count
+    Si,
+    /// Exact byte counts
+    Bytes,
+}
+
+static UNITS: OnceLock<Units> = OnceLock::new();
+
+/// Sets the units for the rest of the run; only the first call counts.
+pub fn set_units(units: Units) {
+    let _ = UNITS.set(units);
+}
+
+fn units() -> Units {
+    UNITS.get().copied().unwrap_or_default()
+}
+
+/// Formats a size in the given units, along with its magnitude (0 for bytes,
+/// 1 for kilobytes, and so on) whichever units are used.
+fn format_in(size: u64, units: Units) -> (String, usize) {
+    let magnitude = std::iter::successors(Some(size), |s| Some(s / 1024))
+        .take_while(|&s| s >= 1024)
+        .count()
+        .min(4);
+    let (base, labels) = match units {
+        Units::Bytes => return (format!("{} B", size), magnitude),
+        Units::Binary => (1024.0, ["B", "KB", "MB", "GB", "TB"]),
+        Units::Si => (1000.0, ["B", "kB", "MB", "GB", "TB"]),
+    };
+
+    let mut size_value = size as f64;
+    let mut unit_index = 0;
+    
+    while size_value >= base && unit_index < labels.len() - 1 {
+        size_value /= base;
+        unit_index += 1;
+    }
+    
+    (format!("{:.1} {}", size_value, labels[unit_index]), magnitude)
+}
+
+/// Formats a size in the units chosen for this run, e.g. `4.2 GB`.
+pub fn format_size(size: u64) -> String {
+    format_in(size, units()).0
+}
+
+pub fn format_size_with_color(size: u64) -> colored::ColoredString {
+    let (formatted, magnitude) = format_in(size, units());
+    formatted.color(crate::theme::theme().sizes[magnitude])
+}
+
+/// Parses a human-readable size such as `500GB`, `1.5 MiB` or `4096`.
+///
+/// Units are 1024-based, whichever units sizes are printed in.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size `{}`", input))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        other => return Err(format!("unknown size unit `{}` in `{}`", other, input)),
+    };
+
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_with_units() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("10KB"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5 MiB"), Ok(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_size("500gb"), Ok(500 << 30));
+        assert!(parse_size("GB").is_err());
+        assert!(parse_size("5 PB").is_err());
+    }
+
+    #[test]
+    fn formats_in_each_unit() {
+        assert_eq!(format_in(1536, Units::Binary), ("1.5 KB".to_string(), 1));
+        assert_eq!(format_in(1536, Units::Si), ("1.5 kB".to_string(), 1));
+        assert_eq!(format_in(1536, Units::Bytes), ("1536 B".to_string(), 1));
+        assert_eq!(format_in(5_000_000_000, Units::Si).0, "5.0 GB");
+        assert_eq!(format_in(5_000_000_000, Units::Binary).0, "4.7 GB");
+        assert_eq!(format_in(1 << 50, Units::Binary), ("1024.0 TB".to_string(), 4));
+    }
+}
@@ -0,0 +1,61 @@
+//! Filesystem snapshots, which keep deleted files' data around until they
+//! expire, so cleaning a path they cover frees nothing for a while.
+//!
+//! ZFS shows a dataset's snapshots in `.zfs/snapshot` at its root, snapper
+//! keeps Btrfs snapshots in `.snapshots`, and NetApp and other NAS shares
+//! show theirs in `.snapshot`. What's inside those is read-only history,
+//! never walked or deleted.
+
+use std::path::{Path, PathBuf};
+
+use crate::mounts::{mount_of, Mount};
+
+/// Directories holding snapshots, which the walk doesn't descend into.
+pub const SNAPSHOT_DIRS: &[&str] = &[".zfs", ".snapshot", ".snapshots"];
+
+/// Where snapshots of a scanned path are kept.
+#[derive(Debug, PartialEq)]
+pub struct Snapshots {
+    pub dir: PathBuf,
+    pub count: usize,
+}
+
+/// The snapshots covering `path`: those in a snapshot directory of it or of
+/// one of its parents, up to the root of its mount.
+pub fn covering(path: &Path, mounts: &[Mount]) -> Option<Snapshots> {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mount_point = mount_of(mounts, &absolute).map(|mount| mount.point.as_path());
+
+    for dir in absolute.ancestors() {
+        for snapshots in [dir.join(".zfs").join("snapshot"), dir.join(".snapshots"), dir.join(".snapshot")] {
+            let count = std::fs::read_dir(&snapshots).map_or(0, |entries| entries.count());
+            if count > 0 {
+                return Some(Snapshots { dir: snapshots, count });
+            }
+        }
+        if Some(dir) == mount_point {
+            break;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_snapshots_of_a_parent() {
+        let root = std::env::temp_dir().join(format!("hica-snapshots-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("home/alice/.cache")).unwrap();
+        let before = covering(&root.join("home/alice"), &[]);
+        for snapshot in ["1", "2"] {
+            std::fs::create_dir_all(root.join(".snapshots").join(snapshot)).unwrap();
+        }
+        let after = covering(&root.join("home/alice"), &[]);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(before, None);
+        assert_eq!(after, Some(Snapshots { dir: root.join(".snapshots"), count: 2 }));
+    }
+}
@@ -0,0 +1,160 @@
+//! `--format-template`: one line per file in a shape the user picks, such as
+//! `{path}\t{size_bytes}\t{category}`, for scripts that would rather split
+//! lines than parse JSON.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::anonymize;
+use crate::paths;
+use crate::scan::CacheFile;
+use crate::size::format_size;
+
+/// A value of a file a template can show.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Path,
+    Name,
+    SizeBytes,
+    Size,
+    Category,
+    Risk,
+    Modified,
+    Accessed,
+    Volume,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 9] = [
+        ("path", Field::Path),
+        ("name", Field::Name),
+        ("size_bytes", Field::SizeBytes),
+        ("size", Field::Size),
+        ("category", Field::Category),
+        ("risk", Field::Risk),
+        ("modified", Field::Modified),
+        ("accessed", Field::Accessed),
+        ("volume", Field::Volume),
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed `--format-template`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Reads a template: `{field}` placeholders, `{{` and `}}` for literal
+    /// braces, and `\t`, `\n`, `\0` and `\\` escapes, since shells don't turn
+    /// them into the characters inside quotes.
+    pub fn parse(input: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = input.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(match chars.next() {
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('0') => '\0',
+                    Some('\\') => '\\',
+                    Some(other) => return Err(format!("unknown escape `\\{}` in `{}`", other, input)),
+                    None => return Err(format!("template `{}` ends with a lone `\\`", input)),
+                }),
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(|| format!("unclosed `{{` in `{}`", input))?;
+                    let name = &rest[..end];
+                    let field = Field::ALL
+                        .iter()
+                        .find(|(known, _)| *known == name)
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| {
+                            let known: Vec<&str> = Field::ALL.iter().map(|(known, _)| *known).collect();
+                            format!("unknown placeholder `{{{}}}`, expected one of {}", name, known.join(", "))
+                        })?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(format!("unmatched `}}` in `{}`, write `}}}}` for a literal one", input)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template { parts })
+    }
+
+    /// The line for `file`, on the volume `volume`, without a line break.
+    /// Times are Unix seconds and empty where the platform doesn't record them.
+    pub fn render(&self, file: &CacheFile, volume: &Path) -> String {
+        let seconds = |time: Option<SystemTime>| {
+            time.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(String::new(), |elapsed| elapsed.as_secs().to_string())
+        };
+
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field) => line.push_str(&match field {
+                    Field::Path => anonymize::text(&paths::display(&file.path)).into_owned(),
+                    Field::Name => file.path.file_name().map(|name| anonymize::text(&paths::escape(name)).into_owned()).unwrap_or_default(),
+                    Field::SizeBytes => file.size.to_string(),
+                    Field::Size => format_size(file.size),
+                    Field::Category => file.category.as_str().to_lowercase(),
+                    Field::Risk => file.risk.as_str().to_string(),
+                    Field::Modified => seconds(file.modified),
+                    Field::Accessed => seconds(file.accessed),
+                    Field::Volume => anonymize::text(&paths::display(volume)).into_owned(),
+                }),
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{CacheCategory, Risk};
+
+    #[test]
+    fn renders_placeholders_and_escapes() {
+        let file = CacheFile {
+            path: "/home/a/.cache/x.tmp".into(),
+            size: 2048,
+            category: CacheCategory::Temporary,
+            risk: Risk::Safe,
+            link_id: None,
+            modified: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(60)),
+            accessed: None,
+        };
+
+        let template = Template::parse(r"{path}\t{size_bytes}\t{category} {{{name}}} {modified}/{accessed}").unwrap();
+        assert_eq!(template.render(&file, Path::new("/")), "/home/a/.cache/x.tmp\t2048\ttemporary {x.tmp} 60/");
+        assert!(Template::parse("{paht}").unwrap_err().contains("unknown placeholder `{paht}`"));
+        assert!(Template::parse("{path").is_err());
+        assert!(Template::parse("size}").is_err());
+    }
+}
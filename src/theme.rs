@@ -0,0 +1,94 @@
+//! The colors sizes, categories, risk levels and file names are printed in,
+//! set from the `[theme]` section of the config for terminals and eyes the
+//! default colors don't suit.
+
+use std::sync::OnceLock;
+
+use colored::Color;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Sizes of bytes, kilobytes, megabytes, gigabytes and terabytes
+    pub sizes: [Color; 5],
+    pub category: Color,
+    /// Safe, likely and risky matches
+    pub risks: [Color; 3],
+    /// File names in the full file list
+    pub name: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            sizes: [Color::Magenta, Color::Blue, Color::Green, Color::Yellow, Color::Red],
+            category: Color::Magenta,
+            risks: [Color::Green, Color::Yellow, Color::Red],
+            name: Color::Yellow,
+        }
+    }
+}
+
+impl Theme {
+    pub const PALETTES: [&'static str; 3] = ["default", "light", "colorblind"];
+
+    /// A built-in palette: `default`, `light` for light backgrounds, where
+    /// yellow is hard to read, or `colorblind`, which doesn't rely on telling
+    /// red from green.
+    pub fn palette(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme {
+                sizes: [Color::BrightBlack, Color::Blue, Color::Green, Color::Magenta, Color::Red],
+                category: Color::Magenta,
+                risks: [Color::Green, Color::Blue, Color::Red],
+                name: Color::Blue,
+            }),
+            "colorblind" => Some(Theme {
+                sizes: [Color::BrightBlack, Color::Cyan, Color::Blue, Color::Yellow, Color::BrightMagenta],
+                category: Color::Cyan,
+                risks: [Color::Blue, Color::Yellow, Color::BrightMagenta],
+                name: Color::Yellow,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Reads a color name such as `red` or `bright blue`, or a `#rrggbb` color
+/// for terminals that show 24-bit colors.
+pub fn parse_color(input: &str) -> Option<Color> {
+    if let Some(hex) = input.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::TrueColor { r: channel(0)?, g: channel(2)?, b: channel(4)? });
+    }
+    input.parse().ok()
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Sets the theme for the rest of the run; only the first call counts.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The theme of this run, the default one unless the config picked another.
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("bright blue"), Some(Color::BrightBlue));
+        assert_eq!(parse_color("#ff8000"), Some(Color::TrueColor { r: 255, g: 128, b: 0 }));
+        assert_eq!(parse_color("#ff80"), None);
+        assert_eq!(parse_color("orange"), None);
+        assert!(Theme::PALETTES.iter().all(|name| Theme::palette(name).is_some()));
+    }
+}
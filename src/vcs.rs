@@ -0,0 +1,103 @@
+//! Recognizing files under version control, which are never cache even when
+//! their names say otherwise (a committed `debug.log` test fixture, say).
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tracked files of the git repositories seen so far, loaded on demand.
+#[derive(Default)]
+pub struct TrackedFiles {
+    /// Each directory's repository root, if it's in one
+    repositories: HashMap<PathBuf, Option<PathBuf>>,
+    /// Each repository's tracked files, or `None` if git couldn't list them
+    tracked: HashMap<PathBuf, Option<HashSet<PathBuf>>>,
+    /// Repositories whose files couldn't be listed
+    pub unlisted: Vec<PathBuf>,
+}
+
+impl TrackedFiles {
+    /// Whether git tracks `path`. Files of a repository git can't list, or
+    /// of any repository when git isn't installed, count as tracked, so
+    /// they're kept rather than mistaken for cache.
+    pub fn is_tracked(&mut self, path: &Path) -> bool {
+        // Resolve the directory but not the file, so a tracked symlink counts
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+        let Ok(dir) = parent.canonicalize() else {
+            return false;
+        };
+
+        let repository = self
+            .repositories
+            .entry(dir.clone())
+            .or_insert_with(|| dir.ancestors().find(|a| a.join(".git").exists()).map(Path::to_path_buf))
+            .clone();
+        let Some(repository) = repository else {
+            return false;
+        };
+
+        let tracked = self.tracked.entry(repository.clone()).or_insert_with(|| {
+            let files = list_tracked(&repository);
+            if files.is_none() {
+                self.unlisted.push(repository.clone());
+            }
+            files
+        });
+        tracked.as_ref().is_none_or(|files| files.contains(&dir.join(name)))
+    }
+}
+
+/// Absolute paths of the files git tracks in the repository at `root`.
+fn list_tracked(root: &Path) -> Option<HashSet<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "-z"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let files = output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| root.join(bytes_to_path(name)))
+        .collect();
+    Some(files)
+}
+
+fn bytes_to_path(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        PathBuf::from(<std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        // git prints forward slashes, which Windows paths accept too
+        PathBuf::from(String::from_utf8_lossy(bytes).replace('/', "\\"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_files_of_repositories_git_cant_list() {
+        let dir = std::env::temp_dir().join(format!("hica-vcs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        // Not a repository git can read
+        std::fs::write(dir.join(".git"), "garbage").unwrap();
+        std::fs::write(dir.join("logs").join("debug.log"), "log").unwrap();
+
+        let mut tracked = TrackedFiles::default();
+        let kept = tracked.is_tracked(&dir.join("logs").join("debug.log"));
+        let root = dir.canonicalize().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(kept);
+        assert_eq!(tracked.unlisted, [root]);
+    }
+}
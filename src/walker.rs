@@ -0,0 +1,153 @@
+//! How a scan lists directories and reads the metadata of their entries.
+//!
+//! tokio's file operations each hop onto a blocking thread, so an async walk
+//! pays that hop for every file it looks at. The `threads` backend lists a
+//! whole directory, metadata and all, in one blocking task instead, and the
+//! scan walks several directories at once with it. Another backend, such as
+//! io_uring on Linux, would be one more `Walker` and `DirReader` variant.
+//! `mft` doesn't read directories at all; see `mft`.
+
+use std::ffi::OsString;
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::platform::long_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum Walker {
+    /// `threads`, unless the scan is throttled with `[nice] ops_per_second`
+    #[default]
+    Auto,
+    /// tokio's async file operations, one at a time, each of which can be throttled
+    Tokio,
+    /// Whole directories listed with blocking calls on a pool of threads, several at once
+    Threads,
+    /// The master file table of an NTFS volume, read in one go; Windows only, from an elevated prompt
+    Mft,
+}
+
+impl Walker {
+    /// The backend `Auto` stands for. A throttled scan paces every single
+    /// operation, which only the tokio walker does.
+    pub fn resolve(self, throttled: bool) -> Walker {
+        match self {
+            Walker::Auto if throttled => Walker::Tokio,
+            Walker::Auto => Walker::Threads,
+            walker => walker,
+        }
+    }
+}
+
+/// Directories the threads walker lists at once, unless `--jobs` says otherwise.
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get()).clamp(2, 16)
+}
+
+/// An entry of a directory being read.
+pub enum DirEntry {
+    Tokio(tokio::fs::DirEntry),
+    Listed { name: OsString, metadata: io::Result<Metadata> },
+}
+
+impl DirEntry {
+    pub fn file_name(&self) -> OsString {
+        match self {
+            DirEntry::Tokio(entry) => entry.file_name(),
+            DirEntry::Listed { name, .. } => name.clone(),
+        }
+    }
+
+    /// The entry's metadata, following symlinks, read at `path`.
+    pub async fn metadata(self, path: &Path) -> io::Result<Metadata> {
+        match self {
+            DirEntry::Tokio(_) => tokio::fs::metadata(long_path(path)).await,
+            DirEntry::Listed { metadata, .. } => metadata,
+        }
+    }
+
+    /// Whether the entry is a directory, as cheaply as the backend can tell.
+    pub async fn is_dir(&self) -> bool {
+        match self {
+            DirEntry::Tokio(entry) => entry.file_type().await.is_ok_and(|t| t.is_dir()),
+            DirEntry::Listed { metadata, .. } => metadata.as_ref().is_ok_and(|m| m.is_dir()),
+        }
+    }
+}
+
+/// A directory being read by one of the backends.
+pub enum DirReader {
+    Tokio(tokio::fs::ReadDir),
+    /// Entries read up front, and the error that cut the listing short, if any
+    Listed(std::vec::IntoIter<DirEntry>, Option<io::Error>),
+}
+
+impl DirReader {
+    pub async fn open(walker: Walker, path: &Path) -> io::Result<DirReader> {
+        match walker {
+            Walker::Threads => {
+                let path = long_path(path).into_owned();
+                tokio::task::spawn_blocking(move || list(&path))
+                    .await
+                    .map_err(io::Error::other)?
+            }
+            _ => Ok(DirReader::Tokio(tokio::fs::read_dir(long_path(path)).await?)),
+        }
+    }
+
+    pub async fn next_entry(&mut self) -> io::Result<Option<DirEntry>> {
+        match self {
+            DirReader::Tokio(entries) => Ok(entries.next_entry().await?.map(DirEntry::Tokio)),
+            DirReader::Listed(entries, error) => match entries.next() {
+                Some(entry) => Ok(Some(entry)),
+                None => error.take().map_or(Ok(None), Err),
+            },
+        }
+    }
+}
+
+/// Reads a directory and the metadata of everything in it with blocking calls.
+fn list(path: &Path) -> io::Result<DirReader> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return Ok(DirReader::Listed(entries.into_iter(), Some(e))),
+        };
+        let metadata = std::fs::metadata(entry.path());
+        entries.push(DirEntry::Listed { name: entry.file_name(), metadata });
+    }
+    Ok(DirReader::Listed(entries.into_iter(), None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn backends_list_the_same_entries() {
+        let root = std::env::temp_dir().join(format!("hica-walker-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("file"), [0u8; 10]).unwrap();
+
+        let mut listings = Vec::new();
+        for walker in [Walker::Tokio, Walker::Threads] {
+            let mut reader = DirReader::open(walker, &root).await.unwrap();
+            let mut names = Vec::new();
+            while let Some(entry) = reader.next_entry().await.unwrap() {
+                let name = entry.file_name();
+                let is_dir = entry.is_dir().await;
+                let size = entry.metadata(&root.join(&name)).await.unwrap().len();
+                names.push((name, is_dir, if is_dir { 0 } else { size }));
+            }
+            names.sort();
+            listings.push(names);
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(listings[0], [("file".into(), false, 10), ("sub".into(), true, 0)]);
+        assert_eq!(listings[0], listings[1]);
+    }
+}